@@ -1,10 +1,32 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    path::PathBuf,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime},
+};
+
 #[cfg(test)]
 use mockall::automock;
 
+use crate::completions::{generate_completions, CompletionNames, Shell};
 use crate::core::{
-    operations::{AddProjectArgs, BackupOperations, DeviceOperations, ProjectOperations},
-    DeviceFactoryKey, QuestionType,
+    operations::{
+        AddDeviceResult, AddProjectArgs, BackupExecutionMetrics, BackupOperations, BackupOptions,
+        BackupPriority, BackupSummary, ConfigOperations, CopyComparisonResult, DeviceOperations,
+        DryRunResult, IndexedFile, IndexedFilesOrder, IntegrityReport, ProjectOperations,
+        RequirementComplianceResult,
+    },
+    util::timestamps::format_utc,
+    BackupEvent, CompressionFormat, DeviceCapability, DeviceFactoryKey, NotificationSender,
+    Project, ProjectTrackingStatus, QuestionType,
 };
+use crate::notifiers::{email::EmailNotifier, webhook::WebhookNotifier};
+use crate::now;
 
 const HELP: &str = r#"
 HibernaCLI
@@ -16,20 +38,197 @@ Commands:
     
     --version or -v             Display the version of the application
     
+    --log-level [level]         Log level (error, warn, info, debug or trace), also read from HIBERNACLI_LOG
+    
+    --completions [bash|zsh|fish]    Print a completion script for this shell, including the current devices and projects
+    
     device [opt]                Manage devices
         ls or list                     List all devices
+            --verbose                           Also display when each device was last seen available and its capabilities
+            --check-availability                Only display whether each device is currently available
+            --projects                          Also display the projects having backups on each available device
         new MountedFolder              Create a new mounted folder device
+            --help [device_type]                Describe a device type instead of creating a device
+            --idempotent                        Do nothing if a device with the same name already exists
+                                                Answer "start over" to any question to go back to the first one
         rm or remove [device_name]     Remove a device
+        list-orphans [device_name]     List the backup directories of a device not matching any project
+            --prune                             Delete these directories, after confirmation
+            --force                             Do not ask for confirmation
     
     project [opt]               Manage projects
         ls or list                     List all projects
+            --filter [tracked|untracked|ignored]    Only list projects with this tracking status
+            --sort-by [name|location|status|last-backup]    Sort the projects, never backed up and oldest backups first for last-backup
+            --with-backup-status                    Also display how long ago each available device was backed up
+            --json                                  Display the projects and their tracking status as JSON
         new                            Create a new project
+            --idempotent                        Do nothing if a project with the same name already exists
+        add --batch [csv_file]         Create the projects of a CSV file with name,path,tracking_status rows
         rm or remove [project_name]    Remove a project
+            --purge-device-data                     Also delete its backups from the available devices, after confirmation
+            --force                                 Do not ask for confirmation
+        set-location [project_name] [new_path]    Change the location of a moved project
+        rename [project_name] [new_name]    Rename a project and its folder on the devices
+            --force                                 Rename it even if some devices are not available, their folder keeps the previous name
+        clone [source_name] [new_name] [new_path]    Create a project with the same backup class as another one
+        describe [project_name]        Display a project and how well its copies meet its backup class
+        stats [project_name] [device_name]    Display the file sizes and extensions of the backup of a project on a device
 
     backup
-        run [project_name] [device_name]    Backup a project to a device
+        run [project_name] [device_name...]           Backup a project to one or more devices
+            --priority [high|normal|low]              Process scheduling priority during the backup
+            --verify-after                            Check the new archive against the index once the backup is done
+            --max-file-size [size]                    Skip files larger than this size, in bytes or with a K, M or G suffix
+            --stats                                   Display the number of changed files and the time spent in each phase
+            --min-interval [minutes]                  Skip the backup if the last one is more recent than this
+            --no-compress                             Store the archives as plain tar files, without gzip
+            --compression-format [gz|bz2|xz|zstd]     Compress the archives with this algorithm instead of gzip, on mounted folders
+            --keep-partial                            Keep the incomplete archive as a .partial file if the backup fails
+            --checksum-verify-before                  Hash the unchanged files to detect silent corruption, and archive them again
+            --skip-special-files                      Leave FIFOs, sockets and device files out instead of failing the backup
+            --max-archives [n]                        Remove the oldest archives beyond this count, when newer ones supersede them
+            --tag [tag]                               Label the backup, e.g. before-upgrade, can be repeated
+            --notify [email|webhook_url]              Send the outcome of the backup by email (through HIBERNACLI_SMTP_URL) or to a webhook
+            --dry-run                                 Only count the files the backup would add, modify or delete
+        auto [project_name]                           Backup a project to the most secure available device meeting its backup class
+        watch [project_name] [device_name]            Backup a project each time it changes, until Ctrl+C is pressed
+        run-all-under [path] [device_name...]         Backup all the projects located under a directory
+            --exclude-device [device_name]            Skip this device, can be repeated
+            --exclude-unchanged-since [minutes]       Skip the devices backed up within this duration
+        status [project_name]                         Check the copies of a project against its backup class
+        health --summary                              Display how many tracked projects and copies meet their backup class
+        restore [project_name] [device_name] [path]   Restore the latest backup of a project to a directory
+        restore --list-restore-points [project_name] [device_name]    List the backups that can be restored, newest first
+        rebuild-index [project_name] [device_name]    Rebuild the index of a project on a device from its latest archive
+        repair-index [project_name] [device_name]     Drop the index entries not matching the project files anymore, e.g. after a clock reset, so the next backup archives them
+        initialize [project_name] [device_name] [copy_path]    Index a project already copied to copy_path on a device by another tool, without archiving it
+        list-indexed-files [project_name] [device_name]    List the files in the index of a project on a device
+            --sort-by [path|mtime]                    Sort by path (default) or by last modification, newest first
+            --format [text|json]                      Output format, json writes the whole index for other tools
+        import-index [project_name] [device_name] [path] --format json    Set the index of a project on a device from a file made by another tool
+        search [project_name] [device_name] [pattern]    List the indexed files matching a glob pattern, like *.log
+        compare [project_name] [device1] [device2]    Check that the copies of a project on two devices are in sync
+        deduplicate-report [project_name] [device_name]    List the indexed files with the same size and modification time
+
+    config
+        check                          Check that the configuration matches the projects and the devices contents
+        export [path]                  Write the configuration to a file
+        import [path]                  Add the devices and projects of a configuration file, keeping the existing ones
+
+Environment:
+    HIBERNACLI_DEVICE_PATH      Register a mounted folder device at startup if absent, for CI jobs
+    HIBERNACLI_DEVICE_NAME      Name of this device, derived from the path when not set
 "#;
 
+const PROJECT_FILTERS: [&str; 3] = ["tracked", "untracked", "ignored"];
+
+// Parse a size in bytes, with an optional K, M or G binary suffix (e.g. 512, 10K, 4G)
+fn parse_file_size(value: &str) -> Result<u64, String> {
+    let invalid_size = || format!("Invalid size: {}", value);
+    let (number, multiplier) = match value.char_indices().last() {
+        Some((i, 'K' | 'k')) => (&value[..i], 1 << 10),
+        Some((i, 'M' | 'm')) => (&value[..i], 1 << 20),
+        Some((i, 'G' | 'g')) => (&value[..i], 1 << 30),
+        _ => (value, 1),
+    };
+    number
+        .parse::<u64>()
+        .map_err(|_| invalid_size())?
+        .checked_mul(multiplier)
+        .ok_or_else(invalid_size)
+}
+
+// Only the largest unit is kept, e.g. 2 days and 5 hours is "2 days ago"
+fn humanize_duration(duration: Duration) -> String {
+    const UNITS: [(&str, u64); 4] = [
+        ("day", 86400),
+        ("hour", 3600),
+        ("minute", 60),
+        ("second", 1),
+    ];
+
+    let seconds = duration.as_secs();
+    for (unit, unit_seconds) in UNITS {
+        let count = seconds / unit_seconds;
+        if count > 0 {
+            let plural = if count > 1 { "s" } else { "" };
+            return format!("{} {}{} ago", count, unit, plural);
+        }
+    }
+    "just now".to_string()
+}
+
+const PROJECT_SORT_KEYS: [&str; 4] = ["name", "location", "status", "last-backup"];
+
+// Stable sorts, so projects with the same key stay in configuration order
+fn sort_projects(projects: &mut [Project], sort_by: &str) -> Result<(), String> {
+    match sort_by {
+        "name" => projects.sort_by_key(|project| project.get_name().clone()),
+        "location" => projects.sort_by_key(|project| project.get_location().clone()),
+        "status" => projects.sort_by_key(|project| match project.get_tracking_status() {
+            ProjectTrackingStatus::TrackedProject { .. } => 0,
+            ProjectTrackingStatus::UntrackedProject => 1,
+            ProjectTrackingStatus::IgnoredProject => 2,
+        }),
+        "last-backup" => {
+            projects.sort_by_key(|project| project.get_tracking_status().get_last_update())
+        }
+        _ => return Err(format!("Invalid sort key: {}", sort_by)),
+    }
+    Ok(())
+}
+
+fn project_matches_filter(project: &Project, filter: &str) -> bool {
+    matches!(
+        (filter, project.get_tracking_status()),
+        ("tracked", ProjectTrackingStatus::TrackedProject { .. })
+            | ("untracked", ProjectTrackingStatus::UntrackedProject)
+            | ("ignored", ProjectTrackingStatus::IgnoredProject)
+    )
+}
+
+// Webhooks are told apart from email addresses by their scheme
+fn build_notifier(target: &str) -> Result<Box<dyn NotificationSender>, String> {
+    if target.starts_with("http://") || target.starts_with("https://") {
+        return Ok(Box::new(WebhookNotifier::new(target)));
+    }
+    let address = target.strip_prefix("mailto:").unwrap_or(target);
+    match address.split_once('@') {
+        Some((user, domain)) if !user.is_empty() && !domain.is_empty() => {
+            Ok(Box::new(EmailNotifier::from_env(address)))
+        }
+        _ => Err(format!("Invalid notification target: {}", target)),
+    }
+}
+
+fn backup_event(
+    project_name: &str,
+    device_names: &[String],
+    result: &Result<BackupSummary, String>,
+) -> BackupEvent {
+    let project = project_name.to_string();
+    let device = device_names.join(", ");
+    match result {
+        Ok(summary) => BackupEvent::Success {
+            project,
+            device,
+            files_changed: summary.metrics.changed_files_count,
+        },
+        Err(error) => BackupEvent::Failure {
+            project,
+            device,
+            error: error.clone(),
+        },
+    }
+}
+
+// Answer restarting the questions of a device creation from the first one
+const START_OVER: &str = "start over";
+
+// Quiet time after the last change of a watched project before backing it up
+const WATCH_DEBOUNCE: Duration = Duration::from_secs(2);
+
 const INVALID_COMMAND: &str = "Invalid command, use 'help' to display available commands";
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -37,6 +236,11 @@ const VERSION: &str = env!("CARGO_PKG_VERSION");
 pub trait UserInterface {
     fn write(&self, message: &str) -> ();
     fn read(&self) -> Result<String, String>;
+
+    // Whether a failed read is worth asking again, the user may still answer
+    fn can_retry_read(&self) -> bool {
+        true
+    }
 }
 
 pub struct Console;
@@ -54,58 +258,209 @@ impl UserInterface for Console {
     }
 }
 
+/// User interface answering questions from a list of pre-supplied answers,
+/// in order, instead of prompting the user. Messages are still written to
+/// the wrapped output interface.
+pub struct VecUserInterface<'a, T: UserInterface> {
+    output: &'a T,
+    answers: RefCell<VecDeque<String>>,
+    exhausted: Cell<bool>,
+}
+
+impl<'a, T: UserInterface> VecUserInterface<'a, T> {
+    pub fn new(output: &'a T, answers: Vec<String>) -> Self {
+        VecUserInterface {
+            output,
+            answers: RefCell::new(answers.into()),
+            exhausted: Cell::new(false),
+        }
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.exhausted.get()
+    }
+}
+
+impl<T: UserInterface> UserInterface for VecUserInterface<'_, T> {
+    fn write(&self, message: &str) {
+        self.output.write(message);
+    }
+    fn read(&self) -> Result<String, String> {
+        self.answers.borrow_mut().pop_front().ok_or_else(|| {
+            self.exhausted.set(true);
+            "No more answers supplied".to_string()
+        })
+    }
+    fn can_retry_read(&self) -> bool {
+        !self.is_exhausted()
+    }
+}
+
 pub struct CommandRunner<
     'a,
     T: UserInterface,
     U: DeviceOperations,
     V: ProjectOperations,
     W: BackupOperations,
+    X: ConfigOperations,
 > {
     console: T,
     device_operations: &'a U,
     project_operations: &'a V,
     backup_operations: &'a W,
+    config_operations: &'a X,
+    context: Option<RunContext<'a>>,
 }
 
-impl<'a, T: UserInterface, U: DeviceOperations, V: ProjectOperations, W: BackupOperations>
-    CommandRunner<'a, T, U, V, W>
+/// Metadata of the running application, added to the error messages to ease support
+#[derive(Debug, Clone)]
+pub struct RunContext<'a> {
+    pub app_version: &'a str,
+    pub config_path: Option<PathBuf>,
+    pub timestamp: SystemTime,
+}
+
+impl RunContext<'_> {
+    fn describe_error(&self, error: &str) -> String {
+        let config = match &self.config_path {
+            Some(path) => format!(", while reading {}", path.display()),
+            None => String::new(),
+        };
+        format!(
+            "{} (hibernacli {}{}, at {})",
+            error,
+            self.app_version,
+            config,
+            format_utc(self.timestamp)
+        )
+    }
+}
+
+impl<
+        'a,
+        T: UserInterface,
+        U: DeviceOperations,
+        V: ProjectOperations,
+        W: BackupOperations,
+        X: ConfigOperations,
+    > CommandRunner<'a, T, U, V, W, X>
 {
     pub fn new(
         console: T,
         device_operations: &'a U,
         project_operations: &'a V,
         backup_operations: &'a W,
+        config_operations: &'a X,
     ) -> Self {
         CommandRunner {
             console,
             device_operations,
             project_operations,
             backup_operations,
+            config_operations,
+            context: None,
         }
     }
 
+    /// Run a command, adding the application metadata to its error messages
+    pub fn run_with_context(&mut self, args: Vec<String>, context: RunContext<'a>) {
+        self.context = Some(context);
+        self.run(args);
+    }
+
     pub fn run(&self, args: Vec<String>) {
+        self.run_command(args)
+            .unwrap_or_else(|e| self.display_error(&e));
+    }
+
+    fn run_command(&self, args: Vec<String>) -> Result<(), String> {
         if args.len() < 2 {
             self.display_invalid_command();
-            return;
+            return Ok(());
         }
 
         match args[1].as_str() {
-            "help" => self.display_help(),
-            "--version" | "-v" => self.display_version(),
+            "help" => {
+                self.display_help();
+                Ok(())
+            }
+            "--version" | "-v" => {
+                self.display_version();
+                Ok(())
+            }
+            "--completions" if args.len() == 3 => self.display_completions(&args[2]),
             "device" => self.run_device_command(args),
             "project" => self.run_project_command(args),
             "backup" => self.run_backup_command(args),
+            "config" => self.run_config_command(args),
             _ => {
                 self.display_invalid_command();
+                Ok(())
             }
         }
     }
 
+    /// Run a command, answering its questions with the given answers in order
+    /// instead of reading them from the console
+    pub fn run_non_interactive(
+        &self,
+        args: Vec<String>,
+        answers: Vec<String>,
+    ) -> Result<(), String> {
+        let mut command_runner = CommandRunner::new(
+            VecUserInterface::new(&self.console, answers),
+            self.device_operations,
+            self.project_operations,
+            self.backup_operations,
+            self.config_operations,
+        );
+        command_runner.context = self.context.clone();
+        let result = command_runner.run_command(args);
+
+        if command_runner.console.is_exhausted() {
+            return Err("Not enough answers supplied for the command".to_string());
+        }
+        result
+    }
+
     fn display_message(&self, message: &str) {
         self.console.write(message);
     }
 
+    fn display_completions(&self, shell: &str) -> Result<(), String> {
+        let shell = Shell::from_str(shell)?;
+        let names = CompletionNames {
+            device_types: self
+                .device_operations
+                .get_available_device_factories()
+                .into_iter()
+                .map(|factory| factory.key)
+                .collect(),
+            devices: self
+                .device_operations
+                .list()?
+                .iter()
+                .map(|device| device.get_name())
+                .collect(),
+            projects: self
+                .project_operations
+                .list_projects()?
+                .iter()
+                .map(|project| project.get_name().clone())
+                .collect(),
+        };
+
+        self.display_message(&generate_completions(shell, &names));
+        Ok(())
+    }
+
+    fn display_error(&self, error: &str) {
+        match &self.context {
+            Some(context) => self.display_message(&context.describe_error(error)),
+            None => self.display_message(error),
+        }
+    }
+
     fn read_string(&self) -> Result<String, String> {
         self.console.read()
     }
@@ -119,29 +474,73 @@ impl<'a, T: UserInterface, U: DeviceOperations, V: ProjectOperations, W: BackupO
             .map_err(|_| "Invalid number".to_string())
     }
 
-    fn ask_question(&self, question_type: &QuestionType, question_statement: &str) -> String {
-        match question_type {
-            QuestionType::String => self.ask_for_string(question_statement),
-            QuestionType::UnixPath => self.ask_for_unix_path(question_statement),
+    fn ask_question(
+        &self,
+        question_type: &QuestionType,
+        question_statement: &str,
+        default_answer: Option<&str>,
+    ) -> Result<String, String> {
+        let question_statement = match default_answer {
+            Some(default_answer) => format!("{} [{}]", question_statement, default_answer),
+            None => question_statement.to_string(),
+        };
+        let answer = match question_type {
+            QuestionType::String => self.ask_for_string(&question_statement),
+            QuestionType::UnixPath => self.ask_for_unix_path(&question_statement),
+            QuestionType::Select(options) => self.ask_for_selection(&question_statement, options),
             _ => panic!("Unsupported question type"),
+        }?;
+
+        // Pressing Enter without typing anything keeps the default answer
+        match default_answer {
+            Some(default_answer) if answer.is_empty() => Ok(default_answer.to_string()),
+            _ => Ok(answer),
         }
     }
 
-    fn ask_for_string(&self, message: &str) -> String {
+    fn ask_for_string(&self, message: &str) -> Result<String, String> {
         self.display_message(message);
-        self.read_string()
-            .map_err(|_| self.ask_for_string(message))
-            .map(|s| s.trim().to_string())
-            .unwrap()
+        match self.read_string() {
+            Ok(answer) => Ok(answer.trim().to_string()),
+            Err(e) if !self.console.can_retry_read() => Err(e),
+            Err(_) => self.ask_for_string(message),
+        }
     }
 
-    fn ask_for_unix_path(&self, message: &str) -> String {
+    fn ask_for_unix_path(&self, message: &str) -> Result<String, String> {
         self.display_message(message);
         self.display_message("Enter a valid Unix path");
-        self.read_string()
-            .map_err(|_| self.ask_for_unix_path(message))
-            .map(|s| s.trim().to_string())
-            .unwrap()
+        match self.read_string() {
+            Ok(answer) => Ok(answer.trim().to_string()),
+            Err(e) if !self.console.can_retry_read() => Err(e),
+            Err(_) => self.ask_for_unix_path(message),
+        }
+    }
+
+    fn ask_for_selection(&self, message: &str, options: &[String]) -> Result<String, String> {
+        self.display_message(message);
+        for (i, option) in options.iter().enumerate() {
+            self.display_message(&format!("  {}. {}", i + 1, option));
+        }
+
+        let invalid_choice = || {
+            format!(
+                "Invalid choice, enter a number between 1 and {}",
+                options.len()
+            )
+        };
+        let answer = self.read_string()?;
+        if answer.trim() == START_OVER {
+            return Ok(START_OVER.to_string());
+        }
+        let choice = answer
+            .trim()
+            .parse::<usize>()
+            .map_err(|_| invalid_choice())?;
+        if choice == 0 || choice > options.len() {
+            return Err(invalid_choice());
+        }
+        Ok(options[choice - 1].clone())
     }
 
     fn display_help(&self) {
@@ -156,28 +555,139 @@ impl<'a, T: UserInterface, U: DeviceOperations, V: ProjectOperations, W: BackupO
         self.display_message(INVALID_COMMAND);
     }
 
-    fn run_device_command(&self, args: Vec<String>) {
+    fn run_device_command(&self, args: Vec<String>) -> Result<(), String> {
         if args.len() < 3 {
             self.display_invalid_command();
-            return;
+            return Ok(());
         }
 
-        let result = match args[2].as_str() {
-            "ls" | "list" => self.display_device_list(),
+        match args[2].as_str() {
+            "ls" | "list" if args.len() == 3 => self.display_device_list(false, false),
+            "ls" | "list" if args.len() == 4 && args[3] == "--verbose" => {
+                self.display_device_list(true, false)
+            }
+            "ls" | "list" if args.len() == 4 && args[3] == "--projects" => {
+                self.display_device_list(false, true)
+            }
+            "ls" | "list" if args.len() == 4 && args[3] == "--check-availability" => {
+                self.display_device_availability()
+            }
             "new" => self.find_device_factory_create_new_device(args),
             "rm" | "remove" => self.remove_device(args),
+            "list-orphans" => self.list_orphaned_directories(args),
             _ => Ok(self.display_invalid_command()),
-        };
+        }
+    }
+
+    fn list_orphaned_directories(&self, args: Vec<String>) -> Result<(), String> {
+        if args.len() < 4 {
+            return Err(INVALID_COMMAND.to_string());
+        }
+
+        let device_name = args[3].as_str();
+        let mut prune = false;
+        let mut force = false;
+        for flag in &args[4..] {
+            match flag.as_str() {
+                "--prune" => prune = true,
+                "--force" => force = true,
+                _ => return Err(INVALID_COMMAND.to_string()),
+            }
+        }
+
+        if !prune || !force {
+            let directories = self
+                .device_operations
+                .list_orphaned_device_directories(device_name)?;
+            if directories.is_empty() {
+                self.display_message(&format!("No orphaned directories on {}", device_name));
+                return Ok(());
+            }
+            self.display_directories(
+                &format!("Orphaned directories on {}:", device_name),
+                directories,
+            );
+            if !prune {
+                return Ok(());
+            }
 
-        result.unwrap_or_else(|e| self.display_message(&e));
+            let answer = self.ask_for_string(&format!(
+                "Delete these directories and all their backups from {}? [y/N]",
+                device_name
+            ))?;
+            if !matches!(answer.to_lowercase().as_str(), "y" | "yes") {
+                self.display_message("Pruning cancelled");
+                return Ok(());
+            }
+        }
+
+        let directories = self
+            .device_operations
+            .prune_orphaned_device_directories(device_name)?;
+        if directories.is_empty() {
+            self.display_message(&format!("No orphaned directories on {}", device_name));
+            return Ok(());
+        }
+        self.display_directories(
+            &format!("Removed orphaned directories on {}:", device_name),
+            directories,
+        );
+        Ok(())
     }
 
-    fn display_device_list(&self) -> Result<(), String> {
+    fn display_directories(&self, heading: &str, directories: Vec<String>) {
+        self.display_message(heading);
+        for directory in directories {
+            self.display_message(&format!("  - {}", directory));
+        }
+    }
+
+    fn display_device_list(&self, verbose: bool, with_projects: bool) -> Result<(), String> {
         self.display_message("Device list:");
         let devices = self.device_operations.list().map_err(|e| e.to_string())?;
         for device in devices {
             self.display_message(&format!("  - Device: {}", device.get_name()));
             self.display_message(&format!("        Location: {}", device.get_location()));
+            self.display_message(&format!(
+                "        Security level: {}",
+                device.get_security_level()
+            ));
+            if verbose {
+                let last_seen = device
+                    .get_last_seen()
+                    .map_or("never".to_string(), format_utc);
+                self.display_message(&format!("        Last seen: {}", last_seen));
+                let capabilities = DeviceCapability::ALL
+                    .iter()
+                    .filter(|capability| device.supports_capability(**capability))
+                    .map(|capability| capability.to_string())
+                    .collect::<Vec<_>>();
+                let capabilities = match capabilities.is_empty() {
+                    true => "none".to_string(),
+                    false => capabilities.join(", "),
+                };
+                self.display_message(&format!("        Capabilities: {}", capabilities));
+            }
+            if with_projects {
+                let projects = match device.test_availability() {
+                    Ok(()) => match device.list_backed_up_projects()?.join(", ") {
+                        projects if projects.is_empty() => "none".to_string(),
+                        projects => projects,
+                    },
+                    Err(_) => "device not available".to_string(),
+                };
+                self.display_message(&format!("        Projects: {}", projects));
+            }
+        }
+        Ok(())
+    }
+
+    fn display_device_availability(&self) -> Result<(), String> {
+        self.display_message("Device list:");
+        let devices = self.device_operations.list_devices_with_availability()?;
+        for (device, is_available) in devices {
+            let status = if is_available { "✓" } else { "✗" };
+            self.display_message(&format!("  {} {}", status, device.get_name()));
         }
         Ok(())
     }
@@ -187,43 +697,108 @@ impl<'a, T: UserInterface, U: DeviceOperations, V: ProjectOperations, W: BackupO
             self.display_invalid_command();
             return Ok(());
         }
+        if args[3] == "--help" {
+            if args.len() != 5 {
+                self.display_invalid_command();
+                return Ok(());
+            }
+            return self.display_device_factory_help(&args[4]);
+        }
+        let idempotent = match &args[4..] {
+            [] => false,
+            [flag] if flag == "--idempotent" => true,
+            _ => return Err(INVALID_COMMAND.to_string()),
+        };
         let device_key = args[3].as_str();
         self.device_operations
             .get_available_device_factories()
             .iter()
             .find(|&key| key.key == device_key)
-            .map(|key| self.create_new_device(key))
+            .map(|key| self.create_new_device(key, idempotent))
             .unwrap_or_else(|| Err("Device factory not found".to_string()))
     }
 
-    fn create_new_device(&self, key: &DeviceFactoryKey) -> Result<(), String> {
+    fn display_device_factory_help(&self, device_key: &str) -> Result<(), String> {
+        let key = self
+            .device_operations
+            .get_available_device_factories()
+            .into_iter()
+            .find(|key| key.key == device_key)
+            .ok_or("Device factory not found")?;
+        let device_factory = self
+            .device_operations
+            .get_device_factory(key.key.clone())
+            .ok_or("No such device configuration exists")?;
+
+        self.display_message(&format!("{} ({})", key.readable_name, key.key));
+        match device_factory.get_description() {
+            "" => self.display_message("No description available"),
+            description => self.display_message(description),
+        }
+        Ok(())
+    }
+
+    fn create_new_device(&self, key: &DeviceFactoryKey, idempotent: bool) -> Result<(), String> {
         self.display_message("Creating new device of type:");
         let mut device_factory = self
             .device_operations
             .get_device_factory(key.key.clone())
             .ok_or("No such device configuration exists")?;
 
+        let setup_time = device_factory.estimated_setup_time();
+        if !setup_time.is_zero() {
+            self.display_message(&format!(
+                "Setting up this device takes about {} seconds",
+                setup_time.as_secs()
+            ));
+        }
+        let default_answers = device_factory.default_answers();
+        let mut question_index = 0;
         while device_factory.has_next() {
             let question_type = device_factory.get_question_type();
             let question_statement = device_factory.get_question_statement();
-            let answer = self.ask_question(&question_type, &question_statement);
+            let default_answer = default_answers.get(question_index).cloned().flatten();
+            let answer = self.ask_question(
+                &question_type,
+                &question_statement,
+                default_answer.as_deref(),
+            )?;
+            if answer == START_OVER {
+                device_factory.reset();
+                question_index = 0;
+                self.display_message("Starting over");
+                continue;
+            }
             device_factory
                 .set_question_answer(answer)
                 .map_err(|_| "Failed to set answer")?;
+            question_index += 1;
         }
         let device = device_factory
             .build()
             .map_err(|_| "Failed to build device")?;
-        self.device_operations
-            .add_device(device)
-            .map_err(|_| "Failed to add device")?;
+        if idempotent {
+            let result = self
+                .device_operations
+                .add_device_idempotent(device)
+                .map_err(|_| "Failed to add device")?;
+            if result == AddDeviceResult::AlreadyPresent {
+                self.display_message("Device already exists, nothing to do");
+                return Ok(());
+            }
+        } else {
+            self.device_operations
+                .add_device(device)
+                .map_err(|_| "Failed to add device")?;
+        }
         self.display_message("Device created successfully");
         Ok(())
     }
 
     fn remove_device(&self, args: Vec<String>) -> Result<(), String> {
         if args.len() < 4 {
-            return Ok(self.display_invalid_command());
+            self.display_invalid_command();
+            return Ok(());
         }
         let device_name = args[3].as_str();
         self.device_operations
@@ -235,132 +810,930 @@ impl<'a, T: UserInterface, U: DeviceOperations, V: ProjectOperations, W: BackupO
         Ok(())
     }
 
-    fn run_project_command(&self, args: Vec<String>) {
+    fn run_project_command(&self, args: Vec<String>) -> Result<(), String> {
         if args.len() < 3 {
             self.display_invalid_command();
-            return;
+            return Ok(());
         }
 
-        let result = match args[2].as_str() {
-            "ls" | "list" => self.display_project_list(),
-            "new" => self.add_project(),
+        match args[2].as_str() {
+            "ls" | "list" => self.display_project_list(&args[3..]),
+            "new" if args.len() == 3 => self.add_project(false),
+            "new" if args.len() == 4 && args[3] == "--idempotent" => self.add_project(true),
+            "add" if args.len() == 5 && args[3] == "--batch" => {
+                self.add_projects_from_csv(&args[4])
+            }
             "rm" | "remove" => self.remove_project(args),
+            "set-location" if args.len() == 5 => self.update_project_location(&args[3], &args[4]),
+            "rename" if args.len() == 5 => self.rename_project(&args[3], &args[4], false),
+            "rename" if args.len() == 6 && args[5] == "--force" => {
+                self.rename_project(&args[3], &args[4], true)
+            }
+            "clone" if args.len() == 6 => self.clone_project(&args[3], &args[4], &args[5]),
+            "describe" if args.len() == 4 => self.describe_project(&args[3]),
+            "stats" if args.len() == 5 => self.display_index_statistics(&args[3], &args[4]),
             _ => Ok(self.display_invalid_command()),
-        };
-
-        result.unwrap_or_else(|e| self.display_message(&e));
+        }
     }
 
-    fn display_project_list(&self) -> Result<(), String> {
+    fn display_project_list(&self, args: &[String]) -> Result<(), String> {
+        let mut filter = None;
+        let mut sort_by = None;
+        let mut with_backup_status = false;
+        let mut json = false;
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--filter" => filter = Some(args.next().ok_or(INVALID_COMMAND)?.as_str()),
+                "--sort-by" => sort_by = Some(args.next().ok_or(INVALID_COMMAND)?.as_str()),
+                "--with-backup-status" => with_backup_status = true,
+                "--json" => json = true,
+                _ => return Err(INVALID_COMMAND.to_string()),
+            }
+        }
+        if json && with_backup_status {
+            return Err("--with-backup-status cannot be combined with --json".to_string());
+        }
+        if let Some(filter) = filter {
+            if !PROJECT_FILTERS.contains(&filter) {
+                return Err(format!(
+                    "Invalid filter: {}. Possible filters are: {}",
+                    filter,
+                    PROJECT_FILTERS.join(", ")
+                ));
+            }
+        }
+        if let Some(sort_by) = sort_by {
+            if !PROJECT_SORT_KEYS.contains(&sort_by) {
+                return Err(format!(
+                    "Invalid sort key: {}. Possible keys are: {}",
+                    sort_by,
+                    PROJECT_SORT_KEYS.join(", ")
+                ));
+            }
+        }
+
+        let mut projects = self.project_operations.list_projects()?;
+        if let Some(sort_by) = sort_by {
+            sort_projects(&mut projects, sort_by)?;
+        }
+        let projects = projects
+            .iter()
+            .filter(|project| filter.is_none_or(|f| project_matches_filter(project, f)))
+            .collect::<Vec<_>>();
+        if json {
+            let json = serde_json::to_string_pretty(&projects)
+                .map_err(|e| format!("Failed to serialize the projects: {}", e))?;
+            self.display_message(&json);
+            return Ok(());
+        }
+
         self.display_message("Project list:");
-        let projects = self.project_operations.list_projects()?;
         for project in projects {
             self.display_message(&format!("  - Project: {}", project.get_name()));
             self.display_message(&format!("        Location: {}", project.get_location()));
+            if with_backup_status {
+                self.display_project_backup_status(project.get_name())?;
+            }
         }
         Ok(())
     }
 
-    fn add_project(&self) -> Result<(), String> {
-        let project_name = self.ask_for_string("What is the name of the project?");
-        let project_path = self.ask_for_unix_path("What is the path to the project?");
-        self.project_operations
-            .add_project(AddProjectArgs {
-                name: project_name,
-                location: project_path,
-            })
-            .map_err(|e| e.to_string())?;
+    fn display_project_backup_status(&self, project_name: &str) -> Result<(), String> {
+        let status = self
+            .project_operations
+            .get_project_backup_status(project_name.to_string())?;
+        if status.is_empty() {
+            self.display_message("        Last backup: never");
+        }
+        for (device_name, last_backup) in status {
+            let elapsed = now!().duration_since(last_backup).unwrap_or_default();
+            self.display_message(&format!(
+                "        Last backup on {}: {}",
+                device_name,
+                humanize_duration(elapsed)
+            ));
+        }
+        Ok(())
+    }
+
+    fn describe_project(&self, project_name: &str) -> Result<(), String> {
+        let projects = self.project_operations.list_projects()?;
+        let project = projects
+            .iter()
+            .find(|project| project.get_name() == project_name)
+            .ok_or_else(|| format!("Project not found: {}", project_name))?;
+
+        self.display_message(&format!("Project: {}", project.get_name()));
+        self.display_message(&format!("    Location: {}", project.get_location()));
+        let Some(requirement) = project.get_tracking_status().get_backup_requirement_class() else {
+            self.display_message("    Not tracked");
+            return Ok(());
+        };
+        self.display_message(&format!(
+            "    Backup class: {} ({} copies in {} locations, security level {} or above)",
+            requirement.get_name(),
+            requirement.get_target_copies(),
+            requirement.get_target_locations(),
+            requirement.get_min_security_level()
+        ));
+
+        let compliance = self
+            .project_operations
+            .get_project_compliance(project_name.to_string())?;
+        self.display_compliance(&compliance);
+        Ok(())
+    }
+
+    fn display_compliance(&self, compliance: &RequirementComplianceResult) {
+        self.display_message(&format!(
+            "    Compliant: {}",
+            if compliance.is_compliant { "yes" } else { "no" }
+        ));
+        self.display_message(&format!(
+            "    Copies: {} ({} missing)",
+            compliance.actual_copies, compliance.missing_copies
+        ));
+        self.display_message(&format!("    Locations: {}", compliance.unique_locations));
+        self.display_message(&format!(
+            "    Lowest security level: {}",
+            compliance
+                .lowest_security_level
+                .as_ref()
+                .map_or("none".to_string(), |level| level.to_string())
+        ));
+    }
+
+    fn add_project(&self, idempotent: bool) -> Result<(), String> {
+        let project_name = self.ask_for_string("What is the name of the project?")?;
+        let project_path = self.ask_for_unix_path("What is the path to the project?")?;
+        let args = AddProjectArgs {
+            name: project_name,
+            location: project_path,
+        };
+        if idempotent {
+            if !self.project_operations.add_project_idempotent(args)? {
+                self.display_message("Project already exists, nothing to do");
+                return Ok(());
+            }
+        } else {
+            self.project_operations
+                .add_project(args)
+                .map_err(|e| e.to_string())?;
+        }
         self.display_message("Project created successfully");
         Ok(())
     }
 
+    fn add_projects_from_csv(&self, csv_path: &str) -> Result<(), String> {
+        let csv_content = std::fs::read_to_string(csv_path)
+            .map_err(|e| format!("Cannot read {}: {}", csv_path, e))?;
+        let report = self
+            .project_operations
+            .add_projects_from_csv(&csv_content)?;
+        for (line, name, result) in &report.rows {
+            match result {
+                Ok(()) => self.display_message(&format!("  - line {} ({}): added", line, name)),
+                Err(e) => self.display_message(&format!("  - line {} ({}): {}", line, name, e)),
+            }
+        }
+        self.display_message(&format!(
+            "{} of {} projects added",
+            report.added_count(),
+            report.rows.len()
+        ));
+        Ok(())
+    }
+
     fn remove_project(&self, args: Vec<String>) -> Result<(), String> {
         if args.len() < 4 {
             return Err(INVALID_COMMAND.to_string());
         }
 
         let project_name = args[3].as_str();
+        let mut purge_device_data = false;
+        let mut force = false;
+        for flag in &args[4..] {
+            match flag.as_str() {
+                "--purge-device-data" => purge_device_data = true,
+                "--force" => force = true,
+                _ => return Err(INVALID_COMMAND.to_string()),
+            }
+        }
+        if purge_device_data && !force {
+            let answer = self.ask_for_string(&format!(
+                "Delete all the backups of {} from the available devices? [y/N]",
+                project_name
+            ))?;
+            if !matches!(answer.to_lowercase().as_str(), "y" | "yes") {
+                self.display_message("Project removal cancelled");
+                return Ok(());
+            }
+        }
+
         self.project_operations
-            .remove_project_by_name(project_name.to_string())
+            .remove_project_by_name(project_name.to_string(), purge_device_data)
             .map_err(|e| e.to_string())?;
 
         self.display_message("Removed project successfully");
         Ok(())
     }
 
-    fn run_backup_command(&self, _args: Vec<String>) {
-        if _args.len() < 5 {
-            self.display_invalid_command();
-            return;
-        }
-
-        let result = match _args[2].as_str() {
-            "run" => self.run_backup(_args[3].as_str(), _args[4].as_str()),
-            "restore" if _args.len() == 6 => {
-                self.restore_backup(_args[3].as_str(), _args[4].as_str(), _args[5].as_str())
-            }
-            _ => Ok(self.display_invalid_command()),
-        };
-
-        result.unwrap_or_else(|e| self.display_message(&e));
-    }
+    fn update_project_location(
+        &self,
+        project_name: &str,
+        new_location: &str,
+    ) -> Result<(), String> {
+        self.project_operations
+            .update_project_location(project_name.to_string(), new_location.to_string())?;
 
-    fn run_backup(&self, project_name: &str, device_name: &str) -> Result<(), String> {
-        self.backup_operations
-            .backup_project_to_device(project_name, device_name)?;
+        self.display_message("Project location updated successfully");
         Ok(())
     }
 
-    fn restore_backup(
+    fn rename_project(
         &self,
         project_name: &str,
-        device_name: &str,
-        restore_to: &str,
+        new_name: &str,
+        force: bool,
     ) -> Result<(), String> {
-        self.backup_operations.restore_project_from_device(
-            project_name,
-            device_name,
-            restore_to,
+        self.project_operations.rename_project(
+            project_name.to_string(),
+            new_name.to_string(),
+            force,
         )?;
+
+        self.display_message("Project renamed successfully");
         Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::core::{
-        operations::{MockBackupOperations, MockDeviceOperations, MockProjectOperations},
-        MockDevice, MockDeviceFactory,
-    };
-    use mockall::predicate::eq;
+    fn clone_project(
+        &self,
+        source_name: &str,
+        new_name: &str,
+        new_location: &str,
+    ) -> Result<(), String> {
+        self.project_operations.clone_project(
+            source_name.to_string(),
+            new_name.to_string(),
+            new_location.to_string(),
+        )?;
 
-    // Extends assertions of automock to easily test read/write to console
-    impl MockUserInterface {
-        fn expect_one_read(mut self, read_value: &str) -> Self {
-            let r = read_value.to_string();
-            self.expect_read().times(1).returning(move || Ok(r.clone()));
-            self
-        }
+        self.display_message("Project cloned successfully");
+        Ok(())
+    }
 
-        fn expect_one_write(mut self, written_value: &str) -> Self {
-            self.expect_write()
-                .times(1)
-                .with(eq(written_value.to_string()))
-                .return_const(());
-            self
+    fn run_backup_command(&self, _args: Vec<String>) -> Result<(), String> {
+        if _args.len() < 4 {
+            self.display_invalid_command();
+            return Ok(());
         }
-    }
+
+        match _args[2].as_str() {
+            "run" if _args.len() >= 5 => self.run_backup(_args[3].as_str(), &_args[4..]),
+            "auto" if _args.len() == 4 => self.run_backup_auto(_args[3].as_str()),
+            "watch" if _args.len() == 5 => {
+                self.run_backup_watch(_args[3].as_str(), _args[4].as_str())
+            }
+            "run-all-under" if _args.len() >= 5 => {
+                self.run_backup_all_under(_args[3].as_str(), &_args[4..])
+            }
+            "status" if _args.len() == 4 => self.display_backup_status(_args[3].as_str()),
+            "health" if _args.len() == 4 && _args[3] == "--summary" => {
+                self.display_requirement_summary()
+            }
+            "restore" if _args.len() == 6 && _args[3] == "--list-restore-points" => {
+                self.list_restore_points(_args[4].as_str(), _args[5].as_str())
+            }
+            "restore" if _args.len() == 6 => {
+                self.restore_backup(_args[3].as_str(), _args[4].as_str(), _args[5].as_str())
+            }
+            "rebuild-index" if _args.len() == 5 => {
+                self.rebuild_index(_args[3].as_str(), _args[4].as_str())
+            }
+            "repair-index" if _args.len() == 5 => {
+                self.repair_index(_args[3].as_str(), _args[4].as_str())
+            }
+            "initialize" if _args.len() == 6 => {
+                self.initialize_index(_args[3].as_str(), _args[4].as_str(), _args[5].as_str())
+            }
+            "list-indexed-files" if _args.len() >= 5 => {
+                self.list_indexed_files(_args[3].as_str(), _args[4].as_str(), &_args[5..])
+            }
+            "import-index" => self.import_index(&_args[3..]),
+            "search" if _args.len() == 6 => {
+                self.search_indexed_files(_args[3].as_str(), _args[4].as_str(), _args[5].as_str())
+            }
+            "deduplicate-report" if _args.len() == 5 => {
+                self.display_duplicate_files(_args[3].as_str(), _args[4].as_str())
+            }
+            "compare" if _args.len() == 6 => {
+                self.compare_copies(_args[3].as_str(), _args[4].as_str(), _args[5].as_str())
+            }
+            _ => Ok(self.display_invalid_command()),
+        }
+    }
+
+    fn display_requirement_summary(&self) -> Result<(), String> {
+        let summary = self.config_operations.get_requirement_summary()?;
+        self.display_message(&format!(
+            "{}/{} projects compliant, {}/{} required copies present",
+            summary.compliant_projects,
+            summary.total_projects,
+            summary.present_copies,
+            summary.required_copies
+        ));
+        Ok(())
+    }
+
+    fn run_config_command(&self, args: Vec<String>) -> Result<(), String> {
+        match &args[2..] {
+            [command] if command == "check" => self.check_config_integrity(),
+            [command, path] if command == "export" => self.export_config(path),
+            [command, path] if command == "import" => self.import_config(path),
+            _ => Ok(self.display_invalid_command()),
+        }
+    }
+
+    fn export_config(&self, output_path: &str) -> Result<(), String> {
+        self.config_operations.export_config(output_path)?;
+        self.display_message(&format!("Configuration exported to {}", output_path));
+        Ok(())
+    }
+
+    fn import_config(&self, input_path: &str) -> Result<(), String> {
+        self.config_operations.import_config(input_path)?;
+        self.display_message(&format!("Configuration imported from {}", input_path));
+        Ok(())
+    }
+
+    fn check_config_integrity(&self) -> Result<(), String> {
+        let report = self.config_operations.integrity_check()?;
+        self.display_integrity_report(&report);
+        Ok(())
+    }
+
+    fn display_integrity_report(&self, report: &IntegrityReport) {
+        for device_name in &report.unavailable_devices {
+            self.display_message(&format!(
+                "Device {} is not available, it was not checked",
+                device_name
+            ));
+        }
+        if report.is_ok() {
+            self.display_message("Configuration is consistent");
+            return;
+        }
+
+        self.display_message("Configuration inconsistencies:");
+        for project_name in &report.missing_project_paths {
+            self.display_message(&format!(
+                "  - Location of project {} does not exist",
+                project_name
+            ));
+        }
+        for mismatch in &report.index_mismatches {
+            self.display_message(&format!(
+                "  - Project {} on {}: {}",
+                mismatch.project_name, mismatch.device_name, mismatch.reason
+            ));
+        }
+        for (device_name, directory) in &report.orphaned_project_directories {
+            self.display_message(&format!(
+                "  - Directory {} on {} belongs to no registered project",
+                directory, device_name
+            ));
+        }
+    }
+
+    fn display_backup_status(&self, project_name: &str) -> Result<(), String> {
+        let compliance = self
+            .project_operations
+            .get_project_compliance(project_name.to_string())?;
+        self.display_message(&format!("Backup status of {}:", project_name));
+        self.display_compliance(&compliance);
+        Ok(())
+    }
+
+    fn run_backup(&self, project_name: &str, args: &[String]) -> Result<(), String> {
+        let mut device_names = Vec::new();
+        let mut options = BackupOptions::default();
+        let mut display_stats = false;
+        let mut dry_run = false;
+        let mut notifier = None;
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--priority" => {
+                    let value = args.next().ok_or("Missing value for --priority")?;
+                    options.priority = BackupPriority::from_str(value)?;
+                }
+                "--verify-after" => options.verify_after = true,
+                "--stats" => display_stats = true,
+                "--dry-run" => dry_run = true,
+                "--no-compress" => options.compress = false,
+                "--compression-format" => {
+                    let value = args
+                        .next()
+                        .ok_or("Missing value for --compression-format")?;
+                    options.compression_format = CompressionFormat::from_str(value)?;
+                }
+                "--keep-partial" => options.keep_partial = true,
+                "--checksum-verify-before" => options.checksum_verify_before = true,
+                "--skip-special-files" => options.skip_special_files = true,
+                "--tag" => {
+                    let value = args.next().ok_or("Missing value for --tag")?;
+                    if value.trim().is_empty() || value.contains('\n') {
+                        return Err(format!("Invalid tag: {:?}", value));
+                    }
+                    options.tags.push(value.clone());
+                }
+                "--notify" => {
+                    let value = args.next().ok_or("Missing value for --notify")?;
+                    notifier = Some(build_notifier(value)?);
+                }
+                "--max-archives" => {
+                    let value = args.next().ok_or("Missing value for --max-archives")?;
+                    let count = value
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|count| *count > 0)
+                        .ok_or(format!("Invalid number of archives: {}", value))?;
+                    options.max_archives = Some(count);
+                }
+                "--max-file-size" => {
+                    let value = args.next().ok_or("Missing value for --max-file-size")?;
+                    options.max_file_size = Some(parse_file_size(value)?);
+                }
+                "--min-interval" => {
+                    let value = args.next().ok_or("Missing value for --min-interval")?;
+                    let invalid_interval = || format!("Invalid interval: {}", value);
+                    let minutes = value.parse::<u64>().map_err(|_| invalid_interval())?;
+                    let seconds = minutes.checked_mul(60).ok_or_else(invalid_interval)?;
+                    options.min_interval = Some(Duration::from_secs(seconds));
+                }
+                _ => device_names.push(arg.clone()),
+            }
+        }
+
+        if dry_run {
+            let result =
+                self.backup_operations
+                    .dry_run_backup(project_name, &device_names, &options)?;
+            self.display_dry_run_result(&result);
+            return Ok(());
+        }
+
+        let result =
+            self.backup_operations
+                .backup_project_to_device(project_name, &device_names, &options);
+        if let Some(notifier) = notifier {
+            let event = backup_event(project_name, &device_names, &result);
+            if let Err(e) = notifier.send(event) {
+                self.display_message(&e);
+            }
+        }
+        let summary = result?;
+
+        if summary.skipped_too_recent {
+            self.display_message("Backup skipped: too recent");
+            return Ok(());
+        }
+
+        if !summary.skipped_large_files.is_empty() {
+            self.display_message("Files skipped because they are above the maximum size:");
+            for (path, size) in &summary.skipped_large_files {
+                self.display_message(&format!("  - {} ({} bytes)", path.display(), size));
+            }
+        }
+        if !summary.skipped_special_files.is_empty() {
+            self.display_message("Special files skipped:");
+            for path in &summary.skipped_special_files {
+                self.display_message(&format!("  - {}", path.display()));
+            }
+        }
+        for warning in &summary.warnings {
+            self.display_message(&warning.to_string());
+        }
+        if summary.pruned_archives > 0 {
+            self.display_message(&format!("Removed {} old archives", summary.pruned_archives));
+        }
+        if display_stats {
+            self.display_backup_metrics(&summary.metrics);
+        }
+        Ok(())
+    }
+
+    fn run_backup_auto(&self, project_name: &str) -> Result<(), String> {
+        let device_name = self.backup_operations.backup_project_auto(project_name)?;
+        self.display_message(&format!(
+            "Backup of {} done on {}",
+            project_name, device_name
+        ));
+        Ok(())
+    }
+
+    fn run_backup_watch(&self, project_name: &str, device_name: &str) -> Result<(), String> {
+        let stop_signal = Arc::new(AtomicBool::new(false));
+        let handler_stop_signal = stop_signal.clone();
+        ctrlc::set_handler(move || handler_stop_signal.store(true, Ordering::Relaxed))
+            .map_err(|e| format!("Failed to handle Ctrl+C: {}", e))?;
+
+        self.display_message(&format!(
+            "Watching {} for changes, press Ctrl+C to stop",
+            project_name
+        ));
+        self.backup_operations.watch_project_on_device(
+            project_name,
+            device_name,
+            WATCH_DEBOUNCE,
+            stop_signal,
+        )?;
+        self.display_message(&format!("Stopped watching {}", project_name));
+        Ok(())
+    }
+
+    fn run_backup_all_under(&self, location: &str, args: &[String]) -> Result<(), String> {
+        let mut device_names = Vec::new();
+        let mut exclude_devices = Vec::new();
+        let mut skip_if_backed_up_within = None;
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--exclude-device" => {
+                    let value = args.next().ok_or("Missing value for --exclude-device")?;
+                    exclude_devices.push(value.clone());
+                }
+                "--exclude-unchanged-since" => {
+                    let value = args
+                        .next()
+                        .ok_or("Missing value for --exclude-unchanged-since")?;
+                    let invalid_interval = || format!("Invalid interval: {}", value);
+                    let minutes = value.parse::<u64>().map_err(|_| invalid_interval())?;
+                    let seconds = minutes.checked_mul(60).ok_or_else(invalid_interval)?;
+                    skip_if_backed_up_within = Some(Duration::from_secs(seconds));
+                }
+                _ => device_names.push(arg.clone()),
+            }
+        }
+
+        let results = self.backup_operations.backup_projects_under(
+            location,
+            &device_names,
+            &exclude_devices,
+            skip_if_backed_up_within,
+        )?;
+
+        for (project_name, result) in &results {
+            match result {
+                Ok(summary) if summary.skipped_too_recent => self.display_message(&format!(
+                    "  - {}: skipped, backed up recently",
+                    project_name
+                )),
+                Ok(_) => self.display_message(&format!("  - {}: done", project_name)),
+                Err(e) => self.display_message(&format!("  - {}: {}", project_name, e)),
+            }
+        }
+        let failures = results.iter().filter(|(_, result)| result.is_err()).count();
+        if failures > 0 {
+            return Err(format!("{} of {} backups failed", failures, results.len()));
+        }
+        Ok(())
+    }
+
+    fn display_dry_run_result(&self, result: &DryRunResult) {
+        self.display_message("Dry run, nothing was written:");
+        self.display_message(&format!("  - Files to add: {}", result.files_to_add));
+        self.display_message(&format!("  - Files to modify: {}", result.files_to_modify));
+        self.display_message(&format!("  - Files to delete: {}", result.files_to_delete));
+        self.display_message(&format!(
+            "  - Bytes to transfer: {}",
+            result.bytes_to_transfer
+        ));
+    }
+
+    fn display_backup_metrics(&self, metrics: &BackupExecutionMetrics) {
+        self.display_message("Backup statistics:");
+        self.display_message(&format!(
+            "  - Changed files: {} out of {}",
+            metrics.changed_files_count, metrics.total_files_count
+        ));
+        self.display_message(&format!("  - Walk: {:?}", metrics.walk_duration));
+        self.display_message(&format!(
+            "  - Archive write: {:?}",
+            metrics.archive_write_duration
+        ));
+        self.display_message(&format!("  - Bytes written: {}", metrics.bytes_written));
+        self.display_message(&format!("  - Finalize: {:?}", metrics.finalize_duration));
+    }
+
+    fn list_indexed_files(
+        &self,
+        project_name: &str,
+        device_name: &str,
+        args: &[String],
+    ) -> Result<(), String> {
+        let mut order = IndexedFilesOrder::default();
+        let mut format = "text";
+        for pair in args.chunks(2) {
+            match pair {
+                [flag, value] if flag == "--sort-by" => order = IndexedFilesOrder::from_str(value)?,
+                [flag, value] if flag == "--format" => format = value.as_str(),
+                _ => return Err(INVALID_COMMAND.to_string()),
+            }
+        }
+
+        match format {
+            "text" => {}
+            "json" => {
+                let json = self
+                    .backup_operations
+                    .export_index_to_json(project_name, device_name)?;
+                self.display_message(&json);
+                return Ok(());
+            }
+            _ => {
+                return Err(format!(
+                    "Invalid format: {}. Possible formats are: text, json",
+                    format
+                ))
+            }
+        }
+        let files = self
+            .backup_operations
+            .list_indexed_files(project_name, device_name, order)?;
+        self.display_indexed_files(&files);
+        Ok(())
+    }
+
+    fn search_indexed_files(
+        &self,
+        project_name: &str,
+        device_name: &str,
+        pattern: &str,
+    ) -> Result<(), String> {
+        let files =
+            self.backup_operations
+                .search_indexed_files(project_name, device_name, pattern)?;
+        if files.is_empty() {
+            self.display_message("No matching file");
+            return Ok(());
+        }
+        self.display_indexed_files(&files);
+        Ok(())
+    }
+
+    fn display_index_statistics(
+        &self,
+        project_name: &str,
+        device_name: &str,
+    ) -> Result<(), String> {
+        let statistics = self
+            .backup_operations
+            .get_index_statistics(project_name, device_name)?;
+        self.display_message(&format!("Files: {}", statistics.file_count));
+        self.display_message(&format!("Total size: {} bytes", statistics.total_bytes));
+        self.display_message(&format!(
+            "File size: min {}, max {}, mean {:.1} bytes",
+            statistics.min_file_size, statistics.max_file_size, statistics.mean_file_size
+        ));
+
+        // Most common extensions first
+        let mut extension_counts = statistics.extension_counts.into_iter().collect::<Vec<_>>();
+        extension_counts.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+        self.display_message("Files by extension:");
+        for (extension, count) in extension_counts {
+            let extension = match extension.is_empty() {
+                true => "(none)".to_string(),
+                false => format!(".{}", extension),
+            };
+            self.display_message(&format!("  - {}: {}", extension, count));
+        }
+        Ok(())
+    }
+
+    fn display_duplicate_files(&self, project_name: &str, device_name: &str) -> Result<(), String> {
+        let groups = self
+            .backup_operations
+            .find_duplicate_files(project_name, device_name)?;
+        if groups.is_empty() {
+            self.display_message("No duplicate file");
+            return Ok(());
+        }
+        self.display_message("Files with the same size and modification time:");
+        for paths in groups {
+            let paths = paths
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>();
+            self.display_message(&format!("  - {}", paths.join(", ")));
+        }
+        Ok(())
+    }
+
+    fn display_indexed_files(&self, files: &[IndexedFile]) {
+        for file in files {
+            self.display_message(&format!(
+                "  - {} ({} bytes, modified {})",
+                file.path.display(),
+                file.size,
+                format_utc(file.mtime)
+            ));
+        }
+    }
+
+    fn rebuild_index(&self, project_name: &str, device_name: &str) -> Result<(), String> {
+        self.backup_operations
+            .rebuild_index(project_name, device_name)?;
+        self.display_message("Backup index rebuilt successfully");
+        Ok(())
+    }
+
+    fn repair_index(&self, project_name: &str, device_name: &str) -> Result<(), String> {
+        let report = self
+            .backup_operations
+            .repair_index(project_name, device_name)?;
+        self.display_message(&format!(
+            "{} index entries dropped, the next backup will archive their files",
+            report.dropped_entries.len()
+        ));
+        for path in report.dropped_entries {
+            self.display_message(&format!("  - {}", path.display()));
+        }
+        Ok(())
+    }
+
+    fn initialize_index(
+        &self,
+        project_name: &str,
+        device_name: &str,
+        copy_path: &str,
+    ) -> Result<(), String> {
+        self.backup_operations
+            .initialize_index(project_name, device_name, copy_path)?;
+        self.display_message(&format!(
+            "Project {} indexed on device {}, the next backup will only archive the changes",
+            project_name, device_name
+        ));
+        Ok(())
+    }
+
+    fn import_index(&self, args: &[String]) -> Result<(), String> {
+        let [project_name, device_name, input_path, flag, format] = args else {
+            return Err(INVALID_COMMAND.to_string());
+        };
+        if flag != "--format" {
+            return Err(INVALID_COMMAND.to_string());
+        }
+        if format != "json" {
+            return Err(format!(
+                "Unsupported index format: {}, only json can be imported",
+                format
+            ));
+        }
+
+        self.backup_operations
+            .import_index_from_json(project_name, device_name, input_path)?;
+        self.display_message(&format!(
+            "Index of project {} imported on device {}, the next backup will only archive the changes",
+            project_name, device_name
+        ));
+        Ok(())
+    }
+
+    fn compare_copies(
+        &self,
+        project_name: &str,
+        device1: &str,
+        device2: &str,
+    ) -> Result<(), String> {
+        let comparison =
+            self.backup_operations
+                .compare_device_copies(project_name, device1, device2)?;
+
+        if comparison.is_identical() {
+            self.display_message(&format!(
+                "Copies on {} and {} are identical",
+                device1, device2
+            ));
+            return Ok(());
+        }
+
+        self.display_message(&format!("Copies on {} and {} differ:", device1, device2));
+        self.display_copy_differences(&comparison, device1, device2);
+        Ok(())
+    }
+
+    fn display_copy_differences(
+        &self,
+        comparison: &CopyComparisonResult,
+        device1: &str,
+        device2: &str,
+    ) {
+        for path in &comparison.only_on_first_device {
+            self.display_message(&format!("  - Only on {}: {}", device1, path.display()));
+        }
+        for path in &comparison.only_on_second_device {
+            self.display_message(&format!("  - Only on {}: {}", device2, path.display()));
+        }
+        for path in &comparison.different {
+            self.display_message(&format!("  - Different: {}", path.display()));
+        }
+    }
+
+    fn list_restore_points(&self, project_name: &str, device_name: &str) -> Result<(), String> {
+        let restore_points = self
+            .backup_operations
+            .list_restore_points(project_name, device_name)?;
+        if restore_points.is_empty() {
+            self.display_message("No restore point");
+            return Ok(());
+        }
+        self.display_message("Restore points:");
+        for (i, restore_point) in restore_points.iter().enumerate() {
+            self.display_message(&format!(
+                "  {}. {} ({}{})",
+                i + 1,
+                format_utc(restore_point.timestamp),
+                restore_point.archive_name,
+                if restore_point.contains_deletions {
+                    ", with deletions"
+                } else {
+                    ""
+                }
+            ));
+        }
+        Ok(())
+    }
+
+    fn restore_backup(
+        &self,
+        project_name: &str,
+        device_name: &str,
+        restore_to: &str,
+    ) -> Result<(), String> {
+        self.backup_operations.restore_project_from_device(
+            project_name,
+            device_name,
+            restore_to,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        operations::{
+            BackupExecutionWarning, BackupSummary, BatchAddReport, IndexMismatch, IndexStatistics,
+            IndexedFile, MockBackupOperations, MockConfigOperations, MockDeviceOperations,
+            MockProjectOperations, RepairReport, RequirementSummary, RestorePoint,
+        },
+        test_utils::fs::create_tmp_dir,
+        MockDevice, MockDeviceFactory, SecurityLevel,
+    };
+    use mockall::{predicate::eq, Sequence};
+    use std::{collections::HashMap, path::PathBuf, time::UNIX_EPOCH};
+
+    // Extends assertions of automock to easily test read/write to console
+    impl MockUserInterface {
+        fn expect_one_read(mut self, read_value: &str) -> Self {
+            let r = read_value.to_string();
+            self.expect_read().times(1).returning(move || Ok(r.clone()));
+            self
+        }
+
+        fn expect_one_write(mut self, written_value: &str) -> Self {
+            self.expect_write()
+                .times(1)
+                .with(eq(written_value.to_string()))
+                .return_const(());
+            self
+        }
+    }
 
     // Helpers to create a command runner with injected mocks
 
     macro_rules! run_command {
         ($console:ident, $device_operations:ident, $project_operations:ident, $backup_operations: ident, $args: expr) => {{
+            let config_operations = MockConfigOperations::new();
+            run_command!(
+                $console,
+                $device_operations,
+                $project_operations,
+                $backup_operations,
+                config_operations,
+                $args
+            )
+        }};
+        ($console:ident, $device_operations:ident, $project_operations:ident, $backup_operations: ident, $config_operations: ident, $args: expr) => {{
             let command_runner = CommandRunner::new(
                 $console,
                 &$device_operations,
                 &$project_operations,
                 &$backup_operations,
+                &$config_operations,
             );
             let args_with_executable = format!("/path/to/executable {}", $args);
             let split_args: Vec<String> = args_with_executable
@@ -378,6 +1751,7 @@ mod tests {
                 &MockDeviceOperations::new(),
                 &MockProjectOperations::new(),
                 &MockBackupOperations::new(),
+                &MockConfigOperations::new(),
             )
         };
     }
@@ -447,6 +1821,50 @@ mod tests {
         ]);
     }
 
+    #[test]
+    fn completions_shall_include_the_device_types_devices_and_projects() {
+        let backup_operations = MockBackupOperations::new();
+        let mut device_operations = MockDeviceOperations::new();
+        device_operations
+            .expect_get_available_device_factories()
+            .returning(|| {
+                vec![DeviceFactoryKey {
+                    key: "MountedFolder".to_string(),
+                    readable_name: "Mounted folder".to_string(),
+                }]
+            });
+        device_operations.expect_list().returning(|| {
+            let mut device = MockDevice::new();
+            device.expect_get_name().return_const("USBkey".to_string());
+            Ok(vec![Box::new(device)])
+        });
+        let mut project_operations = MockProjectOperations::new();
+        project_operations.expect_list_projects().returning(|| {
+            Ok(vec![Project::new(
+                "MyProject".to_string(),
+                "/home/user/my-project".to_string(),
+                None,
+            )])
+        });
+        let expected_script = generate_completions(
+            Shell::Bash,
+            &CompletionNames {
+                device_types: vec!["MountedFolder".to_string()],
+                devices: vec!["USBkey".to_string()],
+                projects: vec!["MyProject".to_string()],
+            },
+        );
+        let console = MockUserInterface::new().expect_one_write(&expected_script);
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "--completions bash"
+        );
+    }
+
     #[test]
     fn display_version_with_short_version_command() {
         let console = MockUserInterface::new().expect_one_write(VERSION);
@@ -470,12 +1888,17 @@ mod tests {
                 .expect_get_location()
                 .times(1)
                 .returning(move || "/".to_string());
+            device
+                .expect_get_security_level()
+                .times(1)
+                .returning(move || SecurityLevel::Local);
             Ok(vec![Box::new(device)])
         });
 
         let console = MockUserInterface::new()
             .expect_one_write("  - Device: USBkey")
             .expect_one_write("        Location: /")
+            .expect_one_write("        Security level: Local")
             .expect_one_write("Device list:");
 
         run_command!(
@@ -525,6 +1948,12 @@ mod tests {
             .with(eq("mounted_folder".to_string()))
             .returning(|_| {
                 let mut device_factory = MockDeviceFactory::new();
+                device_factory
+                    .expect_estimated_setup_time()
+                    .return_const(Duration::from_secs(0));
+                device_factory
+                    .expect_default_answers()
+                    .return_const(Vec::new());
                 device_factory.expect_has_next().times(1).returning(|| true);
                 device_factory
                     .expect_has_next()
@@ -564,15 +1993,14 @@ mod tests {
     }
 
     #[test]
-    fn creating_a_new_usb_key_with_a_unix_path_question() {
-        let question = "What is the path to the device?";
+    fn pressing_enter_on_a_question_with_a_default_answer_shall_use_it() {
         let project_operations = MockProjectOperations::new();
         let backup_operations = MockBackupOperations::new();
 
         let console = MockUserInterface::new()
-            .expect_one_write(question)
+            .expect_one_write("What is the path of the folder? [/Volumes/]")
             .expect_one_write("Enter a valid Unix path")
-            .expect_one_read("/mnt/usbkey")
+            .expect_one_read("")
             .expect_one_write("Creating new device of type:")
             .expect_one_write("Device created successfully");
 
@@ -592,6 +2020,12 @@ mod tests {
             .with(eq("mounted_folder".to_string()))
             .returning(|_| {
                 let mut device_factory = MockDeviceFactory::new();
+                device_factory
+                    .expect_estimated_setup_time()
+                    .return_const(Duration::from_secs(0));
+                device_factory
+                    .expect_default_answers()
+                    .return_const(vec![Some("/Volumes/".to_string())]);
                 device_factory.expect_has_next().times(1).returning(|| true);
                 device_factory
                     .expect_has_next()
@@ -604,11 +2038,84 @@ mod tests {
                 device_factory
                     .expect_get_question_statement()
                     .times(1)
-                    .return_const(question.to_string());
+                    .return_const("What is the path of the folder?".to_string());
                 device_factory
                     .expect_set_question_answer()
                     .times(1)
-                    .with(eq("/mnt/usbkey".to_string()))
+                    .with(eq("/Volumes/".to_string()))
+                    .return_const(Ok(()));
+                device_factory
+                    .expect_build()
+                    .times(1)
+                    .returning(|| Ok(Box::new(MockDevice::new())));
+                Some(Box::new(device_factory))
+            });
+        device_operations
+            .expect_add_device()
+            .times(1)
+            .return_const(Ok(()));
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "device new mounted_folder"
+        );
+    }
+
+    #[test]
+    fn answering_start_over_shall_reset_the_device_factory() {
+        let project_operations = MockProjectOperations::new();
+        let backup_operations = MockBackupOperations::new();
+        let console = MockUserInterface::new()
+            .expect_one_write("Name?")
+            .expect_one_read("start over")
+            .expect_one_write("Starting over")
+            .expect_one_write("Name?")
+            .expect_one_read("MyDevice")
+            .expect_one_write("Creating new device of type:")
+            .expect_one_write("Device created successfully");
+
+        let mut device_operations = MockDeviceOperations::new();
+        device_operations
+            .expect_get_available_device_factories()
+            .times(1)
+            .returning(|| {
+                vec![DeviceFactoryKey {
+                    key: "mounted_folder".to_string(),
+                    readable_name: "Mounted folder".to_string(),
+                }]
+            });
+        device_operations
+            .expect_get_device_factory()
+            .times(1)
+            .returning(|_| {
+                let mut device_factory = MockDeviceFactory::new();
+                device_factory
+                    .expect_estimated_setup_time()
+                    .return_const(Duration::from_secs(0));
+                device_factory
+                    .expect_default_answers()
+                    .return_const(Vec::new());
+                device_factory.expect_has_next().times(2).returning(|| true);
+                device_factory
+                    .expect_has_next()
+                    .times(1)
+                    .returning(|| false);
+                device_factory
+                    .expect_get_question_type()
+                    .times(2)
+                    .return_const(QuestionType::String);
+                device_factory
+                    .expect_get_question_statement()
+                    .times(2)
+                    .return_const("Name?".to_string());
+                device_factory.expect_reset().times(1).return_const(());
+                device_factory
+                    .expect_set_question_answer()
+                    .times(1)
+                    .with(eq("MyDevice".to_string()))
                     .return_const(Ok(()));
                 device_factory
                     .expect_build()
@@ -616,228 +2123,2918 @@ mod tests {
                     .returning(|| Ok(Box::new(MockDevice::new())));
                 Some(Box::new(device_factory))
             });
-        device_operations
-            .expect_add_device()
+        device_operations
+            .expect_add_device()
+            .times(1)
+            .return_const(Ok(()));
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "device new mounted_folder"
+        );
+    }
+
+    #[test]
+    fn creating_a_device_with_a_long_setup_shall_display_its_estimate_first() {
+        let project_operations = MockProjectOperations::new();
+        let backup_operations = MockBackupOperations::new();
+        let console = MockUserInterface::new()
+            .expect_one_write("Creating new device of type:")
+            .expect_one_write("Setting up this device takes about 60 seconds")
+            .expect_one_write("Device created successfully");
+
+        let mut device_operations = MockDeviceOperations::new();
+        device_operations
+            .expect_get_available_device_factories()
+            .times(1)
+            .returning(|| {
+                vec![DeviceFactoryKey {
+                    key: "ssh".to_string(),
+                    readable_name: "SSH server".to_string(),
+                }]
+            });
+        device_operations
+            .expect_get_device_factory()
+            .times(1)
+            .returning(|_| {
+                let mut device_factory = MockDeviceFactory::new();
+                device_factory
+                    .expect_estimated_setup_time()
+                    .times(1)
+                    .return_const(Duration::from_secs(60));
+                device_factory
+                    .expect_default_answers()
+                    .return_const(Vec::new());
+                device_factory.expect_has_next().returning(|| false);
+                device_factory
+                    .expect_build()
+                    .times(1)
+                    .returning(|| Ok(Box::new(MockDevice::new())));
+                Some(Box::new(device_factory))
+            });
+        device_operations
+            .expect_add_device()
+            .times(1)
+            .return_const(Ok(()));
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "device new ssh"
+        );
+    }
+
+    #[test]
+    fn creating_an_existing_device_idempotently_shall_not_fail() {
+        let project_operations = MockProjectOperations::new();
+        let backup_operations = MockBackupOperations::new();
+        let console = MockUserInterface::new()
+            .expect_one_write("Creating new device of type:")
+            .expect_one_write("Device already exists, nothing to do");
+
+        let mut device_operations = MockDeviceOperations::new();
+        device_operations
+            .expect_get_available_device_factories()
+            .times(1)
+            .returning(|| {
+                vec![DeviceFactoryKey {
+                    key: "mounted_folder".to_string(),
+                    readable_name: "Mounted folder".to_string(),
+                }]
+            });
+        device_operations
+            .expect_get_device_factory()
+            .times(1)
+            .returning(|_| {
+                let mut device_factory = MockDeviceFactory::new();
+                device_factory
+                    .expect_estimated_setup_time()
+                    .return_const(Duration::from_secs(0));
+                device_factory
+                    .expect_default_answers()
+                    .return_const(Vec::new());
+                device_factory.expect_has_next().returning(|| false);
+                device_factory
+                    .expect_build()
+                    .times(1)
+                    .returning(|| Ok(Box::new(MockDevice::new())));
+                Some(Box::new(device_factory))
+            });
+        device_operations
+            .expect_add_device_idempotent()
+            .times(1)
+            .returning(|_| Ok(AddDeviceResult::AlreadyPresent));
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "device new mounted_folder --idempotent"
+        );
+    }
+
+    #[test]
+    fn when_asking_help_about_a_device_type_it_shall_display_its_description() {
+        let project_operations = MockProjectOperations::new();
+        let backup_operations = MockBackupOperations::new();
+        let console = MockUserInterface::new()
+            .expect_one_write("Mounted folder (mounted_folder)")
+            .expect_one_write("A folder on a mounted drive");
+
+        let mut device_operations = MockDeviceOperations::new();
+        device_operations
+            .expect_get_available_device_factories()
+            .times(1)
+            .returning(|| {
+                vec![DeviceFactoryKey {
+                    key: "mounted_folder".to_string(),
+                    readable_name: "Mounted folder".to_string(),
+                }]
+            });
+        device_operations
+            .expect_get_device_factory()
+            .times(1)
+            .with(eq("mounted_folder".to_string()))
+            .returning(|_| {
+                let mut device_factory = MockDeviceFactory::new();
+                device_factory
+                    .expect_estimated_setup_time()
+                    .return_const(Duration::from_secs(0));
+                device_factory
+                    .expect_default_answers()
+                    .return_const(Vec::new());
+                device_factory
+                    .expect_get_description()
+                    .return_const("A folder on a mounted drive".to_string());
+                Some(Box::new(device_factory))
+            });
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "device new --help mounted_folder"
+        );
+    }
+
+    #[test]
+    fn when_asking_help_about_an_unknown_device_type_it_shall_fail() {
+        let project_operations = MockProjectOperations::new();
+        let backup_operations = MockBackupOperations::new();
+        let console = MockUserInterface::new().expect_one_write("Device factory not found");
+
+        let mut device_operations = MockDeviceOperations::new();
+        device_operations
+            .expect_get_available_device_factories()
+            .times(1)
+            .returning(Vec::new);
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "device new --help cloud"
+        );
+    }
+
+    #[test]
+    fn creating_a_new_usb_key_with_a_unix_path_question() {
+        let question = "What is the path to the device?";
+        let project_operations = MockProjectOperations::new();
+        let backup_operations = MockBackupOperations::new();
+
+        let console = MockUserInterface::new()
+            .expect_one_write(question)
+            .expect_one_write("Enter a valid Unix path")
+            .expect_one_read("/mnt/usbkey")
+            .expect_one_write("Creating new device of type:")
+            .expect_one_write("Device created successfully");
+
+        let mut device_operations = MockDeviceOperations::new();
+        device_operations
+            .expect_get_available_device_factories()
+            .times(1)
+            .returning(|| {
+                vec![DeviceFactoryKey {
+                    key: "mounted_folder".to_string(),
+                    readable_name: "Mounted folder".to_string(),
+                }]
+            });
+        device_operations
+            .expect_get_device_factory()
+            .times(1)
+            .with(eq("mounted_folder".to_string()))
+            .returning(|_| {
+                let mut device_factory = MockDeviceFactory::new();
+                device_factory
+                    .expect_estimated_setup_time()
+                    .return_const(Duration::from_secs(0));
+                device_factory
+                    .expect_default_answers()
+                    .return_const(Vec::new());
+                device_factory.expect_has_next().times(1).returning(|| true);
+                device_factory
+                    .expect_has_next()
+                    .times(1)
+                    .returning(|| false);
+                device_factory
+                    .expect_get_question_type()
+                    .times(1)
+                    .return_const(QuestionType::UnixPath);
+                device_factory
+                    .expect_get_question_statement()
+                    .times(1)
+                    .return_const(question.to_string());
+                device_factory
+                    .expect_set_question_answer()
+                    .times(1)
+                    .with(eq("/mnt/usbkey".to_string()))
+                    .return_const(Ok(()));
+                device_factory
+                    .expect_build()
+                    .times(1)
+                    .returning(|| Ok(Box::new(MockDevice::new())));
+                Some(Box::new(device_factory))
+            });
+        device_operations
+            .expect_add_device()
+            .times(1)
+            .return_const(Ok(()));
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "device new mounted_folder"
+        );
+    }
+
+    #[test]
+    fn creating_a_new_usb_key_with_a_select_question() {
+        let question = "Which compression level should be used?";
+        let project_operations = MockProjectOperations::new();
+        let backup_operations = MockBackupOperations::new();
+
+        let console = MockUserInterface::new()
+            .expect_one_write(question)
+            .expect_one_write("  1. fast")
+            .expect_one_write("  2. best")
+            .expect_one_read("2")
+            .expect_one_write("Creating new device of type:")
+            .expect_one_write("Device created successfully");
+
+        let mut device_operations = MockDeviceOperations::new();
+        device_operations
+            .expect_get_available_device_factories()
+            .times(1)
+            .returning(|| {
+                vec![DeviceFactoryKey {
+                    key: "mounted_folder".to_string(),
+                    readable_name: "Mounted folder".to_string(),
+                }]
+            });
+        device_operations
+            .expect_get_device_factory()
+            .times(1)
+            .with(eq("mounted_folder".to_string()))
+            .returning(|_| {
+                let mut device_factory = MockDeviceFactory::new();
+                device_factory
+                    .expect_estimated_setup_time()
+                    .return_const(Duration::from_secs(0));
+                device_factory
+                    .expect_default_answers()
+                    .return_const(Vec::new());
+                device_factory.expect_has_next().times(1).returning(|| true);
+                device_factory
+                    .expect_has_next()
+                    .times(1)
+                    .returning(|| false);
+                device_factory
+                    .expect_get_question_type()
+                    .times(1)
+                    .return_const(QuestionType::Select(vec![
+                        "fast".to_string(),
+                        "best".to_string(),
+                    ]));
+                device_factory
+                    .expect_get_question_statement()
+                    .times(1)
+                    .return_const(question.to_string());
+                device_factory
+                    .expect_set_question_answer()
+                    .times(1)
+                    .with(eq("best".to_string()))
+                    .return_const(Ok(()));
+                device_factory
+                    .expect_build()
+                    .times(1)
+                    .returning(|| Ok(Box::new(MockDevice::new())));
+                Some(Box::new(device_factory))
+            });
+        device_operations
+            .expect_add_device()
+            .times(1)
+            .return_const(Ok(()));
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "device new mounted_folder"
+        );
+    }
+
+    #[test]
+    fn creating_a_new_usb_key_with_an_out_of_range_selection_shall_fail() {
+        let project_operations = MockProjectOperations::new();
+        let backup_operations = MockBackupOperations::new();
+
+        let console = MockUserInterface::new()
+            .expect_one_write("Which compression level should be used?")
+            .expect_one_write("  1. fast")
+            .expect_one_write("  2. best")
+            .expect_one_read("3")
+            .expect_one_write("Creating new device of type:")
+            .expect_one_write("Invalid choice, enter a number between 1 and 2");
+
+        let mut device_operations = MockDeviceOperations::new();
+        device_operations
+            .expect_get_available_device_factories()
+            .times(1)
+            .returning(|| {
+                vec![DeviceFactoryKey {
+                    key: "mounted_folder".to_string(),
+                    readable_name: "Mounted folder".to_string(),
+                }]
+            });
+        device_operations
+            .expect_get_device_factory()
+            .times(1)
+            .returning(|_| {
+                let mut device_factory = MockDeviceFactory::new();
+                device_factory
+                    .expect_estimated_setup_time()
+                    .return_const(Duration::from_secs(0));
+                device_factory
+                    .expect_default_answers()
+                    .return_const(Vec::new());
+                device_factory.expect_has_next().times(1).returning(|| true);
+                device_factory
+                    .expect_get_question_type()
+                    .times(1)
+                    .return_const(QuestionType::Select(vec![
+                        "fast".to_string(),
+                        "best".to_string(),
+                    ]));
+                device_factory
+                    .expect_get_question_statement()
+                    .times(1)
+                    .return_const("Which compression level should be used?".to_string());
+                Some(Box::new(device_factory))
+            });
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "device new mounted_folder"
+        );
+    }
+
+    #[test]
+    fn deleting_a_usb_key() {
+        let project_operations = MockProjectOperations::new();
+        let backup_operations = MockBackupOperations::new();
+        let console = MockUserInterface::new().expect_one_write("Removed device successfully");
+        let mut device_operations = MockDeviceOperations::new();
+        device_operations
+            .expect_remove_by_name()
+            .times(1)
+            .with(eq("USBkey".to_string()))
+            .return_const(Ok(()));
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "device remove USBkey"
+        );
+    }
+
+    #[test]
+    fn display_invalid_command_when_running_with_device_command_and_invalid_subcommand() {
+        let project_operations = MockProjectOperations::new();
+        let backup_operations = MockBackupOperations::new();
+        let console = MockUserInterface::new().expect_one_write(INVALID_COMMAND);
+        let device_operations = MockDeviceOperations::new();
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "device invalid"
+        );
+    }
+
+    #[test]
+    fn display_list_of_projects() {
+        let backup_operations = MockBackupOperations::new();
+        let mut project_operations = MockProjectOperations::new();
+        project_operations
+            .expect_list_projects()
+            .times(1)
+            .returning(|| Ok(vec![]));
+        let console = MockUserInterface::new().expect_one_write("Project list:");
+
+        let device_operations = MockDeviceOperations::new();
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "project list"
+        );
+    }
+
+    #[test]
+    fn display_list_of_projects_filtered_by_tracking_status() {
+        let backup_operations = MockBackupOperations::new();
+        let mut project_operations = MockProjectOperations::new();
+        project_operations
+            .expect_list_projects()
+            .times(1)
+            .returning(|| {
+                Ok(vec![
+                    Project::new(
+                        "Untracked".to_string(),
+                        "/untracked".to_string(),
+                        Some(ProjectTrackingStatus::UntrackedProject),
+                    ),
+                    Project::new(
+                        "Ignored".to_string(),
+                        "/ignored".to_string(),
+                        Some(ProjectTrackingStatus::IgnoredProject),
+                    ),
+                ])
+            });
+        let console = MockUserInterface::new()
+            .expect_one_write("Project list:")
+            .expect_one_write("  - Project: Ignored")
+            .expect_one_write("        Location: /ignored");
+
+        let device_operations = MockDeviceOperations::new();
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "project list --filter ignored"
+        );
+    }
+
+    #[test]
+    fn display_list_of_projects_as_json() {
+        let backup_operations = MockBackupOperations::new();
+        let mut project_operations = MockProjectOperations::new();
+        project_operations
+            .expect_list_projects()
+            .times(1)
+            .returning(|| {
+                Ok(vec![Project::new(
+                    "Untracked".to_string(),
+                    "/untracked".to_string(),
+                    Some(ProjectTrackingStatus::UntrackedProject),
+                )])
+            });
+        let console = MockUserInterface::new().expect_one_write(
+            r#"[
+  {
+    "name": "Untracked",
+    "location": "/untracked",
+    "tracking_status": {
+      "type": "UntrackedProject"
+    }
+  }
+]"#,
+        );
+
+        let device_operations = MockDeviceOperations::new();
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "project list --json"
+        );
+    }
+
+    #[test]
+    fn display_error_when_listing_projects_with_unknown_filter() {
+        let backup_operations = MockBackupOperations::new();
+        let project_operations = MockProjectOperations::new();
+        let console = MockUserInterface::new().expect_one_write(
+            "Invalid filter: archived. Possible filters are: tracked, untracked, ignored",
+        );
+        let device_operations = MockDeviceOperations::new();
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "project list --filter archived"
+        );
+    }
+
+    #[test]
+    fn display_error_when_listing_projects_with_unknown_sort_key() {
+        let backup_operations = MockBackupOperations::new();
+        let project_operations = MockProjectOperations::new();
+        let console = MockUserInterface::new().expect_one_write(
+            "Invalid sort key: size. Possible keys are: name, location, status, last-backup",
+        );
+        let device_operations = MockDeviceOperations::new();
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "project list --filter tracked --sort-by size"
+        );
+    }
+
+    #[test]
+    fn display_list_of_projects_with_backup_status() {
+        let backup_operations = MockBackupOperations::new();
+        let mut project_operations = MockProjectOperations::new();
+        project_operations
+            .expect_list_projects()
+            .times(1)
+            .returning(|| {
+                Ok(vec![
+                    Project::new("Saved".to_string(), "/saved".to_string(), None),
+                    Project::new("NeverSaved".to_string(), "/never".to_string(), None),
+                ])
+            });
+        project_operations
+            .expect_get_project_backup_status()
+            .times(2)
+            .returning(|name| match name.as_str() {
+                "Saved" => Ok(vec![("USBkey".to_string(), UNIX_EPOCH)]),
+                _ => Ok(vec![]),
+            });
+        let console = MockUserInterface::new()
+            .expect_one_write("Project list:")
+            .expect_one_write("  - Project: Saved")
+            .expect_one_write("        Location: /saved")
+            .expect_one_write("        Last backup on USBkey: just now")
+            .expect_one_write("  - Project: NeverSaved")
+            .expect_one_write("        Location: /never")
+            .expect_one_write("        Last backup: never");
+        let device_operations = MockDeviceOperations::new();
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "project list --with-backup-status"
+        );
+    }
+
+    #[test]
+    fn durations_shall_be_humanized_with_their_largest_unit() {
+        assert_eq!(humanize_duration(Duration::from_secs(0)), "just now");
+        assert_eq!(humanize_duration(Duration::from_secs(1)), "1 second ago");
+        assert_eq!(humanize_duration(Duration::from_secs(150)), "2 minutes ago");
+        assert_eq!(humanize_duration(Duration::from_secs(3600)), "1 hour ago");
+        assert_eq!(
+            humanize_duration(Duration::from_secs(2 * 86400 + 5 * 3600)),
+            "2 days ago"
+        );
+    }
+
+    fn tracked_project(name: &str, location: &str, last_update: Option<u64>) -> Project {
+        Project::new(
+            name.to_string(),
+            location.to_string(),
+            Some(ProjectTrackingStatus::TrackedProject {
+                backup_requirement_class: Default::default(),
+                last_update: last_update
+                    .map(|secs| std::time::UNIX_EPOCH + Duration::from_secs(secs)),
+                current_copies: vec![],
+                max_archives: None,
+            }),
+        )
+    }
+
+    fn project_names(projects: &[Project]) -> Vec<&str> {
+        projects
+            .iter()
+            .map(|project| project.get_name().as_str())
+            .collect()
+    }
+
+    #[test]
+    fn projects_sorted_by_name_or_location_shall_be_in_alphabetical_order() {
+        let mut projects = vec![
+            tracked_project("Photos", "/b", None),
+            tracked_project("Code", "/c", None),
+            tracked_project("Music", "/a", None),
+        ];
+
+        sort_projects(&mut projects, "name").unwrap();
+        assert_eq!(project_names(&projects), vec!["Code", "Music", "Photos"]);
+
+        sort_projects(&mut projects, "location").unwrap();
+        assert_eq!(project_names(&projects), vec!["Music", "Photos", "Code"]);
+    }
+
+    #[test]
+    fn projects_sorted_by_status_shall_be_grouped_in_configuration_order() {
+        let mut projects = vec![
+            Project::new(
+                "Ignored".to_string(),
+                "/ignored".to_string(),
+                Some(ProjectTrackingStatus::IgnoredProject),
+            ),
+            tracked_project("Second", "/second", None),
+            Project::new(
+                "Untracked".to_string(),
+                "/untracked".to_string(),
+                Some(ProjectTrackingStatus::UntrackedProject),
+            ),
+            tracked_project("First", "/first", None),
+        ];
+
+        sort_projects(&mut projects, "status").unwrap();
+        assert_eq!(
+            project_names(&projects),
+            vec!["Second", "First", "Untracked", "Ignored"]
+        );
+    }
+
+    #[test]
+    fn projects_sorted_by_last_backup_shall_start_with_never_backed_up_ones() {
+        let mut projects = vec![
+            tracked_project("Recent", "/recent", Some(2000)),
+            tracked_project("Old", "/old", Some(1000)),
+            tracked_project("Never", "/never", None),
+        ];
+
+        sort_projects(&mut projects, "last-backup").unwrap();
+        assert_eq!(project_names(&projects), vec!["Never", "Old", "Recent"]);
+    }
+
+    #[test]
+    fn display_invalid_command_when_running_with_project_command_and_invalid_subcommand() {
+        let backup_operations = MockBackupOperations::new();
+        let project_operations = MockProjectOperations::new();
+        let console = MockUserInterface::new().expect_one_write(INVALID_COMMAND);
+        let device_operations = MockDeviceOperations::new();
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "project invalid"
+        );
+    }
+
+    #[test]
+    fn adding_a_new_project() {
+        let backup_operations = MockBackupOperations::new();
+        let mut project_operations = MockProjectOperations::new();
+        project_operations
+            .expect_add_project()
+            .times(1)
+            .with(eq(AddProjectArgs {
+                name: "MyProject".to_string(),
+                location: "/mnt/projects/myproject".to_string(),
+            }))
+            .return_const(Ok(()));
+
+        let console = MockUserInterface::new()
+            .expect_one_write("What is the name of the project?")
+            .expect_one_read("MyProject")
+            .expect_one_write("What is the path to the project?")
+            .expect_one_write("Enter a valid Unix path")
+            .expect_one_read("/mnt/projects/myproject")
+            .expect_one_write("Project created successfully");
+
+        let device_operations = MockDeviceOperations::new();
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "project new"
+        );
+    }
+
+    #[test]
+    fn adding_an_existing_project_idempotently_shall_not_fail() {
+        let backup_operations = MockBackupOperations::new();
+        let mut project_operations = MockProjectOperations::new();
+        project_operations.expect_add_project().never();
+        project_operations
+            .expect_add_project_idempotent()
+            .times(1)
+            .with(eq(AddProjectArgs {
+                name: "MyProject".to_string(),
+                location: "/mnt/projects/myproject".to_string(),
+            }))
+            .return_const(Ok(false));
+
+        let console = MockUserInterface::new()
+            .expect_one_write("What is the name of the project?")
+            .expect_one_read("MyProject")
+            .expect_one_write("What is the path to the project?")
+            .expect_one_write("Enter a valid Unix path")
+            .expect_one_read("/mnt/projects/myproject")
+            .expect_one_write("Project already exists, nothing to do");
+
+        let device_operations = MockDeviceOperations::new();
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "project new --idempotent"
+        );
+    }
+
+    #[test]
+    fn when_failing_to_add_a_project_it_shall_print_error_to_user() {
+        let backup_operations = MockBackupOperations::new();
+        let mut project_operations = MockProjectOperations::new();
+        project_operations
+            .expect_add_project()
+            .times(1)
+            .return_const(Err("Project already exists".to_string()));
+
+        let console = MockUserInterface::new()
+            .expect_one_write("What is the name of the project?")
+            .expect_one_read("MyProject")
+            .expect_one_write("What is the path to the project?")
+            .expect_one_write("Enter a valid Unix path")
+            .expect_one_read("/mnt/projects/myproject")
+            .expect_one_write("Project already exists");
+
+        let device_operations = MockDeviceOperations::new();
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "project new"
+        );
+    }
+
+    #[test]
+    fn when_removing_existing_project_it_shall_send_remove_command() {
+        let backup_operations = MockBackupOperations::new();
+        let console = MockUserInterface::new().expect_one_write("Removed project successfully");
+        let device_operations = MockDeviceOperations::new();
+        let mut project_operations = MockProjectOperations::new();
+        project_operations
+            .expect_remove_project_by_name()
+            .times(1)
+            .with(eq("MyProject".to_string()), eq(false))
+            .return_const(Ok(()));
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "project remove MyProject"
+        );
+    }
+
+    #[test]
+    fn when_removing_project_with_purge_it_shall_ask_for_confirmation() {
+        let backup_operations = MockBackupOperations::new();
+        let console = MockUserInterface::new()
+            .expect_one_write(
+                "Delete all the backups of MyProject from the available devices? [y/N]",
+            )
+            .expect_one_read("y")
+            .expect_one_write("Removed project successfully");
+        let device_operations = MockDeviceOperations::new();
+        let mut project_operations = MockProjectOperations::new();
+        project_operations
+            .expect_remove_project_by_name()
+            .times(1)
+            .with(eq("MyProject".to_string()), eq(true))
+            .return_const(Ok(()));
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "project remove MyProject --purge-device-data"
+        );
+    }
+
+    #[test]
+    fn when_purge_is_not_confirmed_project_shall_not_be_removed() {
+        let backup_operations = MockBackupOperations::new();
+        let console = MockUserInterface::new()
+            .expect_one_write(
+                "Delete all the backups of MyProject from the available devices? [y/N]",
+            )
+            .expect_one_read("n")
+            .expect_one_write("Project removal cancelled");
+        let device_operations = MockDeviceOperations::new();
+        let project_operations = MockProjectOperations::new();
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "project remove MyProject --purge-device-data"
+        );
+    }
+
+    #[test]
+    fn when_removing_project_with_forced_purge_it_shall_not_ask() {
+        let backup_operations = MockBackupOperations::new();
+        let console = MockUserInterface::new().expect_one_write("Removed project successfully");
+        let device_operations = MockDeviceOperations::new();
+        let mut project_operations = MockProjectOperations::new();
+        project_operations
+            .expect_remove_project_by_name()
+            .times(1)
+            .with(eq("MyProject".to_string()), eq(true))
+            .return_const(Ok(()));
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "project rm MyProject --purge-device-data --force"
+        );
+    }
+
+    #[test]
+    fn when_removing_project_but_without_name_it_shall_fail() {
+        let project_operations = MockProjectOperations::new();
+        let console = MockUserInterface::new().expect_one_write(INVALID_COMMAND);
+        let device_operations = MockDeviceOperations::new();
+        let backup_operations = MockBackupOperations::new();
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "project remove"
+        );
+    }
+
+    #[test]
+    fn when_removing_project_with_underlying_error_it_shall_print_it() {
+        let backup_operations = MockBackupOperations::new();
+        let mut project_operations = MockProjectOperations::new();
+        project_operations
+            .expect_remove_project_by_name()
+            .times(1)
+            .return_const(Err("Project not found".to_string()));
+
+        let console = MockUserInterface::new().expect_one_write("Project not found");
+
+        let device_operations = MockDeviceOperations::new();
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "project remove MyProject"
+        );
+    }
+
+    #[test]
+    fn when_removing_project_using_rm_command_it_shall_remove_project_too() {
+        let backup_operations = MockBackupOperations::new();
+        let console = MockUserInterface::new().expect_one_write("Removed project successfully");
+        let device_operations = MockDeviceOperations::new();
+        let mut project_operations = MockProjectOperations::new();
+        project_operations
+            .expect_remove_project_by_name()
+            .times(1)
+            .with(eq("MyProject".to_string()), eq(false))
+            .return_const(Ok(()));
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "project rm MyProject"
+        );
+    }
+
+    #[test]
+    fn when_setting_project_location_it_shall_send_update_command() {
+        let backup_operations = MockBackupOperations::new();
+        let console =
+            MockUserInterface::new().expect_one_write("Project location updated successfully");
+        let device_operations = MockDeviceOperations::new();
+        let mut project_operations = MockProjectOperations::new();
+        project_operations
+            .expect_update_project_location()
+            .times(1)
+            .with(eq("MyProject".to_string()), eq("/new/path".to_string()))
+            .return_const(Ok(()));
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "project set-location MyProject /new/path"
+        );
+    }
+
+    #[test]
+    fn when_setting_project_location_without_path_it_shall_fail() {
+        let project_operations = MockProjectOperations::new();
+        let console = MockUserInterface::new().expect_one_write(INVALID_COMMAND);
+        let device_operations = MockDeviceOperations::new();
+        let backup_operations = MockBackupOperations::new();
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "project set-location MyProject"
+        );
+    }
+
+    #[test]
+    fn when_renaming_a_project_it_shall_send_rename_command() {
+        let backup_operations = MockBackupOperations::new();
+        let console = MockUserInterface::new().expect_one_write("Project renamed successfully");
+        let device_operations = MockDeviceOperations::new();
+        let mut project_operations = MockProjectOperations::new();
+        project_operations
+            .expect_rename_project()
+            .times(1)
+            .with(
+                eq("MyProject".to_string()),
+                eq("NewName".to_string()),
+                eq(false),
+            )
+            .return_const(Ok(()));
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "project rename MyProject NewName"
+        );
+    }
+
+    #[test]
+    fn when_renaming_a_project_with_force_it_shall_send_the_flag() {
+        let backup_operations = MockBackupOperations::new();
+        let console = MockUserInterface::new().expect_one_write("Project renamed successfully");
+        let device_operations = MockDeviceOperations::new();
+        let mut project_operations = MockProjectOperations::new();
+        project_operations
+            .expect_rename_project()
+            .times(1)
+            .with(
+                eq("MyProject".to_string()),
+                eq("NewName".to_string()),
+                eq(true),
+            )
+            .return_const(Ok(()));
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "project rename MyProject NewName --force"
+        );
+    }
+
+    #[test]
+    fn when_cloning_a_project_it_shall_send_clone_command() {
+        let backup_operations = MockBackupOperations::new();
+        let console = MockUserInterface::new().expect_one_write("Project cloned successfully");
+        let device_operations = MockDeviceOperations::new();
+        let mut project_operations = MockProjectOperations::new();
+        project_operations
+            .expect_clone_project()
+            .times(1)
+            .with(
+                eq("MyProject".to_string()),
+                eq("MyClone".to_string()),
+                eq("/path/to/clone".to_string()),
+            )
+            .return_const(Ok(()));
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "project clone MyProject MyClone /path/to/clone"
+        );
+    }
+
+    #[test]
+    fn when_adding_projects_from_csv_it_shall_display_each_row_result() {
+        let csv_path = create_tmp_dir().join("projects.csv");
+        std::fs::write(&csv_path, "Photos,/path/to/photos,tracked\n").unwrap();
+        let backup_operations = MockBackupOperations::new();
+        let console = MockUserInterface::new()
+            .expect_one_write("  - line 1 (Photos): added")
+            .expect_one_write("  - line 2 (Videos): Project with name Videos already exists")
+            .expect_one_write("1 of 2 projects added");
+        let device_operations = MockDeviceOperations::new();
+        let mut project_operations = MockProjectOperations::new();
+        project_operations
+            .expect_add_projects_from_csv()
+            .times(1)
+            .with(eq("Photos,/path/to/photos,tracked\n"))
+            .returning(|_| {
+                Ok(BatchAddReport {
+                    rows: vec![
+                        (1, "Photos".to_string(), Ok(())),
+                        (
+                            2,
+                            "Videos".to_string(),
+                            Err("Project with name Videos already exists".to_string()),
+                        ),
+                    ],
+                })
+            });
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            format!("project add --batch {}", csv_path.display())
+        );
+    }
+
+    #[test]
+    fn when_asking_backup_status_it_shall_display_the_compliance() {
+        let backup_operations = MockBackupOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut project_operations = MockProjectOperations::new();
+        project_operations
+            .expect_get_project_compliance()
+            .times(1)
+            .with(eq("MyProject".to_string()))
+            .returning(|_| {
+                Ok(RequirementComplianceResult {
+                    is_compliant: false,
+                    actual_copies: 2,
+                    unique_locations: 1,
+                    lowest_security_level: Some(SecurityLevel::NetworkLocal),
+                    missing_copies: 1,
+                })
+            });
+        let console = MockUserInterface::new()
+            .expect_one_write("Backup status of MyProject:")
+            .expect_one_write("    Compliant: no")
+            .expect_one_write("    Copies: 2 (1 missing)")
+            .expect_one_write("    Locations: 1")
+            .expect_one_write("    Lowest security level: NetworkLocal");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup status MyProject"
+        );
+    }
+
+    #[test]
+    fn when_describing_an_untracked_project_it_shall_not_check_compliance() {
+        let backup_operations = MockBackupOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut project_operations = MockProjectOperations::new();
+        project_operations
+            .expect_list_projects()
+            .times(1)
+            .returning(|| {
+                Ok(vec![Project::new(
+                    "MyProject".to_string(),
+                    "/path/to/project".to_string(),
+                    Some(ProjectTrackingStatus::UntrackedProject),
+                )])
+            });
+        project_operations.expect_get_project_compliance().never();
+        let console = MockUserInterface::new()
+            .expect_one_write("Project: MyProject")
+            .expect_one_write("    Location: /path/to/project")
+            .expect_one_write("    Not tracked");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "project describe MyProject"
+        );
+    }
+
+    #[test]
+    fn when_running_non_interactive_command_answers_shall_be_used_in_order() {
+        let backup_operations = MockBackupOperations::new();
+        let mut project_operations = MockProjectOperations::new();
+        project_operations
+            .expect_add_project()
+            .times(1)
+            .with(eq(AddProjectArgs {
+                name: "MyProject".to_string(),
+                location: "/mnt/projects/myproject".to_string(),
+            }))
+            .return_const(Ok(()));
+
+        let console = MockUserInterface::new()
+            .expect_one_write("What is the name of the project?")
+            .expect_one_write("What is the path to the project?")
+            .expect_one_write("Enter a valid Unix path")
+            .expect_one_write("Project created successfully");
+
+        let device_operations = MockDeviceOperations::new();
+        let config_operations = MockConfigOperations::new();
+        let command_runner = CommandRunner::new(
+            console,
+            &device_operations,
+            &project_operations,
+            &backup_operations,
+            &config_operations,
+        );
+        command_runner
+            .run_non_interactive(
+                vec![
+                    "/path/to/executable".to_string(),
+                    "project".to_string(),
+                    "new".to_string(),
+                ],
+                vec![
+                    "MyProject".to_string(),
+                    "/mnt/projects/myproject".to_string(),
+                ],
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn when_running_non_interactive_command_without_enough_answers_it_shall_fail() {
+        let backup_operations = MockBackupOperations::new();
+        let project_operations = MockProjectOperations::new();
+
+        let console = MockUserInterface::new()
+            .expect_one_write("What is the name of the project?")
+            .expect_one_write("What is the path to the project?")
+            .expect_one_write("Enter a valid Unix path");
+
+        let device_operations = MockDeviceOperations::new();
+        let config_operations = MockConfigOperations::new();
+        let command_runner = CommandRunner::new(
+            console,
+            &device_operations,
+            &project_operations,
+            &backup_operations,
+            &config_operations,
+        );
+        let result = command_runner.run_non_interactive(
+            vec![
+                "/path/to/executable".to_string(),
+                "project".to_string(),
+                "new".to_string(),
+            ],
+            vec!["MyProject".to_string()],
+        );
+        assert_eq!(
+            result.unwrap_err(),
+            "Not enough answers supplied for the command"
+        );
+    }
+
+    #[test]
+    fn when_running_non_interactive_command_its_error_shall_be_returned() {
+        let backup_operations = MockBackupOperations::new();
+        let mut project_operations = MockProjectOperations::new();
+        project_operations
+            .expect_add_project()
+            .times(1)
+            .return_const(Err("Project MyProject already exists".to_string()));
+
+        let console = MockUserInterface::new()
+            .expect_one_write("What is the name of the project?")
+            .expect_one_write("What is the path to the project?")
+            .expect_one_write("Enter a valid Unix path");
+
+        let device_operations = MockDeviceOperations::new();
+        let config_operations = MockConfigOperations::new();
+        let command_runner = CommandRunner::new(
+            console,
+            &device_operations,
+            &project_operations,
+            &backup_operations,
+            &config_operations,
+        );
+        let result = command_runner.run_non_interactive(
+            vec![
+                "/path/to/executable".to_string(),
+                "project".to_string(),
+                "new".to_string(),
+            ],
+            vec![
+                "MyProject".to_string(),
+                "/mnt/projects/myproject".to_string(),
+            ],
+        );
+        assert_eq!(result.unwrap_err(), "Project MyProject already exists");
+    }
+
+    #[test]
+    fn when_reading_an_answer_fails_the_question_shall_be_asked_again() {
+        let backup_operations = MockBackupOperations::new();
+        let mut project_operations = MockProjectOperations::new();
+        project_operations
+            .expect_add_project()
+            .times(1)
+            .with(eq(AddProjectArgs {
+                name: "MyProject".to_string(),
+                location: "/mnt/projects/myproject".to_string(),
+            }))
+            .return_const(Ok(()));
+
+        let mut console = MockUserInterface::new();
+        let mut sequence = Sequence::new();
+        console
+            .expect_read()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|| Err("stream did not contain valid UTF-8".to_string()));
+        console
+            .expect_read()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|| Ok("MyProject".to_string()));
+        console
+            .expect_read()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|| Ok("/mnt/projects/myproject".to_string()));
+        console.expect_can_retry_read().times(1).return_const(true);
+        let mut console = console
+            .expect_one_write("What is the path to the project?")
+            .expect_one_write("Enter a valid Unix path")
+            .expect_one_write("Project created successfully");
+        console
+            .expect_write()
+            .times(2)
+            .with(eq("What is the name of the project?".to_string()))
+            .return_const(());
+
+        let device_operations = MockDeviceOperations::new();
+        let config_operations = MockConfigOperations::new();
+        let command_runner = CommandRunner::new(
+            console,
+            &device_operations,
+            &project_operations,
+            &backup_operations,
+            &config_operations,
+        );
+        command_runner.run(vec![
+            "/path/to/executable".to_string(),
+            "project".to_string(),
+            "new".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn when_rebuilding_index_it_shall_send_rebuild_command() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_rebuild_index()
+            .times(1)
+            .with(eq("MyProject"), eq("USBkey"))
+            .return_const(Ok(()));
+        let console =
+            MockUserInterface::new().expect_one_write("Backup index rebuilt successfully");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup rebuild-index MyProject USBkey"
+        );
+    }
+
+    #[test]
+    fn when_repairing_index_it_shall_display_the_dropped_entries() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_repair_index()
+            .times(1)
+            .with(eq("MyProject"), eq("USBkey"))
+            .returning(|_, _| {
+                Ok(RepairReport {
+                    dropped_entries: vec![PathBuf::from("notes.txt"), PathBuf::from("README")],
+                })
+            });
+        let console = MockUserInterface::new()
+            .expect_one_write("2 index entries dropped, the next backup will archive their files")
+            .expect_one_write("  - notes.txt")
+            .expect_one_write("  - README");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup repair-index MyProject USBkey"
+        );
+    }
+
+    #[test]
+    fn when_listing_restore_points_it_shall_number_them() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_list_restore_points()
+            .times(1)
+            .with(eq("MyProject"), eq("USBkey"))
+            .returning(|_, _| {
+                Ok(vec![
+                    RestorePoint {
+                        timestamp: UNIX_EPOCH + Duration::from_secs(86400),
+                        archive_name: "86400000-0a1b2c3d.tar.gz".to_string(),
+                        contains_deletions: true,
+                    },
+                    RestorePoint {
+                        timestamp: UNIX_EPOCH,
+                        archive_name: "0-0a1b2c3d.tar.gz".to_string(),
+                        contains_deletions: false,
+                    },
+                ])
+            });
+        let console = MockUserInterface::new()
+            .expect_one_write("Restore points:")
+            .expect_one_write(&format!(
+                "  1. {} (86400000-0a1b2c3d.tar.gz, with deletions)",
+                format_utc(UNIX_EPOCH + Duration::from_secs(86400))
+            ))
+            .expect_one_write(&format!(
+                "  2. {} (0-0a1b2c3d.tar.gz)",
+                format_utc(UNIX_EPOCH)
+            ));
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup restore --list-restore-points MyProject USBkey"
+        );
+    }
+
+    #[test]
+    fn when_initializing_index_it_shall_send_initialize_command() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_initialize_index()
+            .times(1)
+            .with(eq("MyProject"), eq("USBkey"), eq("/media/usb/MyProject"))
+            .return_const(Ok(()));
+        let console = MockUserInterface::new().expect_one_write(
+            "Project MyProject indexed on device USBkey, the next backup will only archive the changes",
+        );
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup initialize MyProject USBkey /media/usb/MyProject"
+        );
+    }
+
+    #[test]
+    fn when_rebuilding_index_fails_it_shall_print_error() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_rebuild_index()
+            .times(1)
+            .return_const(Err("No archive found for project: MyProject".to_string()));
+        let console =
+            MockUserInterface::new().expect_one_write("No archive found for project: MyProject");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup rebuild-index MyProject USBkey"
+        );
+    }
+
+    #[test]
+    fn when_comparing_identical_copies_it_shall_say_so() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_compare_device_copies()
+            .times(1)
+            .with(eq("MyProject"), eq("USBkey"), eq("NAS"))
+            .returning(|_, _, _| {
+                Ok(CopyComparisonResult {
+                    only_on_first_device: vec![],
+                    only_on_second_device: vec![],
+                    different: vec![],
+                })
+            });
+        let console =
+            MockUserInterface::new().expect_one_write("Copies on USBkey and NAS are identical");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup compare MyProject USBkey NAS"
+        );
+    }
+
+    #[test]
+    fn when_comparing_different_copies_it_shall_list_differences() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_compare_device_copies()
+            .times(1)
+            .returning(|_, _, _| {
+                Ok(CopyComparisonResult {
+                    only_on_first_device: vec![PathBuf::from("a.txt")],
+                    only_on_second_device: vec![PathBuf::from("b.txt")],
+                    different: vec![PathBuf::from("c.txt")],
+                })
+            });
+        let console = MockUserInterface::new()
+            .expect_one_write("Copies on USBkey and NAS differ:")
+            .expect_one_write("  - Only on USBkey: a.txt")
+            .expect_one_write("  - Only on NAS: b.txt")
+            .expect_one_write("  - Different: c.txt");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup compare MyProject USBkey NAS"
+        );
+    }
+
+    #[test]
+    fn when_running_backup_to_multiple_devices_it_shall_pass_all_devices() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_backup_project_to_device()
+            .times(1)
+            .withf(|project_name, device_names, options| {
+                project_name == "MyProject"
+                    && device_names == ["USBkey", "NAS"]
+                    && options.priority == BackupPriority::Normal
+                    && !options.verify_after
+            })
+            .returning(|_, _, _| Ok(BackupSummary::default()));
+        let console = MockUserInterface::new();
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup run MyProject USBkey NAS"
+        );
+    }
+
+    #[test]
+    fn when_running_backup_with_priority_it_shall_pass_it_to_operations() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_backup_project_to_device()
+            .times(1)
+            .withf(|project_name, device_names, options| {
+                project_name == "MyProject"
+                    && device_names == ["USBkey"]
+                    && options.priority == BackupPriority::Low
+                    && !options.verify_after
+            })
+            .returning(|_, _, _| Ok(BackupSummary::default()));
+        let console = MockUserInterface::new();
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup run MyProject USBkey --priority low"
+        );
+    }
+
+    #[test]
+    fn when_running_backup_with_invalid_priority_it_shall_display_error() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let backup_operations = MockBackupOperations::new();
+        let console = MockUserInterface::new().expect_one_write("Invalid priority: urgent");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup run MyProject USBkey --priority urgent"
+        );
+    }
+
+    #[test]
+    fn when_running_backup_with_verify_after_it_shall_pass_it_to_operations() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_backup_project_to_device()
+            .times(1)
+            .withf(|project_name, device_names, options| {
+                project_name == "MyProject" && device_names == ["USBkey"] && options.verify_after
+            })
+            .returning(|_, _, _| Ok(BackupSummary::default()));
+        let console = MockUserInterface::new();
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup run MyProject USBkey --verify-after"
+        );
+    }
+
+    #[test]
+    fn when_backup_verification_fails_it_shall_display_error() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_backup_project_to_device()
+            .times(1)
+            .returning(|_, _, _| Err("Backup verification failed: mismatch".to_string()));
+        let console =
+            MockUserInterface::new().expect_one_write("Backup verification failed: mismatch");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup run MyProject USBkey --verify-after"
+        );
+    }
+
+    #[test]
+    fn when_running_backup_with_max_file_size_it_shall_display_skipped_files() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_backup_project_to_device()
+            .times(1)
+            .withf(|_, _, options| options.max_file_size == Some(10 * 1024 * 1024))
+            .returning(|_, _, _| {
+                Ok(BackupSummary {
+                    skipped_large_files: vec![(PathBuf::from("dump.sql"), 20 * 1024 * 1024)],
+                    ..Default::default()
+                })
+            });
+        let console = MockUserInterface::new()
+            .expect_one_write("Files skipped because they are above the maximum size:")
+            .expect_one_write("  - dump.sql (20971520 bytes)");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup run MyProject USBkey --max-file-size 10M"
+        );
+    }
+
+    #[test]
+    fn when_running_backup_with_invalid_max_file_size_it_shall_display_error() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let backup_operations = MockBackupOperations::new();
+        let console = MockUserInterface::new().expect_one_write("Invalid size: 10T");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup run MyProject USBkey --max-file-size 10T"
+        );
+    }
+
+    #[test]
+    fn file_sizes_shall_be_parsed_with_binary_suffixes() {
+        assert_eq!(parse_file_size("512"), Ok(512));
+        assert_eq!(parse_file_size("10K"), Ok(10 * 1024));
+        assert_eq!(parse_file_size("3m"), Ok(3 * 1024 * 1024));
+        assert_eq!(parse_file_size("4G"), Ok(4 * 1024 * 1024 * 1024));
+        assert!(parse_file_size("G").is_err());
+        assert!(parse_file_size("-1").is_err());
+    }
+
+    #[test]
+    fn display_list_of_devices_with_their_backed_up_projects() {
+        let backup_operations = MockBackupOperations::new();
+        let project_operations = MockProjectOperations::new();
+        let mut device_operations = MockDeviceOperations::new();
+
+        device_operations.expect_list().times(1).returning(move || {
+            let mut available_device = MockDevice::new();
+            available_device
+                .expect_get_name()
+                .return_const("USBkey".to_string());
+            available_device
+                .expect_get_location()
+                .return_const("/".to_string());
+            available_device
+                .expect_get_security_level()
+                .returning(|| SecurityLevel::Local);
+            available_device
+                .expect_test_availability()
+                .return_const(Ok(()));
+            available_device
+                .expect_list_backed_up_projects()
+                .returning(|| Ok(vec!["MyProject".to_string(), "OtherProject".to_string()]));
+            let mut unavailable_device = MockDevice::new();
+            unavailable_device
+                .expect_get_name()
+                .return_const("NAS".to_string());
+            unavailable_device
+                .expect_get_location()
+                .return_const("/nas".to_string());
+            unavailable_device
+                .expect_get_security_level()
+                .returning(|| SecurityLevel::NetworkLocal);
+            unavailable_device
+                .expect_test_availability()
+                .return_const(Err("Device not mounted".to_string()));
+            unavailable_device.expect_list_backed_up_projects().never();
+            Ok(vec![
+                Box::new(available_device),
+                Box::new(unavailable_device),
+            ])
+        });
+
+        let console = MockUserInterface::new()
+            .expect_one_write("Device list:")
+            .expect_one_write("  - Device: USBkey")
+            .expect_one_write("        Location: /")
+            .expect_one_write("        Security level: Local")
+            .expect_one_write("        Projects: MyProject, OtherProject")
+            .expect_one_write("  - Device: NAS")
+            .expect_one_write("        Location: /nas")
+            .expect_one_write("        Security level: NetworkLocal")
+            .expect_one_write("        Projects: device not available");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "device list --projects"
+        );
+    }
+
+    #[test]
+    fn display_list_of_devices_with_last_seen_when_verbose() {
+        let backup_operations = MockBackupOperations::new();
+        let project_operations = MockProjectOperations::new();
+        let mut device_operations = MockDeviceOperations::new();
+
+        device_operations.expect_list().times(1).returning(move || {
+            let mut seen_device = MockDevice::new();
+            seen_device
+                .expect_get_name()
+                .return_const("USBkey".to_string());
+            seen_device
+                .expect_get_location()
+                .return_const("/".to_string());
+            seen_device
+                .expect_get_security_level()
+                .returning(|| SecurityLevel::Local);
+            seen_device
+                .expect_get_last_seen()
+                .return_const(Some(UNIX_EPOCH + Duration::from_secs(1709301909)));
+            seen_device
+                .expect_supports_capability()
+                .returning(|capability| {
+                    matches!(
+                        capability,
+                        DeviceCapability::SparseFiles | DeviceCapability::PartialRestore
+                    )
+                });
+            let mut unseen_device = MockDevice::new();
+            unseen_device
+                .expect_get_name()
+                .return_const("NAS".to_string());
+            unseen_device
+                .expect_get_location()
+                .return_const("/nas".to_string());
+            unseen_device
+                .expect_get_security_level()
+                .returning(|| SecurityLevel::NetworkLocal);
+            unseen_device.expect_get_last_seen().return_const(None);
+            unseen_device
+                .expect_supports_capability()
+                .return_const(false);
+            Ok(vec![Box::new(seen_device), Box::new(unseen_device)])
+        });
+
+        let console = MockUserInterface::new()
+            .expect_one_write("Device list:")
+            .expect_one_write("  - Device: USBkey")
+            .expect_one_write("        Location: /")
+            .expect_one_write("        Security level: Local")
+            .expect_one_write("        Last seen: 2024-03-01 14:05:09 UTC")
+            .expect_one_write("        Capabilities: SparseFiles, PartialRestore")
+            .expect_one_write("  - Device: NAS")
+            .expect_one_write("        Location: /nas")
+            .expect_one_write("        Security level: NetworkLocal")
+            .expect_one_write("        Last seen: never")
+            .expect_one_write("        Capabilities: none");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "device list --verbose"
+        );
+    }
+
+    #[test]
+    fn display_availability_of_each_device_when_checking_availability() {
+        let backup_operations = MockBackupOperations::new();
+        let project_operations = MockProjectOperations::new();
+        let mut device_operations = MockDeviceOperations::new();
+
+        device_operations
+            .expect_list_devices_with_availability()
+            .times(1)
+            .returning(move || {
+                let mut available_device = MockDevice::new();
+                available_device
+                    .expect_get_name()
+                    .return_const("USBkey".to_string());
+                let mut unavailable_device = MockDevice::new();
+                unavailable_device
+                    .expect_get_name()
+                    .return_const("NAS".to_string());
+                Ok(vec![
+                    (Box::new(available_device), true),
+                    (Box::new(unavailable_device), false),
+                ])
+            });
+
+        let console = MockUserInterface::new()
+            .expect_one_write("Device list:")
+            .expect_one_write("  ✓ USBkey")
+            .expect_one_write("  ✗ NAS");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "device list --check-availability"
+        );
+    }
+
+    #[test]
+    fn when_listing_orphans_of_a_device_they_shall_be_displayed() {
+        let backup_operations = MockBackupOperations::new();
+        let project_operations = MockProjectOperations::new();
+        let mut device_operations = MockDeviceOperations::new();
+        device_operations
+            .expect_list_orphaned_device_directories()
+            .with(eq("USBkey"))
+            .times(1)
+            .returning(|_| Ok(vec!["OldProject".to_string()]));
+        device_operations
+            .expect_prune_orphaned_device_directories()
+            .never();
+
+        let console = MockUserInterface::new()
+            .expect_one_write("Orphaned directories on USBkey:")
+            .expect_one_write("  - OldProject");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "device list-orphans USBkey"
+        );
+    }
+
+    #[test]
+    fn when_pruning_orphans_of_a_device_with_force_removed_ones_shall_be_displayed() {
+        let backup_operations = MockBackupOperations::new();
+        let project_operations = MockProjectOperations::new();
+        let mut device_operations = MockDeviceOperations::new();
+        device_operations
+            .expect_prune_orphaned_device_directories()
+            .with(eq("USBkey"))
+            .times(1)
+            .returning(|_| Ok(vec![]));
+
+        let console =
+            MockUserInterface::new().expect_one_write("No orphaned directories on USBkey");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "device list-orphans USBkey --prune --force"
+        );
+    }
+
+    #[test]
+    fn when_pruning_orphans_of_a_device_it_shall_ask_for_confirmation() {
+        for (answer, expected_prunes, last_message) in [
+            ("y", 1, "Removed orphaned directories on USBkey:"),
+            ("n", 0, "Pruning cancelled"),
+        ] {
+            let backup_operations = MockBackupOperations::new();
+            let project_operations = MockProjectOperations::new();
+            let mut device_operations = MockDeviceOperations::new();
+            device_operations
+                .expect_list_orphaned_device_directories()
+                .with(eq("USBkey"))
+                .times(1)
+                .returning(|_| Ok(vec!["OldProject".to_string()]));
+            device_operations
+                .expect_prune_orphaned_device_directories()
+                .with(eq("USBkey"))
+                .times(expected_prunes)
+                .returning(|_| Ok(vec!["OldProject".to_string()]));
+
+            let mut console = MockUserInterface::new()
+                .expect_one_write("Orphaned directories on USBkey:")
+                .expect_one_write("  - OldProject")
+                .expect_one_write(
+                    "Delete these directories and all their backups from USBkey? [y/N]",
+                )
+                .expect_one_read(answer)
+                .expect_one_write(last_message);
+            if expected_prunes == 1 {
+                console = console.expect_one_write("  - OldProject");
+            }
+
+            run_command!(
+                console,
+                device_operations,
+                project_operations,
+                backup_operations,
+                "device list-orphans USBkey --prune"
+            );
+        }
+    }
+
+    #[test]
+    fn when_listing_indexed_files_sorted_by_mtime_it_shall_display_them() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_list_indexed_files()
+            .times(1)
+            .with(eq("MyProject"), eq("USBkey"), eq(IndexedFilesOrder::Mtime))
+            .returning(|_, _, _| {
+                Ok(vec![IndexedFile {
+                    path: PathBuf::from("notes.txt"),
+                    size: 42,
+                    mtime: UNIX_EPOCH + Duration::from_secs(1709301909),
+                }])
+            });
+        let console = MockUserInterface::new()
+            .expect_one_write("  - notes.txt (42 bytes, modified 2024-03-01 14:05:09 UTC)");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup list-indexed-files MyProject USBkey --sort-by mtime"
+        );
+    }
+
+    #[test]
+    fn when_listing_indexed_files_as_json_it_shall_display_the_exported_index() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations.expect_list_indexed_files().times(0);
+        backup_operations
+            .expect_export_index_to_json()
+            .times(1)
+            .with(eq("MyProject"), eq("USBkey"))
+            .returning(|_, _| Ok("[]".to_string()));
+        let console = MockUserInterface::new().expect_one_write("[]");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup list-indexed-files MyProject USBkey --format json"
+        );
+    }
+
+    #[test]
+    fn when_listing_indexed_files_with_unknown_format_it_shall_display_error() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let backup_operations = MockBackupOperations::new();
+        let console = MockUserInterface::new()
+            .expect_one_write("Invalid format: csv. Possible formats are: text, json");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup list-indexed-files MyProject USBkey --format csv"
+        );
+    }
+
+    #[test]
+    fn when_importing_a_json_index_it_shall_pass_the_file_to_operations() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_import_index_from_json()
+            .times(1)
+            .with(eq("MyProject"), eq("USBkey"), eq("/tmp/index.json"))
+            .returning(|_, _, _| Ok(()));
+        let console = MockUserInterface::new().expect_one_write(
+            "Index of project MyProject imported on device USBkey, the next backup will only archive the changes",
+        );
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup import-index MyProject USBkey /tmp/index.json --format json"
+        );
+    }
+
+    #[test]
+    fn when_importing_an_index_in_another_format_it_shall_display_error() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations.expect_import_index_from_json().times(0);
+        let console = MockUserInterface::new()
+            .expect_one_write("Unsupported index format: binary, only json can be imported");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup import-index MyProject USBkey /tmp/index.bin --format binary"
+        );
+    }
+
+    #[test]
+    fn when_reporting_duplicates_it_shall_display_each_group() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_find_duplicate_files()
+            .times(1)
+            .with(eq("MyProject"), eq("USBkey"))
+            .returning(|_, _| {
+                Ok(vec![vec![
+                    PathBuf::from("a/original.txt"),
+                    PathBuf::from("b/copy.txt"),
+                ]])
+            });
+        let console = MockUserInterface::new()
+            .expect_one_write("Files with the same size and modification time:")
+            .expect_one_write("  - a/original.txt, b/copy.txt");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup deduplicate-report MyProject USBkey"
+        );
+    }
+
+    #[test]
+    fn when_reporting_duplicates_without_any_it_shall_say_so() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_find_duplicate_files()
+            .times(1)
+            .returning(|_, _| Ok(vec![]));
+        let console = MockUserInterface::new().expect_one_write("No duplicate file");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup deduplicate-report MyProject USBkey"
+        );
+    }
+
+    #[test]
+    fn when_searching_indexed_files_it_shall_display_the_matching_ones() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_search_indexed_files()
+            .times(1)
+            .with(eq("MyProject"), eq("USBkey"), eq("*.log"))
+            .returning(|_, _, _| {
+                Ok(vec![IndexedFile {
+                    path: PathBuf::from("logs/app.log"),
+                    size: 42,
+                    mtime: UNIX_EPOCH + Duration::from_secs(1709301909),
+                }])
+            });
+        let console = MockUserInterface::new()
+            .expect_one_write("  - logs/app.log (42 bytes, modified 2024-03-01 14:05:09 UTC)");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup search MyProject USBkey *.log"
+        );
+    }
+
+    #[test]
+    fn when_searching_indexed_files_without_match_it_shall_say_so() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_search_indexed_files()
+            .times(1)
+            .returning(|_, _, _| Ok(vec![]));
+        let console = MockUserInterface::new().expect_one_write("No matching file");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup search MyProject USBkey *.log"
+        );
+    }
+
+    #[test]
+    fn when_listing_indexed_files_with_invalid_sort_order_it_shall_display_error() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let backup_operations = MockBackupOperations::new();
+        let console = MockUserInterface::new()
+            .expect_one_write("Invalid sort order: size. Possible orders are: path, mtime");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup list-indexed-files MyProject USBkey --sort-by size"
+        );
+    }
+
+    #[test]
+    fn when_running_backup_with_min_interval_it_shall_report_skipped_backup() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_backup_project_to_device()
+            .times(1)
+            .withf(|_, _, options| options.min_interval == Some(Duration::from_secs(30 * 60)))
+            .returning(|_, _, _| {
+                Ok(BackupSummary {
+                    skipped_too_recent: true,
+                    ..Default::default()
+                })
+            });
+        let console = MockUserInterface::new().expect_one_write("Backup skipped: too recent");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup run MyProject USBkey --min-interval 30"
+        );
+    }
+
+    #[test]
+    fn when_running_backup_with_no_compress_archives_shall_not_be_compressed() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_backup_project_to_device()
+            .times(1)
+            .withf(|_, device_names, options| device_names == ["USBkey"] && !options.compress)
+            .returning(|_, _, _| Ok(BackupSummary::default()));
+        let console = MockUserInterface::new();
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup run MyProject USBkey --no-compress"
+        );
+    }
+
+    #[test]
+    fn when_running_backup_with_compression_format_it_shall_pass_the_format() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_backup_project_to_device()
+            .times(1)
+            .withf(|_, _, options| options.compression_format == CompressionFormat::Zstd)
+            .returning(|_, _, _| Ok(BackupSummary::default()));
+        let console = MockUserInterface::new();
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup run MyProject USBkey --compression-format zstd"
+        );
+    }
+
+    #[test]
+    fn when_running_backup_with_invalid_compression_format_it_shall_fail() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations.expect_backup_project_to_device().never();
+        let console = MockUserInterface::new().expect_one_write("Invalid compression format: lz4");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup run MyProject USBkey --compression-format lz4"
+        );
+    }
+
+    #[test]
+    fn when_running_backup_with_keep_partial_it_shall_pass_the_option() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_backup_project_to_device()
+            .times(1)
+            .withf(|_, _, options| options.keep_partial && options.compress)
+            .returning(|_, _, _| Ok(BackupSummary::default()));
+        let console = MockUserInterface::new();
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup run MyProject USBkey --keep-partial"
+        );
+    }
+
+    #[test]
+    fn when_running_backup_with_checksum_verify_it_shall_display_possible_bitrot() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_backup_project_to_device()
+            .times(1)
+            .withf(|_, _, options| options.checksum_verify_before)
+            .returning(|_, _, _| {
+                Ok(BackupSummary {
+                    warnings: vec![BackupExecutionWarning::PossibleBitrot(PathBuf::from(
+                        "photos/cat.jpg",
+                    ))],
+                    ..Default::default()
+                })
+            });
+        let console = MockUserInterface::new().expect_one_write(
+            "Possible bitrot: photos/cat.jpg changed without its metadata changing",
+        );
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup run MyProject USBkey --checksum-verify-before"
+        );
+    }
+
+    #[test]
+    fn when_running_backup_skipping_special_files_they_shall_be_displayed() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_backup_project_to_device()
+            .times(1)
+            .withf(|_, _, options| options.skip_special_files)
+            .returning(|_, _, _| {
+                Ok(BackupSummary {
+                    skipped_special_files: vec![PathBuf::from("run/app.fifo")],
+                    ..Default::default()
+                })
+            });
+        let console = MockUserInterface::new()
+            .expect_one_write("Special files skipped:")
+            .expect_one_write("  - run/app.fifo");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup run MyProject USBkey --skip-special-files"
+        );
+    }
+
+    #[test]
+    fn when_running_backup_with_max_archives_it_shall_display_the_removed_archives() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_backup_project_to_device()
+            .times(1)
+            .withf(|_, _, options| options.max_archives == Some(3))
+            .returning(|_, _, _| {
+                Ok(BackupSummary {
+                    pruned_archives: 2,
+                    ..Default::default()
+                })
+            });
+        let console = MockUserInterface::new().expect_one_write("Removed 2 old archives");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup run MyProject USBkey --max-archives 3"
+        );
+    }
+
+    #[test]
+    fn when_running_auto_backup_the_chosen_device_shall_be_displayed() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_backup_project_auto()
+            .with(eq("MyProject"))
             .times(1)
-            .return_const(Ok(()));
+            .returning(|_| Ok("USBkey".to_string()));
+        let console =
+            MockUserInterface::new().expect_one_write("Backup of MyProject done on USBkey");
 
         run_command!(
             console,
             device_operations,
             project_operations,
             backup_operations,
-            "device new mounted_folder"
+            "backup auto MyProject"
         );
     }
 
     #[test]
-    fn deleting_a_usb_key() {
+    fn when_watching_a_project_the_watch_shall_be_started_and_stopped() {
         let project_operations = MockProjectOperations::new();
-        let backup_operations = MockBackupOperations::new();
-        let console = MockUserInterface::new().expect_one_write("Removed device successfully");
-        let mut device_operations = MockDeviceOperations::new();
-        device_operations
-            .expect_remove_by_name()
+        let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_watch_project_on_device()
+            .withf(|project_name, device_name, debounce, _| {
+                project_name == "MyProject"
+                    && device_name == "USBkey"
+                    && *debounce == WATCH_DEBOUNCE
+            })
             .times(1)
-            .with(eq("USBkey".to_string()))
-            .return_const(Ok(()));
+            .returning(|_, _, _, _| Ok(()));
+        let console = MockUserInterface::new()
+            .expect_one_write("Watching MyProject for changes, press Ctrl+C to stop")
+            .expect_one_write("Stopped watching MyProject");
 
         run_command!(
             console,
             device_operations,
             project_operations,
             backup_operations,
-            "device remove USBkey"
+            "backup watch MyProject USBkey"
         );
     }
 
     #[test]
-    fn display_invalid_command_when_running_with_device_command_and_invalid_subcommand() {
+    fn when_running_backup_with_tags_they_shall_be_passed_in_order() {
         let project_operations = MockProjectOperations::new();
-        let backup_operations = MockBackupOperations::new();
-        let console = MockUserInterface::new().expect_one_write(INVALID_COMMAND);
         let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_backup_project_to_device()
+            .times(1)
+            .withf(|_, device_names, options| {
+                device_names == ["USBkey"] && options.tags == ["before-upgrade", "v1.0"]
+            })
+            .returning(|_, _, _| Ok(BackupSummary::default()));
+        let console = MockUserInterface::new();
 
         run_command!(
             console,
             device_operations,
             project_operations,
             backup_operations,
-            "device invalid"
+            "backup run MyProject USBkey --tag before-upgrade --tag v1.0"
         );
     }
 
     #[test]
-    fn display_list_of_projects() {
+    fn when_running_backup_with_invalid_notify_target_it_shall_not_run_the_backup() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
         let backup_operations = MockBackupOperations::new();
-        let mut project_operations = MockProjectOperations::new();
-        project_operations
-            .expect_list_projects()
+        let console =
+            MockUserInterface::new().expect_one_write("Invalid notification target: nobody");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup run MyProject USBkey --notify nobody"
+        );
+    }
+
+    #[test]
+    fn notify_target_shall_be_an_url_or_an_email_address() {
+        assert!(build_notifier("https://example.com/hook").is_ok());
+        assert!(build_notifier("mailto:me@example.com").is_ok());
+        assert!(build_notifier("@example.com").is_err());
+    }
+
+    #[test]
+    fn failed_backup_shall_be_notified_with_its_error() {
+        let event = backup_event(
+            "MyProject",
+            &["USBkey".to_string(), "NAS".to_string()],
+            &Err("Device not available".to_string()),
+        );
+
+        assert_eq!(
+            event,
+            BackupEvent::Failure {
+                project: "MyProject".to_string(),
+                device: "USBkey, NAS".to_string(),
+                error: "Device not available".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn when_displaying_project_stats_extensions_shall_be_sorted_by_count() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_get_index_statistics()
+            .with(eq("MyProject"), eq("USBkey"))
             .times(1)
-            .returning(|| Ok(vec![]));
-        let console = MockUserInterface::new().expect_one_write("Project list:");
+            .returning(|_, _| {
+                Ok(IndexStatistics {
+                    file_count: 4,
+                    total_bytes: 120,
+                    min_file_size: 10,
+                    max_file_size: 50,
+                    mean_file_size: 30.0,
+                    extension_counts: HashMap::from([
+                        ("rs".to_string(), 1),
+                        ("txt".to_string(), 2),
+                        ("".to_string(), 1),
+                    ]),
+                })
+            });
+        let console = MockUserInterface::new()
+            .expect_one_write("Files: 4")
+            .expect_one_write("Total size: 120 bytes")
+            .expect_one_write("File size: min 10, max 50, mean 30.0 bytes")
+            .expect_one_write("Files by extension:")
+            .expect_one_write("  - .txt: 2")
+            .expect_one_write("  - (none): 1")
+            .expect_one_write("  - .rs: 1");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "project stats MyProject USBkey"
+        );
+    }
 
+    #[test]
+    fn when_running_backup_with_dry_run_it_shall_display_the_changes_only() {
+        let project_operations = MockProjectOperations::new();
         let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations.expect_backup_project_to_device().times(0);
+        backup_operations
+            .expect_dry_run_backup()
+            .times(1)
+            .withf(|project_name, device_names, _| {
+                project_name == "MyProject" && device_names == ["USBkey"]
+            })
+            .returning(|_, _, _| {
+                Ok(DryRunResult {
+                    files_to_add: 2,
+                    files_to_modify: 1,
+                    files_to_delete: 3,
+                    bytes_to_transfer: 1024,
+                })
+            });
+        let console = MockUserInterface::new()
+            .expect_one_write("Dry run, nothing was written:")
+            .expect_one_write("  - Files to add: 2")
+            .expect_one_write("  - Files to modify: 1")
+            .expect_one_write("  - Files to delete: 3")
+            .expect_one_write("  - Bytes to transfer: 1024");
 
         run_command!(
             console,
             device_operations,
             project_operations,
             backup_operations,
-            "project list"
+            "backup run MyProject USBkey --dry-run"
         );
     }
 
     #[test]
-    fn display_invalid_command_when_running_with_project_command_and_invalid_subcommand() {
-        let backup_operations = MockBackupOperations::new();
+    fn when_running_backup_with_invalid_min_interval_it_shall_fail() {
         let project_operations = MockProjectOperations::new();
-        let console = MockUserInterface::new().expect_one_write(INVALID_COMMAND);
         let device_operations = MockDeviceOperations::new();
+        let backup_operations = MockBackupOperations::new();
+        let console = MockUserInterface::new().expect_one_write("Invalid interval: soon");
 
         run_command!(
             console,
             device_operations,
             project_operations,
             backup_operations,
-            "project invalid"
+            "backup run MyProject USBkey --min-interval soon"
         );
     }
 
     #[test]
-    fn adding_a_new_project() {
+    fn when_running_backup_with_a_min_interval_too_large_it_shall_fail() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
         let backup_operations = MockBackupOperations::new();
-        let mut project_operations = MockProjectOperations::new();
-        project_operations
-            .expect_add_project()
-            .times(1)
-            .with(eq(AddProjectArgs {
-                name: "MyProject".to_string(),
-                location: "/mnt/projects/myproject".to_string(),
-            }))
-            .return_const(Ok(()));
+        let console =
+            MockUserInterface::new().expect_one_write("Invalid interval: 307445734561825861");
 
-        let console = MockUserInterface::new()
-            .expect_one_write("What is the name of the project?")
-            .expect_one_read("MyProject")
-            .expect_one_write("What is the path to the project?")
-            .expect_one_write("Enter a valid Unix path")
-            .expect_one_read("/mnt/projects/myproject")
-            .expect_one_write("Project created successfully");
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup run MyProject USBkey --min-interval 307445734561825861"
+        );
+    }
 
+    #[test]
+    fn when_running_backup_of_all_projects_under_a_path_it_shall_display_each_result() {
+        let project_operations = MockProjectOperations::new();
         let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_backup_projects_under()
+            .times(1)
+            .withf(
+                |location, device_names, exclude_devices, skip_if_backed_up_within| {
+                    location == "/home/user"
+                        && device_names == ["USBkey"]
+                        && exclude_devices.is_empty()
+                        && skip_if_backed_up_within.is_none()
+                },
+            )
+            .returning(|_, _, _, _| {
+                Ok(vec![
+                    ("Repo".to_string(), Ok(BackupSummary::default())),
+                    (
+                        "Photos".to_string(),
+                        Err("Backup failed: disk full".to_string()),
+                    ),
+                ])
+            });
+        let console = MockUserInterface::new()
+            .expect_one_write("  - Repo: done")
+            .expect_one_write("  - Photos: Backup failed: disk full")
+            .expect_one_write("1 of 2 backups failed");
+
         run_command!(
             console,
             device_operations,
             project_operations,
             backup_operations,
-            "project new"
+            "backup run-all-under /home/user USBkey"
         );
     }
 
     #[test]
-    fn when_failing_to_add_a_project_it_shall_print_error_to_user() {
-        let backup_operations = MockBackupOperations::new();
-        let mut project_operations = MockProjectOperations::new();
-        project_operations
-            .expect_add_project()
+    fn when_running_backup_of_all_projects_under_a_path_excluded_devices_shall_be_passed() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_backup_projects_under()
             .times(1)
-            .return_const(Err("Project already exists".to_string()));
+            .withf(|_, device_names, exclude_devices, _| {
+                device_names == ["USBkey", "NAS"] && exclude_devices == ["NAS"]
+            })
+            .returning(|_, _, _, _| Ok(vec![("Repo".to_string(), Ok(BackupSummary::default()))]));
+        let console = MockUserInterface::new().expect_one_write("  - Repo: done");
 
-        let console = MockUserInterface::new()
-            .expect_one_write("What is the name of the project?")
-            .expect_one_read("MyProject")
-            .expect_one_write("What is the path to the project?")
-            .expect_one_write("Enter a valid Unix path")
-            .expect_one_read("/mnt/projects/myproject")
-            .expect_one_write("Project already exists");
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup run-all-under /home/user USBkey --exclude-device NAS NAS"
+        );
+    }
 
+    #[test]
+    fn when_running_backup_of_all_projects_under_a_path_recent_ones_shall_be_reported_skipped() {
+        let project_operations = MockProjectOperations::new();
         let device_operations = MockDeviceOperations::new();
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_backup_projects_under()
+            .times(1)
+            .withf(|_, _, _, skip_if_backed_up_within| {
+                *skip_if_backed_up_within == Some(Duration::from_secs(60 * 60))
+            })
+            .returning(|_, _, _, _| {
+                Ok(vec![
+                    (
+                        "Repo".to_string(),
+                        Ok(BackupSummary {
+                            skipped_too_recent: true,
+                            ..Default::default()
+                        }),
+                    ),
+                    ("Photos".to_string(), Ok(BackupSummary::default())),
+                ])
+            });
+        let console = MockUserInterface::new()
+            .expect_one_write("  - Repo: skipped, backed up recently")
+            .expect_one_write("  - Photos: done");
+
         run_command!(
             console,
             device_operations,
             project_operations,
             backup_operations,
-            "project new"
+            "backup run-all-under /home/user USBkey --exclude-unchanged-since 60"
         );
     }
 
     #[test]
-    fn when_removing_existing_project_it_shall_send_remove_command() {
+    fn when_running_backup_under_a_path_with_an_interval_too_large_it_shall_fail() {
+        let project_operations = MockProjectOperations::new();
+        let device_operations = MockDeviceOperations::new();
         let backup_operations = MockBackupOperations::new();
-        let console = MockUserInterface::new().expect_one_write("Removed project successfully");
+        let console =
+            MockUserInterface::new().expect_one_write("Invalid interval: 307445734561825861");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            "backup run-all-under /home/user USBkey --exclude-unchanged-since 307445734561825861"
+        );
+    }
+
+    #[test]
+    fn when_running_backup_with_stats_it_shall_display_metrics() {
+        let project_operations = MockProjectOperations::new();
         let device_operations = MockDeviceOperations::new();
-        let mut project_operations = MockProjectOperations::new();
-        project_operations
-            .expect_remove_project_by_name()
+        let mut backup_operations = MockBackupOperations::new();
+        backup_operations
+            .expect_backup_project_to_device()
             .times(1)
-            .with(eq("MyProject".to_string()))
-            .return_const(Ok(()));
+            .returning(|_, _, _| {
+                Ok(BackupSummary {
+                    skipped_large_files: vec![],
+                    metrics: BackupExecutionMetrics {
+                        walk_duration: Duration::from_millis(1500),
+                        changed_files_count: 3,
+                        total_files_count: 10,
+                        archive_write_duration: Duration::from_secs(2),
+                        finalize_duration: Duration::from_millis(20),
+                        bytes_written: 2048,
+                    },
+                    ..Default::default()
+                })
+            });
+        let console = MockUserInterface::new()
+            .expect_one_write("Backup statistics:")
+            .expect_one_write("  - Changed files: 3 out of 10")
+            .expect_one_write("  - Walk: 1.5s")
+            .expect_one_write("  - Archive write: 2s")
+            .expect_one_write("  - Bytes written: 2048")
+            .expect_one_write("  - Finalize: 20ms");
 
         run_command!(
             console,
             device_operations,
             project_operations,
             backup_operations,
-            "project remove MyProject"
+            "backup run MyProject USBkey --stats"
         );
     }
-
     #[test]
-    fn when_removing_project_but_without_name_it_shall_fail() {
+    fn when_asking_backup_health_summary_it_shall_display_the_dashboard() {
+        let device_operations = MockDeviceOperations::new();
         let project_operations = MockProjectOperations::new();
-        let console = MockUserInterface::new().expect_one_write(INVALID_COMMAND);
+        let backup_operations = MockBackupOperations::new();
+        let mut config_operations = MockConfigOperations::new();
+        config_operations
+            .expect_get_requirement_summary()
+            .times(1)
+            .returning(|| {
+                Ok(RequirementSummary {
+                    total_projects: 12,
+                    compliant_projects: 8,
+                    required_copies: 36,
+                    present_copies: 24,
+                    ..Default::default()
+                })
+            });
+        let console = MockUserInterface::new()
+            .expect_one_write("8/12 projects compliant, 24/36 required copies present");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            config_operations,
+            "backup health --summary"
+        );
+    }
+
+    #[test]
+    fn when_exporting_config_it_shall_send_export_command() {
         let device_operations = MockDeviceOperations::new();
+        let project_operations = MockProjectOperations::new();
         let backup_operations = MockBackupOperations::new();
+        let mut config_operations = MockConfigOperations::new();
+        config_operations
+            .expect_export_config()
+            .times(1)
+            .with(eq("/tmp/hibernacli.toml"))
+            .return_const(Ok(()));
+        let console = MockUserInterface::new()
+            .expect_one_write("Configuration exported to /tmp/hibernacli.toml");
 
         run_command!(
             console,
             device_operations,
             project_operations,
             backup_operations,
-            "project remove"
+            config_operations,
+            "config export /tmp/hibernacli.toml"
         );
     }
 
     #[test]
-    fn when_removing_project_with_underlying_error_it_shall_print_it() {
+    fn when_running_with_context_errors_shall_include_the_application_metadata() {
+        let device_operations = MockDeviceOperations::new();
+        let project_operations = MockProjectOperations::new();
         let backup_operations = MockBackupOperations::new();
-        let mut project_operations = MockProjectOperations::new();
-        project_operations
-            .expect_remove_project_by_name()
+        let mut config_operations = MockConfigOperations::new();
+        config_operations
+            .expect_export_config()
             .times(1)
-            .return_const(Err("Project not found".to_string()));
+            .return_const(Err("Permission denied".to_string()));
+        let console = MockUserInterface::new().expect_one_write(
+            "Permission denied (hibernacli 1.2.3, while reading /home/user/.config/hibernacli/config.toml, at 1970-01-01 00:00:00 UTC)",
+        );
 
-        let console = MockUserInterface::new().expect_one_write("Project not found");
+        let mut command_runner = CommandRunner::new(
+            console,
+            &device_operations,
+            &project_operations,
+            &backup_operations,
+            &config_operations,
+        );
+        command_runner.run_with_context(
+            vec![
+                "hibernacli".to_string(),
+                "config".to_string(),
+                "export".to_string(),
+                "/tmp/hibernacli.toml".to_string(),
+            ],
+            RunContext {
+                app_version: "1.2.3",
+                config_path: Some(PathBuf::from("/home/user/.config/hibernacli/config.toml")),
+                timestamp: UNIX_EPOCH,
+            },
+        );
+    }
 
+    #[test]
+    fn when_importing_config_fails_it_shall_display_the_error() {
         let device_operations = MockDeviceOperations::new();
+        let project_operations = MockProjectOperations::new();
+        let backup_operations = MockBackupOperations::new();
+        let mut config_operations = MockConfigOperations::new();
+        config_operations
+            .expect_import_config()
+            .times(1)
+            .with(eq("/missing.toml"))
+            .return_const(Err("Cannot read /missing.toml: not found".to_string()));
+        let console =
+            MockUserInterface::new().expect_one_write("Cannot read /missing.toml: not found");
+
         run_command!(
             console,
             device_operations,
             project_operations,
             backup_operations,
-            "project remove MyProject"
+            config_operations,
+            "config import /missing.toml"
         );
     }
 
     #[test]
-    fn when_removing_project_using_rm_command_it_shall_remove_project_too() {
+    fn when_checking_a_consistent_config_it_shall_say_so() {
+        let device_operations = MockDeviceOperations::new();
+        let project_operations = MockProjectOperations::new();
         let backup_operations = MockBackupOperations::new();
-        let console = MockUserInterface::new().expect_one_write("Removed project successfully");
+        let mut config_operations = MockConfigOperations::new();
+        config_operations
+            .expect_integrity_check()
+            .times(1)
+            .returning(|| {
+                Ok(IntegrityReport {
+                    unavailable_devices: vec!["NAS".to_string()],
+                    ..Default::default()
+                })
+            });
+        let console = MockUserInterface::new()
+            .expect_one_write("Device NAS is not available, it was not checked")
+            .expect_one_write("Configuration is consistent");
+
+        run_command!(
+            console,
+            device_operations,
+            project_operations,
+            backup_operations,
+            config_operations,
+            "config check"
+        );
+    }
+
+    #[test]
+    fn when_checking_an_inconsistent_config_it_shall_display_the_issues() {
         let device_operations = MockDeviceOperations::new();
-        let mut project_operations = MockProjectOperations::new();
-        project_operations
-            .expect_remove_project_by_name()
+        let project_operations = MockProjectOperations::new();
+        let backup_operations = MockBackupOperations::new();
+        let mut config_operations = MockConfigOperations::new();
+        config_operations
+            .expect_integrity_check()
             .times(1)
-            .with(eq("MyProject".to_string()))
-            .return_const(Ok(()));
+            .returning(|| {
+                Ok(IntegrityReport {
+                    missing_project_paths: vec!["MyProject".to_string()],
+                    unavailable_devices: vec![],
+                    index_mismatches: vec![IndexMismatch {
+                        device_name: "USBkey".to_string(),
+                        project_name: "OtherProject".to_string(),
+                        reason: "Archives found without current index".to_string(),
+                    }],
+                    orphaned_project_directories: vec![(
+                        "USBkey".to_string(),
+                        "OldProject".to_string(),
+                    )],
+                })
+            });
+        let console = MockUserInterface::new()
+            .expect_one_write("Configuration inconsistencies:")
+            .expect_one_write("  - Location of project MyProject does not exist")
+            .expect_one_write(
+                "  - Project OtherProject on USBkey: Archives found without current index",
+            )
+            .expect_one_write(
+                "  - Directory OldProject on USBkey belongs to no registered project",
+            );
 
         run_command!(
             console,
             device_operations,
             project_operations,
             backup_operations,
-            "project rm MyProject"
+            config_operations,
+            "config check"
         );
     }
 }
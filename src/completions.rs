@@ -0,0 +1,317 @@
+use std::str::FromStr;
+
+/// Shell targeted by a completion script
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl FromStr for Shell {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bash" => Ok(Shell::Bash),
+            "zsh" => Ok(Shell::Zsh),
+            "fish" => Ok(Shell::Fish),
+            _ => Err(format!(
+                "Invalid shell: {}. Possible shells are: bash, zsh, fish",
+                s
+            )),
+        }
+    }
+}
+
+/// Names of the current configuration, offered as arguments of the commands
+/// They are written in the script, so it has to be generated again when they change
+#[derive(Debug, Default)]
+pub struct CompletionNames {
+    pub device_types: Vec<String>,
+    pub devices: Vec<String>,
+    pub projects: Vec<String>,
+}
+
+const TOP_LEVEL_COMMANDS: [&str; 8] = [
+    "help",
+    "--version",
+    "--log-level",
+    "--completions",
+    "device",
+    "project",
+    "backup",
+    "config",
+];
+
+// Subcommands of each command, matching the ones handled by the command runner
+const SUBCOMMANDS: [(&str, &[&str]); 4] = [
+    (
+        "device",
+        &["ls", "list", "new", "rm", "remove", "list-orphans"],
+    ),
+    (
+        "project",
+        &[
+            "ls",
+            "list",
+            "new",
+            "add",
+            "rm",
+            "remove",
+            "set-location",
+            "rename",
+            "clone",
+            "describe",
+            "stats",
+        ],
+    ),
+    (
+        "backup",
+        &[
+            "run",
+            "auto",
+            "watch",
+            "run-all-under",
+            "status",
+            "health",
+            "restore",
+            "rebuild-index",
+            "repair-index",
+            "initialize",
+            "list-indexed-files",
+            "import-index",
+            "search",
+            "deduplicate-report",
+            "compare",
+        ],
+    ),
+    ("config", &["check", "export", "import"]),
+];
+
+// Quote a name for bash and zsh, where nothing is expanded between single quotes
+fn quote_posix(name: &str) -> String {
+    format!("'{}'", name.replace('\'', r"'\''"))
+}
+
+// Quote a name for fish, where only \' and \\ are escaped between single quotes
+fn quote_fish(name: &str) -> String {
+    format!("'{}'", name.replace('\\', r"\\").replace('\'', r"\'"))
+}
+
+fn quote_all(names: &[String], quote: fn(&str) -> String) -> String {
+    names
+        .iter()
+        .map(|name| quote(name))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Script completing the commands, then the device types after `device new`,
+/// the device names for the other device commands, and the project and device
+/// names for the project and backup commands
+/// Names are quoted, so that spaces or shell syntax in them are completed as is
+pub fn generate_completions(shell: Shell, names: &CompletionNames) -> String {
+    let top_level = TOP_LEVEL_COMMANDS.join(" ");
+    let all_names = [names.projects.clone(), names.devices.clone()].concat();
+
+    match shell {
+        Shell::Bash | Shell::Zsh => {
+            let device_types = quote_all(&names.device_types, quote_posix);
+            let devices = quote_all(&names.devices, quote_posix);
+            let names = quote_all(&all_names, quote_posix);
+            let subcommands = SUBCOMMANDS
+                .iter()
+                .map(|(command, subcommands)| {
+                    format!(
+                        "                {}) candidates=({}) ;;\n",
+                        command,
+                        subcommands.join(" ")
+                    )
+                })
+                .collect::<String>();
+            if shell == Shell::Bash {
+                format!(
+                    r#"_hibernacli() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}"
+    local -a candidates=()
+    case "$COMP_CWORD" in
+        1) candidates=({top_level}) ;;
+        2)
+            case "${{COMP_WORDS[1]}}" in
+{subcommands}            esac
+            ;;
+        *)
+            case "${{COMP_WORDS[1]}} ${{COMP_WORDS[2]}}" in
+                "device new") candidates=({device_types}) ;;
+                device\ *) candidates=({devices}) ;;
+                *) candidates=({names}) ;;
+            esac
+            ;;
+    esac
+    COMPREPLY=()
+    local candidate
+    for candidate in "${{candidates[@]}}"; do
+        if [[ "$candidate" == "$cur"* ]]; then
+            COMPREPLY+=("$(printf '%q' "$candidate")")
+        fi
+    done
+}}
+complete -F _hibernacli hibernacli
+"#
+                )
+            } else {
+                format!(
+                    r#"#compdef hibernacli
+_hibernacli() {{
+    local -a candidates
+    case $CURRENT in
+        2) candidates=({top_level}) ;;
+        3)
+            case $words[2] in
+{subcommands}            esac
+            ;;
+        *)
+            case "$words[2] $words[3]" in
+                "device new") candidates=({device_types}) ;;
+                device\ *) candidates=({devices}) ;;
+                *) candidates=({names}) ;;
+            esac
+            ;;
+    esac
+    compadd -a candidates
+}}
+compdef _hibernacli hibernacli
+"#
+                )
+            }
+        }
+        Shell::Fish => {
+            // The candidates given to -a are expanded by fish, so they are quoted twice
+            let candidates = |names: &[String]| quote_fish(&quote_all(names, quote_fish));
+            let device_types = candidates(&names.device_types);
+            let devices = candidates(&names.devices);
+            let names = candidates(&all_names);
+            let argument_count = "count (commandline -opc)";
+            let mut script = format!(
+                "complete -c hibernacli -f\n\
+                 complete -c hibernacli -n \"test ({argument_count}) -eq 1\" -a \"{top_level}\"\n"
+            );
+            for (command, subcommands) in SUBCOMMANDS {
+                script += &format!(
+                    "complete -c hibernacli -n \"test ({argument_count}) -eq 2; and test (commandline -opc)[2] = {}\" -a \"{}\"\n",
+                    command,
+                    subcommands.join(" ")
+                );
+            }
+            let from_third =
+                format!("test ({argument_count}) -ge 3; and test (commandline -opc)[2]");
+            script += &format!(
+                "complete -c hibernacli -n \"{from_third} = device; and test (commandline -opc)[3] = new\" -a {device_types}\n\
+                 complete -c hibernacli -n \"{from_third} = device; and test (commandline -opc)[3] != new\" -a {devices}\n\
+                 complete -c hibernacli -n \"{from_third} != device\" -a {names}\n"
+            );
+            script
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names() -> CompletionNames {
+        CompletionNames {
+            device_types: vec!["MountedFolder".to_string()],
+            devices: vec!["USBkey".to_string()],
+            projects: vec!["MyProject".to_string()],
+        }
+    }
+
+    #[test]
+    fn bash_script_shall_complete_subcommands_and_names() {
+        let script = generate_completions(Shell::Bash, &names());
+
+        assert!(script.contains(r#"backup) candidates=(run auto watch"#));
+        assert!(script.contains(r#""device new") candidates=('MountedFolder') ;;"#));
+        assert!(script.contains(r#"*) candidates=('MyProject' 'USBkey') ;;"#));
+        assert!(script.ends_with("complete -F _hibernacli hibernacli\n"));
+    }
+
+    #[test]
+    fn names_shall_be_quoted_for_each_shell() {
+        let names = CompletionNames {
+            device_types: vec![],
+            devices: vec![],
+            projects: vec!["My \"project\" $(rm -rf ~) it's".to_string()],
+        };
+
+        let bash_script = generate_completions(Shell::Bash, &names);
+        assert!(bash_script.contains(r#"*) candidates=('My "project" $(rm -rf ~) it'\''s') ;;"#));
+        let zsh_script = generate_completions(Shell::Zsh, &names);
+        assert!(zsh_script.contains(r#"*) candidates=('My "project" $(rm -rf ~) it'\''s') ;;"#));
+        let fish_script = generate_completions(Shell::Fish, &names);
+        assert!(fish_script.contains(r#"!= device" -a '\'My "project" $(rm -rf ~) it\\\'s\''"#));
+    }
+
+    #[test]
+    fn each_shell_shall_offer_every_subcommand() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
+            let script = generate_completions(shell, &names());
+            for (_, subcommands) in SUBCOMMANDS {
+                assert!(script.contains(&subcommands.join(" ")), "{:?}", shell);
+            }
+        }
+    }
+
+    // Subcommands matched by run_<command>_command in cli.rs, from the patterns of its
+    // match arms, or from their guard for the slice patterns of the config command
+    fn dispatched_subcommands(command: &str) -> Vec<String> {
+        let cli_source = include_str!("cli.rs");
+        let start = cli_source
+            .find(&format!("fn run_{}_command(", command))
+            .unwrap();
+        let body = &cli_source[start..];
+        let body = &body[..body.find("\n    fn ").unwrap()];
+
+        let mut subcommands = body
+            .lines()
+            .filter_map(|line| line.trim().split_once("=>").map(|(arm, _)| arm))
+            .filter_map(|arm| {
+                let (pattern, guard) = arm.split_once(" if ").unwrap_or((arm, ""));
+                if pattern.starts_with('"') {
+                    Some(pattern)
+                } else if pattern.starts_with('[') {
+                    guard.split_once("command ==").map(|(_, names)| names)
+                } else {
+                    None
+                }
+            })
+            .flat_map(|names| names.split('"').skip(1).step_by(2))
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>();
+        subcommands.sort();
+        subcommands.dedup();
+        subcommands
+    }
+
+    #[test]
+    fn subcommands_shall_match_the_ones_handled_by_the_command_runner() {
+        for (command, subcommands) in SUBCOMMANDS {
+            let mut subcommands = subcommands
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>();
+            subcommands.sort();
+            assert_eq!(subcommands, dispatched_subcommands(command), "{}", command);
+        }
+    }
+
+    #[test]
+    fn unknown_shell_shall_be_rejected() {
+        assert_eq!(
+            Shell::from_str("powershell"),
+            Err("Invalid shell: powershell. Possible shells are: bash, zsh, fish".to_string())
+        );
+    }
+}
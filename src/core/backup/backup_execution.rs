@@ -1,65 +1,202 @@
-use std::{fmt::Display, fs::File, path::PathBuf};
-use walkdir::WalkDir;
+use std::{
+    collections::HashSet,
+    fmt::Display,
+    fs::FileType,
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use notify::{EventKind, RecursiveMode, Watcher};
+use tracing::{info, warn};
 
 use crate::core::{
     device::{ArchiveError, ArchiveWriter},
-    util::timestamps::{TimeStampError, Timestamp},
+    util::{
+        sparse_files::find_data_segments,
+        timestamps::{TimeStampError, Timestamp},
+    },
 };
 
-use super::backup_index::{BackupIndex, ToBuffer};
+use super::{
+    backup_index::{BackupIndex, ToBuffer},
+    backup_priority::BackupPriority,
+    file_system_provider::{
+        describe_special_file_type, FileSystemProvider, StandardFileSystemProvider,
+    },
+};
 
 #[derive(Debug)]
 pub enum BackupExecutionError {
-    IoError(std::io::Error),
+    IoError(std::io::Error, PathBuf),
     SystemTimeError(std::time::SystemTimeError),
-    StripPrefixError,
-    ArchiveError(String),
+    StripPrefixError(PathBuf),
+    ArchiveError(String, PathBuf),
+    WatchError(notify::Error, PathBuf),
+    UnsupportedFileType(PathBuf, FileType),
 }
-impl From<std::path::StripPrefixError> for BackupExecutionError {
-    fn from(_: std::path::StripPrefixError) -> Self {
-        Self::StripPrefixError
+impl BackupExecutionError {
+    // Path of the file or directory the error is about, if known
+    pub fn display_path(&self) -> Option<&Path> {
+        match self {
+            Self::IoError(_, path)
+            | Self::StripPrefixError(path)
+            | Self::ArchiveError(_, path)
+            | Self::WatchError(_, path)
+            | Self::UnsupportedFileType(path, _) => Some(path),
+            Self::SystemTimeError(_) => None,
+        }
     }
 }
-impl From<std::io::Error> for BackupExecutionError {
-    fn from(e: std::io::Error) -> Self {
-        Self::IoError(e)
+impl Display for BackupExecutionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let message = match self {
+            Self::IoError(e, _) => format!("IO error: {}", e),
+            Self::SystemTimeError(e) => format!("System time error: {}", e),
+            Self::StripPrefixError(_) => "Strip prefix error".to_string(),
+            Self::ArchiveError(e, _) => format!("Archive error: {}", e),
+            Self::WatchError(e, _) => format!("Watch error: {}", e),
+            Self::UnsupportedFileType(_, file_type) => format!(
+                "Unsupported file type: {}",
+                describe_special_file_type(file_type)
+            ),
+        };
+        match self.display_path() {
+            Some(path) => write!(f, "{} ({})", message, path.display()),
+            None => write!(f, "{}", message),
+        }
     }
 }
-impl From<TimeStampError> for BackupExecutionError {
-    fn from(e: TimeStampError) -> Self {
-        match e {
-            TimeStampError::IoError(e) => Self::IoError(e),
-            TimeStampError::SystemTimeError(e) => Self::SystemTimeError(e),
-        }
+
+// Attach the path being processed to the errors of the backup steps
+trait WithPath<T> {
+    fn with_path(self, path: &Path) -> Result<T, BackupExecutionError>;
+}
+impl<T> WithPath<T> for Result<T, notify::Error> {
+    fn with_path(self, path: &Path) -> Result<T, BackupExecutionError> {
+        self.map_err(|e| BackupExecutionError::WatchError(e, path.to_path_buf()))
     }
 }
-impl From<walkdir::Error> for BackupExecutionError {
-    fn from(e: walkdir::Error) -> Self {
-        Self::IoError(std::io::Error::from(e))
+impl<T> WithPath<T> for Result<T, std::io::Error> {
+    fn with_path(self, path: &Path) -> Result<T, BackupExecutionError> {
+        self.map_err(|e| BackupExecutionError::IoError(e, path.to_path_buf()))
     }
 }
-impl From<ArchiveError> for BackupExecutionError {
-    fn from(e: ArchiveError) -> Self {
-        Self::ArchiveError(e.message)
+impl<T> WithPath<T> for Result<T, std::path::StripPrefixError> {
+    fn with_path(self, path: &Path) -> Result<T, BackupExecutionError> {
+        self.map_err(|_| BackupExecutionError::StripPrefixError(path.to_path_buf()))
     }
 }
-impl Display for BackupExecutionError {
+impl<T> WithPath<T> for Result<T, TimeStampError> {
+    fn with_path(self, path: &Path) -> Result<T, BackupExecutionError> {
+        self.map_err(|e| match e {
+            TimeStampError::IoError(e) => BackupExecutionError::IoError(e, path.to_path_buf()),
+            TimeStampError::SystemTimeError(e) => BackupExecutionError::SystemTimeError(e),
+        })
+    }
+}
+impl<T> WithPath<T> for Result<T, ArchiveError> {
+    fn with_path(self, path: &Path) -> Result<T, BackupExecutionError> {
+        self.map_err(|e| BackupExecutionError::ArchiveError(e.message, path.to_path_buf()))
+    }
+}
+
+/// Anomalies found during a backup, which did not prevent it
+#[derive(Debug, Clone, PartialEq)]
+pub enum BackupExecutionWarning {
+    /// The content of the file changed while its metadata did not, it was archived again
+    PossibleBitrot(PathBuf),
+}
+impl Display for BackupExecutionWarning {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::IoError(e) => write!(f, "IO error: {}", e),
-            Self::SystemTimeError(e) => write!(f, "System time error: {}", e),
-            Self::StripPrefixError => write!(f, "Strip prefix error"),
-            Self::ArchiveError(e) => write!(f, "Archive error: {}", e),
+            Self::PossibleBitrot(path) => write!(
+                f,
+                "Possible bitrot: {} changed without its metadata changing",
+                path.display()
+            ),
         }
     }
 }
 
+/// What to do with the FIFOs, sockets and device files of a project
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum SpecialFilePolicy {
+    /// Fail the backup, so that no file is silently left out
+    #[default]
+    Fail,
+    /// Leave them out of the backup, and list them in the summary
+    Skip,
+}
+
+/// Outcome of a successful backup execution
+#[derive(Debug, Default, PartialEq)]
+pub struct BackupSummary {
+    /// Files left out of the backup because they exceed the maximum file size, with their size
+    pub skipped_large_files: Vec<(PathBuf, u64)>,
+    /// Special files left out of the backup, when they are skipped
+    pub skipped_special_files: Vec<PathBuf>,
+    pub metrics: BackupExecutionMetrics,
+
+    /// Set when the backup was not run because the previous one is too recent
+    pub skipped_too_recent: bool,
+    /// Number of old archives removed after the backup to honor the retention limit
+    pub pruned_archives: usize,
+    pub warnings: Vec<BackupExecutionWarning>,
+}
+
+/// Time spent in each phase of a backup execution
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BackupExecutionMetrics {
+    /// Walking the project and comparing it to the index, archive writes excluded
+    pub walk_duration: Duration,
+    pub changed_files_count: usize,
+    pub total_files_count: usize,
+    /// Adding the changed entries to the archive
+    pub archive_write_duration: Duration,
+    pub finalize_duration: Duration,
+    /// Size of the changed files, as reported by the archive writer
+    pub bytes_written: u64,
+}
+
+/// What a backup would write, computed without touching any archive
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DryRunResult {
+    pub files_to_add: usize,
+    pub files_to_modify: usize,
+    pub files_to_delete: usize,
+    /// Total size of the files to add or modify
+    pub bytes_to_transfer: u64,
+}
+
 pub struct BackupExecution {
     index: BackupIndex,
     new_index: BackupIndex,
     root_path: PathBuf,
     deleted_entries: Vec<PathBuf>,
+    priority: BackupPriority,
+    max_file_size: Option<u64>,
+    skipped_large_files: Vec<(PathBuf, u64)>,
+    special_file_policy: SpecialFilePolicy,
+    skipped_special_files: Vec<PathBuf>,
+    file_system: Box<dyn FileSystemProvider>,
+
+    // Hash the files whose metadata did not change, to detect silent corruption
+    verify_checksums: bool,
+    warnings: Vec<BackupExecutionWarning>,
+    tags: Vec<String>,
+
+    // Set from another thread to stop watching the project
+    stop_signal: Arc<AtomicBool>,
 }
+
+// Longest wait for filesystem events before checking the stop signal again
+const WATCH_POLL_INTERVAL: Duration = Duration::from_millis(100);
 impl BackupExecution {
     pub fn new(index: BackupIndex, root_path: PathBuf) -> Self {
         Self {
@@ -67,83 +204,390 @@ impl BackupExecution {
             root_path,
             new_index: BackupIndex::new(),
             deleted_entries: Vec::new(),
+            priority: BackupPriority::default(),
+            max_file_size: None,
+            skipped_large_files: Vec::new(),
+            special_file_policy: SpecialFilePolicy::default(),
+            skipped_special_files: Vec::new(),
+            file_system: Box::new(StandardFileSystemProvider),
+            verify_checksums: false,
+            warnings: Vec::new(),
+            tags: Vec::new(),
+            stop_signal: Arc::new(AtomicBool::new(false)),
         }
     }
 
+    #[cfg(test)]
+    pub fn with_file_system_provider(mut self, file_system: Box<dyn FileSystemProvider>) -> Self {
+        self.file_system = file_system;
+        self
+    }
+
+    pub fn with_priority(mut self, priority: BackupPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_max_file_size(mut self, max_file_size: Option<u64>) -> Self {
+        self.max_file_size = max_file_size;
+        self
+    }
+
+    pub fn with_special_file_policy(mut self, special_file_policy: SpecialFilePolicy) -> Self {
+        self.special_file_policy = special_file_policy;
+        self
+    }
+
+    pub fn with_checksum_verify(mut self, verify_checksums: bool) -> Self {
+        self.verify_checksums = verify_checksums;
+        self
+    }
+
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    pub fn with_stop_signal(mut self, stop_signal: Arc<AtomicBool>) -> Self {
+        self.stop_signal = stop_signal;
+        self
+    }
+
     pub fn execute(
+        &mut self,
+        archiver_writer: Box<dyn ArchiveWriter>,
+    ) -> Result<BackupSummary, BackupExecutionError> {
+        let metrics = self.execute_with_metrics(archiver_writer)?;
+        Ok(BackupSummary {
+            skipped_large_files: self.skipped_large_files.clone(),
+            skipped_special_files: self.skipped_special_files.clone(),
+            metrics,
+            skipped_too_recent: false,
+            pruned_archives: 0,
+            warnings: self.warnings.clone(),
+        })
+    }
+
+    pub fn execute_with_metrics(
         &mut self,
         mut archiver_writer: Box<dyn ArchiveWriter>,
-    ) -> Result<(), BackupExecutionError> {
+    ) -> Result<BackupExecutionMetrics, BackupExecutionError> {
+        let _priority = self.priority.apply_to_current_process();
+        let mut metrics = BackupExecutionMetrics::default();
+        let walk_start = Instant::now();
+        let mut walked_paths = Vec::new();
+
         // Walk through the folder at root_path, and mark visited entries
         // in the index
-        for entry in WalkDir::new(&self.root_path)
-            .min_depth(1)
-            .sort_by(|a, b| a.file_name().cmp(b.file_name()))
-        {
-            let entry = entry?;
-            let path_relative_to_root = entry.path().strip_prefix(&self.root_path)?;
-            let metadata = entry.metadata()?;
-            let ctime = metadata.created().ms_since_epoch()?;
-            let mtime = metadata.modified().ms_since_epoch()?;
-            let size = metadata.len();
-
-            if self
+        for entry in self.file_system.walk_dir(&self.root_path) {
+            let entry = entry.with_path(&self.root_path)?;
+            let path_relative_to_root = entry
+                .path
+                .strip_prefix(&self.root_path)
+                .with_path(&entry.path)?;
+            let ctime = entry.created.ms_since_epoch().with_path(&entry.path)?;
+            let mtime = entry.modified.ms_since_epoch().with_path(&entry.path)?;
+            let size = entry.size;
+
+            if let Some(file_type) = entry.special_file_type {
+                match self.special_file_policy {
+                    SpecialFilePolicy::Skip => {
+                        warn!("Skipping special file {:?}", path_relative_to_root);
+                        self.skipped_special_files
+                            .push(PathBuf::from(path_relative_to_root));
+                        continue;
+                    }
+                    SpecialFilePolicy::Fail => {
+                        return Err(BackupExecutionError::UnsupportedFileType(
+                            entry.path.clone(),
+                            file_type,
+                        ))
+                    }
+                }
+            }
+
+            // Files above the size limit are not archived. A version archived by a previous
+            // backup is kept in the new index, so that it is not recorded as deleted
+            if entry.is_file && self.max_file_size.is_some_and(|max| size > max) {
+                warn!(
+                    "Skipping {:?}: {} bytes is above the maximum file size",
+                    path_relative_to_root, size
+                );
+                self.skipped_large_files
+                    .push((PathBuf::from(path_relative_to_root), size));
+                self.index.mark_visited(path_relative_to_root);
+                self.new_index
+                    .copy_entry_from(&self.index, path_relative_to_root);
+                walked_paths.push(PathBuf::from(path_relative_to_root));
+                continue;
+            }
+
+            metrics.total_files_count += 1;
+            let mut has_changed = self
                 .index
-                .has_changed(path_relative_to_root, ctime, mtime, size)
+                .has_changed(path_relative_to_root, ctime, mtime, size);
+            let stored_checksum = self
+                .index
+                .get_entry(path_relative_to_root)
+                .and_then(|entry| entry.checksum())
+                .map(|checksum| checksum.to_string());
+            let checksum = if entry.is_file && self.verify_checksums {
+                Some(
+                    compute_checksum(self.file_system.as_ref(), &entry.path)
+                        .with_path(&entry.path)?,
+                )
+            } else {
+                None
+            };
+
+            // Unchanged metadata with a different content is likely a disk corruption, the
+            // file is archived anyway so that the previous archives keep the sane version
+            if !has_changed
+                && checksum
+                    .as_ref()
+                    .zip(stored_checksum.as_ref())
+                    .is_some_and(|(checksum, stored)| checksum != stored)
             {
-                if metadata.is_dir() {
-                    archiver_writer.add_directory(
-                        &entry.path(),
-                        &PathBuf::from(path_relative_to_root),
-                        ctime,
-                        mtime,
-                    )?;
-                } else if metadata.is_file() {
-                    let mut file = File::open(entry.path())?;
-                    archiver_writer.add_file(
-                        &mut file,
-                        &PathBuf::from(path_relative_to_root),
-                        ctime,
-                        mtime,
-                        size,
-                    )?;
+                warn!(
+                    "{:?} changed without its metadata changing, it may be corrupted",
+                    path_relative_to_root
+                );
+                self.warnings.push(BackupExecutionWarning::PossibleBitrot(
+                    path_relative_to_root.to_path_buf(),
+                ));
+                has_changed = true;
+            }
+
+            if has_changed {
+                metrics.changed_files_count += 1;
+                let write_start = Instant::now();
+                if entry.is_dir {
+                    archiver_writer
+                        .add_directory(
+                            &entry.path,
+                            &PathBuf::from(path_relative_to_root),
+                            ctime,
+                            mtime,
+                        )
+                        .with_path(&entry.path)?;
+                } else if entry.is_file {
+                    let mut file = self
+                        .file_system
+                        .read_file(&entry.path)
+                        .with_path(&entry.path)?;
+                    let xattrs = read_extended_attributes(&entry.path);
+                    if !xattrs.is_empty() {
+                        archiver_writer
+                            .add_extended_attributes(&PathBuf::from(path_relative_to_root), xattrs)
+                            .with_path(&entry.path)?;
+                    }
+                    let data_segments = find_data_segments(&file, size).with_path(&entry.path)?;
+                    match data_segments {
+                        Some(data_segments) => archiver_writer.add_sparse_file(
+                            &mut file,
+                            &PathBuf::from(path_relative_to_root),
+                            ctime,
+                            mtime,
+                            size,
+                            &data_segments,
+                        ),
+                        None => archiver_writer.add_file(
+                            &mut file,
+                            &PathBuf::from(path_relative_to_root),
+                            ctime,
+                            mtime,
+                            size,
+                        ),
+                    }
+                    .with_path(&entry.path)?;
                 } else {
-                    return Err(BackupExecutionError::ArchiveError(format!(
-                        "Unsupported entry type: {:?}",
-                        path_relative_to_root
-                    )));
+                    return Err(BackupExecutionError::ArchiveError(
+                        "Unsupported entry type".to_string(),
+                        entry.path.clone(),
+                    ));
                 }
+                metrics.archive_write_duration += write_start.elapsed();
             }
 
             self.index.mark_visited(&path_relative_to_root);
             self.new_index
                 .insert(ctime, mtime, size, PathBuf::from(path_relative_to_root));
+            walked_paths.push(PathBuf::from(path_relative_to_root));
+
+            // Checksums of unchanged files are kept for the next verifications
+            let checksum = checksum.or(stored_checksum.filter(|_| !has_changed));
+            if let Some(checksum) = checksum {
+                self.new_index.set_checksum(path_relative_to_root, checksum);
+            }
         }
 
         for entry in self.index.enumerate_unvisited_entries() {
             self.deleted_entries.push(PathBuf::from(entry.path()));
         }
+        metrics.walk_duration = walk_start
+            .elapsed()
+            .saturating_sub(metrics.archive_write_duration);
+        metrics.bytes_written = archiver_writer.bytes_written();
+
+        let finalize_start = Instant::now();
+        let new_index = self.new_index.to_buffer().with_path(&self.root_path)?;
+        archiver_writer
+            .finalize(&self.deleted_entries, &new_index, &self.tags)
+            .with_path(&self.root_path)?;
+        metrics.finalize_duration = finalize_start.elapsed();
 
-        archiver_writer.finalize(&self.deleted_entries, &self.new_index.to_buffer()?)?;
+        // The written index is the reference of the next execution, it shall only
+        // keep track of the files found by this walk
+        self.new_index.compact(&walked_paths);
 
+        Ok(metrics)
+    }
+
+    /// Backup the project each time it changes, until the stop signal is set
+    /// The backup starts once no change happened for the debounce duration
+    pub fn execute_watched(
+        &mut self,
+        writer_factory: Box<dyn Fn() -> Box<dyn ArchiveWriter>>,
+        debounce: Duration,
+    ) -> Result<(), BackupExecutionError> {
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender).with_path(&self.root_path)?;
+        watcher
+            .watch(&self.root_path, RecursiveMode::Recursive)
+            .with_path(&self.root_path)?;
+
+        let mut last_change: Option<Instant> = None;
+        while !self.stop_signal.load(Ordering::Relaxed) {
+            match receiver.recv_timeout(debounce.min(WATCH_POLL_INTERVAL)) {
+                // Files are read during the backup itself, accesses are not changes
+                Ok(Ok(event)) if matches!(event.kind, EventKind::Access(_)) => {}
+                Ok(Ok(_)) => last_change = Some(Instant::now()),
+                Ok(Err(e)) => return Err(e).with_path(&self.root_path),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+
+            if last_change.is_some_and(|change| change.elapsed() >= debounce) {
+                last_change = None;
+                let summary = self.execute(writer_factory())?;
+                info!(
+                    "Backed up {:?}, {} files changed",
+                    self.root_path, summary.metrics.changed_files_count
+                );
+                self.prepare_next_execution();
+            }
+        }
         Ok(())
     }
+
+    // The index written by the last execution is the reference of the next one
+    fn prepare_next_execution(&mut self) {
+        self.index = std::mem::replace(&mut self.new_index, BackupIndex::new());
+        self.deleted_entries.clear();
+        self.skipped_large_files.clear();
+        self.skipped_special_files.clear();
+        self.warnings.clear();
+    }
+
+    /// Walk the project like a backup would, only counting the changes
+    pub fn dry_run(&self) -> Result<DryRunResult, BackupExecutionError> {
+        let mut result = DryRunResult::default();
+        let mut visited_paths = HashSet::new();
+
+        for entry in self.file_system.walk_dir(&self.root_path) {
+            let entry = entry.with_path(&self.root_path)?;
+            let path_relative_to_root = entry
+                .path
+                .strip_prefix(&self.root_path)
+                .with_path(&entry.path)?;
+            let size = entry.size;
+            // Special files are never archived, they either fail or are skipped
+            if entry.special_file_type.is_some() {
+                continue;
+            }
+            visited_paths.insert(path_relative_to_root.to_path_buf());
+            // Files above the size limit are not archived, but not deleted either
+            if entry.is_file && self.max_file_size.is_some_and(|max| size > max) {
+                continue;
+            }
+
+            let ctime = entry.created.ms_since_epoch().with_path(&entry.path)?;
+            let mtime = entry.modified.ms_since_epoch().with_path(&entry.path)?;
+            if !self
+                .index
+                .has_changed(path_relative_to_root, ctime, mtime, size)
+            {
+                continue;
+            }
+            match self.index.get_entry(path_relative_to_root) {
+                Some(_) => result.files_to_modify += 1,
+                None => result.files_to_add += 1,
+            }
+            if entry.is_file {
+                result.bytes_to_transfer += size;
+            }
+        }
+
+        result.files_to_delete = self
+            .index
+            .enumerate_entries()
+            .filter(|entry| !visited_paths.contains(entry.path()))
+            .count();
+        Ok(result)
+    }
+}
+
+// BLAKE3 hash of the content of a file, as hex
+fn compute_checksum(file_system: &dyn FileSystemProvider, path: &Path) -> io::Result<String> {
+    let mut file = file_system.read_file(path)?;
+    let mut hasher = blake3::Hasher::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+// Extended attributes of a file, empty when the filesystem does not support them
+#[cfg(target_os = "linux")]
+fn read_extended_attributes(path: &Path) -> Vec<(String, Vec<u8>)> {
+    let names = match xattr::list(path) {
+        Ok(names) => names,
+        Err(e) => {
+            tracing::debug!("Cannot list extended attributes of {:?}: {}", path, e);
+            return Vec::new();
+        }
+    };
+    names
+        .filter_map(|name| {
+            let value = xattr::get(path, &name).ok()??;
+            Some((name.to_string_lossy().to_string(), value))
+        })
+        .collect()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn read_extended_attributes(_path: &Path) -> Vec<(String, Vec<u8>)> {
+    Vec::new()
 }
 
 #[cfg(test)]
 mod tests {
-    use std::path::Path;
+    use std::{cell::RefCell, fs::File, path::Path, rc::Rc};
 
     use super::*;
+    use crate::core::backup::file_system_provider::FileSystemEntry;
     use crate::core::test_utils::fs::create_tmp_dir;
+    use crate::core::util::sparse_files::SparseSegment;
 
     struct MockArchiveWriter {
         added_files: Vec<(PathBuf, u128, u128, u64)>,
+        added_sparse_files: Rc<RefCell<Vec<PathBuf>>>,
+        added_xattrs: Rc<RefCell<Vec<(PathBuf, Vec<(String, Vec<u8>)>)>>>,
     }
     impl MockArchiveWriter {
         fn new() -> Self {
             Self {
                 added_files: Vec::new(),
+                added_sparse_files: Rc::new(RefCell::new(Vec::new())),
+                added_xattrs: Rc::new(RefCell::new(Vec::new())),
             }
         }
     }
@@ -159,6 +603,26 @@ mod tests {
             self.added_files.push((path.clone(), ctime, mtime, size));
             Ok(())
         }
+        fn add_sparse_file(
+            &mut self,
+            _file: &mut File,
+            path: &PathBuf,
+            _ctime: u128,
+            _mtime: u128,
+            _size: u64,
+            _data_segments: &[SparseSegment],
+        ) -> Result<(), ArchiveError> {
+            self.added_sparse_files.borrow_mut().push(path.clone());
+            Ok(())
+        }
+        fn add_extended_attributes(
+            &mut self,
+            path: &PathBuf,
+            xattrs: Vec<(String, Vec<u8>)>,
+        ) -> Result<(), ArchiveError> {
+            self.added_xattrs.borrow_mut().push((path.clone(), xattrs));
+            Ok(())
+        }
         fn add_directory(
             &mut self,
             _src_path: &Path,
@@ -181,11 +645,190 @@ mod tests {
             &mut self,
             _deleted_files: &Vec<PathBuf>,
             _new_index: &Vec<u8>,
+            _tags: &[String],
         ) -> Result<(), ArchiveError> {
             Ok(())
         }
     }
 
+    // Simulated project tree, the files all read as empty
+    struct MockFileSystemProvider {
+        entries: Vec<FileSystemEntry>,
+    }
+    impl MockFileSystemProvider {
+        fn with_files(root: &Path, files: &[(&str, u64)]) -> Self {
+            let time = std::time::UNIX_EPOCH + Duration::from_secs(1_000_000);
+            let entries = files
+                .iter()
+                .map(|(path, size)| FileSystemEntry {
+                    path: root.join(path),
+                    is_dir: false,
+                    is_file: true,
+                    created: time,
+                    modified: time,
+                    size: *size,
+                    special_file_type: None,
+                })
+                .collect();
+            Self { entries }
+        }
+    }
+    impl FileSystemProvider for MockFileSystemProvider {
+        fn walk_dir(
+            &self,
+            _path: &Path,
+        ) -> Box<dyn Iterator<Item = std::io::Result<FileSystemEntry>>> {
+            Box::new(self.entries.clone().into_iter().map(Ok))
+        }
+        fn read_file(&self, _path: &Path) -> std::io::Result<File> {
+            File::open("/dev/null")
+        }
+    }
+
+    #[test]
+    fn test_backup_execution_on_simulated_file_tree() {
+        let root = PathBuf::from("/simulated/project");
+        let file_system = MockFileSystemProvider::with_files(&root, &[("a.txt", 0), ("b.txt", 0)]);
+        let index = BackupIndex::new()
+            .with_entry(1_000_000_000, 1_000_000_000, 0, PathBuf::from("a.txt"))
+            .with_entry(1_000_000_000, 1_000_000_000, 0, PathBuf::from("gone.txt"));
+
+        let mut execution =
+            BackupExecution::new(index, root).with_file_system_provider(Box::new(file_system));
+        let summary = execution
+            .execute(Box::new(MockArchiveWriter::new()))
+            .unwrap();
+
+        assert_eq!(summary.metrics.total_files_count, 2);
+        assert_eq!(summary.metrics.changed_files_count, 1);
+        assert_eq!(execution.deleted_entries, vec![PathBuf::from("gone.txt")]);
+    }
+
+    #[test]
+    fn test_backup_execution_with_checksum_verify_flags_possible_bitrot() {
+        // The simulated files are all empty
+        let empty_checksum = "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262";
+        let root = PathBuf::from("/simulated/project");
+        let file_system = MockFileSystemProvider::with_files(
+            &root,
+            &[("sane.txt", 0), ("rotten.txt", 0), ("new.txt", 0)],
+        );
+        let mut index = BackupIndex::new()
+            .with_entry(1_000_000_000, 1_000_000_000, 0, PathBuf::from("sane.txt"))
+            .with_entry(1_000_000_000, 1_000_000_000, 0, PathBuf::from("rotten.txt"));
+        index.set_checksum(Path::new("sane.txt"), empty_checksum.to_string());
+        index.set_checksum(Path::new("rotten.txt"), "0badc0de".to_string());
+
+        let mut execution = BackupExecution::new(index, root)
+            .with_checksum_verify(true)
+            .with_file_system_provider(Box::new(file_system));
+        let summary = execution
+            .execute(Box::new(MockArchiveWriter::new()))
+            .unwrap();
+
+        assert_eq!(
+            summary.warnings,
+            vec![BackupExecutionWarning::PossibleBitrot(PathBuf::from(
+                "rotten.txt"
+            ))]
+        );
+        assert_eq!(summary.metrics.changed_files_count, 2);
+        for path in ["sane.txt", "rotten.txt", "new.txt"] {
+            let entry = execution.new_index.get_entry(Path::new(path)).unwrap();
+            assert_eq!(entry.checksum(), Some(empty_checksum));
+        }
+    }
+
+    #[test]
+    fn test_backup_execution_errors_shall_include_the_path() {
+        let root = PathBuf::from("/simulated/project");
+        let mut file_system = MockFileSystemProvider::with_files(&root, &[("socket", 0)]);
+        file_system.entries[0].is_file = false;
+
+        let mut execution = BackupExecution::new(BackupIndex::new(), root)
+            .with_file_system_provider(Box::new(file_system));
+        let error = execution
+            .execute(Box::new(MockArchiveWriter::new()))
+            .unwrap_err();
+
+        assert_eq!(
+            error.display_path(),
+            Some(Path::new("/simulated/project/socket"))
+        );
+        assert_eq!(
+            error.to_string(),
+            "Archive error: Unsupported entry type (/simulated/project/socket)"
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_special_files_shall_fail_the_backup_or_be_skipped() {
+        let dir = create_tmp_dir();
+        std::fs::write(dir.join("file.txt"), "Hello").unwrap();
+        let fifo_path = std::ffi::CString::new(dir.join("pipe").to_str().unwrap()).unwrap();
+        assert_eq!(unsafe { libc::mkfifo(fifo_path.as_ptr(), 0o644) }, 0);
+
+        let error = BackupExecution::new(BackupIndex::new(), dir.clone())
+            .execute(Box::new(MockArchiveWriter::new()))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Unsupported file type: FIFO ({})",
+                dir.join("pipe").display()
+            )
+        );
+
+        let mut execution = BackupExecution::new(BackupIndex::new(), dir)
+            .with_special_file_policy(SpecialFilePolicy::Skip);
+        let summary = execution
+            .execute(Box::new(MockArchiveWriter::new()))
+            .unwrap();
+        assert_eq!(summary.skipped_special_files, vec![PathBuf::from("pipe")]);
+        let indexed_paths: Vec<&Path> = execution
+            .new_index
+            .enumerate_entries()
+            .map(|entry| entry.path())
+            .collect();
+        assert_eq!(indexed_paths, vec![Path::new("file.txt")]);
+    }
+
+    #[test]
+    fn test_entries_outside_of_the_project_shall_fail_with_their_path() {
+        let root = PathBuf::from("/simulated/project");
+        let file_system =
+            MockFileSystemProvider::with_files(Path::new("/elsewhere"), &[("a.txt", 0)]);
+
+        let execution = BackupExecution::new(BackupIndex::new(), root)
+            .with_file_system_provider(Box::new(file_system));
+        let error = execution.dry_run().unwrap_err();
+
+        assert_eq!(error.to_string(), "Strip prefix error (/elsewhere/a.txt)");
+    }
+
+    #[test]
+    fn test_dry_run_on_simulated_file_tree() {
+        let root = PathBuf::from("/simulated/project");
+        let file_system =
+            MockFileSystemProvider::with_files(&root, &[("new.txt", 10), ("big.bin", 100)]);
+
+        let execution = BackupExecution::new(BackupIndex::new(), root)
+            .with_max_file_size(Some(50))
+            .with_file_system_provider(Box::new(file_system));
+        let result = execution.dry_run().unwrap();
+
+        assert_eq!(
+            result,
+            DryRunResult {
+                files_to_add: 1,
+                files_to_modify: 0,
+                files_to_delete: 0,
+                bytes_to_transfer: 10,
+            }
+        );
+    }
+
     #[test]
     fn test_backup_execution_empty_empty() {
         // Prepare empty directory structure and empty index
@@ -206,4 +849,258 @@ mod tests {
         let expected_new_index = BackupIndex::new();
         assert_eq!(new_index, expected_new_index);
     }
+
+    #[test]
+    fn test_backup_execution_compacts_stale_entries_from_index() {
+        // Prepare a directory with one file, and an index with a deleted file
+        let dir = create_tmp_dir();
+        std::fs::write(dir.join("kept.txt"), "kept").unwrap();
+        let index = BackupIndex::new()
+            .with_entry(1, 2, 3, PathBuf::from("deleted.txt"))
+            .with_entry(1, 2, 3, PathBuf::from("kept.txt"));
+
+        // Run backup execution
+        let mut execution = BackupExecution::new(index, dir);
+        execution
+            .execute(Box::new(MockArchiveWriter::new()))
+            .unwrap();
+
+        // The deleted file is reported, and left out of the written index
+        assert_eq!(
+            execution.deleted_entries,
+            vec![PathBuf::from("deleted.txt")]
+        );
+        assert!(execution
+            .new_index
+            .get_entry(Path::new("deleted.txt"))
+            .is_none());
+        assert!(execution
+            .new_index
+            .get_entry(Path::new("kept.txt"))
+            .is_some());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_backup_execution_adds_sparse_files_as_sparse() {
+        use std::io::{Seek, SeekFrom, Write};
+
+        // Prepare a directory with a sparse file
+        let dir = create_tmp_dir();
+        let mut file = File::create(dir.join("disk.img")).unwrap();
+        file.set_len(4 * 1024 * 1024).unwrap();
+        file.seek(SeekFrom::Start(1024 * 1024)).unwrap();
+        file.write_all(&[42u8; 4096]).unwrap();
+        file.sync_all().unwrap();
+        let file = File::open(dir.join("disk.img")).unwrap();
+        let is_sparse = find_data_segments(&file, 4 * 1024 * 1024)
+            .unwrap()
+            .is_some();
+
+        // Run backup execution
+        let archive_writer = MockArchiveWriter::new();
+        let added_sparse_files = archive_writer.added_sparse_files.clone();
+        let mut execution = BackupExecution::new(BackupIndex::new(), dir);
+        execution.execute(Box::new(archive_writer)).unwrap();
+
+        // The filesystem of the test machine might not support holes
+        if is_sparse {
+            assert_eq!(
+                *added_sparse_files.borrow(),
+                vec![PathBuf::from("disk.img")]
+            );
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_backup_execution_adds_extended_attributes_of_files() {
+        let dir = create_tmp_dir();
+        std::fs::write(dir.join("file.txt"), "Hello").unwrap();
+        // The filesystem of the test machine might not support user extended attributes
+        if xattr::set(dir.join("file.txt"), "user.comment", b"greeting").is_err() {
+            return;
+        }
+
+        let archive_writer = MockArchiveWriter::new();
+        let added_xattrs = archive_writer.added_xattrs.clone();
+        let mut execution = BackupExecution::new(BackupIndex::new(), dir);
+        execution.execute(Box::new(archive_writer)).unwrap();
+
+        assert_eq!(
+            *added_xattrs.borrow(),
+            vec![(
+                PathBuf::from("file.txt"),
+                vec![("user.comment".to_string(), b"greeting".to_vec())]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_backup_execution_skips_files_above_max_file_size() {
+        // Prepare a directory with a small and a large file
+        let dir = create_tmp_dir();
+        std::fs::write(dir.join("large.bin"), [0u8; 2048]).unwrap();
+        std::fs::write(dir.join("small.txt"), "Hello").unwrap();
+
+        // Run backup execution
+        let mut execution =
+            BackupExecution::new(BackupIndex::new(), dir).with_max_file_size(Some(1024));
+        let summary = execution
+            .execute(Box::new(MockArchiveWriter::new()))
+            .unwrap();
+
+        // Only the small file is part of the backup
+        assert_eq!(
+            summary.skipped_large_files,
+            vec![(PathBuf::from("large.bin"), 2048)]
+        );
+        let indexed_paths: Vec<&Path> = execution
+            .new_index
+            .enumerate_entries()
+            .map(|entry| entry.path())
+            .collect();
+        assert_eq!(indexed_paths, vec![Path::new("small.txt")]);
+    }
+
+    #[test]
+    fn test_backup_execution_archives_the_files_dropped_by_an_index_repair() {
+        // Index a project, then modify one of its files
+        let dir = create_tmp_dir();
+        std::fs::write(dir.join("modified.txt"), "Hello").unwrap();
+        std::fs::write(dir.join("unchanged.txt"), "World").unwrap();
+        let mut index = BackupIndex::from_snapshot_directory(&dir).unwrap();
+        std::fs::write(dir.join("modified.txt"), "Hello, world!").unwrap();
+
+        let report = index.repair(&dir).unwrap();
+        assert_eq!(report.dropped_entries, vec![PathBuf::from("modified.txt")]);
+
+        // The modified file is part of the next backup
+        let mut execution = BackupExecution::new(index, dir);
+        let metrics = execution
+            .execute_with_metrics(Box::new(MockArchiveWriter::new()))
+            .unwrap();
+        assert_eq!(metrics.changed_files_count, 1);
+        assert_eq!(
+            execution
+                .new_index
+                .get_entry(Path::new("modified.txt"))
+                .unwrap()
+                .size(),
+            13
+        );
+    }
+
+    #[test]
+    fn test_backup_execution_keeps_the_previous_entry_of_files_above_max_file_size() {
+        // Prepare a directory with a large file archived by a previous backup
+        let dir = create_tmp_dir();
+        std::fs::write(dir.join("large.bin"), [0u8; 2048]).unwrap();
+        let index = BackupIndex::new().with_entry(1, 2, 512, PathBuf::from("large.bin"));
+
+        // Run backup execution, and a dry run
+        let mut execution = BackupExecution::new(index, dir).with_max_file_size(Some(1024));
+        let dry_run = execution.dry_run().unwrap();
+        let summary = execution
+            .execute(Box::new(MockArchiveWriter::new()))
+            .unwrap();
+
+        // The large file is not archived, nor recorded as deleted
+        assert_eq!(dry_run, DryRunResult::default());
+        assert_eq!(summary.metrics.changed_files_count, 0);
+        assert!(execution.deleted_entries.is_empty());
+        let entry = execution
+            .new_index
+            .get_entry(Path::new("large.bin"))
+            .unwrap();
+        assert_eq!(entry.size(), 512);
+    }
+
+    #[test]
+    fn test_dry_run_counts_added_modified_and_deleted_files() {
+        // Prepare a directory with a new file and a modified one, and an index
+        // with the modified file and a deleted one
+        let dir = create_tmp_dir();
+        std::fs::write(dir.join("new.txt"), "Hello").unwrap();
+        std::fs::write(dir.join("modified.txt"), "Hello, world!").unwrap();
+        let index = BackupIndex::new()
+            .with_entry(1, 2, 3, PathBuf::from("modified.txt"))
+            .with_entry(1, 2, 3, PathBuf::from("deleted.txt"));
+
+        // Run the dry run, without any archive writer
+        let result = BackupExecution::new(index, dir).dry_run().unwrap();
+
+        assert_eq!(
+            result,
+            DryRunResult {
+                files_to_add: 1,
+                files_to_modify: 1,
+                files_to_delete: 1,
+                bytes_to_transfer: 18,
+            }
+        );
+    }
+
+    #[test]
+    fn test_backup_execution_metrics_count_changed_and_total_files() {
+        // Prepare a directory with one file already in the index, and a new one
+        let dir = create_tmp_dir();
+        std::fs::write(dir.join("new.txt"), "Hello").unwrap();
+        std::fs::write(dir.join("unchanged.txt"), "World").unwrap();
+        let metadata = std::fs::metadata(dir.join("unchanged.txt")).unwrap();
+        let index = BackupIndex::new().with_entry(
+            metadata.created().ms_since_epoch().unwrap(),
+            metadata.modified().ms_since_epoch().unwrap(),
+            metadata.len(),
+            PathBuf::from("unchanged.txt"),
+        );
+
+        // Run backup execution
+        let mut execution = BackupExecution::new(index, dir);
+        let metrics = execution
+            .execute_with_metrics(Box::new(MockArchiveWriter::new()))
+            .unwrap();
+
+        assert_eq!(metrics.total_files_count, 2);
+        assert_eq!(metrics.changed_files_count, 1);
+    }
+
+    #[test]
+    fn test_watched_backup_execution_runs_after_a_change() {
+        let dir = create_tmp_dir();
+        let stop_signal = Arc::new(AtomicBool::new(false));
+
+        // Change the project once the watch started, and give up after a while
+        let changed_dir = dir.clone();
+        let timeout_stop_signal = stop_signal.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(200));
+            std::fs::write(changed_dir.join("file.txt"), "Hello").unwrap();
+            std::thread::sleep(Duration::from_secs(10));
+            timeout_stop_signal.store(true, Ordering::Relaxed);
+        });
+
+        // Stop watching as soon as the first backup starts
+        let executions = Rc::new(RefCell::new(0));
+        let factory_executions = executions.clone();
+        let factory_stop_signal = stop_signal.clone();
+        let writer_factory = Box::new(move || {
+            *factory_executions.borrow_mut() += 1;
+            factory_stop_signal.store(true, Ordering::Relaxed);
+            Box::new(MockArchiveWriter::new()) as Box<dyn ArchiveWriter>
+        });
+        let mut execution =
+            BackupExecution::new(BackupIndex::new(), dir).with_stop_signal(stop_signal);
+        execution
+            .execute_watched(writer_factory, Duration::from_millis(50))
+            .unwrap();
+
+        assert_eq!(*executions.borrow(), 1);
+        let indexed_paths: Vec<&Path> = execution
+            .index
+            .enumerate_entries()
+            .map(|entry| entry.path())
+            .collect();
+        assert_eq!(indexed_paths, vec![Path::new("file.txt")]);
+    }
 }
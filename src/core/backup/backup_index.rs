@@ -1,21 +1,32 @@
 use std::{
-    collections::BTreeMap,
-    io::{self, BufRead},
+    collections::{BTreeMap, HashMap, HashSet},
+    io::{self, BufRead, BufReader, Read},
     path::{Path, PathBuf},
 };
 
-use crate::core::util::buffer_ext::BufferExt;
+use flate2::read::GzDecoder;
+use globset::Glob;
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::core::util::{buffer_ext::BufferExt, timestamps::Timestamp};
+
+const GZIP_MAGIC_NUMBER: [u8; 2] = [0x1f, 0x8b];
 
 pub trait ToBuffer {
     fn to_buffer(&self) -> Result<Vec<u8>, io::Error>;
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct BackupIndexEntry {
     ctime: u128,
     mtime: u128,
     size: u64,
     path: PathBuf,
+    // BLAKE3 hash of the content as hex, only known for files hashed during a backup
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    checksum: Option<String>,
+    #[serde(skip)]
     visited: bool,
 }
 
@@ -26,6 +37,7 @@ impl BackupIndexEntry {
             mtime,
             size,
             path,
+            checksum: None,
             visited: false,
         }
     }
@@ -44,17 +56,35 @@ impl BackupIndexEntry {
                 .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Invalid data"))?,
         );
 
-        // Read the rest of the line as a path, excluding the newline character
-        let path = String::from_utf8(buffer[40..buffer.len() - 1].to_vec())
-            .map(|s| PathBuf::from(s))
+        // Read the rest of the line as a path, excluding the newline character,
+        // optionally followed by a NUL character and the checksum
+        let line = String::from_utf8(buffer[40..buffer.len() - 1].to_vec())
             .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Invalid data"))?;
-
-        Ok(BackupIndexEntry::new(ctime, mtime, size, path))
+        let (path, checksum) = match line.split_once('\0') {
+            Some((path, checksum)) => (path, Some(checksum.to_string())),
+            None => (line.as_str(), None),
+        };
+
+        let mut entry = BackupIndexEntry::new(ctime, mtime, size, PathBuf::from(path));
+        entry.checksum = checksum;
+        Ok(entry)
     }
 
     pub fn path(&self) -> &Path {
         &self.path
     }
+
+    pub fn mtime(&self) -> u128 {
+        self.mtime
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn checksum(&self) -> Option<&str> {
+        self.checksum.as_deref()
+    }
 }
 
 impl ToBuffer for BackupIndexEntry {
@@ -70,11 +100,43 @@ impl ToBuffer for BackupIndexEntry {
         buffer.extend_from_slice(&self.mtime.to_le_bytes());
         buffer.extend_from_slice(&self.size.to_le_bytes());
         buffer.extend_from_slice(path_str);
+        if let Some(checksum) = &self.checksum {
+            buffer.push(b'\0');
+            buffer.extend_from_slice(checksum.as_bytes());
+        }
         buffer.push(b'\n');
         Ok(buffer)
     }
 }
 
+/// Differences between two indexes, as paths relative to the project root
+#[derive(Debug, PartialEq)]
+pub struct BackupIndexDiff {
+    pub only_in_self: Vec<PathBuf>,
+    pub only_in_other: Vec<PathBuf>,
+    pub changed: Vec<PathBuf>,
+}
+
+/// Distribution of the sizes and extensions of the entries of an index
+/// Directories are indexed like files, so they are counted too, without extension
+#[derive(Debug, Default, PartialEq)]
+pub struct IndexStatistics {
+    pub file_count: usize,
+    pub total_bytes: u64,
+    pub min_file_size: u64,
+    pub max_file_size: u64,
+    pub mean_file_size: f64,
+    /// Number of entries by lowercased extension, the empty string for entries without one
+    pub extension_counts: HashMap<String, usize>,
+}
+
+/// Outcome of the repair of an index against the project files
+#[derive(Debug, Default, PartialEq)]
+pub struct RepairReport {
+    /// Entries whose metadata did not match their file, and were dropped
+    pub dropped_entries: Vec<PathBuf>,
+}
+
 #[derive(Debug, PartialEq)]
 pub struct BackupIndex {
     index: BTreeMap<PathBuf, BackupIndexEntry>,
@@ -104,11 +166,99 @@ impl BackupIndex {
         Ok(BackupIndex { index })
     }
 
+    /// Read an index written by to_json, or by an external tool: a list of entries
+    /// with their ctime and mtime in ms since epoch, size and path
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        let entries: Vec<BackupIndexEntry> = serde_json::from_str(json)?;
+        let index = entries
+            .into_iter()
+            .map(|entry| (entry.path.clone(), entry))
+            .collect();
+        Ok(BackupIndex { index })
+    }
+
+    /// Entries sorted by path, in a format readable by other tools
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(&self.index.values().collect::<Vec<_>>())
+    }
+
+    /// Read the index embedded in a backup archive (.tar.gz or .tar), which is
+    /// the full index of the project at the time of this backup
+    pub fn from_archive(reader: impl Read) -> Result<Self, io::Error> {
+        match read_archive_entry(reader, Path::new(".index"))? {
+            Some(index) => BackupIndex::from_index_reader(index.as_slice()),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "No index found in archive",
+            )),
+        }
+    }
+
+    /// Index an existing copy of a project, as a backup of it would have done,
+    /// to bootstrap the backups on a device without archiving everything again
+    pub fn from_snapshot_directory(dir: &Path) -> Result<Self, io::Error> {
+        let mut index = BackupIndex::new();
+        for entry in WalkDir::new(dir).min_depth(1) {
+            let entry = entry?;
+            let path = entry
+                .path()
+                .strip_prefix(dir)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+            let metadata = entry.metadata()?;
+            let (ctime, mtime) = get_times_since_epoch(&metadata)?;
+            index.insert(ctime, mtime, metadata.len(), path.to_path_buf());
+        }
+        Ok(index)
+    }
+
+    /// Drop the entries whose metadata differs from their file under root_path,
+    /// e.g. after a clock reset, so that the next backup archives these files again.
+    /// Files missing from the index are left untouched
+    pub fn repair(&mut self, root_path: &Path) -> Result<RepairReport, io::Error> {
+        let mut report = RepairReport::default();
+        for entry in WalkDir::new(root_path).min_depth(1) {
+            let entry = entry?;
+            let path = entry
+                .path()
+                .strip_prefix(root_path)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+            if !self.contains(path) {
+                continue;
+            }
+
+            let metadata = entry.metadata()?;
+            let (ctime, mtime) = get_times_since_epoch(&metadata)?;
+            if self.has_changed(path, ctime, mtime, metadata.len()) {
+                self.remove(path);
+                report.dropped_entries.push(path.to_path_buf());
+            }
+        }
+        Ok(report)
+    }
+
     pub fn insert(&mut self, ctime: u128, mtime: u128, size: u64, path: PathBuf) {
         let entry = BackupIndexEntry::new(ctime, mtime, size, path);
         self.index.insert(entry.path.clone(), entry);
     }
 
+    /// Keep the entry of another index as is, for a file left out of a backup
+    /// while its previous version is still in an older archive
+    pub fn copy_entry_from(&mut self, other: &BackupIndex, path: &Path) {
+        if let Some(entry) = other.index.get(path) {
+            let mut copy =
+                BackupIndexEntry::new(entry.ctime, entry.mtime, entry.size, entry.path.clone());
+            copy.checksum = entry.checksum.clone();
+            self.index.insert(copy.path.clone(), copy);
+        }
+    }
+
+    /// Record the hash of the content of an entry already in the index
+    pub fn set_checksum(&mut self, path: &Path, checksum: String) {
+        if let Some(entry) = self.index.get_mut(path) {
+            entry.checksum = Some(checksum);
+        }
+    }
+
     pub fn has_changed(&self, path: &Path, ctime: u128, mtime: u128, size: u64) -> bool {
         match self.index.get(path) {
             Some(entry) => entry.ctime != ctime || entry.mtime != mtime || entry.size != size,
@@ -116,6 +266,15 @@ impl BackupIndex {
         }
     }
 
+    pub fn contains(&self, path: &Path) -> bool {
+        self.index.contains_key(path)
+    }
+
+    // Return whether the entry was in the index
+    pub fn remove(&mut self, path: &Path) -> bool {
+        self.index.remove(path).is_some()
+    }
+
     pub fn mark_visited(&mut self, path: &Path) {
         if let Some(entry) = self.index.get_mut(path) {
             entry.visited = true;
@@ -133,18 +292,175 @@ impl BackupIndex {
         self.index.values().into_iter()
     }
 
+    /// Entries sorted by last modification time, newest first
+    /// Entries modified at the same time stay sorted by path
+    pub fn entries_by_mtime_desc(&self) -> impl Iterator<Item = &BackupIndexEntry> {
+        let mut entries: Vec<&BackupIndexEntry> = self.index.values().collect();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.mtime));
+        entries.into_iter()
+    }
+
+    /// Entries whose path matches a glob pattern, sorted by path
+    pub fn find_entries_matching(&self, pattern: &str) -> Result<Vec<&BackupIndexEntry>, String> {
+        let matcher = Glob::new(pattern)
+            .map_err(|e| format!("Invalid pattern {}: {}", pattern, e))?
+            .compile_matcher();
+        Ok(self
+            .index
+            .values()
+            .filter(|entry| matcher.is_match(&entry.path))
+            .collect())
+    }
+
+    /// Groups of paths with the same size and modification time, candidates for deduplication
+    /// Paths are sorted in each group, and groups by size then modification time
+    pub fn find_duplicates(&self) -> Vec<Vec<&Path>> {
+        let mut groups: BTreeMap<(u64, u128), Vec<&Path>> = BTreeMap::new();
+        for entry in self.index.values() {
+            groups
+                .entry((entry.size, entry.mtime))
+                .or_default()
+                .push(&entry.path);
+        }
+        groups
+            .into_values()
+            .filter(|paths| paths.len() > 1)
+            .collect()
+    }
+
+    pub fn statistics(&self) -> IndexStatistics {
+        let mut statistics = IndexStatistics {
+            min_file_size: u64::MAX,
+            ..Default::default()
+        };
+        for entry in self.index.values() {
+            statistics.file_count += 1;
+            statistics.total_bytes += entry.size;
+            statistics.min_file_size = statistics.min_file_size.min(entry.size);
+            statistics.max_file_size = statistics.max_file_size.max(entry.size);
+            let extension = entry
+                .path
+                .extension()
+                .map(|extension| extension.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            *statistics.extension_counts.entry(extension).or_default() += 1;
+        }
+
+        if statistics.file_count == 0 {
+            statistics.min_file_size = 0;
+        } else {
+            statistics.mean_file_size =
+                statistics.total_bytes as f64 / statistics.file_count as f64;
+        }
+        statistics
+    }
+
+    /// List the paths that differ between the two indexes, sorted by path
+    pub fn diff(&self, other: &BackupIndex) -> BackupIndexDiff {
+        let mut diff = BackupIndexDiff {
+            only_in_self: vec![],
+            only_in_other: vec![],
+            changed: vec![],
+        };
+
+        for (path, entry) in &self.index {
+            match other.index.get(path) {
+                Some(other_entry)
+                    if other_entry.ctime == entry.ctime
+                        && other_entry.mtime == entry.mtime
+                        && other_entry.size == entry.size => {}
+                Some(_) => diff.changed.push(path.clone()),
+                None => diff.only_in_self.push(path.clone()),
+            }
+        }
+        for path in other.index.keys() {
+            if !self.contains(path) {
+                diff.only_in_other.push(path.clone());
+            }
+        }
+
+        diff
+    }
+
+    /// Merge the indexes of several devices receiving the same backup.
+    /// Entries on which both indexes disagree are reset, so that they are
+    /// seen as changed and backed up again on every device.
+    pub fn merge(mut self, other: &BackupIndex) -> Self {
+        let diff = self.diff(other);
+        for path in diff
+            .changed
+            .into_iter()
+            .chain(diff.only_in_self)
+            .chain(diff.only_in_other)
+        {
+            self.insert(0, 0, 0, path);
+        }
+        self
+    }
+
+    /// Remove all entries whose path is not part of the known paths
+    pub fn compact(&mut self, known_paths: &[PathBuf]) {
+        let known_paths: HashSet<&PathBuf> = known_paths.iter().collect();
+        let unknown_paths: Vec<PathBuf> = self
+            .index
+            .keys()
+            .filter(|path| !known_paths.contains(path))
+            .cloned()
+            .collect();
+        for path in unknown_paths {
+            self.remove(&path);
+        }
+    }
+
     #[cfg(test)]
     pub fn with_entry(mut self, ctime: u128, mtime: u128, size: u64, path: PathBuf) -> Self {
         self.insert(ctime, mtime, size, path);
         self
     }
 
-    #[cfg(test)]
     pub fn get_entry(&self, path: &Path) -> Option<&BackupIndexEntry> {
         self.index.get(path)
     }
 }
 
+fn get_times_since_epoch(metadata: &std::fs::Metadata) -> Result<(u128, u128), io::Error> {
+    let ctime = metadata
+        .created()
+        .ms_since_epoch()
+        .map_err(|e| io::Error::other(e.to_string()))?;
+    let mtime = metadata
+        .modified()
+        .ms_since_epoch()
+        .map_err(|e| io::Error::other(e.to_string()))?;
+    Ok((ctime, mtime))
+}
+
+// Compressed archives are detected by the gzip magic number
+fn open_archive<'a>(reader: impl Read + 'a) -> Result<tar::Archive<Box<dyn Read + 'a>>, io::Error> {
+    let mut reader = BufReader::new(reader);
+    let reader: Box<dyn Read + 'a> = if reader.fill_buf()?.starts_with(&GZIP_MAGIC_NUMBER) {
+        Box::new(GzDecoder::new(reader))
+    } else {
+        Box::new(reader)
+    };
+    Ok(tar::Archive::new(reader))
+}
+
+/// Content of one of the entries of a backup archive (.tar.gz or .tar), None when
+/// the archive does not hold it. The archive is read until the entry is found
+pub fn read_archive_entry(reader: impl Read, path: &Path) -> Result<Option<Vec<u8>>, io::Error> {
+    let mut archive = open_archive(reader)?;
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if entry.path()? == path {
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content)?;
+            return Ok(Some(content));
+        }
+    }
+    Ok(None)
+}
+
 impl ToBuffer for BackupIndex {
     fn to_buffer(&self) -> Result<Vec<u8>, io::Error> {
         let mut buffer = Vec::new();
@@ -158,7 +474,22 @@ impl ToBuffer for BackupIndex {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::io::{BufReader, Cursor};
+    use crate::core::test_utils::fs::create_tmp_dir;
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Cursor;
+
+    fn create_tar_gz(files: Vec<(&str, &[u8])>) -> Vec<u8> {
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        for (path, data) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_path(path).unwrap();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append(&header, data).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
 
     #[test]
     fn test_read_from_empty_file() {
@@ -208,6 +539,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn checksums_shall_be_written_and_read_back_with_their_entry() {
+        let mut index = BackupIndex::new()
+            .with_entry(1, 2, 3, PathBuf::from("test1.txt"))
+            .with_entry(4, 5, 6, PathBuf::from("test2.txt"));
+        index.set_checksum(Path::new("test1.txt"), "af1349b9".to_string());
+
+        let buffer = index.to_buffer().unwrap();
+        let index = BackupIndex::from_index_reader(BufReader::new(Cursor::new(buffer))).unwrap();
+
+        let checksum = |path: &str| index.get_entry(Path::new(path)).unwrap().checksum();
+        assert_eq!(checksum("test1.txt"), Some("af1349b9"));
+        assert_eq!(checksum("test2.txt"), None);
+    }
+
     #[test]
     fn test_write_read_index_with_2_entries() {
         let buffer = BackupIndex::new()
@@ -274,4 +620,322 @@ mod tests {
         assert_eq!(unvisited_entries.len(), 1);
         assert_eq!(unvisited_entries[0].path, PathBuf::from("test2.txt"));
     }
+
+    #[test]
+    fn test_remove_returns_whether_the_entry_existed() {
+        let mut index = BackupIndex::new().with_entry(1, 2, 3, PathBuf::from("test.txt"));
+
+        assert!(index.contains(Path::new("test.txt")));
+        assert!(index.remove(Path::new("test.txt")));
+        assert!(!index.contains(Path::new("test.txt")));
+        assert!(!index.remove(Path::new("test.txt")));
+    }
+
+    #[test]
+    fn test_compact_removes_unknown_entries() {
+        let mut index = BackupIndex::new()
+            .with_entry(1, 2, 3, PathBuf::from("test.txt"))
+            .with_entry(4, 5, 6, PathBuf::from("test2.txt"));
+        index.compact(&[PathBuf::from("test2.txt")]);
+
+        assert_eq!(index.index.len(), 1);
+        assert!(index.get_entry(&PathBuf::from("test.txt")).is_none());
+        assert!(index.get_entry(&PathBuf::from("test2.txt")).is_some());
+    }
+
+    #[test]
+    fn test_compact_with_no_known_paths_removes_all_entries() {
+        let mut index = BackupIndex::new()
+            .with_entry(1, 2, 3, PathBuf::from("test.txt"))
+            .with_entry(4, 5, 6, PathBuf::from("test2.txt"));
+        index.compact(&[]);
+
+        assert_eq!(index, BackupIndex::new());
+    }
+
+    #[test]
+    fn test_read_from_tar_gz_with_index() {
+        let index_buffer = BackupIndex::new()
+            .with_entry(1, 2, 3, PathBuf::from("test.txt"))
+            .to_buffer()
+            .unwrap();
+        let archive = create_tar_gz(vec![
+            (".files/test.txt", b"Hello"),
+            (".deleted-files", b""),
+            (".index", &index_buffer),
+        ]);
+
+        let index = BackupIndex::from_archive(Cursor::new(archive)).unwrap();
+        assert_eq!(
+            index,
+            BackupIndex::new().with_entry(1, 2, 3, PathBuf::from("test.txt"))
+        );
+    }
+
+    #[test]
+    fn test_read_from_uncompressed_tar_with_index() {
+        let index_buffer = BackupIndex::new()
+            .with_entry(1, 2, 3, PathBuf::from("test.txt"))
+            .to_buffer()
+            .unwrap();
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_path(".index").unwrap();
+        header.set_size(index_buffer.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append(&header, index_buffer.as_slice()).unwrap();
+        let archive = builder.into_inner().unwrap();
+
+        let index = BackupIndex::from_archive(Cursor::new(archive)).unwrap();
+        assert_eq!(
+            index,
+            BackupIndex::new().with_entry(1, 2, 3, PathBuf::from("test.txt"))
+        );
+    }
+
+    #[test]
+    fn test_read_from_tar_gz_without_index() {
+        let archive = create_tar_gz(vec![(".files/test.txt", b"Hello")]);
+
+        let error = BackupIndex::from_archive(Cursor::new(archive)).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_read_missing_entry_from_tar_gz() {
+        let archive = create_tar_gz(vec![(".files/test.txt", b"Hello")]);
+
+        let index = read_archive_entry(Cursor::new(archive), Path::new(".index")).unwrap();
+        assert_eq!(index, None);
+    }
+
+    #[test]
+    fn test_index_from_snapshot_directory_matches_its_files() {
+        let dir = create_tmp_dir();
+        std::fs::create_dir(dir.join("docs")).unwrap();
+        std::fs::write(dir.join("docs/notes.txt"), "hello").unwrap();
+        std::fs::write(dir.join("README"), "readme").unwrap();
+
+        let index = BackupIndex::from_snapshot_directory(&dir).unwrap();
+        let paths: Vec<&Path> = index.enumerate_entries().map(|e| e.path()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                Path::new("README"),
+                Path::new("docs"),
+                Path::new("docs/notes.txt")
+            ]
+        );
+
+        let metadata = std::fs::metadata(dir.join("docs/notes.txt")).unwrap();
+        assert!(!index.has_changed(
+            Path::new("docs/notes.txt"),
+            metadata.created().ms_since_epoch().unwrap(),
+            metadata.modified().ms_since_epoch().unwrap(),
+            5
+        ));
+    }
+
+    #[test]
+    fn test_repair_drops_only_the_entries_not_matching_their_file() {
+        let dir = create_tmp_dir();
+        std::fs::write(dir.join("README"), "readme").unwrap();
+        std::fs::write(dir.join("notes.txt"), "hello").unwrap();
+        std::fs::write(dir.join("untracked.txt"), "new").unwrap();
+        let mut index = BackupIndex::from_snapshot_directory(&dir).unwrap();
+        index.remove(Path::new("untracked.txt"));
+        let readme_entry = index.get_entry(Path::new("README")).unwrap();
+        let (ctime, size) = (readme_entry.ctime, readme_entry.size);
+        index.insert(ctime, 0, size, PathBuf::from("README"));
+
+        let report = index.repair(&dir).unwrap();
+        assert_eq!(
+            report,
+            RepairReport {
+                dropped_entries: vec![PathBuf::from("README")]
+            }
+        );
+        assert!(!index.contains(Path::new("README")));
+        assert!(index.contains(Path::new("notes.txt")));
+        assert!(!index.contains(Path::new("untracked.txt")));
+    }
+
+    #[test]
+    fn test_index_from_missing_snapshot_directory_fails() {
+        let dir = create_tmp_dir().join("missing");
+        assert!(BackupIndex::from_snapshot_directory(&dir).is_err());
+    }
+
+    #[test]
+    fn test_find_entries_matching_a_glob_pattern() {
+        let index = BackupIndex::new()
+            .with_entry(1, 2, 3, PathBuf::from("app.log"))
+            .with_entry(4, 5, 6, PathBuf::from("logs/server.log"))
+            .with_entry(7, 8, 9, PathBuf::from("notes.txt"));
+
+        let paths = index
+            .find_entries_matching("*.log")
+            .unwrap()
+            .iter()
+            .map(|entry| entry.path().to_path_buf())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![PathBuf::from("app.log"), PathBuf::from("logs/server.log")]
+        );
+    }
+
+    #[test]
+    fn test_find_entries_matching_an_invalid_pattern_fails() {
+        let index = BackupIndex::new().with_entry(1, 2, 3, PathBuf::from("app.log"));
+
+        let error = index.find_entries_matching("logs/[").unwrap_err();
+        assert!(error.starts_with("Invalid pattern logs/["));
+    }
+
+    #[test]
+    fn test_json_round_trip_keeps_all_entries() {
+        let index = BackupIndex::new()
+            .with_entry(1, 2, 3, PathBuf::from("b.txt"))
+            .with_entry(4, 5, 6, PathBuf::from("dir/a.txt"));
+
+        let json = index.to_json().unwrap();
+        assert_eq!(BackupIndex::from_json(&json).unwrap(), index);
+    }
+
+    #[test]
+    fn test_from_json_reads_entries_of_external_tools() {
+        let json = r#"[{"ctime": 1, "mtime": 2, "size": 3, "path": "notes.txt"}]"#;
+
+        let index = BackupIndex::from_json(json).unwrap();
+        assert_eq!(
+            index,
+            BackupIndex::new().with_entry(1, 2, 3, PathBuf::from("notes.txt"))
+        );
+        assert!(BackupIndex::from_json(r#"[{"path": "notes.txt"}]"#).is_err());
+    }
+
+    #[test]
+    fn test_find_duplicates_groups_entries_with_same_size_and_mtime() {
+        let index = BackupIndex::new()
+            .with_entry(1, 2, 3, PathBuf::from("b/copy.txt"))
+            .with_entry(4, 2, 3, PathBuf::from("a/original.txt"))
+            .with_entry(1, 5, 3, PathBuf::from("same_size.txt"))
+            .with_entry(1, 2, 6, PathBuf::from("same_mtime.txt"));
+
+        assert_eq!(
+            index.find_duplicates(),
+            vec![vec![Path::new("a/original.txt"), Path::new("b/copy.txt")]]
+        );
+    }
+
+    #[test]
+    fn test_diff_of_identical_indexes_is_empty() {
+        let index = BackupIndex::new().with_entry(1, 2, 3, PathBuf::from("test.txt"));
+        let other = BackupIndex::new().with_entry(1, 2, 3, PathBuf::from("test.txt"));
+
+        assert_eq!(
+            index.diff(&other),
+            BackupIndexDiff {
+                only_in_self: vec![],
+                only_in_other: vec![],
+                changed: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_diff_reports_missing_and_changed_entries() {
+        let index = BackupIndex::new()
+            .with_entry(1, 2, 3, PathBuf::from("changed.txt"))
+            .with_entry(1, 2, 3, PathBuf::from("only-in-self.txt"))
+            .with_entry(1, 2, 3, PathBuf::from("same.txt"));
+        let other = BackupIndex::new()
+            .with_entry(1, 2, 4, PathBuf::from("changed.txt"))
+            .with_entry(1, 2, 3, PathBuf::from("only-in-other.txt"))
+            .with_entry(1, 2, 3, PathBuf::from("same.txt"));
+
+        assert_eq!(
+            index.diff(&other),
+            BackupIndexDiff {
+                only_in_self: vec![PathBuf::from("only-in-self.txt")],
+                only_in_other: vec![PathBuf::from("only-in-other.txt")],
+                changed: vec![PathBuf::from("changed.txt")],
+            }
+        );
+    }
+
+    #[test]
+    fn test_merge_resets_entries_not_shared_by_both_indexes() {
+        let index = BackupIndex::new()
+            .with_entry(1, 2, 3, PathBuf::from("changed.txt"))
+            .with_entry(1, 2, 3, PathBuf::from("only-in-self.txt"))
+            .with_entry(1, 2, 3, PathBuf::from("same.txt"));
+        let other = BackupIndex::new()
+            .with_entry(1, 2, 4, PathBuf::from("changed.txt"))
+            .with_entry(1, 2, 3, PathBuf::from("only-in-other.txt"))
+            .with_entry(1, 2, 3, PathBuf::from("same.txt"));
+
+        assert_eq!(
+            index.merge(&other),
+            BackupIndex::new()
+                .with_entry(0, 0, 0, PathBuf::from("changed.txt"))
+                .with_entry(0, 0, 0, PathBuf::from("only-in-other.txt"))
+                .with_entry(0, 0, 0, PathBuf::from("only-in-self.txt"))
+                .with_entry(1, 2, 3, PathBuf::from("same.txt"))
+        );
+    }
+
+    #[test]
+    fn test_entries_by_mtime_desc_lists_newest_entries_first() {
+        let index = BackupIndex::new()
+            .with_entry(1, 20, 3, PathBuf::from("a.txt"))
+            .with_entry(1, 30, 3, PathBuf::from("b.txt"))
+            .with_entry(1, 10, 3, PathBuf::from("c.txt"))
+            .with_entry(1, 30, 3, PathBuf::from("d.txt"));
+
+        let paths: Vec<&Path> = index
+            .entries_by_mtime_desc()
+            .map(|entry| entry.path())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                Path::new("b.txt"),
+                Path::new("d.txt"),
+                Path::new("a.txt"),
+                Path::new("c.txt")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_statistics_of_an_empty_index_are_zero() {
+        assert_eq!(BackupIndex::new().statistics(), IndexStatistics::default());
+    }
+
+    #[test]
+    fn test_statistics_count_sizes_and_extensions() {
+        let index = BackupIndex::new()
+            .with_entry(1, 2, 10, PathBuf::from("a.txt"))
+            .with_entry(1, 2, 30, PathBuf::from("dir/b.TXT"))
+            .with_entry(1, 2, 50, PathBuf::from("dir/c.rs"))
+            .with_entry(1, 2, 30, PathBuf::from("Makefile"));
+
+        let statistics = index.statistics();
+        assert_eq!(statistics.file_count, 4);
+        assert_eq!(statistics.total_bytes, 120);
+        assert_eq!(statistics.min_file_size, 10);
+        assert_eq!(statistics.max_file_size, 50);
+        assert_eq!(statistics.mean_file_size, 30.0);
+        assert_eq!(
+            statistics.extension_counts,
+            HashMap::from([
+                ("txt".to_string(), 2),
+                ("rs".to_string(), 1),
+                ("".to_string(), 1)
+            ])
+        );
+    }
 }
@@ -0,0 +1,199 @@
+use std::str::FromStr;
+use tracing::warn;
+
+/// Scheduling priority of the process while a backup is running
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum BackupPriority {
+    High,
+    #[default]
+    Normal,
+    Low,
+}
+
+impl FromStr for BackupPriority {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "high" => Ok(BackupPriority::High),
+            "normal" => Ok(BackupPriority::Normal),
+            "low" => Ok(BackupPriority::Low),
+            _ => Err(format!("Invalid priority: {}", s)),
+        }
+    }
+}
+
+impl BackupPriority {
+    /// Change the scheduling priority of the current process until the returned
+    /// guard is dropped. This is a best effort: if it fails (e.g. raising the
+    /// priority without the required privileges), the backup still runs with the
+    /// current priority.
+    pub fn apply_to_current_process(&self) -> Option<ProcessPriorityGuard> {
+        if *self == BackupPriority::Normal {
+            return None;
+        }
+
+        match process_priority::set(self) {
+            Ok(previous) => Some(ProcessPriorityGuard { previous }),
+            Err(e) => {
+                warn!("Could not change process priority: {}", e);
+                None
+            }
+        }
+    }
+}
+
+/// Restores the priority the process had before the backup when dropped
+pub struct ProcessPriorityGuard {
+    previous: process_priority::Priority,
+}
+
+impl Drop for ProcessPriorityGuard {
+    fn drop(&mut self) {
+        if let Err(e) = process_priority::restore(self.previous) {
+            warn!("Could not restore process priority: {}", e);
+        }
+    }
+}
+
+#[cfg(all(feature = "priority-control", unix))]
+mod process_priority {
+    use tracing::debug;
+
+    use super::BackupPriority;
+
+    pub type Priority = libc::c_int;
+
+    // Change the niceness, returning the previous one
+    pub fn set(priority: &BackupPriority) -> Result<Priority, String> {
+        let increment = match priority {
+            BackupPriority::High => -5,
+            BackupPriority::Normal => 0,
+            BackupPriority::Low => 10,
+        };
+
+        let previous = nice(0)?;
+        nice(increment)?;
+        Ok(previous)
+    }
+
+    pub fn restore(previous: Priority) -> Result<(), String> {
+        let current = nice(0)?;
+        // Raising the priority back requires privileges most users do not have
+        if current > previous {
+            debug!("Keeping the lowered process priority after the backup");
+            return Ok(());
+        }
+        nice(previous - current)?;
+        Ok(())
+    }
+
+    // -1 is a valid niceness, so a failure is only told by errno
+    fn nice(increment: libc::c_int) -> Result<libc::c_int, String> {
+        clear_errno();
+        let niceness = unsafe { libc::nice(increment) };
+        let error = std::io::Error::last_os_error();
+        if niceness == -1 && error.raw_os_error() != Some(0) {
+            return Err(error.to_string());
+        }
+        Ok(niceness)
+    }
+
+    fn clear_errno() {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        unsafe {
+            *libc::__errno_location() = 0
+        };
+        #[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+        unsafe {
+            *libc::__error() = 0
+        };
+        #[cfg(any(target_os = "netbsd", target_os = "openbsd"))]
+        unsafe {
+            *libc::__errno() = 0
+        };
+    }
+}
+
+#[cfg(all(feature = "priority-control", windows))]
+mod process_priority {
+    use super::BackupPriority;
+    use windows_sys::Win32::System::Threading::{
+        GetCurrentProcess, GetPriorityClass, SetPriorityClass, BELOW_NORMAL_PRIORITY_CLASS,
+        HIGH_PRIORITY_CLASS, NORMAL_PRIORITY_CLASS,
+    };
+
+    pub type Priority = u32;
+
+    // Change the priority class, returning the previous one
+    pub fn set(priority: &BackupPriority) -> Result<Priority, String> {
+        let priority_class = match priority {
+            BackupPriority::High => HIGH_PRIORITY_CLASS,
+            BackupPriority::Normal => NORMAL_PRIORITY_CLASS,
+            BackupPriority::Low => BELOW_NORMAL_PRIORITY_CLASS,
+        };
+
+        let previous = unsafe { GetPriorityClass(GetCurrentProcess()) };
+        if previous == 0 {
+            return Err(std::io::Error::last_os_error().to_string());
+        }
+        restore(priority_class)?;
+        Ok(previous)
+    }
+
+    pub fn restore(priority_class: Priority) -> Result<(), String> {
+        if unsafe { SetPriorityClass(GetCurrentProcess(), priority_class) } == 0 {
+            return Err(std::io::Error::last_os_error().to_string());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(all(feature = "priority-control", any(unix, windows))))]
+mod process_priority {
+    use super::BackupPriority;
+
+    // Never changed, so there is no previous priority to restore
+    pub type Priority = std::convert::Infallible;
+
+    pub fn set(_priority: &BackupPriority) -> Result<Priority, String> {
+        Err("priority control is not enabled in this build".to_string())
+    }
+
+    pub fn restore(previous: Priority) -> Result<(), String> {
+        match previous {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_backup_priority() {
+        assert_eq!(BackupPriority::from_str("high"), Ok(BackupPriority::High));
+        assert_eq!(
+            BackupPriority::from_str("normal"),
+            Ok(BackupPriority::Normal)
+        );
+        assert_eq!(BackupPriority::from_str("low"), Ok(BackupPriority::Low));
+    }
+
+    #[test]
+    fn test_parse_unknown_backup_priority_shall_fail() {
+        assert_eq!(
+            BackupPriority::from_str("urgent"),
+            Err("Invalid priority: urgent".to_string())
+        );
+    }
+
+    #[test]
+    fn default_backup_priority_shall_be_normal() {
+        assert_eq!(BackupPriority::default(), BackupPriority::Normal);
+    }
+
+    #[test]
+    fn normal_priority_shall_leave_the_process_priority_unchanged() {
+        assert!(BackupPriority::Normal.apply_to_current_process().is_none());
+    }
+}
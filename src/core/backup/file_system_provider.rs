@@ -0,0 +1,96 @@
+use std::{
+    fs::{File, FileType},
+    io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use walkdir::WalkDir;
+
+/// An entry found while walking a project, with the metadata a backup needs
+#[derive(Debug, Clone)]
+pub struct FileSystemEntry {
+    pub path: PathBuf,
+    pub is_dir: bool,
+    pub is_file: bool,
+    pub created: SystemTime,
+    pub modified: SystemTime,
+    pub size: u64,
+    /// Type of the FIFOs, sockets and device files, which cannot be archived
+    pub special_file_type: Option<FileType>,
+}
+
+/// Access to the files of a project during a backup, so that backups can be
+/// run against a simulated file tree in tests
+pub trait FileSystemProvider {
+    /// Walk the entries below path (path itself excluded), sorted by name in each directory
+    fn walk_dir(&self, path: &Path) -> Box<dyn Iterator<Item = io::Result<FileSystemEntry>>>;
+
+    fn read_file(&self, path: &Path) -> io::Result<File>;
+}
+
+pub struct StandardFileSystemProvider;
+
+impl FileSystemProvider for StandardFileSystemProvider {
+    fn walk_dir(&self, path: &Path) -> Box<dyn Iterator<Item = io::Result<FileSystemEntry>>> {
+        let entries = WalkDir::new(path)
+            .min_depth(1)
+            .sort_by(|a, b| a.file_name().cmp(b.file_name()))
+            .into_iter()
+            .map(|entry| {
+                let entry = entry?;
+                let metadata = entry.metadata()?;
+                Ok(FileSystemEntry {
+                    path: entry.into_path(),
+                    is_dir: metadata.is_dir(),
+                    is_file: metadata.is_file(),
+                    created: metadata.created()?,
+                    modified: metadata.modified()?,
+                    size: metadata.len(),
+                    special_file_type: Some(metadata.file_type())
+                        .filter(|file_type| is_special_file(file_type)),
+                })
+            });
+        Box::new(entries)
+    }
+
+    fn read_file(&self, path: &Path) -> io::Result<File> {
+        File::open(path)
+    }
+}
+
+#[cfg(unix)]
+fn is_special_file(file_type: &FileType) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    file_type.is_fifo()
+        || file_type.is_socket()
+        || file_type.is_block_device()
+        || file_type.is_char_device()
+}
+
+#[cfg(not(unix))]
+fn is_special_file(_file_type: &FileType) -> bool {
+    false
+}
+
+/// Human readable kind of a special file, e.g. "FIFO"
+#[cfg(unix)]
+pub fn describe_special_file_type(file_type: &FileType) -> &'static str {
+    use std::os::unix::fs::FileTypeExt;
+    if file_type.is_fifo() {
+        "FIFO"
+    } else if file_type.is_socket() {
+        "socket"
+    } else if file_type.is_block_device() {
+        "block device"
+    } else if file_type.is_char_device() {
+        "character device"
+    } else {
+        "special file"
+    }
+}
+
+#[cfg(not(unix))]
+pub fn describe_special_file_type(_file_type: &FileType) -> &'static str {
+    "special file"
+}
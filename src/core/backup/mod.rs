@@ -1,7 +1,17 @@
 mod backup_execution;
 mod backup_index;
+mod backup_priority;
+mod file_system_provider;
 mod restore_execution;
 
-pub use backup_execution::BackupExecution;
-pub use backup_index::BackupIndex;
+pub use backup_execution::{
+    BackupExecution, BackupExecutionMetrics, BackupSummary, DryRunResult, SpecialFilePolicy,
+};
+// Warnings are only displayed outside of this module, tests build them
+#[cfg(test)]
+pub use backup_execution::BackupExecutionWarning;
+pub use backup_index::{
+    read_archive_entry, BackupIndex, BackupIndexEntry, IndexStatistics, RepairReport, ToBuffer,
+};
+pub use backup_priority::BackupPriority;
 pub use restore_execution::RestoreExecution;
@@ -1,4 +1,5 @@
 use std::{collections::HashSet, fmt::Display, io, path::PathBuf};
+use tracing::info;
 
 use crate::core::{Extractor, ExtractorError};
 
@@ -49,7 +50,7 @@ impl RestoreExecution {
         // Extract steps in reverse order, extracting only the files that are not yet in the destination
         // and that are in the final index (so we get the last version of each file of the final state)
         for step in self.extractor.by_ref().rev() {
-            println!("Extracting step {}", step.get_step_name());
+            info!("Extracting step {}", step.get_step_name());
             let extracted_paths = step.extract_to(&self.restore_to, &paths_to_extract)?;
 
             // Remove the already extracted paths, so we don't extract them again
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use itertools::Itertools;
 use toml::Table;
 
@@ -7,6 +9,7 @@ use super::toml_try_read::TryRead;
 
 #[derive(serde::Deserialize, serde::Serialize, Debug, Default)]
 struct PartiallyParsedGlobalConfig {
+    sort_on_save: Option<bool>,
     devices: Option<Vec<Table>>,
     projects: Option<Vec<Table>>,
 }
@@ -55,6 +58,7 @@ pub struct ParseTomlResult {
     pub projects: Vec<Project>,
     pub device_errors: Vec<String>,
     pub project_errors: Vec<String>,
+    pub sort_on_save: bool,
 }
 
 pub fn parse_toml_global_config(
@@ -79,5 +83,29 @@ pub fn parse_toml_global_config(
         projects,
         device_errors,
         project_errors,
+        sort_on_save: parsed_config.sort_on_save.unwrap_or(false),
     })
 }
+
+// Number of devices of each type without a registered factory, by type name
+// Devices without a type are left to the full parsing, which reports them
+pub fn count_unknown_device_types(
+    config_toml: &str,
+    device_factories_registry: &DeviceFactoryRegistry,
+) -> Result<BTreeMap<String, usize>, String> {
+    let parsed_config =
+        toml::from_str::<PartiallyParsedGlobalConfig>(config_toml).map_err(|e| e.to_string())?;
+
+    let device_tables = parsed_config.devices.unwrap_or_default();
+    let mut counts = BTreeMap::new();
+    for device_table in &device_tables {
+        let device_type: Result<&str, String> = device_table.try_read("type");
+        match device_type {
+            Ok(device_type) if !device_factories_registry.contains(device_type) => {
+                *counts.entry(device_type.to_string()).or_default() += 1;
+            }
+            _ => {}
+        }
+    }
+    Ok(counts)
+}
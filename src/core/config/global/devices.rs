@@ -1,6 +1,8 @@
-use crate::core::device::Device;
+use std::time::SystemTime;
 
-use super::super::GlobalConfig;
+use crate::core::device::{BackupRequirementClass, Device};
+
+use super::super::{AddDeviceResult, ConfigChange, GlobalConfig};
 
 impl GlobalConfig {
     pub fn get_device_by_name(&self, name: &str) -> Option<&Box<dyn Device>> {
@@ -15,10 +17,25 @@ impl GlobalConfig {
             ));
         }
 
+        let name = device.get_name();
         self.devices.push(device);
+        self.notify_change(ConfigChange::DeviceAdded(name));
         Ok(())
     }
 
+    // Same as add_device, but a device with the same name is not an error, for setup
+    // scripts run several times. The existing device is kept as is
+    pub fn add_device_if_absent(&mut self, device: Box<dyn Device>) -> AddDeviceResult {
+        if self.get_device_by_name(&device.get_name()).is_some() {
+            return AddDeviceResult::AlreadyPresent;
+        }
+
+        let name = device.get_name();
+        self.devices.push(device);
+        self.notify_change(ConfigChange::DeviceAdded(name));
+        AddDeviceResult::Added
+    }
+
     pub fn remove_device(&mut self, name: &str) -> Result<(), String> {
         let index = self
             .devices
@@ -27,6 +44,7 @@ impl GlobalConfig {
             .ok_or_else(|| "Device not found".to_string())?;
 
         self.devices.remove(index);
+        self.notify_change(ConfigChange::DeviceRemoved(name.to_string()));
         Ok(())
     }
 
@@ -37,23 +55,68 @@ impl GlobalConfig {
     pub fn get_devices_iter(&self) -> impl Iterator<Item = &Box<dyn Device>> {
         self.devices.iter()
     }
+
+    // From the most to the least secure device, devices of the same level keeping
+    // their configuration order
+    pub fn devices_by_security_level_desc(&self) -> impl Iterator<Item = &Box<dyn Device>> {
+        let mut devices = self.devices.iter().collect::<Vec<_>>();
+        devices.sort_by_key(|d| std::cmp::Reverse(d.get_security_level()));
+        devices.into_iter()
+    }
+
+    // Devices secure enough for the class, from the most to the least secure
+    pub fn get_devices_satisfying(&self, class: &BackupRequirementClass) -> Vec<&Box<dyn Device>> {
+        self.devices_by_security_level_desc()
+            .filter(|device| device.get_security_level() >= *class.get_min_security_level())
+            .collect()
+    }
+
+    pub fn set_device_last_seen(
+        &mut self,
+        name: &str,
+        last_seen: SystemTime,
+    ) -> Result<(), String> {
+        let device = self
+            .devices
+            .iter_mut()
+            .find(|d| d.get_name() == name)
+            .ok_or_else(|| "Device not found".to_string())?;
+
+        device.set_last_seen(last_seen);
+        self.notify_change(ConfigChange::DeviceUpdated(name.to_string()));
+        Ok(())
+    }
+
+    pub fn device_count(&self) -> usize {
+        self.devices.len()
+    }
+
+    /// Sort devices lexicographically by name
+    pub fn sort_devices(&mut self) {
+        self.devices.sort_by_key(|d| d.get_name());
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
     use crate::core::{
-        test_utils::mocks::{MockDevice, MockDeviceFactory},
-        DeviceFactory,
+        device::BackupRequirementClass,
+        test_utils::mocks::{MockDevice, MockDeviceFactory, MockDeviceWithParameters},
+        DeviceFactory, SecurityLevel,
     };
 
-    use super::GlobalConfig;
+    use std::{cell::RefCell, rc::Rc, time::SystemTime};
+
+    use super::{AddDeviceResult, ConfigChange, GlobalConfig};
 
     #[test]
     fn when_adding_device_to_global_config_it_shall_add_it() {
         let mut global_config = GlobalConfig {
             devices: vec![],
             projects: vec![],
+            sort_on_save: false,
+            change_listeners: Vec::new(),
         };
 
         let device = MockDeviceFactory
@@ -70,6 +133,8 @@ mod tests {
         let mut global_config = GlobalConfig {
             devices: vec![],
             projects: vec![],
+            sort_on_save: false,
+            change_listeners: Vec::new(),
         };
 
         let device = MockDeviceFactory
@@ -93,11 +158,40 @@ mod tests {
         assert_eq!(global_config.devices.len(), 1);
     }
 
+    #[test]
+    fn when_adding_device_if_absent_twice_it_shall_keep_a_single_device() {
+        let mut global_config = GlobalConfig {
+            devices: vec![],
+            projects: vec![],
+            sort_on_save: false,
+            change_listeners: Vec::new(),
+        };
+
+        let device = MockDeviceFactory
+            .build_from_toml_table("MyPersonalDevice", &toml::Table::new())
+            .unwrap();
+        let device2 = MockDeviceFactory
+            .build_from_toml_table("MyPersonalDevice", &toml::Table::new())
+            .unwrap();
+
+        assert_eq!(
+            global_config.add_device_if_absent(device),
+            AddDeviceResult::Added
+        );
+        assert_eq!(
+            global_config.add_device_if_absent(device2),
+            AddDeviceResult::AlreadyPresent
+        );
+        assert_eq!(global_config.devices.len(), 1);
+    }
+
     #[test]
     fn when_removing_device_from_global_config_it_shall_remove_it() {
         let mut global_config = GlobalConfig {
             devices: vec![],
             projects: vec![],
+            sort_on_save: false,
+            change_listeners: Vec::new(),
         };
 
         let device1 = MockDevice::new("MyPersonalDevice");
@@ -115,14 +209,143 @@ mod tests {
         );
     }
 
+    #[test]
+    fn when_adding_and_removing_devices_listeners_shall_be_notified() {
+        let mut global_config = GlobalConfig::new(vec![], vec![]);
+        let changes = Rc::new(RefCell::new(Vec::new()));
+        let listener_changes = changes.clone();
+        global_config.add_change_listener(Box::new(move |change| {
+            listener_changes.borrow_mut().push(change.clone())
+        }));
+
+        global_config
+            .add_device(Box::new(MockDevice::new("MyPersonalDevice")))
+            .unwrap();
+        global_config.add_device_if_absent(Box::new(MockDevice::new("MyPersonalDevice")));
+        global_config
+            .set_device_last_seen("MyPersonalDevice", SystemTime::UNIX_EPOCH)
+            .unwrap();
+        global_config.remove_device("MyPersonalDevice").unwrap();
+
+        assert_eq!(
+            *changes.borrow(),
+            vec![
+                ConfigChange::DeviceAdded("MyPersonalDevice".to_string()),
+                ConfigChange::DeviceUpdated("MyPersonalDevice".to_string()),
+                ConfigChange::DeviceRemoved("MyPersonalDevice".to_string()),
+            ]
+        );
+    }
+
     #[test]
     fn when_removing_non_existant_device_it_shall_return_error() {
         let mut global_config = GlobalConfig {
             devices: vec![],
             projects: vec![],
+            sort_on_save: false,
+            change_listeners: Vec::new(),
         };
         let result = global_config.remove_device("NonExistantDevice");
         assert!(result.is_err());
         assert_eq!(result.err().unwrap(), "Device not found");
     }
+
+    #[test]
+    fn when_sorting_devices_they_shall_be_ordered_by_name() {
+        let mut global_config = GlobalConfig {
+            devices: vec![
+                Box::new(MockDevice::new("Zeta")),
+                Box::new(MockDevice::new("Alpha")),
+            ],
+            projects: vec![],
+            sort_on_save: false,
+            change_listeners: Vec::new(),
+        };
+
+        global_config.sort_devices();
+        assert_eq!(global_config.devices[0].get_name(), "Alpha");
+        assert_eq!(global_config.devices[1].get_name(), "Zeta");
+    }
+
+    #[test]
+    fn devices_by_security_level_desc_shall_start_with_the_most_secure_device() {
+        let global_config = GlobalConfig {
+            devices: vec![
+                Box::new(MockDevice::new("Cloud")),
+                Box::new(MockDeviceWithParameters::new("UsbKey", "param")),
+                Box::new(MockDevice::new("OtherCloud")),
+            ],
+            projects: vec![],
+            sort_on_save: false,
+            change_listeners: Vec::new(),
+        };
+
+        let names = global_config
+            .devices_by_security_level_desc()
+            .map(|d| d.get_name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["UsbKey", "Cloud", "OtherCloud"]);
+    }
+
+    #[test]
+    fn devices_satisfying_a_class_shall_be_at_least_as_secure_as_required() {
+        let global_config = GlobalConfig {
+            devices: vec![
+                Box::new(MockDevice::new("Cloud")),
+                Box::new(MockDeviceWithParameters::new("UsbKey", "param")),
+            ],
+            projects: vec![],
+            sort_on_save: false,
+            change_listeners: Vec::new(),
+        };
+        let class =
+            BackupRequirementClass::new(1, 1, SecurityLevel::NetworkLocal, "Work".to_string())
+                .unwrap();
+
+        let names = global_config
+            .get_devices_satisfying(&class)
+            .iter()
+            .map(|d| d.get_name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["UsbKey"]);
+    }
+
+    #[test]
+    fn device_count_shall_return_the_number_of_registered_devices() {
+        let mut global_config = GlobalConfig {
+            devices: vec![],
+            projects: vec![],
+            sort_on_save: false,
+            change_listeners: Vec::new(),
+        };
+        assert_eq!(global_config.device_count(), 0);
+
+        global_config
+            .add_device(Box::new(MockDevice::new("MyPersonalDevice")))
+            .unwrap();
+        assert_eq!(global_config.device_count(), 1);
+    }
+
+    #[test]
+    fn when_setting_last_seen_of_a_device_it_shall_be_forwarded_to_it() {
+        let mut device = crate::core::MockDevice::new();
+        device.expect_get_name().return_const("MyPersonalDevice");
+        device
+            .expect_set_last_seen()
+            .with(mockall::predicate::eq(std::time::UNIX_EPOCH))
+            .times(1)
+            .return_const(());
+        let mut global_config = GlobalConfig {
+            devices: vec![Box::new(device)],
+            projects: vec![],
+            sort_on_save: false,
+            change_listeners: Vec::new(),
+        };
+
+        global_config
+            .set_device_last_seen("MyPersonalDevice", std::time::UNIX_EPOCH)
+            .unwrap();
+        let result = global_config.set_device_last_seen("Unknown", std::time::UNIX_EPOCH);
+        assert_eq!(result.err().unwrap(), "Device not found");
+    }
 }
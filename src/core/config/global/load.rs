@@ -1,4 +1,5 @@
 use itertools::Itertools;
+use tracing::warn;
 
 use crate::core::{
     device::{Device, DeviceFactoryRegistry},
@@ -6,7 +7,7 @@ use crate::core::{
 };
 
 use super::super::{
-    from_toml::{parse_toml_global_config, ParseTomlResult},
+    from_toml::{count_unknown_device_types, parse_toml_global_config, ParseTomlResult},
     to_toml::ToToml,
     GlobalConfig, GlobalConfigProvider,
 };
@@ -17,13 +18,23 @@ impl GlobalConfig {
         device_factories_registry: &DeviceFactoryRegistry,
     ) -> Result<GlobalConfig, String> {
         let config_toml = config_provider.read_global_config()?;
+        Self::from_toml(&config_toml, device_factories_registry)
+    }
+
+    // Parse and validate a configuration, as read from a configuration file
+    pub fn from_toml(
+        config_toml: &str,
+        device_factories_registry: &DeviceFactoryRegistry,
+    ) -> Result<GlobalConfig, String> {
+        Self::validate_device_factory_compatibility(config_toml, device_factories_registry)?;
 
         let ParseTomlResult {
             devices,
             projects,
             device_errors,
             project_errors,
-        } = parse_toml_global_config(&config_toml, device_factories_registry)?;
+            sort_on_save,
+        } = parse_toml_global_config(config_toml, device_factories_registry)?;
 
         Self::assert_no_errors_in_config(
             &device_errors,
@@ -37,10 +48,38 @@ impl GlobalConfig {
         Self::assert_no_duplicate_project_name(&projects)?;
         Self::assert_no_duplicate_project_path(&projects)?;
 
-        Ok(GlobalConfig { devices, projects })
+        Ok(GlobalConfig {
+            devices,
+            projects,
+            sort_on_save,
+            change_listeners: Vec::new(),
+        })
     }
 
-    pub fn save(&self, config_provider: &dyn GlobalConfigProvider) -> Result<(), String> {
+    // Warn about each device type of the configuration that no registered factory
+    // can load, usually because the device plugin is not enabled, and return them
+    // with their number of devices
+    pub fn validate_device_factory_compatibility(
+        config_toml: &str,
+        device_factories_registry: &DeviceFactoryRegistry,
+    ) -> Result<Vec<(String, usize)>, String> {
+        let unknown_types = count_unknown_device_types(config_toml, device_factories_registry)?;
+        for (device_type, count) in &unknown_types {
+            let plural = if *count > 1 { "s" } else { "" };
+            warn!(
+                "Unknown device type {} used by {} device{} of the configuration, enable or install the plugin providing it",
+                device_type, count, plural
+            );
+        }
+        Ok(unknown_types.into_iter().collect())
+    }
+
+    pub fn save(&mut self, config_provider: &dyn GlobalConfigProvider) -> Result<(), String> {
+        if self.sort_on_save {
+            self.sort_projects();
+            self.sort_devices();
+        }
+
         let config_toml = self.to_toml()?;
 
         config_provider.write_global_config(&config_toml).unwrap();
@@ -48,6 +87,20 @@ impl GlobalConfig {
         Ok(())
     }
 
+    // Add the devices and projects of another configuration, those whose name is
+    // already used are skipped and the current ones are kept
+    pub fn merge_configs(&mut self, other: GlobalConfig) -> Result<(), String> {
+        for device in other.devices {
+            self.add_device_if_absent(device);
+        }
+        for project in other.projects {
+            if self.get_project_by_name(project.get_name()).is_none() {
+                self.add_project(project)?;
+            }
+        }
+        Ok(())
+    }
+
     fn assert_no_errors_in_config(
         errors: &Vec<String>,
         prefix_if_errors: &str,
@@ -352,6 +405,38 @@ mod tests {
         );
     }
 
+    #[test]
+    fn unknown_device_types_shall_be_counted_before_loading() {
+        let device_factories_registry = get_mock_device_factory_registry();
+        let config_toml = r#"
+    [[devices]]
+    name = "MyPersonalDevice"
+    type = "MockDevice"
+
+    [[devices]]
+    name = "Server"
+    type = "SshDevice"
+
+    [[devices]]
+    name = "Backup server"
+    type = "SshDevice"
+
+    [[devices]]
+    name = "Tape"
+    type = "TapeDevice"
+    "#;
+
+        let unknown_types = GlobalConfig::validate_device_factory_compatibility(
+            config_toml,
+            &device_factories_registry,
+        )
+        .unwrap();
+        assert_eq!(
+            unknown_types,
+            vec![("SshDevice".to_string(), 2), ("TapeDevice".to_string(), 1)]
+        );
+    }
+
     #[test]
     fn if_name_is_missing_it_shall_fail() {
         let device_factories_registry = get_mock_device_factory_registry();
@@ -750,11 +835,116 @@ type = "MockDeviceWithParameters"
 
         let device1 = MockDevice::new("MockDevice");
         let device2 = MockDeviceWithParameters::new("MyDevice", "MyParameter");
-        let global_config = GlobalConfig {
+        let mut global_config = GlobalConfig {
             devices: vec![Box::new(device1), Box::new(device2)],
             projects: vec![],
+            sort_on_save: false,
+            change_listeners: Vec::new(),
         };
 
         global_config.save(&config_provider).unwrap();
     }
+
+    #[test]
+    fn when_saving_config_with_sort_on_save_it_shall_save_sorted_devices() {
+        let mut config_provider = MockGlobalConfigProvider::new();
+        config_provider
+            .expect_write_global_config()
+            .times(1)
+            .with(eq(r#"sort_on_save = true
+
+[[devices]]
+name = "Alpha"
+type = "MockDevice"
+
+[[devices]]
+name = "Zeta"
+type = "MockDevice"
+"#))
+            .return_const(Ok(()));
+
+        let mut global_config = GlobalConfig {
+            devices: vec![
+                Box::new(MockDevice::new("Zeta")),
+                Box::new(MockDevice::new("Alpha")),
+            ],
+            projects: vec![],
+            sort_on_save: true,
+            change_listeners: Vec::new(),
+        };
+
+        global_config.save(&config_provider).unwrap();
+    }
+
+    #[test]
+    fn when_loading_config_with_sort_on_save_it_shall_be_enabled() {
+        let device_factories_registry = get_mock_device_factory_registry();
+        let config_provider = MockGlobalConfigProviderFactory::new("sort_on_save = true");
+        let config = GlobalConfig::load(&config_provider, &device_factories_registry).unwrap();
+        assert!(config.sort_on_save);
+    }
+
+    #[test]
+    fn when_merging_configs_it_shall_only_add_new_devices_and_projects() {
+        let registry = get_mock_device_factory_registry();
+        let mut config = GlobalConfig::from_toml(
+            r#"
+[[devices]]
+name = "MyDevice"
+type = "MockDevice"
+
+[[projects]]
+name = "MyProject"
+path = "/path/to/project"
+
+[projects.tracking_status]
+type = "IgnoredProject"
+"#,
+            &registry,
+        )
+        .unwrap();
+        let other = GlobalConfig::from_toml(
+            r#"
+[[devices]]
+name = "MyDevice"
+type = "MockDevice"
+
+[[devices]]
+name = "OtherDevice"
+type = "MockDevice"
+
+[[projects]]
+name = "MyProject"
+path = "/path/to/elsewhere"
+
+[projects.tracking_status]
+type = "IgnoredProject"
+
+[[projects]]
+name = "OtherProject"
+path = "/path/to/other"
+
+[projects.tracking_status]
+type = "IgnoredProject"
+"#,
+            &registry,
+        )
+        .unwrap();
+
+        config.merge_configs(other).unwrap();
+
+        let device_names: Vec<String> = config.get_devices_iter().map(|d| d.get_name()).collect();
+        assert_eq!(device_names, vec!["MyDevice", "OtherDevice"]);
+        let projects: Vec<(&String, &String)> = config
+            .get_projects_iter()
+            .map(|p| (p.get_name(), p.get_location()))
+            .collect();
+        assert_eq!(
+            projects,
+            vec![
+                (&"MyProject".to_string(), &"/path/to/project".to_string()),
+                (&"OtherProject".to_string(), &"/path/to/other".to_string())
+            ]
+        );
+    }
 }
@@ -1,5 +1,10 @@
-use super::super::GlobalConfig;
-use crate::core::project::Project;
+use std::{path::Path, time::SystemTime};
+
+use super::super::{ConfigChange, GlobalConfig};
+use crate::core::{
+    device::{Device, RequirementSummary},
+    project::{Project, ProjectCopy, ProjectTrackingStatus},
+};
 
 impl GlobalConfig {
     pub fn get_project_by_name(&self, name: &str) -> Option<&Project> {
@@ -19,10 +24,28 @@ impl GlobalConfig {
                 project.get_location()
             ));
         }
+        let name = project.get_name().clone();
         self.projects.push(project);
+        self.notify_change(ConfigChange::ProjectAdded(name));
         Ok(())
     }
 
+    // Same as add_project, but an existing project with the same name is returned as is,
+    // for provisioning scripts run several times. The boolean tells whether it was created
+    pub fn get_or_create_project(
+        &mut self,
+        name: String,
+        location: String,
+        default_status: ProjectTrackingStatus,
+    ) -> Result<(&mut Project, bool), String> {
+        if let Some(index) = self.projects.iter().position(|p| *p.get_name() == name) {
+            return Ok((&mut self.projects[index], false));
+        }
+
+        self.add_project(Project::new(name, location, Some(default_status)))?;
+        Ok((self.projects.last_mut().unwrap(), true))
+    }
+
     pub fn remove_project(&mut self, name: &str) -> Result<(), String> {
         let index = self
             .projects
@@ -31,6 +54,49 @@ impl GlobalConfig {
             .ok_or_else(|| "Project not found".to_string())?;
 
         self.projects.remove(index);
+        self.notify_change(ConfigChange::ProjectRemoved(name.to_string()));
+        Ok(())
+    }
+
+    pub fn rename_project(&mut self, name: &str, new_name: &str) -> Result<(), String> {
+        if self.get_project_by_name(new_name).is_some() {
+            return Err(format!("Project with name {} already exists", new_name));
+        }
+
+        self.projects
+            .iter_mut()
+            .find(|p| p.get_name() == name)
+            .ok_or_else(|| "Project not found".to_string())?
+            .set_name(new_name.to_string());
+        // Listeners keyed by name see the rename as a removal followed by an addition
+        self.notify_change(ConfigChange::ProjectRemoved(name.to_string()));
+        self.notify_change(ConfigChange::ProjectAdded(new_name.to_string()));
+        Ok(())
+    }
+
+    pub fn update_project_location(&mut self, name: &str, location: String) -> Result<(), String> {
+        if let Some(project) = self.get_project_by_path(&location) {
+            if project.get_name() != name {
+                return Err(format!("Project with path {} already exists", location));
+            }
+        }
+
+        self.projects
+            .iter_mut()
+            .find(|p| p.get_name() == name)
+            .ok_or_else(|| "Project not found".to_string())?
+            .set_location(location);
+        self.notify_change(ConfigChange::ProjectUpdated(name.to_string()));
+        Ok(())
+    }
+
+    pub fn set_project_last_update(&mut self, name: &str, time: SystemTime) -> Result<(), String> {
+        self.projects
+            .iter_mut()
+            .find(|p| p.get_name() == name)
+            .ok_or_else(|| "Project not found".to_string())?
+            .set_last_update(time);
+        self.notify_change(ConfigChange::ProjectUpdated(name.to_string()));
         Ok(())
     }
 
@@ -42,21 +108,99 @@ impl GlobalConfig {
         self.projects.iter()
     }
 
+    pub fn project_count(&self) -> usize {
+        self.projects.len()
+    }
+
+    /// Sort projects lexicographically by name
+    pub fn sort_projects(&mut self) {
+        self.projects.sort_by(|a, b| a.get_name().cmp(b.get_name()));
+    }
+
+    /// Projects located in the given directory or one of its subdirectories
+    /// Separators are normalized, so that Windows-style paths match as well
+    pub fn find_projects_by_location_prefix(&self, prefix: &str) -> Vec<&Project> {
+        let prefix = prefix.replace('\\', "/");
+        self.projects
+            .iter()
+            .filter(|p| Path::new(&p.get_location().replace('\\', "/")).starts_with(&prefix))
+            .collect()
+    }
+
+    /// Copies of a project, as the backups found on the available devices
+    /// The copies of the tracking status are not persisted, so the devices are asked
+    pub fn get_available_copies(&self, project_name: &str) -> Vec<ProjectCopy> {
+        GlobalConfig::get_copies_on(&self.get_available_devices(), project_name)
+    }
+
+    /// Compliance of all the tracked projects with their backup requirement class
+    pub fn get_requirement_summary(&self) -> RequirementSummary {
+        // Testing availability may be slow (e.g. a remote), so it is done once per device
+        let available_devices = self.get_available_devices();
+        let mut summary = RequirementSummary::default();
+        for project in &self.projects {
+            let Some(requirement) = project.get_tracking_status().get_backup_requirement_class()
+            else {
+                continue;
+            };
+            let copies = GlobalConfig::get_copies_on(&available_devices, project.get_name());
+            summary.add_project(requirement, &copies);
+        }
+        summary
+    }
+
+    fn get_available_devices(&self) -> Vec<&dyn Device> {
+        self.get_devices_iter()
+            .filter(|device| device.test_availability().is_ok())
+            .map(|device| device.as_ref())
+            .collect()
+    }
+
+    fn get_copies_on(devices: &[&dyn Device], project_name: &str) -> Vec<ProjectCopy> {
+        devices
+            .iter()
+            .filter_map(|device| {
+                let last_backup = device.get_last_backup_time(project_name).ok()??;
+                Some(ProjectCopy::new(
+                    device.get_name(),
+                    device.get_location(),
+                    device.get_security_level(),
+                    Some(last_backup),
+                ))
+            })
+            .collect()
+    }
+
+    /// Project registered at the given path, even through a symlink or a relative path
+    /// Paths that cannot be canonicalized, such as missing ones, are compared as they are
+    pub fn project_at_path(&self, path: &Path) -> Option<&Project> {
+        let canonicalize = |path: &Path| std::fs::canonicalize(path).unwrap_or(path.to_path_buf());
+        let path = canonicalize(path);
+        self.projects
+            .iter()
+            .find(|p| canonicalize(Path::new(p.get_location())) == path)
+    }
+
     fn get_project_by_path(&self, path: &str) -> Option<&Project> {
-        self.projects.iter().find(|p| p.get_location() == path)
+        self.project_at_path(Path::new(path))
     }
 }
 
 #[cfg(test)]
 mod tests {
 
+    use std::{cell::RefCell, rc::Rc};
+
     use super::*;
+    use crate::core::test_utils::fs::create_tmp_dir;
 
     #[test]
     fn when_we_add_a_project_to_the_config_it_shall_be_visible() {
         let mut global_config = GlobalConfig {
             devices: vec![],
             projects: vec![],
+            sort_on_save: false,
+            change_listeners: Vec::new(),
         };
 
         let project = Project::new("MyProject".to_string(), "/tmp".to_string(), None);
@@ -67,11 +211,177 @@ mod tests {
         assert_eq!(global_config.projects[0].get_location(), "/tmp");
     }
 
+    #[test]
+    fn when_projects_change_listeners_shall_be_notified() {
+        let mut global_config = GlobalConfig::new(vec![], vec![]);
+        let changes = Rc::new(RefCell::new(Vec::new()));
+        let listener_changes = changes.clone();
+        global_config.add_change_listener(Box::new(move |change| {
+            listener_changes.borrow_mut().push(change.clone())
+        }));
+
+        global_config
+            .add_project(Project::new(
+                "MyProject".to_string(),
+                "/tmp".to_string(),
+                None,
+            ))
+            .unwrap();
+        global_config
+            .update_project_location("MyProject", "/home".to_string())
+            .unwrap();
+        global_config.remove_project("MyProject").unwrap();
+
+        assert_eq!(
+            *changes.borrow(),
+            vec![
+                ConfigChange::ProjectAdded("MyProject".to_string()),
+                ConfigChange::ProjectUpdated("MyProject".to_string()),
+                ConfigChange::ProjectRemoved("MyProject".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn projects_shall_be_found_by_location_prefix() {
+        let mut global_config = GlobalConfig {
+            devices: vec![],
+            projects: vec![],
+            sort_on_save: false,
+            change_listeners: Vec::new(),
+        };
+        for (name, location) in [
+            ("Repo", "/home/user/repo"),
+            ("Nested", "/home/user/work/nested"),
+            ("Windows", "C:\\Users\\user\\project"),
+            ("OtherUser", "/home/username/repo"),
+        ] {
+            global_config
+                .add_project(Project::new(name.to_string(), location.to_string(), None))
+                .unwrap();
+        }
+
+        let names = |projects: Vec<&Project>| {
+            projects
+                .iter()
+                .map(|p| p.get_name().clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names(global_config.find_projects_by_location_prefix("/home/user")),
+            vec!["Repo", "Nested"]
+        );
+        assert_eq!(
+            names(global_config.find_projects_by_location_prefix("C:/Users")),
+            vec!["Windows"]
+        );
+        assert!(global_config
+            .find_projects_by_location_prefix("/mnt")
+            .is_empty());
+    }
+
+    #[test]
+    fn projects_shall_be_found_at_their_canonical_path() {
+        let project_path = create_tmp_dir();
+        let link_path = create_tmp_dir().join("link");
+        std::os::unix::fs::symlink(&project_path, &link_path).unwrap();
+        let mut global_config = GlobalConfig::new(vec![], vec![]);
+        global_config
+            .add_project(Project::new(
+                "MyProject".to_string(),
+                project_path.to_str().unwrap().to_string(),
+                None,
+            ))
+            .unwrap();
+        global_config
+            .add_project(Project::new(
+                "Missing".to_string(),
+                "/missing/project".to_string(),
+                None,
+            ))
+            .unwrap();
+
+        let name_at = |path: &Path| global_config.project_at_path(path).map(|p| p.get_name());
+        assert_eq!(name_at(&project_path).unwrap(), "MyProject");
+        assert_eq!(name_at(&link_path).unwrap(), "MyProject");
+        assert_eq!(name_at(&project_path.join(".")).unwrap(), "MyProject");
+        assert_eq!(name_at(Path::new("/missing/project")).unwrap(), "Missing");
+        assert!(name_at(&link_path.join("child")).is_none());
+
+        let result = global_config.add_project(Project::new(
+            "Linked".to_string(),
+            link_path.to_str().unwrap().to_string(),
+            None,
+        ));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn when_getting_or_creating_a_project_twice_it_shall_only_be_created_once() {
+        let mut global_config = GlobalConfig {
+            devices: vec![],
+            projects: vec![],
+            sort_on_save: false,
+            change_listeners: Vec::new(),
+        };
+
+        let (project, created) = global_config
+            .get_or_create_project(
+                "MyProject".to_string(),
+                "/tmp".to_string(),
+                ProjectTrackingStatus::UntrackedProject,
+            )
+            .unwrap();
+        assert!(created);
+        assert_eq!(project.get_location(), "/tmp");
+
+        let (project, created) = global_config
+            .get_or_create_project(
+                "MyProject".to_string(),
+                "/other".to_string(),
+                ProjectTrackingStatus::IgnoredProject,
+            )
+            .unwrap();
+        assert!(!created);
+        assert_eq!(project.get_location(), "/tmp");
+        assert_eq!(global_config.project_count(), 1);
+    }
+
+    #[test]
+    fn when_getting_or_creating_a_project_at_a_taken_path_it_shall_fail() {
+        let mut global_config = GlobalConfig {
+            devices: vec![],
+            projects: vec![],
+            sort_on_save: false,
+            change_listeners: Vec::new(),
+        };
+        global_config
+            .add_project(Project::new(
+                "MyProject".to_string(),
+                "/tmp".to_string(),
+                None,
+            ))
+            .unwrap();
+
+        let result = global_config.get_or_create_project(
+            "OtherProject".to_string(),
+            "/tmp".to_string(),
+            ProjectTrackingStatus::UntrackedProject,
+        );
+        assert_eq!(
+            result.err().unwrap(),
+            "Project with path /tmp already exists"
+        );
+        assert_eq!(global_config.project_count(), 1);
+    }
+
     #[test]
     fn when_we_add_multiple_projects_to_the_config_it_shall_be_visible() {
         let mut global_config = GlobalConfig {
             devices: vec![],
             projects: vec![],
+            sort_on_save: false,
+            change_listeners: Vec::new(),
         };
 
         let project = Project::new("MyProject".to_string(), "/tmp".to_string(), None);
@@ -91,6 +401,8 @@ mod tests {
         let mut global_config = GlobalConfig {
             devices: vec![],
             projects: vec![],
+            sort_on_save: false,
+            change_listeners: Vec::new(),
         };
 
         let project = Project::new("MyProject".to_string(), "/tmp".to_string(), None);
@@ -110,6 +422,8 @@ mod tests {
         let mut global_config = GlobalConfig {
             devices: vec![],
             projects: vec![],
+            sort_on_save: false,
+            change_listeners: Vec::new(),
         };
 
         let project = Project::new("MyProject".to_string(), "/tmp".to_string(), None);
@@ -129,6 +443,8 @@ mod tests {
         let mut global_config = GlobalConfig {
             devices: vec![],
             projects: vec![],
+            sort_on_save: false,
+            change_listeners: Vec::new(),
         };
 
         let project = Project::new("MyProject".to_string(), "/tmp".to_string(), None);
@@ -148,10 +464,129 @@ mod tests {
         let mut global_config = GlobalConfig {
             devices: vec![],
             projects: vec![],
+            sort_on_save: false,
+            change_listeners: Vec::new(),
         };
 
         let result = global_config.remove_project("MyProject");
         assert!(result.is_err());
         assert_eq!(result.err().unwrap(), "Project not found");
     }
+
+    #[test]
+    fn when_sorting_projects_they_shall_be_ordered_by_name() {
+        let mut global_config = GlobalConfig {
+            devices: vec![],
+            projects: vec![
+                Project::new("Zeta".to_string(), "/zeta".to_string(), None),
+                Project::new("Alpha".to_string(), "/alpha".to_string(), None),
+            ],
+            sort_on_save: false,
+            change_listeners: Vec::new(),
+        };
+
+        global_config.sort_projects();
+        assert_eq!(global_config.projects[0].get_name(), "Alpha");
+        assert_eq!(global_config.projects[1].get_name(), "Zeta");
+    }
+
+    #[test]
+    fn project_count_shall_return_the_number_of_registered_projects() {
+        let mut global_config = GlobalConfig {
+            devices: vec![],
+            projects: vec![],
+            sort_on_save: false,
+            change_listeners: Vec::new(),
+        };
+        assert_eq!(global_config.project_count(), 0);
+
+        let project = Project::new("MyProject".to_string(), "/tmp".to_string(), None);
+        global_config.add_project(project).unwrap();
+        assert_eq!(global_config.project_count(), 1);
+    }
+
+    #[test]
+    fn when_updating_project_location_it_shall_keep_the_rest_of_the_project() {
+        let mut global_config = GlobalConfig {
+            devices: vec![],
+            projects: vec![Project::new(
+                "MyProject".to_string(),
+                "/tmp".to_string(),
+                Some(ProjectTrackingStatus::IgnoredProject),
+            )],
+            sort_on_save: false,
+            change_listeners: Vec::new(),
+        };
+
+        global_config
+            .update_project_location("MyProject", "/home/user/MyProject".to_string())
+            .unwrap();
+        assert_eq!(global_config.projects[0].get_name(), "MyProject");
+        assert_eq!(
+            global_config.projects[0].get_location(),
+            "/home/user/MyProject"
+        );
+        assert!(matches!(
+            global_config.projects[0].get_tracking_status(),
+            ProjectTrackingStatus::IgnoredProject
+        ));
+    }
+
+    #[test]
+    fn when_updating_project_location_to_the_path_of_another_project_it_shall_return_error() {
+        let mut global_config = GlobalConfig {
+            devices: vec![],
+            projects: vec![
+                Project::new("MyProject".to_string(), "/tmp".to_string(), None),
+                Project::new("MySecondProject".to_string(), "/root".to_string(), None),
+            ],
+            sort_on_save: false,
+            change_listeners: Vec::new(),
+        };
+
+        let result = global_config.update_project_location("MyProject", "/root".to_string());
+        assert_eq!(
+            result.err().unwrap(),
+            "Project with path /root already exists"
+        );
+        assert_eq!(global_config.projects[0].get_location(), "/tmp");
+
+        let result = global_config.update_project_location("Unknown", "/home".to_string());
+        assert_eq!(result.err().unwrap(), "Project not found");
+    }
+
+    #[test]
+    fn the_requirement_summary_shall_test_each_device_availability_once() {
+        let mut device = crate::core::MockDevice::new();
+        device.expect_get_name().return_const("Cloud".to_string());
+        device
+            .expect_get_location()
+            .return_const("remote:".to_string());
+        device
+            .expect_get_security_level()
+            .return_const(crate::core::SecurityLevel::NetworkTrustedRestricted);
+        device
+            .expect_test_availability()
+            .times(1)
+            .return_const(Ok(()));
+        device
+            .expect_get_last_backup_time()
+            .times(2)
+            .returning(|_| Ok(Some(SystemTime::UNIX_EPOCH)));
+
+        let projects = ["ProjectA", "ProjectB"]
+            .iter()
+            .map(|name| {
+                Project::new(
+                    name.to_string(),
+                    format!("/tmp/{}", name),
+                    Some(ProjectTrackingStatus::default()),
+                )
+            })
+            .collect();
+        let global_config = GlobalConfig::new(vec![Box::new(device)], projects);
+
+        let summary = global_config.get_requirement_summary();
+        assert_eq!(summary.total_projects, 2);
+    }
 }
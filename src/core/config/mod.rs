@@ -1,6 +1,8 @@
 #[cfg(test)]
 use mockall::automock;
 
+use std::path::PathBuf;
+
 use super::{project::Project, Device};
 
 mod from_toml;
@@ -13,15 +15,60 @@ mod project;
 mod to_toml;
 mod toml_try_read;
 
+pub use to_toml::ToToml;
+
 pub struct GlobalConfig {
     devices: Vec<Box<dyn Device>>,
     projects: Vec<Project>,
+    sort_on_save: bool,
+
+    // Called after each change of the devices or projects, e.g. to refresh a view
+    change_listeners: Vec<ConfigChangeListener>,
+}
+
+type ConfigChangeListener = Box<dyn Fn(&ConfigChange)>;
+
+// Mutation of the configuration, by name of the device or project
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigChange {
+    DeviceAdded(String),
+    DeviceRemoved(String),
+    DeviceUpdated(String),
+    ProjectAdded(String),
+    ProjectRemoved(String),
+    ProjectUpdated(String),
+}
+
+// Outcome of an idempotent device registration
+#[derive(Debug, PartialEq)]
+pub enum AddDeviceResult {
+    Added,
+    AlreadyPresent,
 }
 
 #[cfg(test)]
 impl GlobalConfig {
     pub fn new(devices: Vec<Box<dyn Device>>, projects: Vec<Project>) -> Self {
-        Self { devices, projects }
+        Self {
+            devices,
+            projects,
+            sort_on_save: false,
+            change_listeners: Vec::new(),
+        }
+    }
+}
+
+impl GlobalConfig {
+    // For views or daemons reacting to changes, the CLI itself registers no listener
+    #[allow(dead_code)]
+    pub fn add_change_listener(&mut self, listener: Box<dyn Fn(&ConfigChange)>) {
+        self.change_listeners.push(listener);
+    }
+
+    fn notify_change(&self, change: ConfigChange) {
+        for listener in &self.change_listeners {
+            listener(&change);
+        }
     }
 }
 
@@ -30,4 +77,11 @@ pub trait GlobalConfigProvider {
     fn init_global_config(&self) -> Result<(), String>;
     fn read_global_config(&self) -> Result<String, String>;
     fn write_global_config(&self, content: &str) -> Result<(), String>;
+
+    // Keep other processes from updating the config, from reading it to writing it back
+    fn lock_global_config(&self) -> Result<(), String>;
+    fn unlock_global_config(&self);
+
+    // Location of the config file, for error messages and relative paths
+    fn get_config_path(&self) -> PathBuf;
 }
@@ -11,6 +11,7 @@ use super::GlobalConfig;
 
 #[derive(serde::Deserialize, serde::Serialize, Debug, Default)]
 struct PartiallyParsedGlobalConfig {
+    sort_on_save: Option<bool>,
     devices: Option<Vec<Table>>,
     projects: Option<Vec<Table>>,
 }
@@ -36,6 +37,7 @@ impl ToToml for GlobalConfig {
             .collect::<Vec<_>>();
 
         let config_toml = toml::to_string(&PartiallyParsedGlobalConfig {
+            sort_on_save: if self.sort_on_save { Some(true) } else { None },
             devices: if device_tables.is_empty() {
                 None
             } else {
@@ -103,8 +105,15 @@ impl ToTomlTable for ProjectTrackingStatus {
             ProjectTrackingStatus::TrackedProject {
                 backup_requirement_class,
                 last_update,
+                max_archives,
                 ..
             } => {
+                if let Some(max_archives) = max_archives {
+                    table.insert(
+                        "max_archives".to_string(),
+                        toml::Value::Integer(*max_archives as i64),
+                    );
+                }
                 table.insert(
                     "backup_requirement_class".to_string(),
                     toml::Value::Table(backup_requirement_class.to_toml_table()),
@@ -220,7 +229,8 @@ type = "MockDeviceWithParameters"
 
         let device1 = MockDevice::new("MockDevice");
         let device2 = MockDeviceWithParameters::new("MyDevice", "MyParameter");
-        let global_config = GlobalConfig::new(vec![Box::new(device1), Box::new(device2)], vec![]);
+        let mut global_config =
+            GlobalConfig::new(vec![Box::new(device1), Box::new(device2)], vec![]);
         global_config.save(&config_provider).unwrap();
     }
 
@@ -246,6 +256,7 @@ target_locations = 2
             backup_requirement_class,
             last_update,
             current_copies: vec![],
+            max_archives: None,
         };
         let toml = project_tracking_status.to_toml().unwrap();
         assert_eq!(
@@ -262,6 +273,18 @@ target_locations = 2
         );
     }
 
+    #[test]
+    fn when_converting_tracked_project_with_max_archives_to_toml_it_shall_keep_it() {
+        let project_tracking_status = ProjectTrackingStatus::TrackedProject {
+            backup_requirement_class: BackupRequirementClass::default(),
+            last_update: None,
+            current_copies: vec![],
+            max_archives: Some(10),
+        };
+        let toml = project_tracking_status.to_toml().unwrap();
+        assert!(toml.contains("max_archives = 10\n"));
+    }
+
     #[test]
     fn when_converting_untracked_project_to_toml_it_shall_return_toml() {
         let project_tracking_status = ProjectTrackingStatus::UntrackedProject;
@@ -293,6 +316,7 @@ target_locations = 2
                 last_update: Some(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(100)),
                 backup_requirement_class: BackupRequirementClass::default(),
                 current_copies: vec![],
+                max_archives: None,
             }),
         );
 
@@ -324,6 +348,7 @@ target_locations = 2
                 last_update: Some(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(100)),
                 backup_requirement_class: BackupRequirementClass::default(),
                 current_copies: vec![],
+                max_archives: None,
             }),
         );
 
@@ -357,6 +382,7 @@ target_locations = 2
                 last_update: Some(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(100)),
                 backup_requirement_class: BackupRequirementClass::default(),
                 current_copies: vec![],
+                max_archives: None,
             }),
         );
 
@@ -405,6 +431,7 @@ type = "UntrackedProject"
                 last_update: Some(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(100)),
                 backup_requirement_class: BackupRequirementClass::default(),
                 current_copies: vec![],
+                max_archives: None,
             }),
         );
 
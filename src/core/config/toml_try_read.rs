@@ -1,4 +1,7 @@
-use std::str::FromStr;
+use std::{
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use toml::Table;
 
@@ -28,6 +31,15 @@ impl<'a> TryRead<'a, u32> for &'a Table {
     }
 }
 
+impl<'a> TryRead<'a, bool> for &'a Table {
+    fn try_read(&'a self, key: &'a str) -> Result<bool, String> {
+        self.get(key)
+            .ok_or_else(|| format!("Missing '{}' field", key))?
+            .as_bool()
+            .ok_or_else(|| format!("Invalid boolean for '{}'", key))
+    }
+}
+
 impl<'a> TryRead<'a, Table> for &'a Table {
     fn try_read(&'a self, key: &'a str) -> Result<Table, String> {
         self.get(key)
@@ -38,6 +50,22 @@ impl<'a> TryRead<'a, Table> for &'a Table {
     }
 }
 
+impl<'a> TryRead<'a, Vec<String>> for &'a Table {
+    fn try_read(&'a self, key: &'a str) -> Result<Vec<String>, String> {
+        self.get(key)
+            .ok_or_else(|| format!("Missing '{}' field", key))?
+            .as_array()
+            .ok_or_else(|| format!("Invalid array for '{}'", key))?
+            .iter()
+            .map(|v| {
+                v.as_str()
+                    .map(|s| s.to_string())
+                    .ok_or_else(|| format!("Invalid string in '{}'", key))
+            })
+            .collect()
+    }
+}
+
 impl<'a> TryRead<'a, SecurityLevel> for &'a Table {
     fn try_read(&'a self, key: &'a str) -> Result<SecurityLevel, String> {
         let v: &str = self.try_read(key)?;
@@ -52,12 +80,12 @@ impl<'a> TryRead<'a, BackupRequirementClass> for &'a Table {
         let target_locations = table.try_read("target_locations")?;
         let min_security_level = table.try_read("min_security_level")?;
         let name: &str = table.try_read("name")?;
-        Ok(BackupRequirementClass::new(
+        BackupRequirementClass::new(
             target_copies,
             target_locations,
             min_security_level,
             name.to_string(),
-        ))
+        )
     }
 }
 
@@ -78,8 +106,9 @@ impl<'a> TryRead<'a, ProjectTrackingStatus> for &'a Table {
 
                 ProjectTrackingStatus::TrackedProject {
                     backup_requirement_class,
-                    last_update: None, // Handle last_update if present in your TOML
+                    last_update: read_last_update(tracking_status_table),
                     current_copies: vec![], // Handle current_copies if present in your TOML
+                    max_archives: read_max_archives(tracking_status_table)?,
                 }
             }
             "UntrackedProject" => ProjectTrackingStatus::UntrackedProject,
@@ -91,6 +120,27 @@ impl<'a> TryRead<'a, ProjectTrackingStatus> for &'a Table {
     }
 }
 
+// Optional retention of the archives, at least one archive is always kept
+fn read_max_archives(tracking_status_table: &Table) -> Result<Option<usize>, String> {
+    let Some(value) = tracking_status_table.get("max_archives") else {
+        return Ok(None);
+    };
+    match value.as_integer() {
+        Some(max_archives) if max_archives > 0 => Ok(Some(max_archives as usize)),
+        _ => Err("Invalid format for 'max_archives', shall be a positive integer".to_string()),
+    }
+}
+
+// The last update is saved as seconds since the epoch, an empty or unknown value means never
+fn read_last_update(tracking_status_table: &Table) -> Option<SystemTime> {
+    tracking_status_table
+        .get("last_update")?
+        .as_str()?
+        .parse::<u64>()
+        .ok()
+        .map(|secs| UNIX_EPOCH + Duration::from_secs(secs))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -149,6 +199,34 @@ mod tests {
         assert_eq!(v.unwrap_err(), "Invalid format for 'key'");
     }
 
+    #[test]
+    fn test_try_read_bool() {
+        let mut table = Table::new();
+        table.insert("enabled".to_string(), Value::Boolean(true));
+        table.insert("disabled".to_string(), Value::Boolean(false));
+        let table = &table;
+        let enabled: bool = table.try_read("enabled").unwrap();
+        let disabled: bool = table.try_read("disabled").unwrap();
+        assert!(enabled);
+        assert!(!disabled);
+    }
+
+    #[test]
+    fn test_try_read_bool_missing() {
+        let table = &Table::new();
+        let v: Result<bool, _> = table.try_read("key");
+        assert_eq!(v.unwrap_err(), "Missing 'key' field");
+    }
+
+    #[test]
+    fn test_try_read_bool_invalid() {
+        let mut table = Table::new();
+        table.insert("key".to_string(), Value::String("true".to_string()));
+        let table = &table;
+        let v: Result<bool, _> = table.try_read("key");
+        assert_eq!(v.unwrap_err(), "Invalid boolean for 'key'");
+    }
+
     #[test]
     fn test_try_read_table() {
         let mut table = Table::new();
@@ -175,6 +253,49 @@ mod tests {
         assert_eq!(v.unwrap_err(), "'key' is not a section");
     }
 
+    #[test]
+    fn test_try_read_string_array() {
+        let mut table = Table::new();
+        table.insert(
+            "key".to_string(),
+            Value::Array(vec![
+                Value::String("a".to_string()),
+                Value::String("b".to_string()),
+            ]),
+        );
+        let table = &table;
+        let v: Vec<String> = table.try_read("key").unwrap();
+        assert_eq!(v, vec!["a".to_string(), "b".to_string()]);
+    }
+
+    #[test]
+    fn test_try_read_string_array_empty() {
+        let mut table = Table::new();
+        table.insert("key".to_string(), Value::Array(vec![]));
+        let table = &table;
+        let v: Vec<String> = table.try_read("key").unwrap();
+        assert!(v.is_empty());
+    }
+
+    #[test]
+    fn test_try_read_string_array_missing() {
+        let table = &Table::new();
+        let v: Result<Vec<String>, _> = table.try_read("key");
+        assert_eq!(v.unwrap_err(), "Missing 'key' field");
+    }
+
+    #[test]
+    fn test_try_read_string_array_invalid_element() {
+        let mut table = Table::new();
+        table.insert(
+            "key".to_string(),
+            Value::Array(vec![Value::String("a".to_string()), Value::Integer(42)]),
+        );
+        let table = &table;
+        let v: Result<Vec<String>, _> = table.try_read("key");
+        assert_eq!(v.unwrap_err(), "Invalid string in 'key'");
+    }
+
     #[test]
     fn test_try_read_security_level() {
         let mut table = Table::new();
@@ -303,7 +424,8 @@ target_locations = 42
             ProjectTrackingStatus::TrackedProject {
                 backup_requirement_class,
                 last_update,
-                current_copies
+                current_copies,
+                ..
             } => {
                 assert_eq!(backup_requirement_class.get_target_copies(), 42);
                 assert_eq!(backup_requirement_class.get_target_locations(), 42);
@@ -317,4 +439,54 @@ target_locations = 42
             }
         );
     }
+
+    #[test]
+    fn test_try_read_tracked_project_status_max_archives() {
+        let toml = r#"
+[tracking_status]
+type = "TrackedProject"
+max_archives = 10
+
+[tracking_status.backup_requirement_class]
+min_security_level = "Local"
+name = "name"
+target_copies = 42
+target_locations = 42
+"#;
+        let table: Table = toml::from_str(toml).unwrap();
+        let table = &table;
+        let status: ProjectTrackingStatus = table.try_read("tracking_status").unwrap();
+        assert_eq!(status.get_max_archives(), Some(10));
+
+        let table: Table = toml::from_str(&toml.replace("= 10", "= 0")).unwrap();
+        let table = &table;
+        let status: Result<ProjectTrackingStatus, _> = table.try_read("tracking_status");
+        assert_eq!(
+            status.err().unwrap(),
+            "Invalid format for 'max_archives', shall be a positive integer"
+        );
+    }
+
+    #[test]
+    fn test_try_read_tracked_project_status_last_update() {
+        let toml = r#"
+[tracking_status]
+type = "TrackedProject"
+last_update = "100"
+
+[tracking_status.backup_requirement_class]
+min_security_level = "Local"
+name = "name"
+target_copies = 42
+target_locations = 42
+"#;
+        let table: Table = toml::from_str(toml).unwrap();
+        let table = &table;
+
+        let status: ProjectTrackingStatus = table.try_read("tracking_status").unwrap();
+        assert_eq!(
+            status.get_last_update(),
+            Some(UNIX_EPOCH + Duration::from_secs(100))
+        );
+    }
 }
@@ -2,9 +2,10 @@ use std::{
     fs::File,
     io,
     path::{Path, PathBuf},
+    str::FromStr,
 };
 
-use crate::core::util::timestamps::TimeStampError;
+use crate::core::util::{sparse_files::SparseSegment, timestamps::TimeStampError};
 
 pub trait ArchiveWriter {
     fn add_file(
@@ -16,6 +17,30 @@ pub trait ArchiveWriter {
         size: u64,
     ) -> Result<(), ArchiveError>;
 
+    // Add a sparse file, only storing its data segments
+    // Writers without sparse files support store the whole file instead
+    fn add_sparse_file(
+        &mut self,
+        file: &mut File,
+        path: &PathBuf,
+        ctime: u128,
+        mtime: u128,
+        size: u64,
+        _data_segments: &[SparseSegment],
+    ) -> Result<(), ArchiveError> {
+        self.add_file(file, path, ctime, mtime, size)
+    }
+
+    // Attach extended attributes to the file added next at this path
+    // Writers without extended attributes support ignore them
+    fn add_extended_attributes(
+        &mut self,
+        _path: &PathBuf,
+        _xattrs: Vec<(String, Vec<u8>)>,
+    ) -> Result<(), ArchiveError> {
+        Ok(())
+    }
+
     fn add_directory(
         &mut self,
         src_path: &Path,
@@ -32,13 +57,86 @@ pub trait ArchiveWriter {
         target: &PathBuf,
     ) -> Result<(), ArchiveError>;
 
+    // Total size of the files added so far, for progress reporting
+    // Writers not tracking it report 0
+    fn bytes_written(&self) -> u64 {
+        0
+    }
+
+    // Tags are user defined labels of the backup, stored along the index
     fn finalize(
         &mut self,
         deleted_files: &Vec<PathBuf>,
         new_index: &Vec<u8>,
+        tags: &[String],
     ) -> Result<(), ArchiveError>;
 }
 
+/// How a device shall write the archive of a backup
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ArchiveWriterOptions {
+    /// Archives are left uncompressed when false (devices not supporting it may ignore it)
+    pub compress: bool,
+    /// Keep the incomplete archive of a failed backup for debugging, instead of deleting it
+    pub keep_partial: bool,
+    /// Algorithm of the compressed archives (devices not supporting it may use gzip instead,
+    /// or reject it)
+    pub compression_format: CompressionFormat,
+}
+
+impl Default for ArchiveWriterOptions {
+    fn default() -> Self {
+        ArchiveWriterOptions {
+            compress: true,
+            keep_partial: false,
+            compression_format: CompressionFormat::default(),
+        }
+    }
+}
+
+/// Algorithm used to compress the tar archives
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CompressionFormat {
+    #[default]
+    Gz,
+    Bz2,
+    Xz,
+    Zstd,
+}
+
+impl CompressionFormat {
+    pub const ALL: [CompressionFormat; 4] = [
+        CompressionFormat::Gz,
+        CompressionFormat::Bz2,
+        CompressionFormat::Xz,
+        CompressionFormat::Zstd,
+    ];
+
+    /// File extension of the archives compressed with this format
+    pub fn archive_extension(&self) -> &'static str {
+        match self {
+            CompressionFormat::Gz => ".tar.gz",
+            CompressionFormat::Bz2 => ".tar.bz2",
+            CompressionFormat::Xz => ".tar.xz",
+            CompressionFormat::Zstd => ".tar.zst",
+        }
+    }
+}
+
+impl FromStr for CompressionFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gz" => Ok(CompressionFormat::Gz),
+            "bz2" => Ok(CompressionFormat::Bz2),
+            "xz" => Ok(CompressionFormat::Xz),
+            "zstd" => Ok(CompressionFormat::Zstd),
+            _ => Err(format!("Invalid compression format: {}", s)),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct ArchiveError {
     pub message: String,
@@ -1,11 +1,15 @@
 use std::{
+    collections::{BTreeMap, HashSet},
     fmt::{Display, Formatter},
     str::FromStr,
 };
 
 use serde::Serialize;
 
-#[derive(Serialize)]
+use crate::core::project::ProjectCopy;
+
+// Variants are declared from the least to the most secure, which gives the ordering
+#[derive(Serialize, Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
 pub enum SecurityLevel {
     // Connected to network, no authorization required
     NetworkPublic,       // referenced, accessible to anyone
@@ -54,7 +58,7 @@ impl Display for SecurityLevel {
     }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Clone)]
 pub struct BackupRequirementClass {
     // The number of distinct copies we want to keep (including the primary)
     target_copies: u32,
@@ -75,13 +79,20 @@ impl BackupRequirementClass {
         target_locations: u32,
         min_security_level: SecurityLevel,
         name: String,
-    ) -> BackupRequirementClass {
-        BackupRequirementClass {
+    ) -> Result<BackupRequirementClass, String> {
+        // Each location holds at least one copy
+        if target_copies < target_locations {
+            return Err(format!(
+                "Backup requirement class {} asks for {} copies in {} locations, there cannot be more locations than copies",
+                name, target_copies, target_locations
+            ));
+        }
+        Ok(BackupRequirementClass {
             target_copies,
             target_locations,
             min_security_level,
             name,
-        }
+        })
     }
 
     pub fn get_target_copies(&self) -> u32 {
@@ -99,6 +110,87 @@ impl BackupRequirementClass {
     pub fn get_name(&self) -> &String {
         &self.name
     }
+
+    // The primary copy counts as one copy and one location, as the targets include it
+    pub fn is_satisfied_by(&self, copies: &[ProjectCopy]) -> RequirementComplianceResult {
+        let actual_copies = copies.len() + 1;
+        let unique_locations = copies
+            .iter()
+            .map(|copy| copy.get_location())
+            .collect::<HashSet<_>>()
+            .len()
+            + 1;
+        let lowest_security_level = copies
+            .iter()
+            .map(|copy| copy.get_security_level())
+            .min()
+            .cloned();
+        let missing_copies = self.target_copies.saturating_sub(actual_copies as u32);
+
+        let is_compliant = missing_copies == 0
+            && unique_locations as u32 >= self.target_locations
+            && lowest_security_level
+                .as_ref()
+                .is_none_or(|level| *level >= self.min_security_level);
+
+        RequirementComplianceResult {
+            is_compliant,
+            actual_copies,
+            unique_locations,
+            lowest_security_level,
+            missing_copies,
+        }
+    }
+}
+
+/// How well the copies of a project meet its backup requirement class
+#[derive(Debug, PartialEq)]
+pub struct RequirementComplianceResult {
+    pub is_compliant: bool,
+
+    /// Number of copies, including the primary one
+    pub actual_copies: usize,
+
+    /// Number of distinct physical locations, including the one of the primary copy
+    pub unique_locations: usize,
+
+    /// Lowest security level of the secondary copies, None when there is none
+    pub lowest_security_level: Option<SecurityLevel>,
+    pub missing_copies: u32,
+}
+
+/// Compliance of a set of tracked projects, aggregated over their backup requirement classes
+#[derive(Debug, Default, PartialEq)]
+pub struct RequirementSummary {
+    pub total_projects: usize,
+    pub compliant_projects: usize,
+
+    /// Copies required by the backup classes, including the primary ones
+    pub required_copies: usize,
+    /// Copies counting towards the required ones, extra copies of a project are left out
+    pub present_copies: usize,
+
+    /// Number of secondary copies on devices of each security level
+    pub copies_by_security_level: BTreeMap<SecurityLevel, usize>,
+}
+
+impl RequirementSummary {
+    pub fn add_project(&mut self, requirement: &BackupRequirementClass, copies: &[ProjectCopy]) {
+        let compliance = requirement.is_satisfied_by(copies);
+        let required_copies = requirement.get_target_copies() as usize;
+        self.total_projects += 1;
+        if compliance.is_compliant {
+            self.compliant_projects += 1;
+        }
+        self.required_copies += required_copies;
+        self.present_copies += compliance.actual_copies.min(required_copies);
+        for copy in copies {
+            *self
+                .copies_by_security_level
+                .entry(copy.get_security_level().clone())
+                .or_default() += 1;
+        }
+    }
 }
 
 impl Default for BackupRequirementClass {
@@ -111,3 +203,155 @@ impl Default for BackupRequirementClass {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn security_levels_shall_be_ordered_from_network_public_to_local_max_security() {
+        assert!(SecurityLevel::NetworkPublic < SecurityLevel::NetworkUnreferenced);
+        assert!(
+            SecurityLevel::NetworkUntrustedRestricted < SecurityLevel::NetworkTrustedRestricted
+        );
+        assert!(SecurityLevel::NetworkLocal < SecurityLevel::Local);
+        assert!(SecurityLevel::Local < SecurityLevel::LocalMaxSecurity);
+        assert!(SecurityLevel::Local >= SecurityLevel::NetworkUntrustedRestricted);
+    }
+
+    fn copy(device_name: &str, location: &str, security_level: SecurityLevel) -> ProjectCopy {
+        ProjectCopy::new(
+            device_name.to_string(),
+            location.to_string(),
+            security_level,
+            None,
+        )
+    }
+
+    #[test]
+    fn without_copies_only_the_primary_shall_be_counted() {
+        let result = BackupRequirementClass::default().is_satisfied_by(&[]);
+        assert_eq!(
+            result,
+            RequirementComplianceResult {
+                is_compliant: false,
+                actual_copies: 1,
+                unique_locations: 1,
+                lowest_security_level: None,
+                missing_copies: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn copies_in_enough_locations_with_enough_security_shall_be_compliant() {
+        let requirement =
+            BackupRequirementClass::new(3, 2, SecurityLevel::NetworkLocal, "Work".to_string())
+                .unwrap();
+        let result = requirement.is_satisfied_by(&[
+            copy("USBkey", "home", SecurityLevel::Local),
+            copy("NAS", "home", SecurityLevel::NetworkLocal),
+        ]);
+        assert_eq!(
+            result,
+            RequirementComplianceResult {
+                is_compliant: true,
+                actual_copies: 3,
+                unique_locations: 2,
+                lowest_security_level: Some(SecurityLevel::NetworkLocal),
+                missing_copies: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn a_copy_below_the_minimum_security_level_shall_not_be_compliant() {
+        let requirement =
+            BackupRequirementClass::new(2, 2, SecurityLevel::Local, "Secret".to_string()).unwrap();
+        let result =
+            requirement.is_satisfied_by(&[copy("Cloud", "aws", SecurityLevel::NetworkPublic)]);
+        assert!(!result.is_compliant);
+        assert_eq!(result.missing_copies, 0);
+        assert_eq!(
+            result.lowest_security_level,
+            Some(SecurityLevel::NetworkPublic)
+        );
+    }
+
+    #[test]
+    fn a_class_with_as_many_locations_as_copies_shall_be_valid() {
+        let requirement =
+            BackupRequirementClass::new(2, 2, SecurityLevel::Local, "Spread".to_string()).unwrap();
+        assert_eq!(requirement.get_target_copies(), 2);
+        assert_eq!(requirement.get_target_locations(), 2);
+    }
+
+    #[test]
+    fn a_class_with_more_locations_than_copies_shall_be_rejected() {
+        let result = BackupRequirementClass::new(3, 4, SecurityLevel::Local, "Broken".to_string());
+        assert_eq!(
+            result.err().unwrap(),
+            "Backup requirement class Broken asks for 3 copies in 4 locations, there cannot be more locations than copies"
+        );
+    }
+
+    #[test]
+    fn default_class_shall_have_at_least_as_many_copies_as_locations() {
+        let requirement = BackupRequirementClass::default();
+        assert!(requirement.get_target_copies() >= requirement.get_target_locations());
+    }
+
+    #[test]
+    fn requirement_summary_shall_aggregate_the_projects_compliance() {
+        let requirement = BackupRequirementClass::default();
+        let mut summary = RequirementSummary::default();
+
+        summary.add_project(
+            &requirement,
+            &[
+                copy("Disk", "Home", SecurityLevel::Local),
+                copy("Cloud", "Remote", SecurityLevel::NetworkTrustedRestricted),
+                copy("Usb", "Home", SecurityLevel::Local),
+            ],
+        );
+        summary.add_project(&requirement, &[]);
+
+        assert_eq!(
+            summary,
+            RequirementSummary {
+                total_projects: 2,
+                compliant_projects: 1,
+                required_copies: 6,
+                present_copies: 4,
+                copies_by_security_level: BTreeMap::from([
+                    (SecurityLevel::NetworkTrustedRestricted, 1),
+                    (SecurityLevel::Local, 2),
+                ]),
+            }
+        );
+    }
+
+    #[test]
+    fn security_levels_shall_be_sortable() {
+        let mut levels = vec![
+            SecurityLevel::LocalMaxSecurity,
+            SecurityLevel::NetworkLocal,
+            SecurityLevel::NetworkPublic,
+            SecurityLevel::Local,
+        ];
+        levels.sort();
+        assert_eq!(
+            levels,
+            vec![
+                SecurityLevel::NetworkPublic,
+                SecurityLevel::NetworkLocal,
+                SecurityLevel::Local,
+                SecurityLevel::LocalMaxSecurity,
+            ]
+        );
+        assert_eq!(
+            SecurityLevel::Local.max(SecurityLevel::NetworkLocal),
+            SecurityLevel::Local
+        );
+    }
+}
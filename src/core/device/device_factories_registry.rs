@@ -33,6 +33,10 @@ impl DeviceFactoryRegistry {
         );
     }
 
+    pub fn contains(&self, device_factory_key: &str) -> bool {
+        self.devices.contains_key(device_factory_key)
+    }
+
     pub fn get_device_factory(&self, device_factory_key: &str) -> Option<Box<dyn DeviceFactory>> {
         let factory_box = self.devices.get(device_factory_key)?;
         Some((factory_box.factory)())
@@ -2,6 +2,8 @@ use std::{collections::HashSet, io, path::PathBuf};
 
 pub trait DifferentialArchiveStep {
     fn get_step_name(&self) -> &str;
+    // User defined labels given to the backup, empty when it has none
+    fn get_tags(&self) -> Vec<String>;
     fn extract_to(
         &self,
         to: &PathBuf,
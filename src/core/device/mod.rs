@@ -2,15 +2,19 @@ mod archiver;
 mod backup_requirement;
 mod device_factories_registry;
 mod extractor;
+mod multiplex_archiver;
 mod question;
 mod secondary_device;
 
-pub use archiver::{ArchiveError, ArchiveWriter};
-pub use backup_requirement::{BackupRequirementClass, SecurityLevel};
+pub use archiver::{ArchiveError, ArchiveWriter, ArchiveWriterOptions, CompressionFormat};
+pub use backup_requirement::{
+    BackupRequirementClass, RequirementComplianceResult, RequirementSummary, SecurityLevel,
+};
 pub use device_factories_registry::DeviceFactoryRegistry;
 pub use extractor::{DifferentialArchiveStep, Extractor, ExtractorError};
+pub use multiplex_archiver::MultiplexArchiveWriter;
 pub use question::{Question, QuestionType};
-pub use secondary_device::{Device, DeviceFactory, DeviceFactoryKey};
+pub use secondary_device::{Device, DeviceCapability, DeviceFactory, DeviceFactoryKey};
 
 #[cfg(test)]
-pub use secondary_device::{MockDevice, MockDeviceFactory};
+pub use secondary_device::{DeviceEq, MockDevice, MockDeviceFactory};
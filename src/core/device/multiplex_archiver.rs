@@ -0,0 +1,266 @@
+use std::{
+    fs::File,
+    io::{Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use crate::core::util::sparse_files::SparseSegment;
+
+use super::{ArchiveError, ArchiveWriter};
+
+/// Archive writer forwarding every call to several writers, to backup a
+/// project on multiple devices in a single pass
+pub struct MultiplexArchiveWriter {
+    writers: Vec<Box<dyn ArchiveWriter>>,
+}
+
+impl MultiplexArchiveWriter {
+    pub fn new(writers: Vec<Box<dyn ArchiveWriter>>) -> Self {
+        MultiplexArchiveWriter { writers }
+    }
+
+    // Run the operation on every writer, even if some of them fail,
+    // and combine the errors into a single one
+    fn for_each_writer(
+        &mut self,
+        mut operation: impl FnMut(&mut Box<dyn ArchiveWriter>) -> Result<(), ArchiveError>,
+    ) -> Result<(), ArchiveError> {
+        let errors: Vec<String> = self
+            .writers
+            .iter_mut()
+            .filter_map(|writer| operation(writer).err())
+            .map(|e| e.message)
+            .collect();
+
+        if !errors.is_empty() {
+            return Err(ArchiveError {
+                message: errors.join("; "),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl ArchiveWriter for MultiplexArchiveWriter {
+    fn add_file(
+        &mut self,
+        file: &mut File,
+        path: &PathBuf,
+        ctime: u128,
+        mtime: u128,
+        size: u64,
+    ) -> Result<(), ArchiveError> {
+        self.for_each_writer(|writer| {
+            // Each writer reads the whole file, so rewind it for the next one
+            file.seek(SeekFrom::Start(0))?;
+            writer.add_file(file, path, ctime, mtime, size)
+        })
+    }
+
+    fn add_sparse_file(
+        &mut self,
+        file: &mut File,
+        path: &PathBuf,
+        ctime: u128,
+        mtime: u128,
+        size: u64,
+        data_segments: &[SparseSegment],
+    ) -> Result<(), ArchiveError> {
+        self.for_each_writer(|writer| {
+            file.seek(SeekFrom::Start(0))?;
+            writer.add_sparse_file(file, path, ctime, mtime, size, data_segments)
+        })
+    }
+
+    fn add_extended_attributes(
+        &mut self,
+        path: &PathBuf,
+        xattrs: Vec<(String, Vec<u8>)>,
+    ) -> Result<(), ArchiveError> {
+        self.for_each_writer(|writer| writer.add_extended_attributes(path, xattrs.clone()))
+    }
+
+    fn add_directory(
+        &mut self,
+        src_path: &Path,
+        path: &PathBuf,
+        ctime: u128,
+        mtime: u128,
+    ) -> Result<(), ArchiveError> {
+        self.for_each_writer(|writer| writer.add_directory(src_path, path, ctime, mtime))
+    }
+
+    fn add_symlink(
+        &mut self,
+        path: &PathBuf,
+        ctime: u128,
+        mtime: u128,
+        target: &PathBuf,
+    ) -> Result<(), ArchiveError> {
+        self.for_each_writer(|writer| writer.add_symlink(path, ctime, mtime, target))
+    }
+
+    // Every writer receives the same files, those failing on some of them excepted
+    fn bytes_written(&self) -> u64 {
+        self.writers
+            .iter()
+            .map(|writer| writer.bytes_written())
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn finalize(
+        &mut self,
+        deleted_files: &Vec<PathBuf>,
+        new_index: &Vec<u8>,
+        tags: &[String],
+    ) -> Result<(), ArchiveError> {
+        self.for_each_writer(|writer| writer.finalize(deleted_files, new_index, tags))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, fs, io::Read, rc::Rc};
+
+    use crate::core::test_utils::fs::create_tmp_dir;
+
+    use super::*;
+
+    struct RecordingArchiveWriter {
+        files: Rc<RefCell<Vec<String>>>,
+        fail_with: Option<&'static str>,
+        bytes_written: u64,
+    }
+
+    impl ArchiveWriter for RecordingArchiveWriter {
+        fn add_file(
+            &mut self,
+            file: &mut File,
+            _path: &PathBuf,
+            _ctime: u128,
+            _mtime: u128,
+            size: u64,
+        ) -> Result<(), ArchiveError> {
+            if let Some(message) = self.fail_with {
+                return Err(ArchiveError::from(message));
+            }
+            let mut content = String::new();
+            file.read_to_string(&mut content)?;
+            self.files.borrow_mut().push(content);
+            self.bytes_written += size;
+            Ok(())
+        }
+
+        fn bytes_written(&self) -> u64 {
+            self.bytes_written
+        }
+
+        fn add_directory(
+            &mut self,
+            _src_path: &Path,
+            _path: &PathBuf,
+            _ctime: u128,
+            _mtime: u128,
+        ) -> Result<(), ArchiveError> {
+            Ok(())
+        }
+
+        fn add_symlink(
+            &mut self,
+            _path: &PathBuf,
+            _ctime: u128,
+            _mtime: u128,
+            _target: &PathBuf,
+        ) -> Result<(), ArchiveError> {
+            Ok(())
+        }
+
+        fn finalize(
+            &mut self,
+            _deleted_files: &Vec<PathBuf>,
+            _new_index: &Vec<u8>,
+            _tags: &[String],
+        ) -> Result<(), ArchiveError> {
+            Ok(())
+        }
+    }
+
+    fn open_test_file() -> File {
+        let path = Path::join(&create_tmp_dir(), "file.txt");
+        fs::write(&path, "Hello").unwrap();
+        File::open(path).unwrap()
+    }
+
+    #[test]
+    fn when_adding_a_file_every_writer_shall_receive_its_whole_content() {
+        let files = Rc::new(RefCell::new(vec![]));
+        let mut writer = MultiplexArchiveWriter::new(vec![
+            Box::new(RecordingArchiveWriter {
+                files: files.clone(),
+                fail_with: None,
+                bytes_written: 0,
+            }),
+            Box::new(RecordingArchiveWriter {
+                files: files.clone(),
+                fail_with: None,
+                bytes_written: 0,
+            }),
+        ]);
+
+        writer
+            .add_file(&mut open_test_file(), &PathBuf::from("file.txt"), 0, 0, 5)
+            .unwrap();
+        assert_eq!(*files.borrow(), vec!["Hello", "Hello"]);
+    }
+
+    #[test]
+    fn when_some_writers_fail_errors_shall_be_combined_and_others_shall_still_be_called() {
+        let files = Rc::new(RefCell::new(vec![]));
+        let mut writer = MultiplexArchiveWriter::new(vec![
+            Box::new(RecordingArchiveWriter {
+                files: files.clone(),
+                fail_with: Some("Device 1 is full"),
+                bytes_written: 0,
+            }),
+            Box::new(RecordingArchiveWriter {
+                files: files.clone(),
+                fail_with: None,
+                bytes_written: 0,
+            }),
+            Box::new(RecordingArchiveWriter {
+                files: files.clone(),
+                fail_with: Some("Device 3 is gone"),
+                bytes_written: 0,
+            }),
+        ]);
+
+        let error = writer
+            .add_file(&mut open_test_file(), &PathBuf::from("file.txt"), 0, 0, 5)
+            .unwrap_err();
+        assert_eq!(error.message, "Device 1 is full; Device 3 is gone");
+        assert_eq!(*files.borrow(), vec!["Hello"]);
+    }
+
+    #[test]
+    fn bytes_written_shall_be_the_most_written_by_a_writer() {
+        let files = Rc::new(RefCell::new(vec![]));
+        let mut writer = MultiplexArchiveWriter::new(vec![
+            Box::new(RecordingArchiveWriter {
+                files: files.clone(),
+                fail_with: Some("Device 1 is full"),
+                bytes_written: 0,
+            }),
+            Box::new(RecordingArchiveWriter {
+                files: files.clone(),
+                fail_with: None,
+                bytes_written: 0,
+            }),
+        ]);
+
+        writer
+            .add_file(&mut open_test_file(), &PathBuf::from("file.txt"), 0, 0, 5)
+            .unwrap_err();
+        assert_eq!(writer.bytes_written(), 5);
+    }
+}
@@ -3,6 +3,7 @@ pub enum QuestionType {
     String,
     UnixPath,
     SingleChoice(Vec<String>),
+    Select(Vec<String>),
 }
 
 pub struct Question {
@@ -34,6 +35,10 @@ impl Question {
         Ok(())
     }
 
+    pub fn clear_answer(&mut self) {
+        self.answer = None;
+    }
+
     pub fn get_answer(&self) -> Result<String, String> {
         match &self.answer {
             Some(answer) => Ok(answer.to_string()),
@@ -41,12 +46,16 @@ impl Question {
         }
     }
 
-    fn validate_answer(&self, answer: &String) -> Result<(), String> {
+    pub fn validate_answer(&self, answer: &str) -> Result<(), String> {
         match &self.question_type {
-            QuestionType::SingleChoice(answers) if !answers.contains(&answer) => Err(format!(
-                "Invalid answer. Possible answers are: {}",
-                answers.join(", ")
-            )),
+            QuestionType::SingleChoice(answers) | QuestionType::Select(answers)
+                if !answers.iter().any(|a| a == answer) =>
+            {
+                Err(format!(
+                    "Invalid answer. Possible answers are: {}",
+                    answers.join(", ")
+                ))
+            }
             QuestionType::UnixPath if !answer.starts_with('/') && !answer.starts_with('~') => {
                 Err("Invalid answer. Should be a valid Unix path".to_string())
             }
@@ -65,6 +74,14 @@ mod tests {
         assert_eq!(question.get_statement(), "What is your name?");
     }
 
+    #[test]
+    fn test_clearing_an_answer_shall_forget_it() {
+        let mut question = Question::new("What is your name?".to_string(), QuestionType::String);
+        question.set_answer("John".to_string()).unwrap();
+        question.clear_answer();
+        assert_eq!(question.get_answer(), Err("No answer provided".to_string()));
+    }
+
     #[test]
     fn test_create_a_string_question_and_answer_it() {
         let mut question = Question::new("What is your name?".to_string(), QuestionType::String);
@@ -132,4 +149,24 @@ mod tests {
         question.set_answer("~/john".to_string()).unwrap();
         assert_eq!(question.get_answer().unwrap(), "~/john");
     }
+
+    #[test]
+    fn test_create_a_select_question_and_answer_it_with_an_option_should_save_answer() {
+        let mut question = Question::new(
+            "Which compression level?".to_string(),
+            QuestionType::Select(vec!["fast".to_string(), "best".to_string()]),
+        );
+        question.set_answer("best".to_string()).unwrap();
+        assert_eq!(question.get_answer().unwrap(), "best");
+    }
+
+    #[test]
+    fn test_create_a_select_question_and_answer_it_with_an_unknown_option_should_return_error() {
+        let mut question = Question::new(
+            "Which compression level?".to_string(),
+            QuestionType::Select(vec!["fast".to_string(), "best".to_string()]),
+        );
+        let error = question.set_answer("medium".to_string()).unwrap_err();
+        assert_eq!(error, "Invalid answer. Possible answers are: fast, best");
+    }
 }
@@ -1,9 +1,15 @@
 #[cfg(test)]
 use mockall::automock;
 
-use std::{io::BufRead, time::Instant};
+use std::{
+    fmt::{Display, Formatter},
+    io::{BufRead, Read},
+    path::Path,
+    time::{Duration, Instant, SystemTime},
+};
 
-use super::{ArchiveWriter, Extractor, QuestionType, SecurityLevel};
+use super::{ArchiveWriter, ArchiveWriterOptions, Extractor, QuestionType, SecurityLevel};
+use crate::core::backup::read_archive_entry;
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct DeviceFactoryKey {
@@ -11,8 +17,41 @@ pub struct DeviceFactoryKey {
     pub readable_name: String,
 }
 
+// Optional features a device may support, to select optimized code paths
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DeviceCapability {
+    Encryption,
+    Reflink,
+    SparseFiles,
+    Streaming,
+    PartialRestore,
+}
+
+impl DeviceCapability {
+    pub const ALL: [DeviceCapability; 5] = [
+        DeviceCapability::Encryption,
+        DeviceCapability::Reflink,
+        DeviceCapability::SparseFiles,
+        DeviceCapability::Streaming,
+        DeviceCapability::PartialRestore,
+    ];
+}
+
+impl Display for DeviceCapability {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeviceCapability::Encryption => write!(f, "Encryption"),
+            DeviceCapability::Reflink => write!(f, "Reflink"),
+            DeviceCapability::SparseFiles => write!(f, "SparseFiles"),
+            DeviceCapability::Streaming => write!(f, "Streaming"),
+            DeviceCapability::PartialRestore => write!(f, "PartialRestore"),
+        }
+    }
+}
+
+// Devices are shared between threads, to test their availability in parallel
 #[cfg_attr(test, automock)]
-pub trait Device {
+pub trait Device: Sync {
     // The name of the device
     fn get_name(&self) -> String;
 
@@ -31,27 +70,138 @@ pub trait Device {
     // The last time the device was disconnected
     fn get_last_disconnection(&self) -> Option<Instant>;
 
+    // The last time the device was found available, persisted across runs
+    fn get_last_seen(&self) -> Option<SystemTime>;
+
+    // Record that the device has been found available
+    fn set_last_seen(&mut self, last_seen: SystemTime);
+
+    // Whether the device supports an optional feature
+    fn supports_capability(&self, _capability: DeviceCapability) -> bool {
+        false
+    }
+
     // Serialize the device to a TOML table
     fn to_toml_table(&self) -> toml::value::Table;
 
     // Read the index of a backup from the device if the project is backed up on this device
     fn read_backup_index(&self, project_name: &str) -> Result<Option<Box<dyn BufRead>>, String>;
 
+    // Replace the index of the latest backup of a project on the device
+    fn write_backup_index(&self, project_name: &str, index: &[u8]) -> Result<(), String>;
+
     // Test if the device is connected
     fn test_availability(&self) -> Result<(), String>;
 
+    // Test if files can be written on the device, which may be connected but read-only
+    fn test_write_permission(&self) -> Result<(), String>;
+
+    // Free space left on the device in bytes, if it can be known
+    fn get_free_space(&self) -> Option<u64>;
+
+    // List the names of the archives stored on the device for a project, oldest first
+    fn list_archives(&self, project_name: &str) -> Result<Vec<String>, String>;
+
+    // List the names of the projects having backups stored on the device
+    fn list_backed_up_projects(&self) -> Result<Vec<String>, String>;
+
+    // Rename the archives of a project written with an older naming format,
+    // and return the number of renamed archives
+    fn migrate_archives(&self, project_name: &str) -> Result<usize, String>;
+
+    // Time of the backup written to an archive, read from the archive name,
+    // None when the name is not one of an archive of the device
+    fn get_archive_time(&self, archive_name: &str) -> Option<SystemTime>;
+
+    // Time of the latest backup of a project on the device, if any
+    fn get_last_backup_time(&self, project_name: &str) -> Result<Option<SystemTime>, String>;
+
+    // Read the tar content of one of the archives returned by list_archives,
+    // decompressed when the archive is compressed
+    fn get_archive_reader(
+        &self,
+        project_name: &str,
+        archive_name: &str,
+    ) -> Result<Box<dyn Read>, String>;
+
+    // Read one of the files written along with the files of an archive, such as
+    // .index or .deleted-files, None when the archive does not hold it
+    fn read_archive_file(
+        &self,
+        project_name: &str,
+        archive_name: &str,
+        file_name: &str,
+    ) -> Result<Option<Vec<u8>>, String> {
+        let reader = self.get_archive_reader(project_name, archive_name)?;
+        read_archive_entry(reader, Path::new(file_name)).map_err(|e| e.to_string())
+    }
+
+    // Create the folder of a project on the device, before its first backup
+    fn create_project_directory(&self, project_name: &str) -> Result<(), String>;
+
+    // Rename the folder of a project after the project itself is renamed, so that
+    // its next backups keep the history of the previous ones
+    fn rename_project_directory(&self, project_name: &str, new_name: &str) -> Result<(), String>;
+
+    // Delete all the backups and the index of a project from the device
+    fn delete_project_data(&self, project_name: &str) -> Result<(), String>;
+
+    // Delete the oldest archives of a project beyond max_archives, returning how many
+    // were deleted. Archives holding the only copy of a file of the latest backup are kept
+    fn prune_archives(&self, project_name: &str, max_archives: usize) -> Result<usize, String>;
+
     // Get the archive writer for the device
-    fn get_archive_writer(&self, project_name: &str) -> Box<dyn ArchiveWriter>;
+    fn get_archive_writer(
+        &self,
+        project_name: &str,
+        options: &ArchiveWriterOptions,
+    ) -> Box<dyn ArchiveWriter>;
 
     // Get the extractor for the device
     fn get_extractor(&self, project_name: &str) -> Box<dyn Extractor>;
 }
 
+// Devices are identified by their name and type, whatever their runtime state
+pub trait DeviceEq: Device {
+    fn device_eq(&self, other: &dyn Device) -> bool {
+        self.get_name() == other.get_name()
+            && self.get_device_type_name() == other.get_device_type_name()
+    }
+}
+
+impl DeviceEq for dyn Device {}
+
+impl PartialEq<dyn Device> for Box<dyn Device> {
+    fn eq(&self, other: &dyn Device) -> bool {
+        self.as_ref().device_eq(other)
+    }
+}
+
 #[cfg_attr(test, automock)]
 pub trait DeviceFactory {
+    // Help text describing the device type, its requirements and security characteristics
+    fn get_description(&self) -> &str {
+        ""
+    }
+    // Rough time needed to answer the questions and prepare the device, shown
+    // before starting; zero when the setup is immediate
+    fn estimated_setup_time(&self) -> Duration {
+        Duration::from_secs(0)
+    }
+    // Answer used when none is typed, for each question in order, e.g. the usual
+    // mount point of drives on the current platform
+    fn default_answers(&self) -> Vec<Option<String>> {
+        Vec::new()
+    }
     fn get_question_statement(&self) -> &str;
     fn get_question_type(&self) -> &QuestionType;
+    // Check an answer to the current question without storing it
+    fn validate_answer(&self, _answer: &str) -> Result<(), String> {
+        Ok(())
+    }
     fn set_question_answer(&mut self, answer: String) -> Result<(), String>;
+    // Forget all the answers, to ask the questions again from the first one
+    fn reset(&mut self);
     fn has_next(&self) -> bool;
     fn build(&self) -> Result<Box<dyn Device>, String>;
     fn build_from_toml_table(
@@ -60,3 +210,27 @@ pub trait DeviceFactory {
         table: &toml::value::Table,
     ) -> Result<Box<dyn Device>, String>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Device, DeviceEq};
+    use crate::core::test_utils::mocks::{MockDevice, MockDeviceWithParameters};
+
+    #[test]
+    fn devices_with_same_name_and_type_shall_be_equal() {
+        let device: Box<dyn Device> = Box::new(MockDevice::new("MyDevice"));
+        let same_device: Box<dyn Device> = Box::new(MockDevice::new("MyDevice"));
+        assert!(device == *same_device);
+        assert!(MockDevice::new("MyDevice").device_eq(device.as_ref()));
+    }
+
+    #[test]
+    fn devices_with_different_name_or_type_shall_differ() {
+        let device: Box<dyn Device> = Box::new(MockDevice::new("MyDevice"));
+        let renamed: Box<dyn Device> = Box::new(MockDevice::new("OtherDevice"));
+        let other_type: Box<dyn Device> =
+            Box::new(MockDeviceWithParameters::new("MyDevice", "param"));
+        assert!(device != *renamed);
+        assert!(device != *other_type);
+    }
+}
@@ -0,0 +1,77 @@
+use std::fmt::{Display, Formatter};
+
+use serde::Serialize;
+
+/// Outcome of a backup run, sent to the user once the run is over
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum BackupEvent {
+    Success {
+        project: String,
+        device: String,
+        files_changed: usize,
+    },
+    Failure {
+        project: String,
+        device: String,
+        error: String,
+    },
+}
+
+impl Display for BackupEvent {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            BackupEvent::Success {
+                project,
+                device,
+                files_changed,
+            } => write!(
+                f,
+                "Backup of {} to {} succeeded, {} files changed",
+                project, device, files_changed
+            ),
+            BackupEvent::Failure {
+                project,
+                device,
+                error,
+            } => write!(f, "Backup of {} to {} failed: {}", project, device, error),
+        }
+    }
+}
+
+pub trait NotificationSender {
+    fn send(&self, event: BackupEvent) -> Result<(), String>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn success_event_shall_be_serialized_with_its_status() {
+        let event = BackupEvent::Success {
+            project: "my-project".to_string(),
+            device: "usb".to_string(),
+            files_changed: 3,
+        };
+
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"status":"success","project":"my-project","device":"usb","files_changed":3}"#
+        );
+    }
+
+    #[test]
+    fn failure_event_shall_be_displayed_with_its_error() {
+        let event = BackupEvent::Failure {
+            project: "my-project".to_string(),
+            device: "usb".to_string(),
+            error: "Device not available".to_string(),
+        };
+
+        assert_eq!(
+            event.to_string(),
+            "Backup of my-project to usb failed: Device not available"
+        );
+    }
+}
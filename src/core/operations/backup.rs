@@ -1,13 +1,34 @@
-use std::path::PathBuf;
+use std::{
+    fs,
+    io::{self, BufRead, BufReader},
+    path::{Path, PathBuf},
+    sync::{atomic::AtomicBool, Arc},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use tracing::warn;
+use walkdir::WalkDir;
 
-use crate::core::{
-    backup::{BackupExecution, BackupIndex, RestoreExecution},
-    config::GlobalConfig,
-    project::Project,
-    Device,
+use crate::{
+    core::{
+        backup::{
+            BackupExecution, BackupIndex, BackupIndexEntry, BackupSummary, DryRunResult,
+            RestoreExecution, SpecialFilePolicy, ToBuffer,
+        },
+        config::GlobalConfig,
+        device::{
+            ArchiveWriter, ArchiveWriterOptions, BackupRequirementClass, MultiplexArchiveWriter,
+        },
+        project::Project,
+        Device,
+    },
+    now,
 };
 
-use super::{BackupOperations, Operations};
+use super::{
+    BackupOperations, BackupOptions, CopyComparisonResult, IndexStatistics, IndexedFile,
+    IndexedFilesOrder, Operations, ProjectBackupResults, RepairReport, RestorePoint,
+};
 
 impl Operations {
     fn get_project_and_device<'a>(
@@ -42,7 +63,27 @@ impl Operations {
         Ok((project, device))
     }
 
-    fn get_index_file(project: &Project, device: &Box<dyn Device>) -> Result<BackupIndex, String> {
+    fn get_available_device<'a>(
+        &self,
+        config: &'a GlobalConfig,
+        device_name: &str,
+    ) -> Result<&'a dyn Device, String> {
+        let device = config
+            .get_device_by_name(device_name)
+            .ok_or_else(|| format!("Device not found: {}", device_name))?;
+
+        device.test_availability().map_err(|e| {
+            format!(
+                "Device not available at location {}: {}",
+                device.get_location(),
+                e
+            )
+        })?;
+
+        Ok(device.as_ref())
+    }
+
+    fn get_index_file(project: &Project, device: &dyn Device) -> Result<BackupIndex, String> {
         device
             .read_backup_index(project.get_name())?
             .map_or(Ok(BackupIndex::new()), |reader| {
@@ -50,29 +91,393 @@ impl Operations {
             })
             .map_err(|e| format!("Backup index read failed: {}", e))
     }
+
+    fn check_security_level(
+        requirement: &BackupRequirementClass,
+        device: &dyn Device,
+    ) -> Result<(), String> {
+        let security_level = device.get_security_level();
+        if security_level < *requirement.get_min_security_level() {
+            return Err(format!(
+                "Device {} security level ({}) is lower than the required {}",
+                device.get_name(),
+                security_level,
+                requirement.get_min_security_level()
+            ));
+        }
+        Ok(())
+    }
+
+    // The most secure device comes first, devices of the same level keeping their
+    // configuration order, so that availability is only tested until one is found
+    fn select_auto_device<'a>(
+        config: &'a GlobalConfig,
+        project: &Project,
+    ) -> Result<&'a dyn Device, String> {
+        let candidates = match project.get_tracking_status().get_backup_requirement_class() {
+            Some(class) => config.get_devices_satisfying(class),
+            None => config.devices_by_security_level_desc().collect(),
+        };
+
+        candidates
+            .into_iter()
+            .find(|device| device.test_availability().is_ok())
+            .map(|device| device.as_ref())
+            .ok_or_else(|| {
+                format!(
+                    "No available device satisfies the backup requirement class of {}",
+                    project.get_name()
+                )
+            })
+    }
+
+    fn is_last_backup_too_recent(
+        config: &GlobalConfig,
+        project_name: &str,
+        min_interval: Duration,
+    ) -> bool {
+        config
+            .get_project_by_name(project_name)
+            .and_then(|project| project.get_tracking_status().get_last_update())
+            .and_then(|last_update| now!().duration_since(last_update).ok())
+            .is_some_and(|elapsed| elapsed < min_interval)
+    }
+
+    // Devices whose last archive of the project is older than the interval, or missing
+    // The devices that cannot be read are kept, the backup reports why they failed
+    fn devices_not_backed_up_within(
+        config: &GlobalConfig,
+        project_name: &str,
+        device_names: &[String],
+        interval: Duration,
+    ) -> Vec<String> {
+        device_names
+            .iter()
+            .filter(|device_name| {
+                config
+                    .get_device_by_name(device_name)
+                    .and_then(|device| device.get_last_backup_time(project_name).ok().flatten())
+                    .and_then(|last_backup| now!().duration_since(last_backup).ok())
+                    .is_none_or(|elapsed| elapsed >= interval)
+            })
+            .cloned()
+            .collect()
+    }
+
+    // Merge the indexes of the project on each device, so that files missing
+    // from any of them are written again
+    fn get_merged_index<'a>(
+        project: &Project,
+        devices: impl Iterator<Item = &'a dyn Device>,
+    ) -> Result<BackupIndex, String> {
+        let mut index: Option<BackupIndex> = None;
+        for device in devices {
+            let device_index = Operations::get_index_file(project, device)?;
+            index = Some(match index {
+                Some(index) => index.merge(&device_index),
+                None => device_index,
+            });
+        }
+        index.ok_or_else(|| "No device specified for the backup".to_string())
+    }
+
+    // Full index of the project at the time of an archive, as stored along with its files
+    fn read_archive_index(
+        device: &dyn Device,
+        project_name: &str,
+        archive_name: &str,
+    ) -> Result<BackupIndex, String> {
+        let index = match device.get_archive_reader(project_name, archive_name) {
+            Ok(reader) => BackupIndex::from_archive(reader),
+            // Snapshots are not tar archives, their index is a file of their directory
+            Err(_) => match device.read_archive_file(project_name, archive_name, ".index")? {
+                Some(index) => BackupIndex::from_index_reader(index.as_slice()),
+                None => return Err(format!("No index found in {}", archive_name)),
+            },
+        };
+        index.map_err(|e| format!("Cannot read the index of {}: {}", archive_name, e))
+    }
+
+    // Check that the index embedded in the latest archive matches the current index of the device
+    pub fn verify_latest_backup(project: &Project, device: &dyn Device) -> Result<(), String> {
+        let project_name = project.get_name();
+        let latest_archive = device
+            .list_archives(project_name)?
+            .pop()
+            .ok_or_else(|| format!("No archive found on {}", device.get_name()))?;
+        let archived_index = Operations::read_archive_index(device, project_name, &latest_archive)?;
+        let current_index = Operations::get_index_file(project, device)?;
+
+        let diff = archived_index.diff(&current_index);
+        if !diff.only_in_self.is_empty()
+            || !diff.only_in_other.is_empty()
+            || !diff.changed.is_empty()
+        {
+            return Err(format!(
+                "Index of {} on {} does not match the current index",
+                latest_archive,
+                device.get_name()
+            ));
+        }
+        Ok(())
+    }
 }
 
 impl BackupOperations for Operations {
     fn backup_project_to_device(
         &self,
         project_name: &str,
-        device_name: &str,
-    ) -> Result<(), String> {
+        device_names: &[String],
+        options: &BackupOptions,
+    ) -> Result<BackupSummary, String> {
         let config = &GlobalConfig::load(
             self.global_config_provider.as_ref(),
             &self.device_factory_registry,
         )?;
-        let (project, device) = self.get_project_and_device(&config, project_name, device_name)?;
-        let index = Operations::get_index_file(project, device)?;
+        if config.project_count() == 0 {
+            return Err("No project registered, create one with 'project new'".to_string());
+        }
+        if config.device_count() == 0 {
+            return Err("No device registered, create one with 'device new'".to_string());
+        }
+        if device_names.is_empty() {
+            return Err("No device specified for the backup".to_string());
+        }
+        if let Some(min_interval) = options.min_interval {
+            if Operations::is_last_backup_too_recent(config, project_name, min_interval) {
+                return Ok(BackupSummary {
+                    skipped_too_recent: true,
+                    ..Default::default()
+                });
+            }
+        }
+
+        let projects_and_devices = device_names
+            .iter()
+            .map(|device_name| self.get_project_and_device(&config, project_name, device_name))
+            .collect::<Result<Vec<_>, _>>()?;
+        let project = projects_and_devices[0].0;
+        if let Some(requirement) = project.get_tracking_status().get_backup_requirement_class() {
+            for (_, device) in &projects_and_devices {
+                Operations::check_security_level(requirement, device.as_ref())?;
+            }
+        }
+        for (_, device) in &projects_and_devices {
+            device
+                .test_write_permission()
+                .map_err(|e| format!("Device {} is not writable: {}", device.get_name(), e))?;
+        }
+
+        let index = Operations::get_merged_index(
+            project,
+            projects_and_devices
+                .iter()
+                .map(|(_, device)| device.as_ref()),
+        )?;
+        let writer_options = ArchiveWriterOptions {
+            compress: options.compress,
+            keep_partial: options.keep_partial,
+            compression_format: options.compression_format,
+        };
+        let mut archive_writers = projects_and_devices
+            .iter()
+            .map(|(_, device)| device.get_archive_writer(&project.get_name(), &writer_options))
+            .collect::<Vec<_>>();
+        let archive_writer: Box<dyn ArchiveWriter> = if archive_writers.len() == 1 {
+            archive_writers.pop().unwrap()
+        } else {
+            Box::new(MultiplexArchiveWriter::new(archive_writers))
+        };
 
         let project_root_path = PathBuf::from(project.get_location());
-        let archive_writer = device.get_archive_writer(&project.get_name());
 
-        BackupExecution::new(index, project_root_path)
+        let mut summary = BackupExecution::new(index, project_root_path)
+            .with_priority(options.priority)
+            .with_max_file_size(options.max_file_size)
+            .with_checksum_verify(options.checksum_verify_before)
+            .with_special_file_policy(if options.skip_special_files {
+                SpecialFilePolicy::Skip
+            } else {
+                SpecialFilePolicy::Fail
+            })
+            .with_tags(options.tags.clone())
             .execute(archive_writer)
+            .map_err(|e| format!("Backup failed: {}", e))?;
+        self.record_project_backed_up(project_name, device_names);
+
+        // The archives replaced by the new one are only pruned once it is verified
+        let max_archives = options
+            .max_archives
+            .or(project.get_tracking_status().get_max_archives());
+        let mut verification_errors = vec![];
+        for (_, device) in &projects_and_devices {
+            if options.verify_after {
+                if let Err(e) = Operations::verify_latest_backup(project, device.as_ref()) {
+                    verification_errors.push(e);
+                    continue;
+                }
+            }
+            if let Some(max_archives) = max_archives {
+                match device.prune_archives(project.get_name(), max_archives) {
+                    Ok(pruned) => summary.pruned_archives += pruned,
+                    Err(e) => warn!("Failed to prune archives of {}: {}", device.get_name(), e),
+                }
+            }
+        }
+
+        if !verification_errors.is_empty() {
+            return Err(format!(
+                "Backup verification failed: {}",
+                verification_errors.join("; ")
+            ));
+        }
+        Ok(summary)
+    }
+
+    fn dry_run_backup(
+        &self,
+        project_name: &str,
+        device_names: &[String],
+        options: &BackupOptions,
+    ) -> Result<DryRunResult, String> {
+        let config = &GlobalConfig::load(
+            self.global_config_provider.as_ref(),
+            &self.device_factory_registry,
+        )?;
+        if device_names.is_empty() {
+            return Err("No device specified for the backup".to_string());
+        }
+
+        let projects_and_devices = device_names
+            .iter()
+            .map(|device_name| self.get_project_and_device(config, project_name, device_name))
+            .collect::<Result<Vec<_>, _>>()?;
+        let project = projects_and_devices[0].0;
+        let index = Operations::get_merged_index(
+            project,
+            projects_and_devices
+                .iter()
+                .map(|(_, device)| device.as_ref()),
+        )?;
+
+        BackupExecution::new(index, PathBuf::from(project.get_location()))
+            .with_max_file_size(options.max_file_size)
+            .dry_run()
+            .map_err(|e| format!("Dry run failed: {}", e))
+    }
+
+    fn backup_project_auto(&self, project_name: &str) -> Result<String, String> {
+        let config = GlobalConfig::load(
+            self.global_config_provider.as_ref(),
+            &self.device_factory_registry,
+        )?;
+        let project = config
+            .get_project_by_name(project_name)
+            .ok_or_else(|| format!("Project not found: {}", project_name))?;
+        let device_name = Operations::select_auto_device(&config, project)?.get_name();
+
+        self.backup_project_to_device(
+            project_name,
+            std::slice::from_ref(&device_name),
+            &BackupOptions::default(),
+        )?;
+        Ok(device_name)
+    }
+
+    fn watch_project_on_device(
+        &self,
+        project_name: &str,
+        device_name: &str,
+        debounce: Duration,
+        stop_signal: Arc<AtomicBool>,
+    ) -> Result<(), String> {
+        let config = GlobalConfig::load(
+            self.global_config_provider.as_ref(),
+            &self.device_factory_registry,
+        )?;
+        let (project, device) = self.get_project_and_device(&config, project_name, device_name)?;
+        if let Some(requirement) = project.get_tracking_status().get_backup_requirement_class() {
+            Operations::check_security_level(requirement, device.as_ref())?;
+        }
+        device
+            .test_write_permission()
+            .map_err(|e| format!("Device {} is not writable: {}", device.get_name(), e))?;
+        let index = Operations::get_merged_index(project, std::iter::once(device.as_ref()))?;
+        let mut execution = BackupExecution::new(index, PathBuf::from(project.get_location()))
+            .with_stop_signal(stop_signal);
+
+        // The configuration is moved to the factory, so that it can open a new archive
+        // on the device for each backup
+        let project_name = project.get_name().clone();
+        let device_name = device.get_name();
+        let writer_options = ArchiveWriterOptions::default();
+        let writer_factory = Box::new(move || {
+            config
+                .get_device_by_name(&device_name)
+                .expect("The device was found when the watch started")
+                .get_archive_writer(&project_name, &writer_options)
+        });
+
+        execution
+            .execute_watched(writer_factory, debounce)
             .map_err(|e| format!("Backup failed: {}", e))
     }
 
+    fn backup_projects_under(
+        &self,
+        location_prefix: &str,
+        device_names: &[String],
+        exclude_devices: &[String],
+        skip_if_backed_up_within: Option<Duration>,
+    ) -> Result<ProjectBackupResults, String> {
+        let config = GlobalConfig::load(
+            self.global_config_provider.as_ref(),
+            &self.device_factory_registry,
+        )?;
+        let project_names = config
+            .find_projects_by_location_prefix(location_prefix)
+            .iter()
+            .map(|project| project.get_name().clone())
+            .collect::<Vec<_>>();
+        if project_names.is_empty() {
+            return Err(format!("No project found under {}", location_prefix));
+        }
+        let device_names = device_names
+            .iter()
+            .filter(|device_name| !exclude_devices.contains(device_name))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        Ok(project_names
+            .into_iter()
+            .map(|project_name| {
+                let device_names = match skip_if_backed_up_within {
+                    Some(interval) => Operations::devices_not_backed_up_within(
+                        &config,
+                        &project_name,
+                        &device_names,
+                        interval,
+                    ),
+                    None => device_names.clone(),
+                };
+                if skip_if_backed_up_within.is_some() && device_names.is_empty() {
+                    let summary = BackupSummary {
+                        skipped_too_recent: true,
+                        ..Default::default()
+                    };
+                    return (project_name, Ok(summary));
+                }
+
+                let result = self.backup_project_to_device(
+                    &project_name,
+                    &device_names,
+                    &BackupOptions::default(),
+                );
+                (project_name, result)
+            })
+            .collect())
+    }
+
     fn restore_project_from_device(
         &self,
         project_name: &str,
@@ -84,7 +489,7 @@ impl BackupOperations for Operations {
             &self.device_factory_registry,
         )?;
         let (project, device) = self.get_project_and_device(&config, project_name, device_name)?;
-        let index = Operations::get_index_file(project, device)?;
+        let index = Operations::get_index_file(project, device.as_ref())?;
 
         let restoration_path = PathBuf::from(to);
         let extractor = device.get_extractor(project_name);
@@ -93,7 +498,908 @@ impl BackupOperations for Operations {
             .extract()
             .map_err(|e| format!("Restore failed: {}", e))
     }
+
+    fn rebuild_index(&self, project_name: &str, device_name: &str) -> Result<(), String> {
+        let config = &GlobalConfig::load(
+            self.global_config_provider.as_ref(),
+            &self.device_factory_registry,
+        )?;
+        let device = self.get_available_device(config, device_name)?;
+
+        let latest_archive = device
+            .list_archives(project_name)?
+            .pop()
+            .ok_or_else(|| format!("No archive found for project: {}", project_name))?;
+
+        let index = Operations::read_archive_index(device, project_name, &latest_archive)
+            .and_then(|index| {
+                index
+                    .to_buffer()
+                    .map_err(|e| format!("Backup index rebuild failed: {}", e))
+            })?;
+        device.write_backup_index(project_name, &index)
+    }
+
+    fn repair_index(&self, project_name: &str, device_name: &str) -> Result<RepairReport, String> {
+        let config = &GlobalConfig::load(
+            self.global_config_provider.as_ref(),
+            &self.device_factory_registry,
+        )?;
+        let (project, device) = self.get_project_and_device(config, project_name, device_name)?;
+        let mut index = Operations::get_index_file(project, device.as_ref())?;
+
+        let report = index
+            .repair(&PathBuf::from(project.get_location()))
+            .map_err(|e| format!("Backup index repair failed: {}", e))?;
+        if !report.dropped_entries.is_empty() {
+            let index = index
+                .to_buffer()
+                .map_err(|e| format!("Backup index repair failed: {}", e))?;
+            device.write_backup_index(project_name, &index)?;
+        }
+        Ok(report)
+    }
+
+    fn initialize_index(
+        &self,
+        project_name: &str,
+        device_name: &str,
+        copy_path: &str,
+    ) -> Result<(), String> {
+        let config = &GlobalConfig::load(
+            self.global_config_provider.as_ref(),
+            &self.device_factory_registry,
+        )?;
+        let (project, device) = self.get_project_and_device(config, project_name, device_name)?;
+        if device.read_backup_index(project_name)?.is_some() {
+            return Err(format!(
+                "Project {} is already backed up on device {}",
+                project_name, device_name
+            ));
+        }
+
+        // The project files are indexed, so their ctime values match the next backup,
+        // which is only right as long as the copy holds the same content
+        let project_path = PathBuf::from(project.get_location());
+        let differences = find_copy_differences(&project_path, Path::new(copy_path))
+            .map_err(|e| format!("Backup index initialization failed: {}", e))?;
+        if let Some(first_difference) = differences.first() {
+            return Err(format!(
+                "The copy at {} differs from project {} on {} paths, e.g. {}",
+                copy_path,
+                project_name,
+                differences.len(),
+                first_difference.display()
+            ));
+        }
+        let index = BackupIndex::from_snapshot_directory(&project_path)
+            .and_then(|index| index.to_buffer())
+            .map_err(|e| format!("Backup index initialization failed: {}", e))?;
+
+        // An archive holding only the index keeps the index and the archives of the
+        // device consistent, as after any other backup
+        device.create_project_directory(project_name)?;
+        device
+            .get_archive_writer(project_name, &ArchiveWriterOptions::default())
+            .finalize(&vec![], &index, &[])
+            .map_err(|e| format!("Backup index initialization failed: {}", e.message))
+    }
+
+    fn export_index_to_json(
+        &self,
+        project_name: &str,
+        device_name: &str,
+    ) -> Result<String, String> {
+        let config = &GlobalConfig::load(
+            self.global_config_provider.as_ref(),
+            &self.device_factory_registry,
+        )?;
+        let project = config
+            .get_project_by_name(project_name)
+            .ok_or_else(|| format!("Project not found: {}", project_name))?;
+        let index =
+            Operations::get_index_file(project, self.get_available_device(config, device_name)?)?;
+
+        index
+            .to_json()
+            .map_err(|e| format!("Backup index export failed: {}", e))
+    }
+
+    fn import_index_from_json(
+        &self,
+        project_name: &str,
+        device_name: &str,
+        input_path: &str,
+    ) -> Result<(), String> {
+        let config = &GlobalConfig::load(
+            self.global_config_provider.as_ref(),
+            &self.device_factory_registry,
+        )?;
+        config
+            .get_project_by_name(project_name)
+            .ok_or_else(|| format!("Project not found: {}", project_name))?;
+        let device = self.get_available_device(config, device_name)?;
+        if device.read_backup_index(project_name)?.is_some() {
+            return Err(format!(
+                "Project {} is already backed up on device {}",
+                project_name, device_name
+            ));
+        }
+
+        let json = std::fs::read_to_string(input_path)
+            .map_err(|e| format!("Cannot read {}: {}", input_path, e))?;
+        let index = BackupIndex::from_json(&json)
+            .map_err(|e| format!("Invalid index in {}: {}", input_path, e))?
+            .to_buffer()
+            .map_err(|e| format!("Backup index import failed: {}", e))?;
+        device.create_project_directory(project_name)?;
+        device.write_backup_index(project_name, &index)
+    }
+
+    fn compare_device_copies(
+        &self,
+        project_name: &str,
+        device1: &str,
+        device2: &str,
+    ) -> Result<CopyComparisonResult, String> {
+        let config = &GlobalConfig::load(
+            self.global_config_provider.as_ref(),
+            &self.device_factory_registry,
+        )?;
+        let project = config
+            .get_project_by_name(project_name)
+            .ok_or_else(|| format!("Project not found: {}", project_name))?;
+        let first_index =
+            Operations::get_index_file(project, self.get_available_device(config, device1)?)?;
+        let second_index =
+            Operations::get_index_file(project, self.get_available_device(config, device2)?)?;
+
+        let diff = first_index.diff(&second_index);
+        Ok(CopyComparisonResult {
+            only_on_first_device: diff.only_in_self,
+            only_on_second_device: diff.only_in_other,
+            different: diff.changed,
+        })
+    }
+
+    fn list_indexed_files(
+        &self,
+        project_name: &str,
+        device_name: &str,
+        order: IndexedFilesOrder,
+    ) -> Result<Vec<IndexedFile>, String> {
+        let config = &GlobalConfig::load(
+            self.global_config_provider.as_ref(),
+            &self.device_factory_registry,
+        )?;
+        let project = config
+            .get_project_by_name(project_name)
+            .ok_or_else(|| format!("Project not found: {}", project_name))?;
+        let index =
+            Operations::get_index_file(project, self.get_available_device(config, device_name)?)?;
+
+        let entries: Box<dyn Iterator<Item = _>> = match order {
+            IndexedFilesOrder::Path => Box::new(index.enumerate_entries()),
+            IndexedFilesOrder::Mtime => Box::new(index.entries_by_mtime_desc()),
+        };
+        Ok(entries.map(to_indexed_file).collect())
+    }
+
+    fn search_indexed_files(
+        &self,
+        project_name: &str,
+        device_name: &str,
+        pattern: &str,
+    ) -> Result<Vec<IndexedFile>, String> {
+        let config = &GlobalConfig::load(
+            self.global_config_provider.as_ref(),
+            &self.device_factory_registry,
+        )?;
+        let project = config
+            .get_project_by_name(project_name)
+            .ok_or_else(|| format!("Project not found: {}", project_name))?;
+        let index =
+            Operations::get_index_file(project, self.get_available_device(config, device_name)?)?;
+
+        Ok(index
+            .find_entries_matching(pattern)?
+            .into_iter()
+            .map(to_indexed_file)
+            .collect())
+    }
+
+    fn find_duplicate_files(
+        &self,
+        project_name: &str,
+        device_name: &str,
+    ) -> Result<Vec<Vec<PathBuf>>, String> {
+        let config = &GlobalConfig::load(
+            self.global_config_provider.as_ref(),
+            &self.device_factory_registry,
+        )?;
+        let project = config
+            .get_project_by_name(project_name)
+            .ok_or_else(|| format!("Project not found: {}", project_name))?;
+        let index =
+            Operations::get_index_file(project, self.get_available_device(config, device_name)?)?;
+
+        Ok(index
+            .find_duplicates()
+            .into_iter()
+            .map(|paths| paths.into_iter().map(Path::to_path_buf).collect())
+            .collect())
+    }
+
+    fn get_index_statistics(
+        &self,
+        project_name: &str,
+        device_name: &str,
+    ) -> Result<IndexStatistics, String> {
+        let config = &GlobalConfig::load(
+            self.global_config_provider.as_ref(),
+            &self.device_factory_registry,
+        )?;
+        let project = config
+            .get_project_by_name(project_name)
+            .ok_or_else(|| format!("Project not found: {}", project_name))?;
+        let index =
+            Operations::get_index_file(project, self.get_available_device(config, device_name)?)?;
+
+        Ok(index.statistics())
+    }
+
+    fn list_restore_points(
+        &self,
+        project_name: &str,
+        device_name: &str,
+    ) -> Result<Vec<RestorePoint>, String> {
+        let config = &GlobalConfig::load(
+            self.global_config_provider.as_ref(),
+            &self.device_factory_registry,
+        )?;
+        config
+            .get_project_by_name(project_name)
+            .ok_or_else(|| format!("Project not found: {}", project_name))?;
+        let device = self.get_available_device(config, device_name)?;
+
+        let mut restore_points = device
+            .list_archives(project_name)?
+            .into_iter()
+            .filter_map(|archive_name| {
+                let timestamp = device.get_archive_time(&archive_name)?;
+                let contains_deletions = device
+                    .read_archive_file(project_name, &archive_name, ".deleted-files")
+                    .ok()
+                    .flatten()
+                    .is_some_and(|deleted_files| !deleted_files.is_empty());
+                Some(RestorePoint {
+                    timestamp,
+                    archive_name,
+                    contains_deletions,
+                })
+            })
+            .collect::<Vec<_>>();
+        restore_points.sort_by_key(|restore_point| std::cmp::Reverse(restore_point.timestamp));
+        Ok(restore_points)
+    }
+}
+
+// Paths missing on either side, or whose type or content differs, sorted by path
+fn find_copy_differences(project_path: &Path, copy_path: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut differences = vec![];
+    for entry in WalkDir::new(project_path).min_depth(1) {
+        let entry = entry?;
+        let path = entry
+            .path()
+            .strip_prefix(project_path)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let copy_entry_path = copy_path.join(path);
+        let is_same = match fs::symlink_metadata(&copy_entry_path) {
+            Ok(copy_metadata) if entry.file_type().is_file() && copy_metadata.is_file() => {
+                entry.metadata()?.len() == copy_metadata.len()
+                    && has_same_content(entry.path(), &copy_entry_path)?
+            }
+            Ok(copy_metadata) if entry.file_type().is_symlink() && copy_metadata.is_symlink() => {
+                fs::read_link(entry.path())? == fs::read_link(&copy_entry_path)?
+            }
+            Ok(copy_metadata) => entry.file_type().is_dir() && copy_metadata.is_dir(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => false,
+            Err(e) => return Err(e),
+        };
+        if !is_same {
+            differences.push(path.to_path_buf());
+        }
+    }
+
+    for entry in WalkDir::new(copy_path).min_depth(1) {
+        let entry = entry?;
+        let path = entry
+            .path()
+            .strip_prefix(copy_path)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        if fs::symlink_metadata(project_path.join(path)).is_err() {
+            differences.push(path.to_path_buf());
+        }
+    }
+
+    differences.sort();
+    Ok(differences)
+}
+
+fn has_same_content(first_path: &Path, second_path: &Path) -> io::Result<bool> {
+    let mut first = BufReader::new(fs::File::open(first_path)?);
+    let mut second = BufReader::new(fs::File::open(second_path)?);
+    loop {
+        let (first_chunk, second_chunk) = (first.fill_buf()?, second.fill_buf()?);
+        let length = first_chunk.len().min(second_chunk.len());
+        if length == 0 {
+            return Ok(first_chunk.is_empty() && second_chunk.is_empty());
+        }
+        if first_chunk[..length] != second_chunk[..length] {
+            return Ok(false);
+        }
+        first.consume(length);
+        second.consume(length);
+    }
+}
+
+fn to_indexed_file(entry: &BackupIndexEntry) -> IndexedFile {
+    IndexedFile {
+        path: entry.path().to_path_buf(),
+        size: entry.size(),
+        mtime: UNIX_EPOCH + Duration::from_millis(entry.mtime() as u64),
+    }
 }
 
 #[cfg(test)]
-mod test {}
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use mockall::predicate::eq;
+
+    use crate::core::{
+        device::DeviceFactoryRegistry,
+        test_utils::{
+            fs::create_tmp_dir,
+            mocks::{
+                MockDevice, MockDeviceFactory, MockDeviceWithParameters,
+                MockGlobalConfigProviderFactory,
+            },
+        },
+        ProjectTrackingStatus, SecurityLevel,
+    };
+
+    use super::*;
+
+    #[test]
+    fn when_last_backup_is_more_recent_than_min_interval_backup_shall_be_skipped() {
+        let mut registry = DeviceFactoryRegistry::new();
+        registry.register_device("MockDevice".to_string(), "Mock Device".to_string(), || {
+            Box::new(MockDeviceFactory)
+        });
+        let operations = Operations {
+            device_factory_registry: registry,
+            global_config_provider: Box::new(MockGlobalConfigProviderFactory::new(
+                r#"
+[[devices]]
+name = "MockDevice"
+type = "MockDevice"
+
+[[projects]]
+name = "MyProject"
+path = "/path/that/does/not/exist"
+
+[projects.tracking_status]
+last_update = "0"
+type = "TrackedProject"
+
+[projects.tracking_status.backup_requirement_class]
+min_security_level = "Local"
+name = "Default"
+target_copies = 3
+target_locations = 2
+"#,
+            )),
+        };
+
+        let summary = operations
+            .backup_project_to_device(
+                "MyProject",
+                &["MockDevice".to_string()],
+                &BackupOptions {
+                    min_interval: Some(Duration::from_secs(60)),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert!(summary.skipped_too_recent);
+    }
+
+    // Operations with two devices, RecentDevice which received a backup of MyProject
+    // at the current time, and StaleDevice which never received any
+    fn operations_with_recent_and_stale_devices() -> Operations {
+        let mut registry = DeviceFactoryRegistry::new();
+        registry.register_device("MockDevice".to_string(), "Mock Device".to_string(), || {
+            let mut factory = crate::core::MockDeviceFactory::new();
+            factory.expect_build_from_toml_table().returning(|name, _| {
+                let mut device = crate::core::MockDevice::new();
+                device.expect_get_name().return_const(name.to_string());
+                device
+                    .expect_get_location()
+                    .return_const(format!("{}-location", name));
+                if name == "RecentDevice" {
+                    device
+                        .expect_get_last_backup_time()
+                        .returning(|_| Ok(Some(now!())));
+                    device.expect_test_availability().never();
+                } else {
+                    device.expect_get_last_backup_time().returning(|_| Ok(None));
+                    device
+                        .expect_test_availability()
+                        .return_const(Err("unplugged".to_string()));
+                }
+                Ok(Box::new(device))
+            });
+            Box::new(factory)
+        });
+        Operations {
+            device_factory_registry: registry,
+            global_config_provider: Box::new(MockGlobalConfigProviderFactory::new(
+                r#"
+[[devices]]
+name = "RecentDevice"
+type = "MockDevice"
+
+[[devices]]
+name = "StaleDevice"
+type = "MockDevice"
+
+[[projects]]
+name = "MyProject"
+path = "/home/user/MyProject"
+
+[projects.tracking_status]
+type = "IgnoredProject"
+"#,
+            )),
+        }
+    }
+
+    #[test]
+    fn when_backing_up_projects_under_a_path_recently_backed_up_ones_shall_be_skipped() {
+        let results = operations_with_recent_and_stale_devices()
+            .backup_projects_under(
+                "/home/user",
+                &["RecentDevice".to_string()],
+                &[],
+                Some(Duration::from_secs(3600)),
+            )
+            .unwrap();
+        assert_eq!(
+            results,
+            vec![(
+                "MyProject".to_string(),
+                Ok(BackupSummary {
+                    skipped_too_recent: true,
+                    ..Default::default()
+                })
+            )]
+        );
+    }
+
+    #[test]
+    fn when_backing_up_projects_under_a_path_only_devices_without_recent_backup_shall_be_used() {
+        let results = operations_with_recent_and_stale_devices()
+            .backup_projects_under(
+                "/home/user",
+                &["RecentDevice".to_string(), "StaleDevice".to_string()],
+                &[],
+                Some(Duration::from_secs(3600)),
+            )
+            .unwrap();
+        assert_eq!(
+            results,
+            vec![(
+                "MyProject".to_string(),
+                Err("Device not available at location StaleDevice-location: unplugged".to_string())
+            )]
+        );
+    }
+
+    #[test]
+    fn when_backing_up_projects_under_a_path_excluded_devices_shall_be_skipped() {
+        let mut registry = DeviceFactoryRegistry::new();
+        registry.register_device("MockDevice".to_string(), "Mock Device".to_string(), || {
+            Box::new(MockDeviceFactory)
+        });
+        let operations = Operations {
+            device_factory_registry: registry,
+            global_config_provider: Box::new(MockGlobalConfigProviderFactory::new(
+                r#"
+[[devices]]
+name = "MockDevice"
+type = "MockDevice"
+
+[[projects]]
+name = "MyProject"
+path = "/home/user/MyProject"
+
+[projects.tracking_status]
+last_update = ""
+type = "TrackedProject"
+
+[projects.tracking_status.backup_requirement_class]
+min_security_level = "Local"
+name = "Default"
+target_copies = 3
+target_locations = 2
+"#,
+            )),
+        };
+
+        let results = operations
+            .backup_projects_under(
+                "/home/user",
+                &["MockDevice".to_string()],
+                &["MockDevice".to_string()],
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            results,
+            vec![(
+                "MyProject".to_string(),
+                Err("No device specified for the backup".to_string())
+            )]
+        );
+    }
+
+    fn config_with_devices(devices: Vec<Box<dyn Device>>) -> GlobalConfig {
+        let mut config = GlobalConfig::from_toml("", &DeviceFactoryRegistry::new()).unwrap();
+        for device in devices {
+            config.add_device(device).unwrap();
+        }
+        config
+    }
+
+    fn tracked_project(min_security_level: SecurityLevel) -> Project {
+        Project::new(
+            "MyProject".to_string(),
+            "/home/user/MyProject".to_string(),
+            Some(ProjectTrackingStatus::TrackedProject {
+                backup_requirement_class: BackupRequirementClass::new(
+                    1,
+                    1,
+                    min_security_level,
+                    "Default".to_string(),
+                )
+                .unwrap(),
+                last_update: None,
+                current_copies: vec![],
+                max_archives: None,
+            }),
+        )
+    }
+
+    #[test]
+    fn auto_device_shall_be_the_most_secure_one() {
+        let config = config_with_devices(vec![
+            Box::new(MockDevice::new("Cloud")),
+            Box::new(MockDeviceWithParameters::new("UsbKey", "param")),
+        ]);
+
+        let device =
+            Operations::select_auto_device(&config, &tracked_project(SecurityLevel::NetworkPublic))
+                .unwrap();
+        assert_eq!(device.get_name(), "UsbKey");
+    }
+
+    #[test]
+    fn auto_device_shall_satisfy_the_backup_requirement_class() {
+        let config = config_with_devices(vec![Box::new(MockDevice::new("Cloud"))]);
+
+        let result =
+            Operations::select_auto_device(&config, &tracked_project(SecurityLevel::Local));
+        assert_eq!(
+            result.err().unwrap(),
+            "No available device satisfies the backup requirement class of MyProject"
+        );
+    }
+
+    #[test]
+    fn copy_differences_shall_list_missing_extra_and_modified_paths() {
+        let project_path = create_tmp_dir();
+        let copy_path = create_tmp_dir();
+        for path in [&project_path, &copy_path] {
+            std::fs::create_dir(path.join("docs")).unwrap();
+            std::fs::write(path.join("docs/same.txt"), "Hello").unwrap();
+        }
+        std::fs::write(project_path.join("modified.txt"), "Hello").unwrap();
+        std::fs::write(copy_path.join("modified.txt"), "World").unwrap();
+        std::fs::write(project_path.join("missing.txt"), "Hello").unwrap();
+        std::fs::write(copy_path.join("extra.txt"), "Hello").unwrap();
+
+        assert_eq!(
+            find_copy_differences(&project_path, &copy_path).unwrap(),
+            vec![
+                PathBuf::from("extra.txt"),
+                PathBuf::from("missing.txt"),
+                PathBuf::from("modified.txt"),
+            ]
+        );
+        assert!(
+            find_copy_differences(&project_path.join("docs"), &copy_path.join("docs"))
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn when_initializing_an_index_from_a_differing_copy_it_shall_fail() {
+        let project_path = create_tmp_dir();
+        let copy_path = create_tmp_dir();
+        std::fs::write(project_path.join("notes.txt"), "Hello, world!").unwrap();
+        std::fs::write(copy_path.join("notes.txt"), "Hello").unwrap();
+        let mut registry = DeviceFactoryRegistry::new();
+        registry.register_device("MockDevice".to_string(), "Mock Device".to_string(), || {
+            Box::new(MockDeviceFactory)
+        });
+        let mut global_config_provider = MockGlobalConfigProviderFactory::new(&format!(
+            r#"
+[[devices]]
+name = "MockDevice"
+type = "MockDevice"
+
+[[projects]]
+name = "MyProject"
+path = "{}"
+
+[projects.tracking_status]
+type = "IgnoredProject"
+"#,
+            project_path.to_str().unwrap()
+        ));
+        global_config_provider.expect_write_global_config().never();
+        let operations = Operations {
+            device_factory_registry: registry,
+            global_config_provider: Box::new(global_config_provider),
+        };
+
+        // The mock device panics if an archive is written
+        let result =
+            operations.initialize_index("MyProject", "MockDevice", copy_path.to_str().unwrap());
+        assert_eq!(
+            result.unwrap_err(),
+            format!(
+                "The copy at {} differs from project MyProject on 1 paths, e.g. notes.txt",
+                copy_path.to_str().unwrap()
+            )
+        );
+    }
+
+    fn operations_with_device(build_device: fn(&str) -> crate::core::MockDevice) -> Operations {
+        let mut registry = DeviceFactoryRegistry::new();
+        registry.register_device(
+            "MockDevice".to_string(),
+            "Mock Device".to_string(),
+            move || {
+                let mut factory = crate::core::MockDeviceFactory::new();
+                factory
+                    .expect_build_from_toml_table()
+                    .returning(move |name, _| Ok(Box::new(build_device(name))));
+                Box::new(factory)
+            },
+        );
+        // Commands only reading backups shall not rewrite the configuration
+        let mut global_config_provider = MockGlobalConfigProviderFactory::new(
+            r#"
+[[devices]]
+name = "MockDevice"
+type = "MockDevice"
+
+[[projects]]
+name = "MyProject"
+path = "/home/user/MyProject"
+
+[projects.tracking_status]
+type = "IgnoredProject"
+"#,
+        );
+        global_config_provider.expect_write_global_config().never();
+        Operations {
+            device_factory_registry: registry,
+            global_config_provider: Box::new(global_config_provider),
+        }
+    }
+
+    // Device whose latest archive is a snapshot, holding an index with a single file
+    fn device_with_latest_snapshot(name: &str) -> crate::core::MockDevice {
+        let index = BackupIndex::new()
+            .with_entry(1, 2, 3, PathBuf::from("notes.txt"))
+            .to_buffer()
+            .unwrap();
+        let mut device = crate::core::MockDevice::new();
+        device.expect_get_name().return_const(name.to_string());
+        device
+            .expect_get_location()
+            .return_const("Home".to_string());
+        device.expect_test_availability().return_const(Ok(()));
+        device.expect_to_toml_table().returning(toml::Table::new);
+        device
+            .expect_list_archives()
+            .returning(|_| Ok(vec!["10.tar.gz".to_string(), "20.snapshot".to_string()]));
+        device
+            .expect_get_archive_reader()
+            .with(eq("MyProject"), eq("20.snapshot"))
+            .returning(|_, archive_name| {
+                Err(format!("Not a tar or tar.gz archive: {}", archive_name))
+            });
+        device
+            .expect_read_archive_file()
+            .with(eq("MyProject"), eq("20.snapshot"), eq(".index"))
+            .returning(move |_, _, _| Ok(Some(index.clone())));
+        device
+    }
+
+    #[test]
+    fn when_rebuilding_an_index_from_a_snapshot_it_shall_read_the_snapshot_index() {
+        // Devices are built each time the configuration is loaded
+        static WRITTEN_INDEXES: AtomicUsize = AtomicUsize::new(0);
+        let operations = operations_with_device(|name| {
+            let mut device = device_with_latest_snapshot(name);
+            let expected_index = BackupIndex::new()
+                .with_entry(1, 2, 3, PathBuf::from("notes.txt"))
+                .to_buffer()
+                .unwrap();
+            device
+                .expect_write_backup_index()
+                .withf(move |project_name, index| {
+                    project_name == "MyProject" && index == expected_index.as_slice()
+                })
+                .returning(|_, _| {
+                    WRITTEN_INDEXES.fetch_add(1, Ordering::SeqCst);
+                    Ok(())
+                });
+            device
+        });
+
+        operations.rebuild_index("MyProject", "MockDevice").unwrap();
+        assert_eq!(WRITTEN_INDEXES.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn when_verifying_a_backup_ending_with_a_snapshot_it_shall_compare_the_snapshot_index() {
+        let project = Project::new(
+            "MyProject".to_string(),
+            "/home/user/MyProject".to_string(),
+            None,
+        );
+        for (current_size, expected_result) in [
+            (3, Ok(())),
+            (
+                4,
+                Err(
+                    "Index of 20.snapshot on MockDevice does not match the current index"
+                        .to_string(),
+                ),
+            ),
+        ] {
+            let mut device = device_with_latest_snapshot("MockDevice");
+            device.expect_read_backup_index().returning(move |_| {
+                let index = BackupIndex::new()
+                    .with_entry(1, 2, current_size, PathBuf::from("notes.txt"))
+                    .to_buffer()
+                    .unwrap();
+                Ok(Some(Box::new(std::io::Cursor::new(index))))
+            });
+
+            assert_eq!(
+                Operations::verify_latest_backup(&project, &device),
+                expected_result
+            );
+        }
+    }
+
+    #[test]
+    fn restore_points_shall_tell_which_archives_and_snapshots_record_deletions() {
+        let operations = operations_with_device(|name| {
+            let mut device = crate::core::MockDevice::new();
+            device.expect_get_name().return_const(name.to_string());
+            device.expect_test_availability().return_const(Ok(()));
+            device
+                .expect_list_archives()
+                .returning(|_| Ok(vec!["10.tar.gz".to_string(), "20.snapshot".to_string()]));
+            device
+                .expect_get_archive_time()
+                .returning(|archive_name| match archive_name {
+                    "10.tar.gz" => Some(UNIX_EPOCH + Duration::from_millis(10)),
+                    _ => Some(UNIX_EPOCH + Duration::from_millis(20)),
+                });
+            device
+                .expect_read_archive_file()
+                .with(eq("MyProject"), eq("10.tar.gz"), eq(".deleted-files"))
+                .returning(|_, _, _| Ok(Some(vec![])));
+            device
+                .expect_read_archive_file()
+                .with(eq("MyProject"), eq("20.snapshot"), eq(".deleted-files"))
+                .returning(|_, _, _| Ok(Some(b"old.txt".to_vec())));
+            device
+        });
+
+        let restore_points = operations
+            .list_restore_points("MyProject", "MockDevice")
+            .unwrap();
+        assert_eq!(
+            restore_points
+                .iter()
+                .map(|point| (point.archive_name.as_str(), point.contains_deletions))
+                .collect::<Vec<_>>(),
+            vec![("20.snapshot", true), ("10.tar.gz", false)]
+        );
+    }
+
+    fn operations_with_mock_device() -> Operations {
+        let mut registry = DeviceFactoryRegistry::new();
+        registry.register_device("MockDevice".to_string(), "Mock Device".to_string(), || {
+            Box::new(MockDeviceFactory)
+        });
+        // Commands only reading backups shall not rewrite the configuration
+        let mut global_config_provider = MockGlobalConfigProviderFactory::new(
+            r#"
+[[devices]]
+name = "MockDevice"
+type = "MockDevice"
+
+[[projects]]
+name = "MyProject"
+path = "/home/user/MyProject"
+
+[projects.tracking_status]
+type = "IgnoredProject"
+"#,
+        );
+        global_config_provider.expect_write_global_config().never();
+        Operations {
+            device_factory_registry: registry,
+            global_config_provider: Box::new(global_config_provider),
+        }
+    }
+
+    #[test]
+    fn when_exporting_an_empty_index_to_json_it_shall_be_an_empty_list() {
+        let json = operations_with_mock_device()
+            .export_index_to_json("MyProject", "MockDevice")
+            .unwrap();
+        assert_eq!(json, "[]");
+    }
+
+    #[test]
+    fn when_importing_an_invalid_json_index_it_shall_fail() {
+        let input_path = create_tmp_dir().join("index.json");
+        std::fs::write(&input_path, "not json").unwrap();
+        let input_path = input_path.to_str().unwrap();
+
+        let result = operations_with_mock_device().import_index_from_json(
+            "MyProject",
+            "MockDevice",
+            input_path,
+        );
+        assert!(result
+            .unwrap_err()
+            .starts_with(&format!("Invalid index in {}", input_path)));
+    }
+
+    #[test]
+    fn when_importing_a_json_index_it_shall_be_written_to_the_device() {
+        let input_path = create_tmp_dir().join("index.json");
+        std::fs::write(
+            &input_path,
+            r#"[{"ctime": 1, "mtime": 2, "size": 3, "path": "notes.txt"}]"#,
+        )
+        .unwrap();
+
+        operations_with_mock_device()
+            .import_index_from_json("MyProject", "MockDevice", input_path.to_str().unwrap())
+            .unwrap();
+    }
+}
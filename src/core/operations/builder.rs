@@ -0,0 +1,117 @@
+use std::{path::PathBuf, sync::Arc};
+
+use crate::core::{DeviceFactory, GlobalConfigProvider};
+
+use super::Operations;
+
+type DeviceFactoryConstructor = Arc<dyn Fn() -> Box<dyn DeviceFactory>>;
+
+/// Build several Operations instances sharing the same global config provider
+/// and the same device factories
+#[derive(Clone)]
+pub struct OperationsBuilder {
+    global_config_provider: Arc<dyn GlobalConfigProvider>,
+    device_factories: Vec<(String, String, DeviceFactoryConstructor)>,
+}
+
+impl OperationsBuilder {
+    pub fn new(global_config_provider: Arc<dyn GlobalConfigProvider>) -> Self {
+        OperationsBuilder {
+            global_config_provider,
+            device_factories: Vec::new(),
+        }
+    }
+
+    pub fn register_device_factory(
+        mut self,
+        device_factory_key: String,
+        device_factory_readable_name: String,
+        device_factory: impl Fn() -> Box<dyn DeviceFactory> + 'static,
+    ) -> Self {
+        self.device_factories.push((
+            device_factory_key,
+            device_factory_readable_name,
+            Arc::new(device_factory),
+        ));
+        self
+    }
+
+    pub fn build(&self) -> Operations {
+        let mut operations = Operations::new(Box::new(SharedGlobalConfigProvider(
+            self.global_config_provider.clone(),
+        )));
+        for (key, readable_name, device_factory) in &self.device_factories {
+            let device_factory = device_factory.clone();
+            operations.register_device_factory(key.clone(), readable_name.clone(), move || {
+                device_factory()
+            });
+        }
+        operations
+    }
+}
+
+struct SharedGlobalConfigProvider(Arc<dyn GlobalConfigProvider>);
+
+impl GlobalConfigProvider for SharedGlobalConfigProvider {
+    fn init_global_config(&self) -> Result<(), String> {
+        self.0.init_global_config()
+    }
+
+    fn read_global_config(&self) -> Result<String, String> {
+        self.0.read_global_config()
+    }
+
+    fn write_global_config(&self, content: &str) -> Result<(), String> {
+        self.0.write_global_config(content)
+    }
+
+    fn lock_global_config(&self) -> Result<(), String> {
+        self.0.lock_global_config()
+    }
+
+    fn unlock_global_config(&self) {
+        self.0.unlock_global_config()
+    }
+
+    fn get_config_path(&self) -> PathBuf {
+        self.0.get_config_path()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::{
+        operations::DeviceOperations,
+        test_utils::mocks::{MockDeviceFactory, MockGlobalConfigProviderFactory},
+    };
+
+    use super::*;
+
+    #[test]
+    fn operations_built_from_a_cloned_builder_shall_share_the_provider_and_factories() {
+        let builder = OperationsBuilder::new(Arc::new(MockGlobalConfigProviderFactory::new(
+            r#"
+[[devices]]
+name = "MockDevice"
+type = "MockDevice"
+"#,
+        )))
+        .register_device_factory(
+            "MockDevice".to_string(),
+            "Mock Device".to_string(),
+            || Box::new(MockDeviceFactory),
+        );
+
+        let first_operations = builder.build();
+        let second_operations = builder.clone().build();
+
+        for operations in [first_operations, second_operations] {
+            let devices = operations.list().unwrap();
+            assert_eq!(devices.len(), 1);
+            assert_eq!(devices[0].get_name(), "MockDevice");
+            assert!(operations
+                .get_device_factory("MockDevice".to_string())
+                .is_some());
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use crate::core::{
+    config::{GlobalConfig, ToToml},
+    device::RequirementSummary,
+    project::Project,
+    Device,
+};
+
+use super::{ConfigOperations, IndexMismatch, IntegrityReport, Operations};
+
+impl Operations {
+    // Check that the current index of a project on a device is consistent with its archives
+    fn check_project_index(project: &Project, device: &dyn Device) -> Result<(), String> {
+        let has_archives = !device.list_archives(project.get_name())?.is_empty();
+        let has_index = device.read_backup_index(project.get_name())?.is_some();
+        match (has_archives, has_index) {
+            (false, false) => Ok(()),
+            (true, false) => Err("Archives found without current index".to_string()),
+            (false, true) => Err("Current index found without archive".to_string()),
+            (true, true) => Operations::verify_latest_backup(project, device),
+        }
+    }
+}
+
+impl ConfigOperations for Operations {
+    fn integrity_check(&self) -> Result<IntegrityReport, String> {
+        let config = GlobalConfig::load(
+            self.global_config_provider.as_ref(),
+            &self.device_factory_registry,
+        )?;
+        let mut report = IntegrityReport::default();
+
+        for project in config.get_projects_iter() {
+            if project.test_availability().is_err() {
+                report
+                    .missing_project_paths
+                    .push(project.get_name().clone());
+            }
+        }
+
+        for device in config.get_devices_iter() {
+            if device.test_availability().is_err() {
+                report.unavailable_devices.push(device.get_name());
+                continue;
+            }
+
+            for project in config.get_projects_iter() {
+                if let Err(reason) = Operations::check_project_index(project, device.as_ref()) {
+                    report.index_mismatches.push(IndexMismatch {
+                        device_name: device.get_name(),
+                        project_name: project.get_name().clone(),
+                        reason,
+                    });
+                }
+            }
+
+            for directory in device.list_backed_up_projects()? {
+                if config.get_project_by_name(&directory).is_none() {
+                    report
+                        .orphaned_project_directories
+                        .push((device.get_name(), directory));
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    fn get_requirement_summary(&self) -> Result<RequirementSummary, String> {
+        let config = GlobalConfig::load(
+            self.global_config_provider.as_ref(),
+            &self.device_factory_registry,
+        )?;
+        Ok(config.get_requirement_summary())
+    }
+
+    fn export_config(&self, output_path: &str) -> Result<(), String> {
+        let config = GlobalConfig::load(
+            self.global_config_provider.as_ref(),
+            &self.device_factory_registry,
+        )?;
+        std::fs::write(output_path, config.to_toml()?)
+            .map_err(|e| format!("Cannot write {}: {}", output_path, e))
+    }
+
+    fn import_config(&self, input_path: &str) -> Result<(), String> {
+        let _lock = self.lock_config()?;
+        let mut config = GlobalConfig::load(
+            self.global_config_provider.as_ref(),
+            &self.device_factory_registry,
+        )?;
+        let imported_toml = std::fs::read_to_string(input_path)
+            .map_err(|e| format!("Cannot read {}: {}", input_path, e))?;
+        let imported = GlobalConfig::from_toml(&imported_toml, &self.device_factory_registry)
+            .map_err(|e| format!("Invalid configuration in {}: {}", input_path, e))?;
+
+        config.merge_configs(imported)?;
+        config.save(self.global_config_provider.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::{
+        device::DeviceFactoryRegistry,
+        test_utils::{fs::create_tmp_dir, mocks::MockGlobalConfigProviderFactory},
+        MockDevice, MockDeviceFactory,
+    };
+
+    use super::*;
+
+    fn operations_with_device(config: &str, build_device: fn(&str) -> MockDevice) -> Operations {
+        let mut registry = DeviceFactoryRegistry::new();
+        registry.register_device(
+            "MockDevice".to_string(),
+            "Mock Device".to_string(),
+            move || {
+                let mut factory = MockDeviceFactory::new();
+                factory
+                    .expect_build_from_toml_table()
+                    .returning(move |name, _| Ok(Box::new(build_device(name))));
+                Box::new(factory)
+            },
+        );
+
+        Operations {
+            device_factory_registry: registry,
+            global_config_provider: Box::new(MockGlobalConfigProviderFactory::new(config)),
+        }
+    }
+
+    #[test]
+    fn when_config_matches_devices_the_report_shall_be_ok() {
+        let project_path = create_tmp_dir();
+        let operations = operations_with_device(
+            &format!(
+                r#"
+[[devices]]
+name = "MockDevice"
+type = "MockDevice"
+
+[[projects]]
+name = "MyProject"
+path = "{}"
+tracking_status = {{ type = "IgnoredProject" }}
+"#,
+                project_path.display()
+            ),
+            |name| {
+                let mut device = MockDevice::new();
+                device.expect_get_name().return_const(name.to_string());
+                device.expect_test_availability().return_const(Ok(()));
+                device.expect_list_archives().returning(|_| Ok(vec![]));
+                device.expect_read_backup_index().returning(|_| Ok(None));
+                device
+                    .expect_list_backed_up_projects()
+                    .returning(|| Ok(vec![]));
+                device
+            },
+        );
+
+        let report = operations.integrity_check().unwrap();
+        assert_eq!(report, IntegrityReport::default());
+        assert!(report.is_ok());
+    }
+
+    #[test]
+    fn integrity_check_shall_report_missing_paths_index_mismatches_and_orphans() {
+        let operations = operations_with_device(
+            r#"
+[[devices]]
+name = "MockDevice"
+type = "MockDevice"
+
+[[projects]]
+name = "MyProject"
+path = "/path/that/does/not/exist"
+tracking_status = { type = "IgnoredProject" }
+"#,
+            |name| {
+                let mut device = MockDevice::new();
+                device.expect_get_name().return_const(name.to_string());
+                device.expect_test_availability().return_const(Ok(()));
+                device
+                    .expect_list_archives()
+                    .returning(|_| Ok(vec!["1.tar.gz".to_string()]));
+                device.expect_read_backup_index().returning(|_| Ok(None));
+                device
+                    .expect_list_backed_up_projects()
+                    .returning(|| Ok(vec!["MyProject".to_string(), "OldProject".to_string()]));
+                device
+            },
+        );
+
+        let report = operations.integrity_check().unwrap();
+        assert_eq!(
+            report,
+            IntegrityReport {
+                missing_project_paths: vec!["MyProject".to_string()],
+                unavailable_devices: vec![],
+                index_mismatches: vec![IndexMismatch {
+                    device_name: "MockDevice".to_string(),
+                    project_name: "MyProject".to_string(),
+                    reason: "Archives found without current index".to_string(),
+                }],
+                orphaned_project_directories: vec![(
+                    "MockDevice".to_string(),
+                    "OldProject".to_string()
+                )],
+            }
+        );
+        assert!(!report.is_ok());
+    }
+
+    #[test]
+    fn unavailable_devices_shall_be_reported_and_not_checked() {
+        let operations = operations_with_device(
+            r#"
+[[devices]]
+name = "MockDevice"
+type = "MockDevice"
+"#,
+            |name| {
+                let mut device = MockDevice::new();
+                device.expect_get_name().return_const(name.to_string());
+                device
+                    .expect_test_availability()
+                    .return_const(Err("Not mounted".to_string()));
+                device.expect_list_backed_up_projects().never();
+                device
+            },
+        );
+
+        let report = operations.integrity_check().unwrap();
+        assert_eq!(report.unavailable_devices, vec!["MockDevice".to_string()]);
+        assert!(report.is_ok());
+    }
+}
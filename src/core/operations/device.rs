@@ -1,9 +1,13 @@
+use std::collections::HashMap;
+
+use rayon::prelude::*;
+
 use crate::core::{
     config::GlobalConfig,
     device::{Device, DeviceFactory, DeviceFactoryKey},
 };
 
-use super::{DeviceOperations, Operations};
+use super::{AddDeviceResult, DeviceInfo, DeviceOperations, DevicesWithAvailability, Operations};
 
 impl DeviceOperations for Operations {
     fn get_available_device_factories(&self) -> Vec<DeviceFactoryKey> {
@@ -16,6 +20,7 @@ impl DeviceOperations for Operations {
     }
 
     fn add_device(&self, device: Box<dyn Device>) -> Result<(), Box<String>> {
+        let _lock = self.lock_config()?;
         let mut config = GlobalConfig::load(
             self.global_config_provider.as_ref(),
             &self.device_factory_registry,
@@ -26,7 +31,22 @@ impl DeviceOperations for Operations {
         Ok(())
     }
 
+    fn add_device_idempotent(&self, device: Box<dyn Device>) -> Result<AddDeviceResult, String> {
+        let _lock = self.lock_config()?;
+        let mut config = GlobalConfig::load(
+            self.global_config_provider.as_ref(),
+            &self.device_factory_registry,
+        )?;
+
+        let result = config.add_device_if_absent(device);
+        if result == AddDeviceResult::Added {
+            config.save(self.global_config_provider.as_ref())?;
+        }
+        Ok(result)
+    }
+
     fn remove_by_name(&self, name: String) -> Result<(), Box<String>> {
+        let _lock = self.lock_config()?;
         let mut config = GlobalConfig::load(
             self.global_config_provider.as_ref(),
             &self.device_factory_registry,
@@ -47,6 +67,89 @@ impl DeviceOperations for Operations {
         let devices = config.get_devices();
         Ok(devices)
     }
+
+    fn list_devices_with_availability(&self) -> Result<DevicesWithAvailability, String> {
+        let devices = self.list()?;
+        let availability = devices
+            .par_iter()
+            .map(|device| device.test_availability().is_ok())
+            .collect::<Vec<_>>();
+        Ok(devices.into_iter().zip(availability).collect())
+    }
+
+    fn get_device_info(&self, name: &str) -> Result<DeviceInfo, String> {
+        let config = GlobalConfig::load(
+            self.global_config_provider.as_ref(),
+            &self.device_factory_registry,
+        )?;
+
+        let device = config
+            .get_device_by_name(name)
+            .ok_or_else(|| format!("Device not found: {}", name))?;
+
+        let is_available = device.test_availability().is_ok();
+        let mut archive_counts = HashMap::new();
+        if is_available {
+            for project in config.get_projects_iter() {
+                let archives = device.list_archives(project.get_name())?;
+                archive_counts.insert(project.get_name().clone(), archives.len());
+            }
+        }
+
+        Ok(DeviceInfo {
+            is_available,
+            free_space: if is_available {
+                device.get_free_space()
+            } else {
+                None
+            },
+            archive_counts,
+            security_level: device.get_security_level(),
+        })
+    }
+
+    fn list_orphaned_device_directories(&self, device_name: &str) -> Result<Vec<String>, String> {
+        let config = GlobalConfig::load(
+            self.global_config_provider.as_ref(),
+            &self.device_factory_registry,
+        )?;
+        let device = config
+            .get_device_by_name(device_name)
+            .ok_or_else(|| format!("Device not found: {}", device_name))?;
+
+        Operations::find_orphaned_directories(&config, device.as_ref())
+    }
+
+    fn prune_orphaned_device_directories(&self, device_name: &str) -> Result<Vec<String>, String> {
+        let config = GlobalConfig::load(
+            self.global_config_provider.as_ref(),
+            &self.device_factory_registry,
+        )?;
+        let device = config
+            .get_device_by_name(device_name)
+            .ok_or_else(|| format!("Device not found: {}", device_name))?;
+
+        let orphans = Operations::find_orphaned_directories(&config, device.as_ref())?;
+        for directory in &orphans {
+            device.delete_project_data(directory)?;
+        }
+        Ok(orphans)
+    }
+}
+
+impl Operations {
+    // Only directories holding backups are considered, other data may be stored on the device
+    fn find_orphaned_directories(
+        config: &GlobalConfig,
+        device: &dyn Device,
+    ) -> Result<Vec<String>, String> {
+        device.test_availability()?;
+        Ok(device
+            .list_backed_up_projects()?
+            .into_iter()
+            .filter(|directory| config.get_project_by_name(directory).is_none())
+            .collect())
+    }
 }
 
 #[cfg(test)]
@@ -55,6 +158,7 @@ mod tests {
         config::MockGlobalConfigProvider,
         device::DeviceFactoryRegistry,
         test_utils::mocks::{MockDevice, MockDeviceFactory, MockGlobalConfigProviderFactory},
+        SecurityLevel,
     };
 
     use super::*;
@@ -154,6 +258,42 @@ type = "MockDevice"
         assert_eq!(devices[0].get_device_type_name(), "MockDevice");
     }
 
+    #[test]
+    fn when_listing_devices_with_availability_each_device_is_paired_with_its_status() {
+        let mut registry = DeviceFactoryRegistry::new();
+        registry.register_device("MockDevice".to_string(), "Mock Device".to_string(), || {
+            Box::new(MockDeviceFactory)
+        });
+
+        let operations = Operations {
+            device_factory_registry: registry,
+            global_config_provider: Box::new(MockGlobalConfigProviderFactory::new(
+                r#"
+[[devices]]
+name = "MockDevice1"
+type = "MockDevice"
+
+[[devices]]
+name = "MockDevice2"
+type = "MockDevice"
+"#,
+            )),
+        };
+
+        let devices = operations.list_devices_with_availability().unwrap();
+        let statuses = devices
+            .iter()
+            .map(|(device, is_available)| (device.get_name(), *is_available))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            vec![
+                ("MockDevice1".to_string(), true),
+                ("MockDevice2".to_string(), true)
+            ]
+        );
+    }
+
     #[test]
     fn when_adding_a_device_to_empty_config_it_shall_add_it_to_the_configuration() {
         let mut registry = DeviceFactoryRegistry::new();
@@ -162,6 +302,8 @@ type = "MockDevice"
         });
 
         let mut provider = MockGlobalConfigProvider::new();
+        provider.expect_lock_global_config().return_const(Ok(()));
+        provider.expect_unlock_global_config().return_const(());
         provider
             .expect_read_global_config()
             .return_const(Ok(r#""#.to_string()));
@@ -192,6 +334,8 @@ type = "MockDevice"
         });
 
         let mut provider = MockGlobalConfigProvider::new();
+        provider.expect_lock_global_config().return_const(Ok(()));
+        provider.expect_unlock_global_config().return_const(());
         provider
             .expect_read_global_config()
             .return_const(Ok(r#"[[devices]]
@@ -222,6 +366,37 @@ type = "MockDevice"
         operations.add_device(device).unwrap();
     }
 
+    #[test]
+    fn when_adding_an_existing_device_idempotently_it_shall_not_write_the_configuration() {
+        let mut registry = DeviceFactoryRegistry::new();
+        registry.register_device("MockDevice".to_string(), "Mock Device".to_string(), || {
+            Box::new(MockDeviceFactory)
+        });
+
+        let mut provider = MockGlobalConfigProvider::new();
+        provider.expect_lock_global_config().return_const(Ok(()));
+        provider.expect_unlock_global_config().return_const(());
+        provider
+            .expect_read_global_config()
+            .return_const(Ok(r#"[[devices]]
+name = "MockDevice"
+type = "MockDevice"
+"#
+            .to_string()));
+        provider.expect_write_global_config().times(0);
+
+        let operations = Operations {
+            device_factory_registry: registry,
+            global_config_provider: Box::new(provider),
+        };
+
+        let device = Box::new(MockDevice::new("MockDevice"));
+        assert_eq!(
+            operations.add_device_idempotent(device).unwrap(),
+            AddDeviceResult::AlreadyPresent
+        );
+    }
+
     #[test]
     fn when_removing_last_device_by_name_it_shall_update_the_configuration() {
         let mut registry = DeviceFactoryRegistry::new();
@@ -230,6 +405,8 @@ type = "MockDevice"
         });
 
         let mut provider = MockGlobalConfigProvider::new();
+        provider.expect_lock_global_config().return_const(Ok(()));
+        provider.expect_unlock_global_config().return_const(());
         provider
             .expect_read_global_config()
             .return_const(Ok(r#"[[devices]]
@@ -253,4 +430,132 @@ type = "MockDevice"
             .remove_by_name("AnotherDevice".to_string())
             .unwrap();
     }
+
+    #[test]
+    fn when_getting_info_of_unknown_device_it_shall_fail() {
+        let operations = Operations {
+            device_factory_registry: DeviceFactoryRegistry::new(),
+            global_config_provider: Box::new(MockGlobalConfigProviderFactory::new(r#""#)),
+        };
+
+        let result = operations.get_device_info("MockDevice");
+        assert_eq!(result.err().unwrap(), "Device not found: MockDevice");
+    }
+
+    #[test]
+    fn when_getting_info_of_a_device_it_shall_count_archives_of_each_project() {
+        let mut registry = DeviceFactoryRegistry::new();
+        registry.register_device("MockDevice".to_string(), "Mock Device".to_string(), || {
+            Box::new(MockDeviceFactory)
+        });
+
+        let operations = Operations {
+            device_factory_registry: registry,
+            global_config_provider: Box::new(MockGlobalConfigProviderFactory::new(
+                r#"
+[[devices]]
+name = "MockDevice"
+type = "MockDevice"
+
+[[projects]]
+name = "MyProject"
+path = "/path/to/project"
+tracking_status = { type = "IgnoredProject" }
+"#,
+            )),
+        };
+
+        let device_info = operations.get_device_info("MockDevice").unwrap();
+        assert!(device_info.is_available);
+        assert_eq!(device_info.free_space, None);
+        assert_eq!(device_info.archive_counts.get("MyProject"), Some(&0));
+        assert!(matches!(
+            device_info.security_level,
+            SecurityLevel::NetworkUntrustedRestricted
+        ));
+    }
+
+    fn operations_with_orphan(build_device: fn(&str) -> crate::core::MockDevice) -> Operations {
+        let mut registry = DeviceFactoryRegistry::new();
+        registry.register_device(
+            "MockDevice".to_string(),
+            "Mock Device".to_string(),
+            move || {
+                let mut factory = crate::core::MockDeviceFactory::new();
+                factory
+                    .expect_build_from_toml_table()
+                    .returning(move |name, _| Ok(Box::new(build_device(name))));
+                Box::new(factory)
+            },
+        );
+
+        Operations {
+            device_factory_registry: registry,
+            global_config_provider: Box::new(MockGlobalConfigProviderFactory::new(
+                r#"
+[[devices]]
+name = "MockDevice"
+type = "MockDevice"
+
+[[projects]]
+name = "MyProject"
+path = "/path/to/project"
+tracking_status = { type = "IgnoredProject" }
+"#,
+            )),
+        }
+    }
+
+    #[test]
+    fn directories_of_unknown_projects_shall_be_listed_as_orphans() {
+        let operations = operations_with_orphan(|name| {
+            let mut device = crate::core::MockDevice::new();
+            device.expect_get_name().return_const(name.to_string());
+            device.expect_test_availability().return_const(Ok(()));
+            device
+                .expect_list_backed_up_projects()
+                .returning(|| Ok(vec!["MyProject".to_string(), "OldProject".to_string()]));
+            device.expect_delete_project_data().never();
+            device
+        });
+
+        let orphans = operations
+            .list_orphaned_device_directories("MockDevice")
+            .unwrap();
+        assert_eq!(orphans, vec!["OldProject".to_string()]);
+    }
+
+    #[test]
+    fn when_pruning_orphans_only_their_directories_shall_be_deleted() {
+        let operations = operations_with_orphan(|name| {
+            let mut device = crate::core::MockDevice::new();
+            device.expect_get_name().return_const(name.to_string());
+            device.expect_test_availability().return_const(Ok(()));
+            device
+                .expect_list_backed_up_projects()
+                .returning(|| Ok(vec!["MyProject".to_string(), "OldProject".to_string()]));
+            device
+                .expect_delete_project_data()
+                .with(eq("OldProject"))
+                .times(1)
+                .return_const(Ok(()));
+            device
+        });
+
+        let pruned = operations
+            .prune_orphaned_device_directories("MockDevice")
+            .unwrap();
+        assert_eq!(pruned, vec!["OldProject".to_string()]);
+    }
+
+    #[test]
+    fn when_listing_orphans_of_unknown_device_it_shall_fail() {
+        let operations = Operations {
+            device_factory_registry: DeviceFactoryRegistry::new(),
+            global_config_provider: Box::new(MockGlobalConfigProviderFactory::new(r#""#)),
+        };
+
+        let result = operations.list_orphaned_device_directories("MockDevice");
+        assert_eq!(result.err().unwrap(), "Device not found: MockDevice");
+    }
 }
@@ -1,18 +1,44 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    str::FromStr,
+    sync::{atomic::AtomicBool, Arc},
+    time::{Duration, SystemTime},
+};
+
 #[cfg(test)]
 use super::config::MockGlobalConfigProvider;
 use super::{
-    device::{Device, DeviceFactory, DeviceFactoryKey, DeviceFactoryRegistry},
+    config::GlobalConfig,
+    device::{
+        CompressionFormat, Device, DeviceFactory, DeviceFactoryKey, DeviceFactoryRegistry,
+        SecurityLevel,
+    },
     project::Project,
     GlobalConfigProvider,
 };
+use crate::now;
+use tracing::warn;
 
 #[cfg(test)]
 use mockall::automock;
 
 mod backup;
+mod builder;
+mod config;
 mod device;
 mod project;
 
+#[cfg(test)]
+pub use super::backup::BackupExecutionWarning;
+pub use super::backup::{
+    BackupExecutionMetrics, BackupPriority, BackupSummary, DryRunResult, IndexStatistics,
+    RepairReport,
+};
+pub use super::config::AddDeviceResult;
+pub use super::device::{RequirementComplianceResult, RequirementSummary};
+pub use builder::OperationsBuilder;
+
 pub struct Operations {
     device_factory_registry: DeviceFactoryRegistry,
     global_config_provider: Box<dyn GlobalConfigProvider>,
@@ -38,6 +64,45 @@ impl Operations {
             device_factory,
         );
     }
+
+    // Lock the config for an update, until the returned guard is dropped
+    // Read-only commands shall not take it, so that they do not wait for each other
+    fn lock_config(&self) -> Result<ConfigLock<'_>, String> {
+        self.global_config_provider.lock_global_config()?;
+        Ok(ConfigLock(self.global_config_provider.as_ref()))
+    }
+
+    // Persist the time of the last successful backup of a project, and the time its
+    // devices were last found available
+    // This is best effort: failing to save it shall not fail the backup
+    fn record_project_backed_up(&self, project_name: &str, device_names: &[String]) {
+        let result = self.lock_config().and_then(|_lock| {
+            let mut config = GlobalConfig::load(
+                self.global_config_provider.as_ref(),
+                &self.device_factory_registry,
+            )?;
+            let now = now!();
+            config.set_project_last_update(project_name, now)?;
+            for device_name in device_names {
+                config.set_device_last_seen(device_name, now)?;
+            }
+            config.save(self.global_config_provider.as_ref())
+        });
+        if let Err(e) = result {
+            warn!(
+                "Could not save the last backup time of {}: {}",
+                project_name, e
+            );
+        }
+    }
+}
+
+struct ConfigLock<'a>(&'a dyn GlobalConfigProvider);
+
+impl Drop for ConfigLock<'_> {
+    fn drop(&mut self) {
+        self.0.unlock_global_config();
+    }
 }
 
 #[cfg(test)]
@@ -76,6 +141,10 @@ pub trait DeviceOperations {
     /// The device is built by the factory returned by get_device_factory
     fn add_device(&self, device: Box<dyn Device>) -> Result<(), Box<String>>;
 
+    /// Add a device unless a device with the same name already exists, in which
+    /// case the configuration is left untouched
+    fn add_device_idempotent(&self, device: Box<dyn Device>) -> Result<AddDeviceResult, String>;
+
     /// Once created, a device is identified by its unique name
     /// This function removes the device by its name
     fn remove_by_name(&self, name: String) -> Result<(), Box<String>>;
@@ -83,14 +152,61 @@ pub trait DeviceOperations {
     /// List all devices
     /// The list is sorted by the device name
     fn list(&self) -> Result<Vec<Box<dyn Device>>, String>;
+
+    /// List all devices, each paired with whether it is currently available
+    /// Availability of the devices is tested in parallel
+    fn list_devices_with_availability(&self) -> Result<DevicesWithAvailability, String>;
+
+    /// Get the runtime state of a device by its name
+    /// If the device is not available, only its static information is filled
+    fn get_device_info(&self, name: &str) -> Result<DeviceInfo, String>;
+
+    /// List the project directories of a device not matching any registered project,
+    /// left behind by renamed or removed projects
+    fn list_orphaned_device_directories(&self, device_name: &str) -> Result<Vec<String>, String>;
+
+    /// Delete the orphaned project directories of a device, and return their names
+    fn prune_orphaned_device_directories(&self, device_name: &str) -> Result<Vec<String>, String>;
+}
+
+/// Devices paired with whether they are currently available
+pub type DevicesWithAvailability = Vec<(Box<dyn Device>, bool)>;
+
+/// Runtime state of a device
+pub struct DeviceInfo {
+    pub is_available: bool,
+    pub free_space: Option<u64>,
+
+    /// Number of archives stored on the device for each known project
+    pub archive_counts: HashMap<String, usize>,
+    pub security_level: SecurityLevel,
 }
 
+/// Last backup time of a project on each device holding a backup of it, by device name
+pub type ProjectBackupStatus = Vec<(String, SystemTime)>;
+
 #[derive(Debug, PartialEq)]
 pub struct AddProjectArgs {
     pub name: String,
     pub location: String,
 }
 
+/// Outcome of a batch project registration, for each CSV row
+#[derive(Debug, Default, PartialEq)]
+pub struct BatchAddReport {
+    /// Line number, project name and whether the project could be added
+    pub rows: Vec<(usize, String, Result<(), String>)>,
+}
+
+impl BatchAddReport {
+    pub fn added_count(&self) -> usize {
+        self.rows
+            .iter()
+            .filter(|(_, _, result)| result.is_ok())
+            .count()
+    }
+}
+
 /// Projects are a set of files that are a single unit for the user
 /// The operations in this trait allow the user to manage the projects
 ///
@@ -105,19 +221,114 @@ pub trait ProjectOperations {
     /// could be extended in the future to include more information
     fn add_project(&self, args: AddProjectArgs) -> Result<(), String>;
 
+    /// Same as add_project, but a project with the same name is not an error, for setup
+    /// scripts run several times. Returns whether the project was created
+    fn add_project_idempotent(&self, args: AddProjectArgs) -> Result<bool, String>;
+
+    /// Add the projects of a CSV content with `name,path,tracking_status` rows,
+    /// where tracking_status is tracked, untracked or ignored
+    /// Invalid rows are reported without preventing the other ones to be added
+    fn add_projects_from_csv(&self, csv_content: &str) -> Result<BatchAddReport, String>;
+
     /// A project shall be uniquely identified by its name
     /// So the name is enough to remove a project
-    fn remove_project_by_name(&self, name: String) -> Result<(), String>;
+    /// With purge_device_data, its backups are also deleted from the available devices
+    fn remove_project_by_name(&self, name: String, purge_device_data: bool) -> Result<(), String>;
 
     /// List all projects with their status
     fn list_projects(&self) -> Result<Vec<Project>, String>;
+
+    /// Move a project to a new location, keeping its tracking status and copies
+    /// The new location shall not be used by another project
+    fn update_project_location(&self, name: String, new_location: String) -> Result<(), String>;
+
+    /// Rename a project, along with its folder on each device holding backups of it
+    /// Fails if a device is unavailable, unless forced: its folder then keeps the previous name
+    fn rename_project(&self, name: String, new_name: String, force: bool) -> Result<(), String>;
+
+    /// Check the copies of a tracked project found on the available devices
+    /// against its backup requirement class
+    fn get_project_compliance(&self, name: String) -> Result<RequirementComplianceResult, String>;
+
+    /// Get the last backup time of a project on each available device holding a backup of it
+    fn get_project_backup_status(&self, name: String) -> Result<ProjectBackupStatus, String>;
+
+    /// Add a project with the same backup requirement class as an existing one
+    /// The new project starts without any copy
+    fn clone_project(
+        &self,
+        source_name: String,
+        new_name: String,
+        new_location: String,
+    ) -> Result<(), String>;
+}
+
+/// Result of the backup of each project of a batch, by project name
+pub type ProjectBackupResults = Vec<(String, Result<BackupSummary, String>)>;
+
+/// Settings of a single backup run
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackupOptions {
+    /// Process scheduling priority during the backup
+    pub priority: BackupPriority,
+    /// Check the new archive of each device against its current index
+    pub verify_after: bool,
+    /// Files larger than this size (in bytes) are skipped, and listed in the returned summary
+    pub max_file_size: Option<u64>,
+    /// Nothing is done when the last backup of the project is more recent than this
+    pub min_interval: Option<Duration>,
+    /// Store the archives uncompressed when false, on devices supporting it
+    pub compress: bool,
+    /// Algorithm of the compressed archives, on devices supporting it
+    pub compression_format: CompressionFormat,
+    /// Keep the incomplete archive as a .partial file when the backup fails
+    pub keep_partial: bool,
+    /// Old archives are removed beyond this count, overriding the project setting
+    pub max_archives: Option<usize>,
+    /// Hash the files with unchanged metadata, to archive again the ones silently corrupted
+    pub checksum_verify_before: bool,
+    /// User defined labels stored in the archive, e.g. to mark a milestone
+    pub tags: Vec<String>,
+    /// FIFOs, sockets and device files are skipped instead of failing the backup
+    pub skip_special_files: bool,
+}
+
+impl Default for BackupOptions {
+    fn default() -> Self {
+        BackupOptions {
+            priority: BackupPriority::default(),
+            verify_after: false,
+            max_file_size: None,
+            min_interval: None,
+            compress: true,
+            compression_format: CompressionFormat::default(),
+            keep_partial: false,
+            max_archives: None,
+            checksum_verify_before: false,
+            tags: Vec::new(),
+            skip_special_files: false,
+        }
+    }
 }
 
 #[cfg_attr(test, automock)]
 pub trait BackupOperations {
-    /// Backup one project by its name to one device by its name
-    fn backup_project_to_device(&self, project_name: &str, device_name: &str)
-        -> Result<(), String>;
+    /// Backup one project by its name to one or more devices by their names
+    /// When several devices are given, the backup is written to all of them in a single pass
+    fn backup_project_to_device(
+        &self,
+        project_name: &str,
+        device_names: &[String],
+        options: &BackupOptions,
+    ) -> Result<BackupSummary, String>;
+
+    /// Count what a backup of the project to the devices would write, without writing anything
+    fn dry_run_backup(
+        &self,
+        project_name: &str,
+        device_names: &[String],
+        options: &BackupOptions,
+    ) -> Result<DryRunResult, String>;
 
     // Restore the given project from its backup on the device to a local location
     fn restore_project_from_device(
@@ -126,4 +337,219 @@ pub trait BackupOperations {
         device_name: &str,
         to: &str,
     ) -> Result<(), String>;
+
+    /// Backup one project to the most secure available device satisfying its backup
+    /// requirement class, any device being suitable for projects without one
+    /// Return the name of the chosen device
+    fn backup_project_auto(&self, project_name: &str) -> Result<String, String>;
+
+    /// Backup the project to the device each time one of its files changes, once no change
+    /// happened for the debounce duration, until the stop signal is set
+    fn watch_project_on_device(
+        &self,
+        project_name: &str,
+        device_name: &str,
+        debounce: Duration,
+        stop_signal: Arc<AtomicBool>,
+    ) -> Result<(), String>;
+
+    /// Backup all the projects located under a directory to the given devices
+    /// Each project is backed up independently, the result of each one is returned by project name
+    /// Devices listed in exclude_devices are skipped, e.g. when they are temporarily unavailable
+    /// Devices holding a backup of a project made within skip_if_backed_up_within are skipped
+    /// for it, and the project is skipped when all of them are
+    fn backup_projects_under(
+        &self,
+        location_prefix: &str,
+        device_names: &[String],
+        exclude_devices: &[String],
+        skip_if_backed_up_within: Option<Duration>,
+    ) -> Result<ProjectBackupResults, String>;
+
+    /// Rebuild the current index of a project on a device from its latest archive,
+    /// for instance when it has been deleted or corrupted
+    fn rebuild_index(&self, project_name: &str, device_name: &str) -> Result<(), String>;
+
+    /// Update the entries of the current index of a project on a device whose metadata
+    /// does not match the project files anymore, e.g. after a clock reset
+    fn repair_index(&self, project_name: &str, device_name: &str) -> Result<RepairReport, String>;
+
+    /// Bootstrap the backups of a project on a device that already holds a copy of it,
+    /// made by another tool: the project files are indexed as if they had been backed
+    /// up, so that the next backup only archives the files changed since.
+    /// Fails when the copy at copy_path does not hold the same files as the project
+    fn initialize_index(
+        &self,
+        project_name: &str,
+        device_name: &str,
+        copy_path: &str,
+    ) -> Result<(), String>;
+
+    /// Write the current index of a project on a device as JSON, for other tools
+    fn export_index_to_json(&self, project_name: &str, device_name: &str)
+        -> Result<String, String>;
+
+    /// Set the index of a project on a device from a JSON file made by another tool,
+    /// so that the next backup only archives the files changed since
+    fn import_index_from_json(
+        &self,
+        project_name: &str,
+        device_name: &str,
+        input_path: &str,
+    ) -> Result<(), String>;
+
+    /// Compare the latest backups of a project on two devices
+    fn compare_device_copies(
+        &self,
+        project_name: &str,
+        device1: &str,
+        device2: &str,
+    ) -> Result<CopyComparisonResult, String>;
+
+    /// List the files of the current index of a project on a device
+    fn list_indexed_files(
+        &self,
+        project_name: &str,
+        device_name: &str,
+        order: IndexedFilesOrder,
+    ) -> Result<Vec<IndexedFile>, String>;
+
+    /// List the files of the current index of a project on a device matching a glob pattern
+    fn search_indexed_files(
+        &self,
+        project_name: &str,
+        device_name: &str,
+        pattern: &str,
+    ) -> Result<Vec<IndexedFile>, String>;
+
+    /// Group the files of the current index of a project on a device having the same size
+    /// and modification time, as candidates for deduplication
+    fn find_duplicate_files(
+        &self,
+        project_name: &str,
+        device_name: &str,
+    ) -> Result<Vec<Vec<PathBuf>>, String>;
+
+    /// Compute the distribution of the file sizes and extensions of the current index
+    /// of a project on a device
+    fn get_index_statistics(
+        &self,
+        project_name: &str,
+        device_name: &str,
+    ) -> Result<IndexStatistics, String>;
+
+    /// List the backups of a project on a device that can be restored, newest first
+    fn list_restore_points(
+        &self,
+        project_name: &str,
+        device_name: &str,
+    ) -> Result<Vec<RestorePoint>, String>;
+}
+
+/// Check that the configuration matches the actual state of the projects and devices,
+/// for instance after a manual edit of the configuration file
+#[cfg_attr(test, automock)]
+pub trait ConfigOperations {
+    /// Cross-validate the configuration against the projects and the devices contents
+    /// Devices that are not available are listed in the report, but not checked
+    fn integrity_check(&self) -> Result<IntegrityReport, String>;
+
+    /// Aggregate the compliance of all the tracked projects, using the available devices
+    fn get_requirement_summary(&self) -> Result<RequirementSummary, String>;
+
+    /// Write the current configuration to a file, to back it up or share it
+    fn export_config(&self, output_path: &str) -> Result<(), String>;
+
+    /// Add the devices and projects of a configuration file to the current configuration
+    /// Devices and projects with a name already in use are skipped
+    fn import_config(&self, input_path: &str) -> Result<(), String>;
+}
+
+/// Inconsistencies found by an integrity check
+#[derive(Debug, Default, PartialEq)]
+pub struct IntegrityReport {
+    /// Projects whose location does not exist
+    pub missing_project_paths: Vec<String>,
+
+    /// Devices that could not be checked
+    pub unavailable_devices: Vec<String>,
+
+    /// Copies whose current index does not match the archives of the device
+    pub index_mismatches: Vec<IndexMismatch>,
+
+    /// Backup directories of a device belonging to no registered project, as (device, directory)
+    pub orphaned_project_directories: Vec<(String, String)>,
+}
+
+impl IntegrityReport {
+    pub fn is_ok(&self) -> bool {
+        self.missing_project_paths.is_empty()
+            && self.index_mismatches.is_empty()
+            && self.orphaned_project_directories.is_empty()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct IndexMismatch {
+    pub device_name: String,
+    pub project_name: String,
+    pub reason: String,
+}
+
+/// Files differing between the copies of a project on two devices,
+/// as paths relative to the project root
+#[derive(Debug, PartialEq)]
+pub struct CopyComparisonResult {
+    pub only_on_first_device: Vec<PathBuf>,
+    pub only_on_second_device: Vec<PathBuf>,
+    pub different: Vec<PathBuf>,
+}
+
+impl CopyComparisonResult {
+    pub fn is_identical(&self) -> bool {
+        self.only_on_first_device.is_empty()
+            && self.only_on_second_device.is_empty()
+            && self.different.is_empty()
+    }
+}
+
+/// A file known by the backup index of a project, with its path relative to the project root
+#[derive(Debug, PartialEq)]
+pub struct IndexedFile {
+    pub path: PathBuf,
+    pub size: u64,
+    pub mtime: SystemTime,
+}
+
+/// A backup of a project on a device, from which the project can be restored
+#[derive(Debug, PartialEq)]
+pub struct RestorePoint {
+    pub timestamp: SystemTime,
+    pub archive_name: String,
+
+    /// Whether files were deleted from the project since the previous backup
+    pub contains_deletions: bool,
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub enum IndexedFilesOrder {
+    #[default]
+    Path,
+    // Last modified first
+    Mtime,
+}
+
+impl FromStr for IndexedFilesOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "path" => Ok(IndexedFilesOrder::Path),
+            "mtime" => Ok(IndexedFilesOrder::Mtime),
+            _ => Err(format!(
+                "Invalid sort order: {}. Possible orders are: path, mtime",
+                s
+            )),
+        }
+    }
 }
@@ -1,45 +1,163 @@
-use crate::{
-    core::{
-        config::GlobalConfig,
-        device::BackupRequirementClass,
-        project::{Project, ProjectTrackingStatus},
-    },
-    now,
+use crate::core::{
+    config::GlobalConfig,
+    device::RequirementComplianceResult,
+    project::{Project, ProjectTrackingStatus},
 };
-use std::time::SystemTime;
+use std::path::Path;
+use tracing::warn;
 
-use super::{AddProjectArgs, Operations, ProjectOperations};
+use super::{AddProjectArgs, BatchAddReport, Operations, ProjectBackupStatus, ProjectOperations};
+
+const CSV_HEADER: &str = "name,path,tracking_status";
+
+fn parse_csv_row(line: &str) -> Result<Project, String> {
+    let fields = line.split(',').map(str::trim).collect::<Vec<_>>();
+    let [name, path, tracking_status] = fields[..] else {
+        return Err(format!(
+            "Expected 3 fields ({}), got {}",
+            CSV_HEADER,
+            fields.len()
+        ));
+    };
+    if name.is_empty() || path.is_empty() {
+        return Err("Name and path shall not be empty".to_string());
+    }
+    let tracking_status = match tracking_status {
+        "tracked" => ProjectTrackingStatus::default(),
+        "untracked" => ProjectTrackingStatus::UntrackedProject,
+        "ignored" => ProjectTrackingStatus::IgnoredProject,
+        _ => {
+            return Err(format!(
+                "Invalid tracking status: {}, shall be tracked, untracked or ignored",
+                tracking_status
+            ))
+        }
+    };
+    Ok(Project::new(
+        name.to_string(),
+        path.to_string(),
+        Some(tracking_status),
+    ))
+}
+
+impl Operations {
+    // Prepare the project folder on the devices already plugged in, best effort
+    fn create_project_directories(config: &GlobalConfig, project_name: &str) {
+        for device in config.get_devices_iter() {
+            if device.test_availability().is_err() {
+                continue;
+            }
+            if let Err(e) = device.create_project_directory(project_name) {
+                warn!(
+                    "Could not create the directory of {} on {}: {}",
+                    project_name,
+                    device.get_name(),
+                    e
+                );
+            }
+        }
+    }
+}
 
 impl ProjectOperations for Operations {
     fn add_project(&self, args: AddProjectArgs) -> Result<(), String> {
+        let _lock = self.lock_config()?;
         let mut config = GlobalConfig::load(
             self.global_config_provider.as_ref(),
             &self.device_factory_registry,
         )?;
 
+        let project_name = args.name.clone();
         let project = Project::new(
             args.name,
             args.location,
-            Some(ProjectTrackingStatus::TrackedProject {
-                backup_requirement_class: BackupRequirementClass::default(),
-                last_update: Some(now!()),
-                current_copies: vec![],
-            }),
+            Some(ProjectTrackingStatus::default()),
         );
 
         config.add_project(project)?;
         config.save(self.global_config_provider.as_ref())?;
+        Operations::create_project_directories(&config, &project_name);
 
         Ok(())
     }
 
-    fn remove_project_by_name(&self, name: String) -> Result<(), String> {
+    fn add_project_idempotent(&self, args: AddProjectArgs) -> Result<bool, String> {
+        let _lock = self.lock_config()?;
+        let mut config = GlobalConfig::load(
+            self.global_config_provider.as_ref(),
+            &self.device_factory_registry,
+        )?;
+
+        let project_name = args.name.clone();
+        let (_, created) = config.get_or_create_project(
+            args.name,
+            args.location,
+            ProjectTrackingStatus::default(),
+        )?;
+        if created {
+            config.save(self.global_config_provider.as_ref())?;
+            Operations::create_project_directories(&config, &project_name);
+        }
+        Ok(created)
+    }
+
+    fn add_projects_from_csv(&self, csv_content: &str) -> Result<BatchAddReport, String> {
+        let _lock = self.lock_config()?;
+        let mut config = GlobalConfig::load(
+            self.global_config_provider.as_ref(),
+            &self.device_factory_registry,
+        )?;
+
+        let mut report = BatchAddReport::default();
+        for (i, line) in csv_content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || (i == 0 && line == CSV_HEADER) {
+                continue;
+            }
+            let result = parse_csv_row(line).and_then(|project| config.add_project(project));
+            let name = line.split(',').next().unwrap_or_default().trim();
+            report.rows.push((i + 1, name.to_string(), result));
+        }
+
+        if report.added_count() == 0 {
+            return Ok(report);
+        }
+        config.save(self.global_config_provider.as_ref())?;
+        for (_, name, _) in report.rows.iter().filter(|(_, _, result)| result.is_ok()) {
+            Operations::create_project_directories(&config, name);
+        }
+        Ok(report)
+    }
+
+    fn remove_project_by_name(&self, name: String, purge_device_data: bool) -> Result<(), String> {
+        let _lock = self.lock_config()?;
         let mut config = GlobalConfig::load(
             self.global_config_provider.as_ref(),
             &self.device_factory_registry,
         )?;
 
         config.remove_project(&name)?;
+        if purge_device_data {
+            // The project is kept in the configuration if some data could not be deleted
+            for device in config.get_devices_iter() {
+                if device.test_availability().is_err() {
+                    warn!(
+                        "Device {} is not available, the backups of {} are left on it",
+                        device.get_name(),
+                        name
+                    );
+                    continue;
+                }
+                device.delete_project_data(&name).map_err(|e| {
+                    format!(
+                        "Could not delete the backups of {} on {}: {}",
+                        name,
+                        device.get_name(),
+                        e
+                    )
+                })?;
+            }
+        }
         config.save(self.global_config_provider.as_ref())?;
 
         Ok(())
@@ -54,6 +172,175 @@ impl ProjectOperations for Operations {
         let projects = config.get_projects();
         Ok(projects)
     }
+
+    fn update_project_location(&self, name: String, new_location: String) -> Result<(), String> {
+        let _lock = self.lock_config()?;
+        let mut config = GlobalConfig::load(
+            self.global_config_provider.as_ref(),
+            &self.device_factory_registry,
+        )?;
+
+        if !Path::new(&new_location).exists() {
+            warn!("{} does not exist", new_location);
+        }
+        config.update_project_location(&name, new_location)?;
+        config.save(self.global_config_provider.as_ref())?;
+
+        Ok(())
+    }
+
+    fn rename_project(&self, name: String, new_name: String, force: bool) -> Result<(), String> {
+        let _lock = self.lock_config()?;
+        let mut config = GlobalConfig::load(
+            self.global_config_provider.as_ref(),
+            &self.device_factory_registry,
+        )?;
+        if config.get_project_by_name(&name).is_none() {
+            return Err(format!("Project not found: {}", name));
+        }
+        if config.get_project_by_name(&new_name).is_some() {
+            return Err(format!("Project with name {} already exists", new_name));
+        }
+
+        // A folder left with the previous name would be seen as an orphan afterwards
+        let unavailable_devices = config
+            .get_devices_iter()
+            .filter(|device| device.test_availability().is_err())
+            .map(|device| device.get_name())
+            .collect::<Vec<_>>();
+        if !unavailable_devices.is_empty() && !force {
+            return Err(format!(
+                "Devices {} are not available, their folder of {} would keep this name",
+                unavailable_devices.join(", "),
+                name
+            ));
+        }
+
+        // Folders are renamed first, so that the next backups find the archive history.
+        // They are renamed back if one of them fails, to keep matching the configuration
+        let mut renamed_devices = vec![];
+        for device in config.get_devices_iter() {
+            if unavailable_devices.contains(&device.get_name()) {
+                warn!(
+                    "Device {} is not available, its folder of {} keeps this name",
+                    device.get_name(),
+                    name
+                );
+                continue;
+            }
+            let result = device.list_backed_up_projects().and_then(|projects| {
+                match projects.contains(&name) {
+                    true => device
+                        .rename_project_directory(&name, &new_name)
+                        .map(|_| true),
+                    false => Ok(false),
+                }
+            });
+            match result {
+                Ok(true) => renamed_devices.push(device),
+                Ok(false) => {}
+                Err(e) => {
+                    for renamed_device in renamed_devices {
+                        if let Err(e) = renamed_device.rename_project_directory(&new_name, &name) {
+                            warn!(
+                                "Could not rename back the folder of {} on {}: {}",
+                                new_name,
+                                renamed_device.get_name(),
+                                e
+                            );
+                        }
+                    }
+                    return Err(format!(
+                        "Could not rename the folder of {} on {}: {}",
+                        name,
+                        device.get_name(),
+                        e
+                    ));
+                }
+            }
+        }
+        config.rename_project(&name, &new_name)?;
+        config.save(self.global_config_provider.as_ref())?;
+
+        Ok(())
+    }
+
+    fn get_project_compliance(&self, name: String) -> Result<RequirementComplianceResult, String> {
+        let config = GlobalConfig::load(
+            self.global_config_provider.as_ref(),
+            &self.device_factory_registry,
+        )?;
+
+        let project = config
+            .get_project_by_name(&name)
+            .ok_or_else(|| format!("Project not found: {}", name))?;
+        let requirement = project
+            .get_tracking_status()
+            .get_backup_requirement_class()
+            .ok_or_else(|| format!("Project {} is not tracked", name))?;
+
+        // Only the devices holding a backup of the project count as copies
+        let copies = config.get_available_copies(&name);
+        Ok(requirement.is_satisfied_by(&copies))
+    }
+
+    fn get_project_backup_status(&self, name: String) -> Result<ProjectBackupStatus, String> {
+        let config = GlobalConfig::load(
+            self.global_config_provider.as_ref(),
+            &self.device_factory_registry,
+        )?;
+
+        config
+            .get_project_by_name(&name)
+            .ok_or_else(|| format!("Project not found: {}", name))?;
+
+        // Same copies as the compliance check: the current copies are not persisted
+        let status = config
+            .get_devices_iter()
+            .filter(|device| device.test_availability().is_ok())
+            .filter_map(|device| {
+                let last_backup = device.get_last_backup_time(&name).ok()??;
+                Some((device.get_name(), last_backup))
+            })
+            .collect();
+        Ok(status)
+    }
+
+    fn clone_project(
+        &self,
+        source_name: String,
+        new_name: String,
+        new_location: String,
+    ) -> Result<(), String> {
+        let _lock = self.lock_config()?;
+        let mut config = GlobalConfig::load(
+            self.global_config_provider.as_ref(),
+            &self.device_factory_registry,
+        )?;
+
+        let source = config
+            .get_project_by_name(&source_name)
+            .ok_or_else(|| format!("Project not found: {}", source_name))?;
+        let tracking_status = match source.get_tracking_status() {
+            ProjectTrackingStatus::TrackedProject {
+                backup_requirement_class,
+                max_archives,
+                ..
+            } => ProjectTrackingStatus::TrackedProject {
+                backup_requirement_class: backup_requirement_class.clone(),
+                last_update: None,
+                current_copies: vec![],
+                max_archives: *max_archives,
+            },
+            ProjectTrackingStatus::UntrackedProject => ProjectTrackingStatus::UntrackedProject,
+            ProjectTrackingStatus::IgnoredProject => ProjectTrackingStatus::IgnoredProject,
+        };
+
+        config.add_project(Project::new(new_name, new_location, Some(tracking_status)))?;
+        config.save(self.global_config_provider.as_ref())?;
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -81,6 +368,22 @@ mod tests {
         assert!(projects.is_empty());
     }
 
+    #[test]
+    fn when_retrieving_projects_the_configuration_shall_not_be_locked() {
+        let mut provider = MockGlobalConfigProvider::new();
+        provider
+            .expect_read_global_config()
+            .return_const(Ok(r#""#.to_string()));
+        provider.expect_lock_global_config().never();
+
+        let operations = Operations {
+            device_factory_registry: DeviceFactoryRegistry::new(),
+            global_config_provider: Box::new(provider),
+        };
+
+        operations.list_projects().unwrap();
+    }
+
     #[test]
     fn when_retrieving_projects_from_config_with_one_ignored_project_it_shall_return_it() {
         let mut registry = DeviceFactoryRegistry::new();
@@ -204,6 +507,14 @@ type = "UntrackedProject"
         });
 
         let mut provider = MockGlobalConfigProvider::new();
+        provider
+            .expect_lock_global_config()
+            .times(1)
+            .return_const(Ok(()));
+        provider
+            .expect_unlock_global_config()
+            .times(1)
+            .return_const(());
         provider
             .expect_read_global_config()
             .return_const(Ok(r#""#.to_string()));
@@ -215,7 +526,7 @@ name = "MyProject"
 path = "/path/to/project"
 
 [projects.tracking_status]
-last_update = "0"
+last_update = ""
 type = "TrackedProject"
 
 [projects.tracking_status.backup_requirement_class]
@@ -240,6 +551,114 @@ target_locations = 2
         operations.add_project(project).unwrap();
     }
 
+    #[test]
+    fn when_adding_an_existing_project_idempotently_the_configuration_shall_not_be_written() {
+        let mut provider = MockGlobalConfigProviderFactory::new(
+            r#"
+[[projects]]
+name = "MyProject"
+path = "/path/to/project"
+tracking_status = { type = "IgnoredProject" }
+"#,
+        );
+        provider.expect_write_global_config().never();
+
+        let operations = Operations {
+            device_factory_registry: DeviceFactoryRegistry::new(),
+            global_config_provider: Box::new(provider),
+        };
+
+        let created = operations
+            .add_project_idempotent(AddProjectArgs {
+                name: "MyProject".to_string(),
+                location: "/other/path".to_string(),
+            })
+            .unwrap();
+        assert!(!created);
+    }
+
+    #[test]
+    fn when_adding_projects_from_csv_each_row_shall_be_reported() {
+        let mut provider = MockGlobalConfigProvider::new();
+        provider.expect_lock_global_config().return_const(Ok(()));
+        provider.expect_unlock_global_config().return_const(());
+        provider
+            .expect_read_global_config()
+            .return_const(Ok(r#""#.to_string()));
+        provider
+            .expect_write_global_config()
+            .times(1)
+            .withf(|config| {
+                config.contains(r#"name = "Photos""#)
+                    && config.contains(r#"name = "Music""#)
+                    && !config.contains(r#"name = "Videos""#)
+            })
+            .return_const(Ok(()));
+        let operations = Operations {
+            device_factory_registry: DeviceFactoryRegistry::new(),
+            global_config_provider: Box::new(provider),
+        };
+
+        let report = operations
+            .add_projects_from_csv(
+                "name,path,tracking_status\n\
+                 Photos,/path/to/photos,tracked\n\
+                 Videos,/path/to/videos,archived\n\
+                 \n\
+                 Music,/path/to/music,ignored\n\
+                 Photos,/path/to/other,untracked\n\
+                 Notes,/path/to/notes\n",
+            )
+            .unwrap();
+
+        assert_eq!(report.added_count(), 2);
+        assert_eq!(
+            report.rows,
+            vec![
+                (2, "Photos".to_string(), Ok(())),
+                (
+                    3,
+                    "Videos".to_string(),
+                    Err(
+                        "Invalid tracking status: archived, shall be tracked, untracked or ignored"
+                            .to_string()
+                    )
+                ),
+                (5, "Music".to_string(), Ok(())),
+                (
+                    6,
+                    "Photos".to_string(),
+                    Err("Project with name Photos already exists".to_string())
+                ),
+                (
+                    7,
+                    "Notes".to_string(),
+                    Err("Expected 3 fields (name,path,tracking_status), got 2".to_string())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn when_no_project_of_the_csv_is_valid_the_configuration_shall_not_be_written() {
+        let operations = Operations {
+            device_factory_registry: DeviceFactoryRegistry::new(),
+            global_config_provider: Box::new(MockGlobalConfigProviderFactory::new(r#""#)),
+        };
+
+        let report = operations.add_projects_from_csv("Photos,,tracked").unwrap();
+
+        assert_eq!(report.added_count(), 0);
+        assert_eq!(
+            report.rows,
+            vec![(
+                1,
+                "Photos".to_string(),
+                Err("Name and path shall not be empty".to_string())
+            )]
+        );
+    }
+
     #[test]
     fn when_adding_project_to_config_with_another_project_it_shall_add_it_to_the_configuration() {
         let mut registry = DeviceFactoryRegistry::new();
@@ -248,6 +667,8 @@ target_locations = 2
         });
 
         let mut provider = MockGlobalConfigProvider::new();
+        provider.expect_lock_global_config().return_const(Ok(()));
+        provider.expect_unlock_global_config().return_const(());
         provider
             .expect_read_global_config()
             .return_const(Ok(r#"[[projects]]
@@ -273,7 +694,7 @@ name = "MyProject"
 path = "/path/to/project2"
 
 [projects.tracking_status]
-last_update = "0"
+last_update = ""
 type = "TrackedProject"
 
 [projects.tracking_status.backup_requirement_class]
@@ -306,6 +727,8 @@ target_locations = 2
         });
 
         let mut provider = MockGlobalConfigProvider::new();
+        provider.expect_lock_global_config().return_const(Ok(()));
+        provider.expect_unlock_global_config().return_const(());
         provider
             .expect_read_global_config()
             .return_const(Ok(r#"[[projects]]
@@ -328,7 +751,7 @@ type = "IgnoredProject"
         };
 
         operations
-            .remove_project_by_name("AnotherProject".to_string())
+            .remove_project_by_name("AnotherProject".to_string(), false)
             .unwrap();
     }
 
@@ -339,7 +762,7 @@ type = "IgnoredProject"
             global_config_provider: Box::new(MockGlobalConfigProviderFactory::new(r#""#)),
         };
 
-        let result = operations.remove_project_by_name("NotInConfig".to_string());
+        let result = operations.remove_project_by_name("NotInConfig".to_string(), false);
         assert!(result.err().unwrap().contains("Project not found"));
     }
 
@@ -351,6 +774,8 @@ type = "IgnoredProject"
         });
 
         let mut provider = MockGlobalConfigProvider::new();
+        provider.expect_lock_global_config().return_const(Ok(()));
+        provider.expect_unlock_global_config().return_const(());
         provider
             .expect_read_global_config()
             .return_const(Ok(r#"[[projects]]
@@ -365,7 +790,7 @@ name = "MyProject"
 path = "/path/to/project2"
 
 [projects.tracking_status]
-last_update = "0"
+last_update = ""
 type = "TrackedProject"
 
 [projects.tracking_status.backup_requirement_class]
@@ -394,7 +819,411 @@ type = "IgnoredProject"
         };
 
         operations
-            .remove_project_by_name("MyProject".to_string())
+            .remove_project_by_name("MyProject".to_string(), false)
+            .unwrap();
+    }
+
+    #[test]
+    fn when_updating_project_location_it_shall_save_the_new_path() {
+        let mut provider = MockGlobalConfigProvider::new();
+        provider.expect_lock_global_config().return_const(Ok(()));
+        provider.expect_unlock_global_config().return_const(());
+        provider
+            .expect_read_global_config()
+            .return_const(Ok(r#"[[projects]]
+name = "MyProject"
+path = "/path/to/project"
+
+[projects.tracking_status]
+type = "IgnoredProject"
+"#
+            .to_string()));
+        provider
+            .expect_write_global_config()
+            .times(1)
+            .with(eq(r#"[[projects]]
+name = "MyProject"
+path = "/new/path/to/project"
+
+[projects.tracking_status]
+type = "IgnoredProject"
+"#
+            .to_string()))
+            .return_const(Ok(()));
+
+        let operations = Operations {
+            device_factory_registry: DeviceFactoryRegistry::new(),
+            global_config_provider: Box::new(provider),
+        };
+
+        operations
+            .update_project_location("MyProject".to_string(), "/new/path/to/project".to_string())
+            .unwrap();
+    }
+
+    #[test]
+    fn when_renaming_a_project_its_folders_on_devices_shall_be_renamed() {
+        let mut registry = DeviceFactoryRegistry::new();
+        registry.register_device("MockDevice".to_string(), "Mock Device".to_string(), || {
+            let mut factory = crate::core::MockDeviceFactory::new();
+            factory.expect_build_from_toml_table().returning(|name, _| {
+                let mut device = crate::core::MockDevice::new();
+                device.expect_get_name().return_const(name.to_string());
+                device.expect_test_availability().return_const(Ok(()));
+                device
+                    .expect_list_backed_up_projects()
+                    .returning(|| Ok(vec!["MyProject".to_string()]));
+                device
+                    .expect_rename_project_directory()
+                    .with(eq("MyProject"), eq("NewName"))
+                    .times(1)
+                    .return_const(Ok(()));
+                device.expect_to_toml_table().returning(toml::Table::new);
+                Ok(Box::new(device))
+            });
+            Box::new(factory)
+        });
+
+        let mut provider = MockGlobalConfigProviderFactory::new(
+            r#"
+[[devices]]
+name = "MockDevice"
+type = "MockDevice"
+
+[[projects]]
+name = "MyProject"
+path = "/path/to/project"
+tracking_status = { type = "IgnoredProject" }
+"#,
+        );
+        provider
+            .expect_write_global_config()
+            .times(1)
+            .withf(|toml| toml.contains(r#"name = "NewName""#) && !toml.contains("MyProject"))
+            .return_const(Ok(()));
+
+        let operations = Operations {
+            device_factory_registry: registry,
+            global_config_provider: Box::new(provider),
+        };
+
+        operations
+            .rename_project("MyProject".to_string(), "NewName".to_string(), false)
+            .unwrap();
+    }
+
+    fn operations_with_devices_for_rename(
+        build_device: fn(&str) -> crate::core::MockDevice,
+    ) -> Operations {
+        let mut registry = DeviceFactoryRegistry::new();
+        registry.register_device(
+            "MockDevice".to_string(),
+            "Mock Device".to_string(),
+            move || {
+                let mut factory = crate::core::MockDeviceFactory::new();
+                factory
+                    .expect_build_from_toml_table()
+                    .returning(move |name, _| Ok(Box::new(build_device(name))));
+                Box::new(factory)
+            },
+        );
+
+        // The configuration is not expected to be written
+        Operations {
+            device_factory_registry: registry,
+            global_config_provider: Box::new(MockGlobalConfigProviderFactory::new(
+                r#"
+[[devices]]
+name = "FirstDevice"
+type = "MockDevice"
+
+[[devices]]
+name = "SecondDevice"
+type = "MockDevice"
+
+[[projects]]
+name = "MyProject"
+path = "/path/to/project"
+tracking_status = { type = "IgnoredProject" }
+"#,
+            )),
+        }
+    }
+
+    #[test]
+    fn when_renaming_a_folder_fails_the_renamed_folders_shall_be_renamed_back() {
+        let operations = operations_with_devices_for_rename(|name| {
+            let mut device = crate::core::MockDevice::new();
+            device.expect_get_name().return_const(name.to_string());
+            device.expect_test_availability().return_const(Ok(()));
+            device
+                .expect_list_backed_up_projects()
+                .returning(|| Ok(vec!["MyProject".to_string()]));
+            if name == "FirstDevice" {
+                let mut sequence = mockall::Sequence::new();
+                device
+                    .expect_rename_project_directory()
+                    .with(eq("MyProject"), eq("NewName"))
+                    .times(1)
+                    .in_sequence(&mut sequence)
+                    .return_const(Ok(()));
+                device
+                    .expect_rename_project_directory()
+                    .with(eq("NewName"), eq("MyProject"))
+                    .times(1)
+                    .in_sequence(&mut sequence)
+                    .return_const(Ok(()));
+            } else {
+                device
+                    .expect_rename_project_directory()
+                    .times(1)
+                    .return_const(Err("Permission denied".to_string()));
+            }
+            device
+        });
+
+        let result =
+            operations.rename_project("MyProject".to_string(), "NewName".to_string(), false);
+
+        assert_eq!(
+            result,
+            Err(
+                "Could not rename the folder of MyProject on SecondDevice: Permission denied"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn when_renaming_a_project_with_an_unavailable_device_it_shall_fail_unless_forced() {
+        let operations = operations_with_devices_for_rename(|name| {
+            let mut device = crate::core::MockDevice::new();
+            device.expect_get_name().return_const(name.to_string());
+            if name == "FirstDevice" {
+                device.expect_test_availability().return_const(Ok(()));
+            } else {
+                device
+                    .expect_test_availability()
+                    .return_const(Err("Unplugged".to_string()));
+            }
+            device
+        });
+
+        // No folder is renamed on the available device either
+        let result =
+            operations.rename_project("MyProject".to_string(), "NewName".to_string(), false);
+
+        assert_eq!(
+            result,
+            Err("Devices SecondDevice are not available, their folder of MyProject would keep this name".to_string())
+        );
+    }
+
+    #[test]
+    fn when_renaming_a_project_to_a_taken_name_it_shall_fail() {
+        let operations = Operations {
+            device_factory_registry: DeviceFactoryRegistry::new(),
+            global_config_provider: Box::new(MockGlobalConfigProviderFactory::new(
+                r#"
+[[projects]]
+name = "MyProject"
+path = "/path/to/project"
+tracking_status = { type = "IgnoredProject" }
+
+[[projects]]
+name = "OtherProject"
+path = "/path/to/other"
+tracking_status = { type = "IgnoredProject" }
+"#,
+            )),
+        };
+
+        let result =
+            operations.rename_project("MyProject".to_string(), "OtherProject".to_string(), false);
+
+        assert_eq!(
+            result,
+            Err("Project with name OtherProject already exists".to_string())
+        );
+    }
+
+    #[test]
+    fn when_cloning_a_project_it_shall_share_its_backup_requirement_class() {
+        let mut provider = MockGlobalConfigProvider::new();
+        provider.expect_lock_global_config().return_const(Ok(()));
+        provider.expect_unlock_global_config().return_const(());
+        provider
+            .expect_read_global_config()
+            .return_const(Ok(r#"[[projects]]
+name = "MyProject"
+path = "/path/to/project"
+
+[projects.tracking_status]
+type = "TrackedProject"
+
+[projects.tracking_status.backup_requirement_class]
+min_security_level = "Local"
+name = "Critical"
+target_copies = 4
+target_locations = 3
+"#
+            .to_string()));
+        provider
+            .expect_write_global_config()
+            .times(1)
+            .with(eq(r#"[[projects]]
+name = "MyProject"
+path = "/path/to/project"
+
+[projects.tracking_status]
+last_update = ""
+type = "TrackedProject"
+
+[projects.tracking_status.backup_requirement_class]
+min_security_level = "Local"
+name = "Critical"
+target_copies = 4
+target_locations = 3
+
+[[projects]]
+name = "MyClone"
+path = "/path/to/clone"
+
+[projects.tracking_status]
+last_update = ""
+type = "TrackedProject"
+
+[projects.tracking_status.backup_requirement_class]
+min_security_level = "Local"
+name = "Critical"
+target_copies = 4
+target_locations = 3
+"#
+            .to_string()))
+            .return_const(Ok(()));
+
+        let operations = Operations {
+            device_factory_registry: DeviceFactoryRegistry::new(),
+            global_config_provider: Box::new(provider),
+        };
+
+        operations
+            .clone_project(
+                "MyProject".to_string(),
+                "MyClone".to_string(),
+                "/path/to/clone".to_string(),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn when_cloning_an_unknown_project_it_shall_fail() {
+        let operations = Operations {
+            device_factory_registry: DeviceFactoryRegistry::new(),
+            global_config_provider: Box::new(MockGlobalConfigProviderFactory::new("")),
+        };
+
+        let result = operations.clone_project(
+            "MyProject".to_string(),
+            "MyClone".to_string(),
+            "/path/to/clone".to_string(),
+        );
+        assert_eq!(result, Err("Project not found: MyProject".to_string()));
+    }
+
+    #[test]
+    fn when_getting_compliance_of_an_untracked_project_it_shall_fail() {
+        let operations = Operations {
+            device_factory_registry: DeviceFactoryRegistry::new(),
+            global_config_provider: Box::new(MockGlobalConfigProviderFactory::new(
+                r#"[[projects]]
+name = "MyProject"
+path = "/path/to/project"
+
+[projects.tracking_status]
+type = "IgnoredProject"
+"#,
+            )),
+        };
+
+        let result = operations.get_project_compliance("MyProject".to_string());
+        assert_eq!(result, Err("Project MyProject is not tracked".to_string()));
+    }
+
+    #[test]
+    fn when_getting_compliance_without_backup_only_the_primary_copy_shall_count() {
+        let mut registry = DeviceFactoryRegistry::new();
+        registry.register_device("MockDevice".to_string(), "Mock Device".to_string(), || {
+            Box::new(MockDeviceFactory)
+        });
+        let operations = Operations {
+            device_factory_registry: registry,
+            global_config_provider: Box::new(MockGlobalConfigProviderFactory::new(
+                r#"[[devices]]
+name = "MockDevice"
+type = "MockDevice"
+
+[[projects]]
+name = "MyProject"
+path = "/path/to/project"
+
+[projects.tracking_status]
+type = "TrackedProject"
+
+[projects.tracking_status.backup_requirement_class]
+min_security_level = "Local"
+name = "Default"
+target_copies = 2
+target_locations = 1
+"#,
+            )),
+        };
+
+        let result = operations
+            .get_project_compliance("MyProject".to_string())
+            .unwrap();
+        assert!(!result.is_compliant);
+        assert_eq!(result.actual_copies, 1);
+        assert_eq!(result.missing_copies, 1);
+    }
+
+    #[test]
+    fn when_getting_backup_status_of_unknown_project_it_shall_fail() {
+        let operations = Operations {
+            device_factory_registry: DeviceFactoryRegistry::new(),
+            global_config_provider: Box::new(MockGlobalConfigProviderFactory::new(r#""#)),
+        };
+
+        let result = operations.get_project_backup_status("MyProject".to_string());
+        assert_eq!(result, Err("Project not found: MyProject".to_string()));
+    }
+
+    #[test]
+    fn when_getting_backup_status_without_backup_it_shall_be_empty() {
+        let mut registry = DeviceFactoryRegistry::new();
+        registry.register_device("MockDevice".to_string(), "Mock Device".to_string(), || {
+            Box::new(MockDeviceFactory)
+        });
+        let operations = Operations {
+            device_factory_registry: registry,
+            global_config_provider: Box::new(MockGlobalConfigProviderFactory::new(
+                r#"[[devices]]
+name = "MockDevice"
+type = "MockDevice"
+
+[[projects]]
+name = "MyProject"
+path = "/path/to/project"
+
+[projects.tracking_status]
+type = "IgnoredProject"
+"#,
+            )),
+        };
+
+        let result = operations
+            .get_project_backup_status("MyProject".to_string())
             .unwrap();
+        assert!(result.is_empty());
     }
 }
@@ -2,4 +2,4 @@ mod project;
 mod project_status;
 mod projects_scan;
 
-pub use project::{Project, ProjectTrackingStatus};
+pub use project::{Project, ProjectCopy, ProjectTrackingStatus};
@@ -1,10 +1,13 @@
-use std::{
-    path::PathBuf,
-    time::{Instant, SystemTime},
-};
+use std::{path::PathBuf, time::SystemTime};
+
+use serde::Serialize;
 
-use crate::core::device::{BackupRequirementClass, Device};
+use crate::core::{
+    device::{BackupRequirementClass, SecurityLevel},
+    util::timestamps::serialize_optional_seconds_since_epoch,
+};
 
+#[derive(Serialize)]
 pub struct Project {
     // The name of the project
     name: String,
@@ -37,14 +40,30 @@ impl Project {
         &self.name
     }
 
+    pub fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
     pub fn get_location(&self) -> &String {
         &self.location
     }
 
+    pub fn set_location(&mut self, location: String) {
+        self.location = location;
+    }
+
     pub fn get_tracking_status(&self) -> &ProjectTrackingStatus {
         &self.tracking_status
     }
 
+    // Only tracked projects keep their last update time
+    pub fn set_last_update(&mut self, time: SystemTime) {
+        if let ProjectTrackingStatus::TrackedProject { last_update, .. } = &mut self.tracking_status
+        {
+            *last_update = Some(time);
+        }
+    }
+
     pub fn test_availability(&self) -> Result<(), String> {
         PathBuf::from(&self.location)
             .read_dir()
@@ -53,15 +72,23 @@ impl Project {
     }
 }
 
+#[derive(Serialize)]
+#[serde(tag = "type")]
 pub enum ProjectTrackingStatus {
     TrackedProject {
         // The target backup requirement class
         backup_requirement_class: BackupRequirementClass,
 
-        // Update date of the last updated file in the project
+        // Time of the last successful backup of the project
+        #[serde(serialize_with = "serialize_optional_seconds_since_epoch")]
         last_update: Option<SystemTime>,
         // The actual copies of the project on secondary drives
-        current_copies: Vec<Box<ProjectCopy>>,
+        // Not read from the configuration, so not listed either
+        #[serde(skip)]
+        current_copies: Vec<ProjectCopy>,
+
+        // Older archives are pruned after each backup beyond this count, if set
+        max_archives: Option<usize>,
     },
     UntrackedProject,
     IgnoredProject,
@@ -71,8 +98,9 @@ impl ProjectTrackingStatus {
     pub fn default() -> ProjectTrackingStatus {
         ProjectTrackingStatus::TrackedProject {
             backup_requirement_class: BackupRequirementClass::default(),
-            last_update: Some(SystemTime::now()),
+            last_update: None,
             current_copies: Vec::new(),
+            max_archives: None,
         }
     }
 
@@ -93,7 +121,14 @@ impl ProjectTrackingStatus {
         }
     }
 
-    pub fn get_current_copies(&self) -> Option<&Vec<Box<ProjectCopy>>> {
+    pub fn get_max_archives(&self) -> Option<usize> {
+        match self {
+            ProjectTrackingStatus::TrackedProject { max_archives, .. } => *max_archives,
+            _ => None,
+        }
+    }
+
+    pub fn get_current_copies(&self) -> Option<&Vec<ProjectCopy>> {
         match self {
             ProjectTrackingStatus::TrackedProject { current_copies, .. } => Some(current_copies),
             _ => None,
@@ -103,8 +138,90 @@ impl ProjectTrackingStatus {
 
 pub struct ProjectCopy {
     // What is the last time a backup was made
-    _last_backup: Option<Instant>,
+    last_backup: Option<SystemTime>,
 
     // What is the device on which it was done?
-    _secondary_device: dyn Device,
+    device_name: String,
+
+    // The physical location and the security level of this device
+    location: String,
+    security_level: SecurityLevel,
+}
+
+impl ProjectCopy {
+    pub fn new(
+        device_name: String,
+        location: String,
+        security_level: SecurityLevel,
+        last_backup: Option<SystemTime>,
+    ) -> ProjectCopy {
+        ProjectCopy {
+            last_backup,
+            device_name,
+            location,
+            security_level,
+        }
+    }
+
+    pub fn get_last_backup(&self) -> Option<SystemTime> {
+        self.last_backup
+    }
+
+    pub fn get_device_name(&self) -> &String {
+        &self.device_name
+    }
+
+    pub fn get_location(&self) -> &String {
+        &self.location
+    }
+
+    pub fn get_security_level(&self) -> &SecurityLevel {
+        &self.security_level
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::*;
+
+    #[test]
+    fn tracked_project_shall_be_serialized_with_its_status_type() {
+        let project = Project::new(
+            "MyProject".to_string(),
+            "/home/user/my-project".to_string(),
+            Some(ProjectTrackingStatus::TrackedProject {
+                backup_requirement_class: BackupRequirementClass::default(),
+                last_update: Some(UNIX_EPOCH + Duration::from_secs(1713453720)),
+                current_copies: vec![ProjectCopy::new(
+                    "USBkey".to_string(),
+                    "/media/usb".to_string(),
+                    SecurityLevel::Local,
+                    None,
+                )],
+                max_archives: None,
+            }),
+        );
+
+        let json: serde_json::Value = serde_json::to_value(&project).unwrap();
+        let tracking_status = &json["tracking_status"];
+        assert_eq!(tracking_status["type"], "TrackedProject");
+        assert_eq!(tracking_status["last_update"], 1713453720);
+        assert_eq!(tracking_status.get("current_copies"), None);
+    }
+
+    #[test]
+    fn ignored_project_shall_be_serialized_with_its_status_type_only() {
+        let project = Project::new(
+            "MyProject".to_string(),
+            "/home/user/my-project".to_string(),
+            Some(ProjectTrackingStatus::IgnoredProject),
+        );
+
+        assert_eq!(
+            serde_json::to_string(&project).unwrap(),
+            r#"{"name":"MyProject","location":"/home/user/my-project","tracking_status":{"type":"IgnoredProject"}}"#
+        );
+    }
 }
@@ -1,4 +1,6 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
 
 pub fn create_tmp_dir() -> PathBuf {
     let random_project_name = format!("hibernacli-tests-{}", uuid::Uuid::new_v4());
@@ -6,3 +8,39 @@ pub fn create_tmp_dir() -> PathBuf {
     std::fs::create_dir_all(&tmp_path).unwrap();
     tmp_path
 }
+
+// Create a project directory holding the given files, parent directories included
+pub fn create_tmp_project(files: &[(&str, &[u8])]) -> PathBuf {
+    let project_path = create_tmp_dir();
+    for (name, content) in files {
+        let file_path = project_path.join(name);
+        std::fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+        std::fs::write(file_path, content).unwrap();
+    }
+    project_path
+}
+
+pub fn create_tmp_device() -> PathBuf {
+    create_tmp_dir()
+}
+
+// Check that a .tar.gz archive holds entries at each of the expected paths
+pub fn assert_archive_contains(archive_path: &Path, expected_files: &[&str]) {
+    let file = std::fs::File::open(archive_path).unwrap();
+    let mut archive = tar::Archive::new(GzDecoder::new(file));
+    let paths = archive
+        .entries()
+        .unwrap()
+        .map(|entry| entry.unwrap().path().unwrap().to_path_buf())
+        .collect::<Vec<_>>();
+
+    for expected_file in expected_files {
+        assert!(
+            paths.contains(&PathBuf::from(expected_file)),
+            "{} not found in {:?}, entries are {:?}",
+            expected_file,
+            archive_path,
+            paths
+        );
+    }
+}
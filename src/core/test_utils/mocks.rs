@@ -1,8 +1,11 @@
-use std::{io::BufRead, time::Instant};
+use std::{
+    io::{BufRead, Read},
+    time::{Instant, SystemTime},
+};
 
 use crate::core::{
     config::MockGlobalConfigProvider,
-    device::{ArchiveWriter, QuestionType},
+    device::{ArchiveWriter, ArchiveWriterOptions, DeviceEq, QuestionType},
     Device, DeviceFactory, Extractor, SecurityLevel,
 };
 
@@ -17,6 +20,7 @@ impl DeviceFactory for MockDeviceFactory {
     fn set_question_answer(&mut self, _answer: String) -> Result<(), String> {
         panic!("No question")
     }
+    fn reset(&mut self) {}
     fn has_next(&self) -> bool {
         false
     }
@@ -65,6 +69,10 @@ impl Device for MockDeviceWithParameters {
     fn get_last_disconnection(&self) -> Option<Instant> {
         None
     }
+    fn get_last_seen(&self) -> Option<SystemTime> {
+        None
+    }
+    fn set_last_seen(&mut self, _last_seen: SystemTime) {}
     fn get_security_level(&self) -> SecurityLevel {
         SecurityLevel::Local
     }
@@ -78,10 +86,57 @@ impl Device for MockDeviceWithParameters {
     fn read_backup_index(&self, _project_name: &str) -> Result<Option<Box<dyn BufRead>>, String> {
         Ok(None)
     }
+    fn write_backup_index(&self, _project_name: &str, _index: &[u8]) -> Result<(), String> {
+        Ok(())
+    }
     fn test_availability(&self) -> Result<(), String> {
         Ok(())
     }
-    fn get_archive_writer(&self, _project_name: &str) -> Box<dyn ArchiveWriter> {
+    fn test_write_permission(&self) -> Result<(), String> {
+        Ok(())
+    }
+    fn get_free_space(&self) -> Option<u64> {
+        None
+    }
+    fn list_archives(&self, _project_name: &str) -> Result<Vec<String>, String> {
+        Ok(vec![])
+    }
+    fn list_backed_up_projects(&self) -> Result<Vec<String>, String> {
+        Ok(vec![])
+    }
+    fn migrate_archives(&self, _project_name: &str) -> Result<usize, String> {
+        Ok(0)
+    }
+    fn get_archive_time(&self, _archive_name: &str) -> Option<SystemTime> {
+        None
+    }
+    fn get_last_backup_time(&self, _project_name: &str) -> Result<Option<SystemTime>, String> {
+        Ok(None)
+    }
+    fn get_archive_reader(
+        &self,
+        _project_name: &str,
+        _archive_name: &str,
+    ) -> Result<Box<dyn Read>, String> {
+        panic!("Mock not implemented for this use case")
+    }
+    fn create_project_directory(&self, _project_name: &str) -> Result<(), String> {
+        Ok(())
+    }
+    fn rename_project_directory(&self, _project_name: &str, _new_name: &str) -> Result<(), String> {
+        Ok(())
+    }
+    fn delete_project_data(&self, _project_name: &str) -> Result<(), String> {
+        Ok(())
+    }
+    fn prune_archives(&self, _project_name: &str, _max_archives: usize) -> Result<usize, String> {
+        Ok(0)
+    }
+    fn get_archive_writer(
+        &self,
+        _project_name: &str,
+        _options: &ArchiveWriterOptions,
+    ) -> Box<dyn ArchiveWriter> {
         panic!("Mock not implemented for this use case")
     }
     fn get_extractor(&self, _project_name: &str) -> Box<dyn Extractor> {
@@ -98,6 +153,7 @@ impl DeviceFactory for MockDeviceWithParametersFactory {
     fn set_question_answer(&mut self, _answer: String) -> Result<(), String> {
         panic!("No question")
     }
+    fn reset(&mut self) {}
     fn has_next(&self) -> bool {
         false
     }
@@ -150,6 +206,10 @@ impl Device for MockDevice {
     fn get_last_disconnection(&self) -> Option<Instant> {
         None
     }
+    fn get_last_seen(&self) -> Option<SystemTime> {
+        None
+    }
+    fn set_last_seen(&mut self, _last_seen: SystemTime) {}
     fn to_toml_table(&self) -> toml::value::Table {
         let mut table = toml::value::Table::new();
         table.insert("name".to_string(), self.get_name().into());
@@ -159,10 +219,57 @@ impl Device for MockDevice {
     fn read_backup_index(&self, _project_name: &str) -> Result<Option<Box<dyn BufRead>>, String> {
         Ok(None)
     }
+    fn write_backup_index(&self, _project_name: &str, _index: &[u8]) -> Result<(), String> {
+        Ok(())
+    }
     fn test_availability(&self) -> Result<(), String> {
         Ok(())
     }
-    fn get_archive_writer(&self, _project_name: &str) -> Box<dyn ArchiveWriter> {
+    fn test_write_permission(&self) -> Result<(), String> {
+        Ok(())
+    }
+    fn get_free_space(&self) -> Option<u64> {
+        None
+    }
+    fn list_archives(&self, _project_name: &str) -> Result<Vec<String>, String> {
+        Ok(vec![])
+    }
+    fn list_backed_up_projects(&self) -> Result<Vec<String>, String> {
+        Ok(vec![])
+    }
+    fn migrate_archives(&self, _project_name: &str) -> Result<usize, String> {
+        Ok(0)
+    }
+    fn get_archive_time(&self, _archive_name: &str) -> Option<SystemTime> {
+        None
+    }
+    fn get_last_backup_time(&self, _project_name: &str) -> Result<Option<SystemTime>, String> {
+        Ok(None)
+    }
+    fn get_archive_reader(
+        &self,
+        _project_name: &str,
+        _archive_name: &str,
+    ) -> Result<Box<dyn Read>, String> {
+        panic!("Mock not implemented for this use case")
+    }
+    fn create_project_directory(&self, _project_name: &str) -> Result<(), String> {
+        Ok(())
+    }
+    fn rename_project_directory(&self, _project_name: &str, _new_name: &str) -> Result<(), String> {
+        Ok(())
+    }
+    fn delete_project_data(&self, _project_name: &str) -> Result<(), String> {
+        Ok(())
+    }
+    fn prune_archives(&self, _project_name: &str, _max_archives: usize) -> Result<usize, String> {
+        Ok(0)
+    }
+    fn get_archive_writer(
+        &self,
+        _project_name: &str,
+        _options: &ArchiveWriterOptions,
+    ) -> Box<dyn ArchiveWriter> {
         panic!("Mock not implemented for this use case")
     }
     fn get_extractor(&self, _project_name: &str) -> Box<dyn Extractor> {
@@ -170,6 +277,8 @@ impl Device for MockDevice {
     }
 }
 
+impl DeviceEq for MockDevice {}
+
 pub struct MockGlobalConfigProviderFactory;
 impl MockGlobalConfigProviderFactory {
     pub fn new(global_config_toml: &str) -> MockGlobalConfigProvider {
@@ -177,6 +286,8 @@ impl MockGlobalConfigProviderFactory {
         provider
             .expect_read_global_config()
             .return_const(Ok(global_config_toml.to_string()));
+        provider.expect_lock_global_config().return_const(Ok(()));
+        provider.expect_unlock_global_config().return_const(());
         provider
     }
 
@@ -1,11 +1,25 @@
 pub struct BufferReadError;
 
 pub trait BufferExt {
+    // Not read by the index format yet, kept for smaller sizes or signed timestamps
+    #[allow(dead_code)]
+    fn read_u32_from_le(&mut self, offset: usize) -> Result<u32, BufferReadError>;
     fn read_u64_from_le(&mut self, offset: usize) -> Result<u64, BufferReadError>;
+    #[allow(dead_code)]
+    fn read_i64_from_le(&mut self, offset: usize) -> Result<i64, BufferReadError>;
     fn read_u128_from_le(&mut self, offset: usize) -> Result<u128, BufferReadError>;
 }
 
 impl BufferExt for Vec<u8> {
+    fn read_u32_from_le(&mut self, offset: usize) -> Result<u32, BufferReadError> {
+        Ok(u32::from_le_bytes(
+            self.get(offset..offset + 4)
+                .ok_or(BufferReadError)?
+                .try_into()
+                .map_err(|_| BufferReadError)?,
+        ))
+    }
+
     fn read_u64_from_le(&mut self, offset: usize) -> Result<u64, BufferReadError> {
         Ok(u64::from_le_bytes(
             self.get(offset..offset + 8)
@@ -15,6 +29,15 @@ impl BufferExt for Vec<u8> {
         ))
     }
 
+    fn read_i64_from_le(&mut self, offset: usize) -> Result<i64, BufferReadError> {
+        Ok(i64::from_le_bytes(
+            self.get(offset..offset + 8)
+                .ok_or(BufferReadError)?
+                .try_into()
+                .map_err(|_| BufferReadError)?,
+        ))
+    }
+
     fn read_u128_from_le(&mut self, offset: usize) -> Result<u128, BufferReadError> {
         Ok(u128::from_le_bytes(
             self.get(offset..offset + 16)
@@ -24,3 +47,32 @@ impl BufferExt for Vec<u8> {
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn u32_shall_be_read_at_offset() {
+        let mut buffer = vec![0xff, 0x78, 0x56, 0x34, 0x12];
+        assert_eq!(buffer.read_u32_from_le(1).ok(), Some(0x12345678));
+    }
+
+    #[test]
+    fn u32_shall_not_be_read_past_the_end_of_the_buffer() {
+        let mut buffer = vec![0x78, 0x56, 0x34];
+        assert!(buffer.read_u32_from_le(0).is_err());
+    }
+
+    #[test]
+    fn negative_i64_shall_be_read() {
+        let mut buffer = (-42i64).to_le_bytes().to_vec();
+        assert_eq!(buffer.read_i64_from_le(0).ok(), Some(-42));
+    }
+
+    #[test]
+    fn i64_shall_not_be_read_past_the_end_of_the_buffer() {
+        let mut buffer = vec![0; 8];
+        assert!(buffer.read_i64_from_le(1).is_err());
+    }
+}
@@ -0,0 +1,107 @@
+use std::{fs::File, io};
+
+/// A segment of a sparse file holding data, everything else being holes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SparseSegment {
+    pub offset: u64,
+    pub length: u64,
+}
+
+/// Find the data segments of a file, if it is sparse
+///
+/// Returns None if the file has no hole, or if the platform cannot tell
+#[cfg(target_os = "linux")]
+pub fn find_data_segments(file: &File, size: u64) -> io::Result<Option<Vec<SparseSegment>>> {
+    use std::os::unix::{fs::MetadataExt, io::AsRawFd};
+
+    // A file using as many blocks as its size has no hole
+    if file.metadata()?.blocks() * 512 >= size {
+        return Ok(None);
+    }
+
+    let fd = file.as_raw_fd();
+    let mut segments = Vec::new();
+    let mut offset = 0;
+    while offset < size {
+        let data_start = match seek(fd, offset, libc::SEEK_DATA) {
+            Ok(data_start) => data_start,
+            // No more data until the end of the file
+            Err(e) if e.raw_os_error() == Some(libc::ENXIO) => break,
+            Err(e) => return Err(e),
+        };
+        let data_end = seek(fd, data_start, libc::SEEK_HOLE)?.min(size);
+        segments.push(SparseSegment {
+            offset: data_start,
+            length: data_end - data_start,
+        });
+        offset = data_end;
+    }
+    seek(fd, 0, libc::SEEK_SET)?;
+
+    // Filesystems without hole support report the whole file as data
+    if segments
+        == [SparseSegment {
+            offset: 0,
+            length: size,
+        }]
+    {
+        return Ok(None);
+    }
+    Ok(Some(segments))
+}
+
+#[cfg(target_os = "linux")]
+fn seek(fd: std::os::unix::io::RawFd, offset: u64, whence: libc::c_int) -> io::Result<u64> {
+    let result = unsafe { libc::lseek(fd, offset as libc::off_t, whence) };
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(result as u64)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn find_data_segments(_file: &File, _size: u64) -> io::Result<Option<Vec<SparseSegment>>> {
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        io::{Seek, SeekFrom, Write},
+        path::Path,
+    };
+
+    use crate::core::test_utils::fs::create_tmp_dir;
+
+    use super::*;
+
+    #[test]
+    fn a_dense_file_shall_not_be_sparse() {
+        let path = Path::join(&create_tmp_dir(), "dense.bin");
+        fs::write(&path, vec![1u8; 64 * 1024]).unwrap();
+
+        let file = File::open(&path).unwrap();
+        assert_eq!(find_data_segments(&file, 64 * 1024).unwrap(), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn data_segments_of_a_sparse_file_shall_contain_its_data() {
+        let path = Path::join(&create_tmp_dir(), "sparse.bin");
+        let mut file = File::create(&path).unwrap();
+        file.set_len(4 * 1024 * 1024).unwrap();
+        file.seek(SeekFrom::Start(1024 * 1024)).unwrap();
+        file.write_all(&[1u8; 4096]).unwrap();
+        file.sync_all().unwrap();
+
+        let file = File::open(&path).unwrap();
+        // The filesystem of the test machine might not support holes
+        if let Some(segments) = find_data_segments(&file, 4 * 1024 * 1024).unwrap() {
+            assert!(segments.iter().any(|segment| segment.offset <= 1024 * 1024
+                && segment.offset + segment.length >= 1024 * 1024 + 4096));
+            let data_size: u64 = segments.iter().map(|segment| segment.length).sum();
+            assert!(data_size < 4 * 1024 * 1024);
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use std::{fmt::Display, io, time::SystemTime};
 
+use serde::{Serialize, Serializer};
+
 #[derive(Debug)]
 pub enum TimeStampError {
     IoError(std::io::Error),
@@ -50,6 +52,47 @@ impl Timestamp for io::Result<SystemTime> {
     }
 }
 
+/// Serialize an optional time as seconds since epoch, as stored in the configuration file
+pub fn serialize_optional_seconds_since_epoch<S: Serializer>(
+    time: &Option<SystemTime>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    time.and_then(|time| time.s_since_epoch().ok())
+        .serialize(serializer)
+}
+
+/// Format a time as a UTC date, e.g. "2024-03-01 14:05:09 UTC"
+pub fn format_utc(time: SystemTime) -> String {
+    let seconds = time.s_since_epoch().unwrap_or(0);
+    let (days, seconds_of_day) = (seconds / 86400, seconds % 86400);
+
+    // Convert days since epoch to a civil date in the proleptic Gregorian calendar
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -213,4 +256,17 @@ mod tests {
             "System time error: second time provided was later than self"
         );
     }
+
+    #[test]
+    fn test_format_utc() {
+        assert_eq!(format_utc(EPOCH), "1970-01-01 00:00:00 UTC");
+        assert_eq!(
+            format_utc(EPOCH + Duration::from_secs(951782400 + 3661)),
+            "2000-02-29 01:01:01 UTC"
+        );
+        assert_eq!(
+            format_utc(EPOCH + Duration::from_secs(1709301909)),
+            "2024-03-01 14:05:09 UTC"
+        );
+    }
 }
@@ -0,0 +1,90 @@
+use std::io::{self, Read, Write};
+
+use flate2::Compression;
+
+use crate::core::CompressionFormat;
+
+/// Compress the whole input into the output
+///
+/// The level is the one of the gzip settings (fast, default or best), mapped
+/// onto the level range of the other formats
+pub fn compress(
+    mut input: impl Read,
+    output: impl Write,
+    format: CompressionFormat,
+    level: Compression,
+) -> io::Result<()> {
+    match format {
+        CompressionFormat::Gz => {
+            let mut encoder = flate2::write::GzEncoder::new(output, level);
+            io::copy(&mut input, &mut encoder)?;
+            encoder.finish()?;
+        }
+        CompressionFormat::Bz2 => {
+            let level = bzip2::Compression::new(level.level().clamp(1, 9));
+            let mut encoder = bzip2::write::BzEncoder::new(output, level);
+            io::copy(&mut input, &mut encoder)?;
+            encoder.finish()?;
+        }
+        CompressionFormat::Xz => {
+            let mut encoder = xz2::write::XzEncoder::new(output, level.level());
+            io::copy(&mut input, &mut encoder)?;
+            encoder.finish()?;
+        }
+        CompressionFormat::Zstd => {
+            let mut encoder = zstd::stream::write::Encoder::new(output, get_zstd_level(level))?;
+            io::copy(&mut input, &mut encoder)?;
+            encoder.finish()?;
+        }
+    }
+    Ok(())
+}
+
+/// Reader of the decompressed content of the input
+pub fn decompress(
+    input: impl Read + 'static,
+    format: CompressionFormat,
+) -> io::Result<Box<dyn Read>> {
+    Ok(match format {
+        CompressionFormat::Gz => Box::new(flate2::read::GzDecoder::new(input)),
+        CompressionFormat::Bz2 => Box::new(bzip2::read::BzDecoder::new(input)),
+        CompressionFormat::Xz => Box::new(xz2::read::XzDecoder::new(input)),
+        CompressionFormat::Zstd => Box::new(zstd::stream::read::Decoder::new(input)?),
+    })
+}
+
+// Zstd levels go up to 22, the highest ones being too slow for backups
+fn get_zstd_level(level: Compression) -> i32 {
+    if level == Compression::fast() {
+        1
+    } else if level == Compression::best() {
+        19
+    } else {
+        zstd::DEFAULT_COMPRESSION_LEVEL
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn compressed_data_shall_be_decompressed_back_in_each_format() {
+        let data = b"Hello, world! Hello, world! Hello, world!".repeat(100);
+
+        for format in CompressionFormat::ALL {
+            let mut compressed = Vec::new();
+            compress(&data[..], &mut compressed, format, Compression::default()).unwrap();
+            assert!(compressed.len() < data.len());
+
+            let mut decompressed = Vec::new();
+            decompress(Cursor::new(compressed), format)
+                .unwrap()
+                .read_to_end(&mut decompressed)
+                .unwrap();
+            assert_eq!(decompressed, data, "{:?}", format);
+        }
+    }
+}
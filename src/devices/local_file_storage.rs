@@ -1,18 +1,42 @@
+#[cfg(not(feature = "xdg-strict"))]
 use directories::ProjectDirs;
-use std::path::{Path, PathBuf};
+use fs2::FileExt;
+use std::{
+    fs::{File, OpenOptions},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
 
 use crate::core::GlobalConfigProvider;
 
+const CONFIG_FILE_NAME: &str = "config.toml";
+const LOCK_FILE_NAME: &str = "config.toml.lock";
+const LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+const LOCK_RETRY_DELAY: Duration = Duration::from_millis(100);
+
 pub struct LocalFileStorage<'a> {
     config_dir: Box<Path>,
-    file_system: &'a dyn FileSystem,
+    file_system: &'a (dyn FileSystem + Sync),
     default_config: &'a str,
+
+    // Advisory lock on the config, held while a config is read, modified and written back,
+    // so that concurrent hibernacli processes do not overwrite each other's changes
+    lock: Mutex<Option<HeldLock>>,
+    lock_timeout: Duration,
+}
+
+// Nested updates of the config share the lock, it is released with the last of them
+struct HeldLock {
+    _file: File,
+    holders: usize,
 }
 
 impl<'a> LocalFileStorage<'a> {
     pub fn new(
         path_provider: &impl PathProvider,
-        file_system: &'a dyn FileSystem,
+        file_system: &'a (dyn FileSystem + Sync),
         default_config: &'a str,
     ) -> LocalFileStorage<'a> {
         let config_dir = path_provider
@@ -23,18 +47,65 @@ impl<'a> LocalFileStorage<'a> {
             config_dir,
             file_system,
             default_config,
+            lock: Mutex::new(None),
+            lock_timeout: LOCK_TIMEOUT,
         }
     }
-}
 
-impl<'a> GlobalConfigProvider for LocalFileStorage<'a> {
-    fn init_global_config(&self) -> Result<(), String> {
+    fn acquire_lock(&self) -> Result<(), String> {
+        let mut lock = self.lock.lock().map_err(|e| e.to_string())?;
+        if let Some(held_lock) = lock.as_mut() {
+            held_lock.holders += 1;
+            return Ok(());
+        }
+
+        let lock_file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.config_dir.join(LOCK_FILE_NAME))
+            .map_err(|e| e.to_string())?;
+        let start = Instant::now();
+        while lock_file.try_lock_exclusive().is_err() {
+            if start.elapsed() >= self.lock_timeout {
+                return Err("Another hibernacli process is running".to_string());
+            }
+            thread::sleep(LOCK_RETRY_DELAY);
+        }
+
+        *lock = Some(HeldLock {
+            _file: lock_file,
+            holders: 1,
+        });
+        Ok(())
+    }
+
+    // Closing the lock file releases the lock
+    fn release_lock(&self) {
+        if let Ok(mut lock) = self.lock.lock() {
+            if let Some(held_lock) = lock.as_mut() {
+                held_lock.holders -= 1;
+                if held_lock.holders == 0 {
+                    lock.take();
+                }
+            }
+        }
+    }
+
+    fn create_config_dir(&self) -> Result<(), String> {
         if !self.config_dir.exists() {
             self.file_system
                 .create_dir_all(self.config_dir.to_owned().into_path_buf())?;
         }
+        Ok(())
+    }
+}
 
-        let config_path = self.config_dir.join("config.toml");
+impl<'a> GlobalConfigProvider for LocalFileStorage<'a> {
+    fn init_global_config(&self) -> Result<(), String> {
+        self.create_config_dir()?;
+
+        let config_path = self.get_config_path();
         if !config_path.exists() {
             self.file_system
                 .write_file(config_path, &self.default_config)?;
@@ -48,18 +119,29 @@ impl<'a> GlobalConfigProvider for LocalFileStorage<'a> {
             "Config file was missing and an error occurred while creating it.".to_string()
         })?;
 
-        self.file_system
-            .read_file(self.config_dir.join("config.toml"))
+        self.file_system.read_file(self.get_config_path())
     }
 
     fn write_global_config(&self, content: &str) -> Result<(), String> {
-        if !self.config_dir.exists() {
-            self.file_system
-                .create_dir_all(self.config_dir.to_owned().into_path_buf())?;
-        }
+        self.create_config_dir()?;
+        self.acquire_lock()?;
 
-        self.file_system
-            .write_file(self.config_dir.join("config.toml"), content)
+        let result = self.file_system.write_file(self.get_config_path(), content);
+        self.release_lock();
+        result
+    }
+
+    fn lock_global_config(&self) -> Result<(), String> {
+        self.create_config_dir()?;
+        self.acquire_lock()
+    }
+
+    fn unlock_global_config(&self) {
+        self.release_lock();
+    }
+
+    fn get_config_path(&self) -> PathBuf {
+        self.config_dir.join(CONFIG_FILE_NAME)
     }
 }
 
@@ -67,7 +149,9 @@ pub trait PathProvider {
     fn get_config_dir(&self, project_name: &str) -> Option<Box<Path>>;
 }
 
+#[cfg(not(feature = "xdg-strict"))]
 pub struct StandardPathProvider;
+#[cfg(not(feature = "xdg-strict"))]
 impl PathProvider for StandardPathProvider {
     fn get_config_dir(&self, project_name: &str) -> Option<Box<Path>> {
         let project_dir = ProjectDirs::from("", "", project_name)?;
@@ -75,6 +159,33 @@ impl PathProvider for StandardPathProvider {
     }
 }
 
+// Follows the XDG base directory specification on every platform: the config is
+// stored in $XDG_CONFIG_HOME/<project>, or ~/.config/<project> by default
+#[cfg(feature = "xdg-strict")]
+pub struct XdgPathProvider;
+#[cfg(feature = "xdg-strict")]
+impl PathProvider for XdgPathProvider {
+    fn get_config_dir(&self, project_name: &str) -> Option<Box<Path>> {
+        let xdg_config_home = std::env::var_os("XDG_CONFIG_HOME").map(PathBuf::from);
+        let home_dir = directories::BaseDirs::new().map(|dirs| dirs.home_dir().to_path_buf());
+        xdg_config_dir(xdg_config_home, home_dir, project_name)
+    }
+}
+
+// Empty or relative XDG_CONFIG_HOME values are invalid, and shall be ignored
+#[cfg(feature = "xdg-strict")]
+fn xdg_config_dir(
+    xdg_config_home: Option<PathBuf>,
+    home_dir: Option<PathBuf>,
+    project_name: &str,
+) -> Option<Box<Path>> {
+    let config_home = match xdg_config_home {
+        Some(path) if path.is_absolute() => path,
+        _ => home_dir?.join(".config"),
+    };
+    Some(config_home.join(project_name).into_boxed_path())
+}
+
 pub trait FileSystem {
     fn write_file(&self, file_path: PathBuf, content: &str) -> Result<(), String>;
     fn read_file(&self, _file_path: PathBuf) -> Result<String, String>;
@@ -97,7 +208,11 @@ impl FileSystem for StandardFileSystem {
 // tests only run on linux systems
 #[cfg(test)]
 mod tests {
-    use std::path::{Path, PathBuf};
+    use fs2::FileExt;
+    use std::{
+        path::{Path, PathBuf},
+        time::Duration,
+    };
 
     use crate::{
         core::GlobalConfigProvider,
@@ -385,4 +500,117 @@ mod tests {
         // assert
         assert_eq!(res, Err("Could not write file".to_string()));
     }
+
+    #[test]
+    fn the_config_path_shall_be_the_config_file_in_the_config_dir() {
+        let mock_path_provider = TmpLinuxPathProvider::new();
+        let file_system = StandardFileSystem {};
+
+        let local_file_storage = LocalFileStorage::new(&mock_path_provider, &file_system, "");
+
+        assert_eq!(
+            local_file_storage.get_config_path(),
+            mock_path_provider
+                .get_tmp_path()
+                .join("hibernacli")
+                .join("config.toml")
+        );
+    }
+
+    #[test]
+    fn when_another_process_holds_the_lock_locking_config_shall_fail() {
+        // arrange
+        let mock_path_provider = TmpLinuxPathProvider::new();
+        let file_system = StandardFileSystem {};
+        let config_dir = mock_path_provider.get_tmp_path().join("hibernacli");
+        std::fs::create_dir_all(&config_dir).unwrap();
+        let other_process_lock =
+            std::fs::File::create(config_dir.join("config.toml.lock")).unwrap();
+        other_process_lock.try_lock_exclusive().unwrap();
+
+        // act
+        let mut local_unix_file_storage =
+            LocalFileStorage::new(&mock_path_provider, &file_system, "config");
+        local_unix_file_storage.lock_timeout = Duration::from_millis(200);
+        let res = local_unix_file_storage.lock_global_config();
+
+        // assert
+        assert_eq!(
+            res,
+            Err("Another hibernacli process is running".to_string())
+        );
+    }
+
+    #[test]
+    fn the_lock_shall_be_held_until_the_last_nested_update_of_the_config_ends() {
+        // arrange
+        let mock_path_provider = TmpLinuxPathProvider::new();
+        let file_system = StandardFileSystem {};
+        let config_dir = mock_path_provider.get_tmp_path().join("hibernacli");
+
+        // act
+        let local_unix_file_storage =
+            LocalFileStorage::new(&mock_path_provider, &file_system, "config");
+        local_unix_file_storage.lock_global_config().unwrap();
+        local_unix_file_storage.lock_global_config().unwrap();
+        let lock_file = std::fs::File::open(config_dir.join("config.toml.lock")).unwrap();
+        local_unix_file_storage
+            .write_global_config("new-content")
+            .unwrap();
+        local_unix_file_storage.unlock_global_config();
+        let locked_during_outer_update = lock_file.try_lock_exclusive().is_err();
+        local_unix_file_storage.unlock_global_config();
+        let locked_after_updates = lock_file.try_lock_exclusive().is_err();
+
+        // assert
+        assert!(locked_during_outer_update);
+        assert!(!locked_after_updates);
+    }
+
+    #[test]
+    fn reading_the_config_shall_not_lock_it() {
+        // arrange
+        let mock_path_provider = TmpLinuxPathProvider::new();
+        let file_system = StandardFileSystem {};
+        let config_dir = mock_path_provider.get_tmp_path().join("hibernacli");
+
+        // act
+        let local_unix_file_storage =
+            LocalFileStorage::new(&mock_path_provider, &file_system, "config");
+        local_unix_file_storage.read_global_config().unwrap();
+        let lock_file = std::fs::File::create(config_dir.join("config.toml.lock")).unwrap();
+
+        // assert
+        assert!(lock_file.try_lock_exclusive().is_ok());
+    }
+
+    #[cfg(feature = "xdg-strict")]
+    #[test]
+    fn with_xdg_config_home_set_the_config_shall_be_stored_under_it() {
+        // arrange
+        let mock_path_provider = TmpLinuxPathProvider::new();
+        let xdg_config_home = mock_path_provider.get_tmp_path().clone();
+        std::env::set_var("XDG_CONFIG_HOME", &xdg_config_home);
+
+        // act
+        let local_file_storage =
+            LocalFileStorage::new(&super::XdgPathProvider, &StandardFileSystem {}, "config");
+        local_file_storage.init_global_config().unwrap();
+
+        // assert
+        let config_path = xdg_config_home.join("hibernacli").join("config.toml");
+        assert_eq!(std::fs::read_to_string(config_path).unwrap(), "config");
+    }
+
+    #[cfg(feature = "xdg-strict")]
+    #[test]
+    fn without_a_valid_xdg_config_home_the_config_shall_default_to_home_config_dir() {
+        let home_dir = Some(PathBuf::from("/home/user"));
+        let expected = Path::new("/home/user/.config/hibernacli");
+
+        for xdg_config_home in [None, Some(PathBuf::from("")), Some(PathBuf::from("rel"))] {
+            let config_dir = super::xdg_config_dir(xdg_config_home, home_dir.clone(), "hibernacli");
+            assert_eq!(config_dir.as_deref(), Some(expected));
+        }
+    }
 }
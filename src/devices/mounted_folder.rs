@@ -1,30 +1,79 @@
-use flate2::write::GzEncoder;
+use flate2::{Compression, CrcReader, CrcWriter};
 use itertools::Itertools;
+use tracing::{debug, info, warn};
+use walkdir::WalkDir;
 
 use crate::{
     core::{
-        util::timestamps::Timestamp, ArchiveError, ArchiveWriter, Device, DeviceFactory,
-        DifferentialArchiveStep, Extractor, ExtractorError, Question, QuestionType, SecurityLevel,
+        read_archive_entry,
+        util::{sparse_files::SparseSegment, timestamps::Timestamp},
+        ArchiveError, ArchiveWriter, ArchiveWriterOptions, CompressionFormat, Device,
+        DeviceCapability, DeviceFactory, DifferentialArchiveStep, Extractor, ExtractorError,
+        Question, QuestionType, SecurityLevel,
+    },
+    devices::{
+        compression::{compress, decompress},
+        reflink_snapshot::{ReflinkSnapshotArchiveWriter, ReflinkSnapshotDifferentialArchiveStep},
+        sparse_tar::append_sparse_file,
+        tar_xattrs::append_xattrs_header,
+        unpack_file_in::UnpackFileIn,
     },
-    devices::unpack_file_in::UnpackFileIn,
     now,
 };
 use std::{
     collections::HashSet,
     fs::File,
     io::{self, BufRead, Cursor, Read},
-    path::{Path, PathBuf},
-    time::{Instant, SystemTime},
+    path::{Component, Path, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 struct MountedFolder {
     name: Option<String>,
     path: PathBuf,
+
+    // Store backups as directories of reflink copies instead of tar archives
+    // (only on filesystems supporting it, falls back to tar otherwise)
+    use_reflink: bool,
+
+    // Compression level of the tar.gz archives
+    compression: Compression,
+
+    // Store archives as plain tar files, for fast devices where space matters less
+    compress: bool,
+
+    // Last time the folder was found available, persisted in the configuration
+    last_seen: Option<SystemTime>,
+
+    // Directory of the folder holding the backups, for drives shared with other data
+    subpath: Option<PathBuf>,
 }
 
 impl MountedFolder {
+    // Device storing its projects under root_path/subpath instead of root_path. The
+    // factory builds it from the answers, this is for code creating devices directly
+    #[allow(dead_code)]
+    pub fn with_subpath(name: String, root_path: PathBuf, subpath: PathBuf) -> Self {
+        MountedFolder {
+            name: Some(name),
+            path: root_path,
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: Some(subpath),
+        }
+    }
+
+    fn get_storage_path(&self) -> PathBuf {
+        match &self.subpath {
+            Some(subpath) => Path::join(&self.path, subpath),
+            None => self.path.clone(),
+        }
+    }
+
     fn get_project_path(&self, project_name: &str) -> PathBuf {
-        Path::join(&self.path, &project_name)
+        Path::join(&self.get_storage_path(), project_name)
     }
 }
 
@@ -56,11 +105,48 @@ impl Device for MountedFolder {
         None
     }
 
+    fn get_last_seen(&self) -> Option<SystemTime> {
+        self.last_seen
+    }
+
+    fn set_last_seen(&mut self, last_seen: SystemTime) {
+        self.last_seen = Some(last_seen);
+    }
+
+    fn supports_capability(&self, capability: DeviceCapability) -> bool {
+        match capability {
+            DeviceCapability::Reflink => self.use_reflink,
+            DeviceCapability::SparseFiles | DeviceCapability::PartialRestore => true,
+            DeviceCapability::Encryption | DeviceCapability::Streaming => false,
+        }
+    }
+
     fn to_toml_table(&self) -> toml::value::Table {
         let mut table = toml::value::Table::new();
         table.insert("type".to_string(), self.get_device_type_name().into());
         table.insert("path".to_string(), self.path.display().to_string().into());
         table.insert("name".to_string(), self.get_name().into());
+        if self.use_reflink {
+            table.insert("use_reflink".to_string(), true.into());
+        }
+        if self.compression != Compression::default() {
+            table.insert(
+                "compression_level".to_string(),
+                get_compression_level_name(self.compression).into(),
+            );
+        }
+        if !self.compress {
+            table.insert("compress".to_string(), false.into());
+        }
+        if let Some(last_seen) = self.last_seen.and_then(|t| t.ms_since_epoch().ok()) {
+            table.insert("last_seen".to_string(), (last_seen as i64).into());
+        }
+        if let Some(subpath) = &self.subpath {
+            table.insert(
+                "base_path".to_string(),
+                subpath.display().to_string().into(),
+            );
+        }
         table
     }
 
@@ -76,35 +162,448 @@ impl Device for MountedFolder {
         }
     }
 
+    fn write_backup_index(&self, project_name: &str, index: &[u8]) -> Result<(), String> {
+        let index_path = Path::join(&self.get_project_path(project_name), "current.index");
+        std::fs::write(index_path, index).map_err(|e| e.to_string())
+    }
+
     fn test_availability(&self) -> Result<(), String> {
         self.path.read_dir().map(|_| ()).map_err(|e| e.to_string())
     }
 
-    fn get_archive_writer(&self, project_name: &str) -> Box<dyn ArchiveWriter> {
+    // Reading the folder succeeds on read-only mounts, only a write tells them apart
+    fn test_write_permission(&self) -> Result<(), String> {
+        let test_file_path = Path::join(&self.path, WRITE_TEST_FILE_NAME);
+        File::create(&test_file_path).map_err(|e| e.to_string())?;
+        std::fs::remove_file(&test_file_path).map_err(|e| e.to_string())
+    }
+
+    fn get_free_space(&self) -> Option<u64> {
+        fs2::available_space(&self.path).ok()
+    }
+
+    fn list_archives(&self, project_name: &str) -> Result<Vec<String>, String> {
+        let entries = match self.get_project_path(project_name).read_dir() {
+            Ok(entries) => entries,
+            Err(e) => match e.kind() {
+                std::io::ErrorKind::NotFound => return Ok(vec![]),
+                _ => return Err(e.to_string()),
+            },
+        };
+
+        let archive_names = entries
+            .map(|entry| entry.map(|entry| entry.file_name()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .filter_map(|name| name.to_str().map(|s| s.to_string()))
+            .filter(|name| is_archive_file_name(name))
+            .sorted_by_key(|name| get_archive_timestamp(name))
+            .collect();
+
+        Ok(archive_names)
+    }
+
+    fn list_backed_up_projects(&self) -> Result<Vec<String>, String> {
+        // The directory of the backups is only created by the first backup
+        let storage_path = self.get_storage_path();
+        if self.subpath.is_some() && !storage_path.is_dir() {
+            self.test_availability()?;
+            return Ok(Vec::new());
+        }
+        let entries = storage_path.read_dir().map_err(|e| e.to_string())?;
+
+        // Other directories may be stored on the folder, only keep the ones with backups
+        let mut project_names = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(|e| e.to_string())?;
+            if !entry.file_type().map_err(|e| e.to_string())?.is_dir() {
+                continue;
+            }
+            let Some(name) = entry.file_name().to_str().map(|s| s.to_string()) else {
+                continue;
+            };
+            if Path::join(&entry.path(), "current.index").exists()
+                || !self.list_archives(&name)?.is_empty()
+            {
+                project_names.push(name);
+            }
+        }
+        project_names.sort();
+        Ok(project_names)
+    }
+
+    fn migrate_archives(&self, project_name: &str) -> Result<usize, String> {
+        let project_path = self.get_project_path(project_name);
+        let legacy_archives = self
+            .list_archives(project_name)?
+            .into_iter()
+            .filter(|name| is_legacy_archive_file_name(name))
+            .collect::<Vec<_>>();
+
+        for archive_name in &legacy_archives {
+            let archive_path = Path::join(&project_path, archive_name);
+            let checksum = compute_checksum(&archive_path).map_err(|e| e.to_string())?;
+            let timestamp = archive_name.trim_end_matches(ARCHIVE_EXTENSION);
+            let new_archive_path = Path::join(
+                &project_path,
+                get_archive_file_name(timestamp, checksum, ARCHIVE_EXTENSION),
+            );
+            std::fs::rename(&archive_path, new_archive_path).map_err(|e| e.to_string())?;
+        }
+
+        Ok(legacy_archives.len())
+    }
+
+    fn get_archive_time(&self, archive_name: &str) -> Option<SystemTime> {
+        get_archive_timestamp(archive_name).map(|ms| UNIX_EPOCH + Duration::from_millis(ms as u64))
+    }
+
+    fn get_last_backup_time(&self, project_name: &str) -> Result<Option<SystemTime>, String> {
+        let last_backup_time = self
+            .list_archives(project_name)?
+            .pop()
+            .and_then(|name| self.get_archive_time(&name));
+
+        Ok(last_backup_time)
+    }
+
+    fn get_archive_reader(
+        &self,
+        project_name: &str,
+        archive_name: &str,
+    ) -> Result<Box<dyn Read>, String> {
+        if archive_name.ends_with(SNAPSHOT_EXTENSION) || !is_archive_file_name(archive_name) {
+            return Err(format!("Not a tar or tar.gz archive: {}", archive_name));
+        }
+
+        let archive_path = Path::join(&self.get_project_path(project_name), archive_name);
+        open_tar_archive(&archive_path).map_err(|e| e.to_string())
+    }
+
+    fn read_archive_file(
+        &self,
+        project_name: &str,
+        archive_name: &str,
+        file_name: &str,
+    ) -> Result<Option<Vec<u8>>, String> {
+        let archive_path = Path::join(&self.get_project_path(project_name), archive_name);
+        if archive_name.ends_with(SNAPSHOT_EXTENSION) {
+            return match std::fs::read(Path::join(&archive_path, file_name)) {
+                Ok(content) => Ok(Some(content)),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e.to_string()),
+            };
+        }
+        if !archive_name.ends_with(UNCOMPRESSED_ARCHIVE_EXTENSION) {
+            let reader = self.get_archive_reader(project_name, archive_name)?;
+            return read_archive_entry(reader, Path::new(file_name)).map_err(|e| e.to_string());
+        }
+
+        // Uncompressed archives are walked through without reading the archived files
+        let read_entry = || -> io::Result<Option<Vec<u8>>> {
+            let mut archive = tar::Archive::new(File::open(&archive_path)?);
+            for entry in archive.entries_with_seek()? {
+                let mut entry = entry?;
+                if entry.path()? == Path::new(file_name) {
+                    let mut content = Vec::new();
+                    entry.read_to_end(&mut content)?;
+                    return Ok(Some(content));
+                }
+            }
+            Ok(None)
+        };
+        read_entry().map_err(|e| e.to_string())
+    }
+
+    fn create_project_directory(&self, project_name: &str) -> Result<(), String> {
+        // Never create the device folder itself, it may be an unmounted mount point
+        self.test_availability()?;
+        std::fs::create_dir_all(self.get_project_path(project_name)).map_err(|e| e.to_string())
+    }
+
+    fn rename_project_directory(&self, project_name: &str, new_name: &str) -> Result<(), String> {
+        let project_path = self.get_project_path(project_name);
+        let new_project_path = self.get_project_path(new_name);
+        if !project_path.is_dir() {
+            return Ok(());
+        }
+        if new_project_path.exists() {
+            return Err(format!(
+                "Cannot rename {:?}, {:?} already exists",
+                project_path, new_project_path
+            ));
+        }
+        std::fs::rename(project_path, new_project_path).map_err(|e| e.to_string())
+    }
+
+    fn delete_project_data(&self, project_name: &str) -> Result<(), String> {
+        self.test_availability()?;
+        let project_path = self.get_project_path(project_name);
+        if !project_path.exists() {
+            return Ok(());
+        }
+        info!("Deleting backups of {} in {:?}", project_name, project_path);
+        std::fs::remove_dir_all(project_path).map_err(|e| e.to_string())
+    }
+
+    fn prune_archives(&self, project_name: &str, max_archives: usize) -> Result<usize, String> {
+        self.test_availability()?;
+        let archives = self.list_archives(project_name)?;
+        if archives.len() <= max_archives {
+            return Ok(0);
+        }
+
+        // Restoring reads each file from the newest archive holding it, so an archive is
+        // useless once all its files are archived again, or deleted, by newer archives
+        let project_path = self.get_project_path(project_name);
+        let mut superseded_paths = HashSet::new();
+        let mut prunable_archives = Vec::new();
+        for (i, archive_name) in archives.iter().enumerate().rev() {
+            let archive_path = Path::join(&project_path, archive_name);
+            let (files, deleted_files) = read_archive_contents(&archive_path)
+                .map_err(|e| format!("Cannot read archive {}: {}", archive_name, e))?;
+            let is_latest = i + 1 == archives.len();
+            if !is_latest && files.is_subset(&superseded_paths) {
+                prunable_archives.push(archive_path);
+            }
+            superseded_paths.extend(files);
+            superseded_paths.extend(deleted_files);
+        }
+
+        let excess = archives.len() - max_archives;
+        let mut pruned = 0;
+        for archive_path in prunable_archives.iter().rev().take(excess) {
+            info!("Pruning archive {:?}", archive_path);
+            let result = if archive_path.is_dir() {
+                std::fs::remove_dir_all(archive_path)
+            } else {
+                std::fs::remove_file(archive_path)
+            };
+            result.map_err(|e| format!("Cannot delete archive {:?}: {}", archive_path, e))?;
+            pruned += 1;
+        }
+        if pruned < excess {
+            warn!(
+                "{} archives of {} are kept beyond the limit of {}, as they hold the only copy of some files",
+                excess - pruned,
+                project_name,
+                max_archives
+            );
+        }
+        Ok(pruned)
+    }
+
+    fn get_archive_writer(
+        &self,
+        project_name: &str,
+        options: &ArchiveWriterOptions,
+    ) -> Box<dyn ArchiveWriter> {
+        let compress = options.compress && self.compress;
+        if let Err(e) = self.create_project_directory(project_name) {
+            warn!("Could not create the directory of {}: {}", project_name, e);
+        }
         let now = now!().ms_since_epoch().unwrap();
-        let project_dir = Path::join(&self.path, &project_name);
+        let project_dir = self.get_project_path(project_name);
         let archive_path = Path::join(&project_dir, format!("{}.tar", now));
 
-        Box::new(MountedFolderArchiveWriter::new(
-            self.path.clone(),
-            project_dir,
-            archive_path,
-        ))
+        if self.use_reflink {
+            let snapshot_path = Path::join(&project_dir, format!("{}{}", now, SNAPSHOT_EXTENSION));
+            return Box::new(
+                ReflinkSnapshotArchiveWriter::new(
+                    self.path.clone(),
+                    project_dir,
+                    snapshot_path,
+                    archive_path,
+                    self.compression,
+                    compress,
+                )
+                .with_compression_format(options.compression_format),
+            );
+        }
+
+        Box::new(
+            MountedFolderArchiveWriter::new(
+                self.path.clone(),
+                project_dir,
+                archive_path,
+                self.compression,
+                compress,
+            )
+            .with_compression_format(options.compression_format)
+            .with_keep_partial(options.keep_partial),
+        )
     }
 
     fn get_extractor(&self, project_name: &str) -> Box<dyn Extractor> {
-        let project_dir = Path::join(&self.path, &project_name);
+        let project_dir = self.get_project_path(project_name);
 
         Box::new(MountedFolderExtractor::new(self.path.clone(), project_dir))
     }
 }
 
+const ARCHIVE_EXTENSION: &str = ".tar.gz";
+const UNCOMPRESSED_ARCHIVE_EXTENSION: &str = ".tar";
+const SNAPSHOT_EXTENSION: &str = ".snapshot";
+// User defined labels of a backup, one per line, stored along the index
+pub const TAGS_FILE_NAME: &str = ".tags";
+// Created then deleted right away to check that the folder is writable
+const WRITE_TEST_FILE_NAME: &str = ".hibernacli-write-test";
+
+// Archives are named after the time of the backup in ms since epoch, either as
+// a compressed tar file followed by the CRC32 of its content (<timestamp>-<crc32>.tar.gz,
+// or .tar.bz2, .tar.xz, .tar.zst), an uncompressed tar file (<timestamp>-<crc32>.tar),
+// or as a reflink snapshot directory (<timestamp>.snapshot). Archives written before
+// checksums were added are tar.gz files with no suffix, see migrate_archives.
+fn get_archive_timestamp(file_name: &str) -> Option<u128> {
+    let timestamp = if let Some(format) = get_compression_format(file_name) {
+        let stem = file_name.strip_suffix(format.archive_extension())?;
+        match stem.split_once('-') {
+            Some((timestamp, checksum)) if is_checksum(checksum) => timestamp,
+            None if format == CompressionFormat::Gz => stem,
+            _ => return None,
+        }
+    } else if let Some(stem) = file_name.strip_suffix(UNCOMPRESSED_ARCHIVE_EXTENSION) {
+        // The checksum is mandatory, so that the tar file being written is not listed
+        match stem.split_once('-') {
+            Some((timestamp, checksum)) if is_checksum(checksum) => timestamp,
+            _ => return None,
+        }
+    } else {
+        file_name.strip_suffix(SNAPSHOT_EXTENSION)?
+    };
+    if !timestamp.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    timestamp.parse().ok()
+}
+
+// Paths of the files stored in an archive, and of the files it records as deleted
+fn read_archive_contents(archive_path: &Path) -> io::Result<(HashSet<PathBuf>, HashSet<PathBuf>)> {
+    let deleted_files_path = Path::new(".deleted-files");
+    if archive_path.is_dir() {
+        let files_path = Path::join(archive_path, ".files");
+        let files = WalkDir::new(&files_path)
+            .min_depth(1)
+            .into_iter()
+            .map(|entry| {
+                let entry = entry?;
+                let path = entry.path().strip_prefix(&files_path);
+                path.map(Path::to_path_buf).map_err(io::Error::other)
+            })
+            .collect::<io::Result<HashSet<_>>>()?;
+        let deleted_files = std::fs::read_to_string(Path::join(archive_path, deleted_files_path))
+            .unwrap_or_default();
+        return Ok((files, deleted_files.lines().map(PathBuf::from).collect()));
+    }
+
+    let reader = open_tar_archive(archive_path)?;
+    let mut files = HashSet::new();
+    let mut deleted_files = HashSet::new();
+    for entry in tar::Archive::new(reader).entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.to_path_buf();
+        if let Ok(path) = path.strip_prefix(".files") {
+            files.insert(path.to_path_buf());
+        } else if path == deleted_files_path {
+            let mut content = String::new();
+            entry.read_to_string(&mut content)?;
+            deleted_files.extend(content.lines().map(PathBuf::from));
+        }
+    }
+    Ok((files, deleted_files))
+}
+
+fn get_compression_format(file_name: &str) -> Option<CompressionFormat> {
+    CompressionFormat::ALL
+        .into_iter()
+        .find(|format| file_name.ends_with(format.archive_extension()))
+}
+
+// Open a tar archive, decompressing it according to its extension
+fn open_tar_archive(archive_path: &Path) -> io::Result<Box<dyn Read>> {
+    let file = File::open(archive_path)?;
+    match get_compression_format(&archive_path.to_string_lossy()) {
+        Some(format) => decompress(file, format),
+        None => Ok(Box::new(file)),
+    }
+}
+
+fn is_checksum(checksum: &str) -> bool {
+    checksum.len() == 8 && checksum.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn get_archive_file_name(timestamp: &str, checksum: u32, extension: &str) -> String {
+    format!("{}-{:08x}{}", timestamp, checksum, extension)
+}
+
+fn is_legacy_archive_file_name(file_name: &str) -> bool {
+    file_name
+        .strip_suffix(ARCHIVE_EXTENSION)
+        .is_some_and(|timestamp| {
+            !timestamp.is_empty() && timestamp.chars().all(|c| c.is_ascii_digit())
+        })
+}
+
+fn compute_checksum(path: &Path) -> io::Result<u32> {
+    let mut reader = CrcReader::new(File::open(path)?);
+    io::copy(&mut reader, &mut io::sink())?;
+    Ok(reader.crc().sum())
+}
+
+fn is_archive_file_name(file_name: &str) -> bool {
+    get_archive_timestamp(file_name).is_some()
+}
+
+const COMPRESSION_LEVELS: [&str; 3] = ["default", "fast", "best"];
+
+fn parse_compression_level(level: &str) -> Result<Compression, String> {
+    match level {
+        "default" => Ok(Compression::default()),
+        "fast" => Ok(Compression::fast()),
+        "best" => Ok(Compression::best()),
+        _ => Err(format!("Invalid compression level: {}", level)),
+    }
+}
+
+// The backups shall stay inside the device folder, whatever the subpath
+fn parse_subpath(subpath: &str) -> Result<PathBuf, String> {
+    let subpath = PathBuf::from(subpath);
+    let stays_inside = subpath
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if !stays_inside {
+        return Err(format!(
+            "Invalid 'base_path' {}, it shall be relative and stay inside the device folder",
+            subpath.display()
+        ));
+    }
+    Ok(subpath)
+}
+
+fn get_compression_level_name(compression: Compression) -> &'static str {
+    if compression == Compression::fast() {
+        "fast"
+    } else if compression == Compression::best() {
+        "best"
+    } else {
+        "default"
+    }
+}
+
 pub struct MountedFolderArchiveWriter {
     path: PathBuf,
     project_dir: PathBuf,
     archive_path: PathBuf,
+    compression: Compression,
+    compress: bool,
+    compression_format: CompressionFormat,
     tar_builder: Option<tar::Builder<std::fs::File>>,
     finalized: bool,
+    bytes_written: u64,
+
+    // Keep the archive of a failed backup as <archive>.partial instead of deleting it
+    keep_partial: bool,
 }
 
 impl MountedFolderArchiveWriter {
@@ -112,13 +611,82 @@ impl MountedFolderArchiveWriter {
         path: PathBuf,
         project_dir: PathBuf,
         archive_path: PathBuf,
+        compression: Compression,
+        compress: bool,
     ) -> MountedFolderArchiveWriter {
         MountedFolderArchiveWriter {
             path,
             project_dir,
             archive_path,
+            compression,
+            compress,
+            compression_format: CompressionFormat::default(),
             tar_builder: None,
             finalized: false,
+            bytes_written: 0,
+            keep_partial: false,
+        }
+    }
+
+    pub fn with_compression_format(mut self, compression_format: CompressionFormat) -> Self {
+        self.compression_format = compression_format;
+        self
+    }
+
+    pub fn with_keep_partial(mut self, keep_partial: bool) -> Self {
+        self.keep_partial = keep_partial;
+        self
+    }
+
+    fn get_partial_archive_path(&self) -> PathBuf {
+        PathBuf::from(format!("{}.partial", self.archive_path.display()))
+    }
+
+    // Name the kept partial archive in the error, so that it can be found
+    fn with_partial_archive_path<T>(
+        &mut self,
+        result: Result<T, ArchiveError>,
+    ) -> Result<T, ArchiveError> {
+        result.map_err(|e| match self.keep_partial_archive() {
+            Some(partial_path) => ArchiveError {
+                message: format!(
+                    "{} (partial archive kept at {})",
+                    e.message,
+                    partial_path.display()
+                ),
+            },
+            None => e,
+        })
+    }
+
+    // Move the incomplete archive aside, or delete it, returning where it was kept
+    fn keep_partial_archive(&mut self) -> Option<PathBuf> {
+        self.tar_builder = None;
+        if self.finalized || !self.archive_path.exists() {
+            return None;
+        }
+        let gz_path = PathBuf::from(format!("{}.gz", self.archive_path.display()));
+        let _ = std::fs::remove_file(gz_path);
+        if !self.keep_partial {
+            if let Err(e) = std::fs::remove_file(&self.archive_path) {
+                warn!(
+                    "Cannot delete partial archive {:?}: {}",
+                    self.archive_path, e
+                );
+            }
+            return None;
+        }
+
+        let partial_path = self.get_partial_archive_path();
+        match std::fs::rename(&self.archive_path, &partial_path) {
+            Ok(()) => {
+                warn!("Partial archive kept at {:?}", partial_path);
+                Some(partial_path)
+            }
+            Err(e) => {
+                warn!("Cannot keep partial archive {:?}: {}", self.archive_path, e);
+                None
+            }
         }
     }
 
@@ -145,9 +713,7 @@ impl MountedFolderArchiveWriter {
 
         // create dir if missing
         if !self.project_dir.exists() {
-            std::fs::create_dir_all(&self.project_dir).map_err(|_| {
-                "Project directory is missing on secondary device and failed to be created"
-            })?;
+            std::fs::create_dir_all(&self.project_dir)?;
         }
 
         // Verify that the archive file does not exist
@@ -189,11 +755,49 @@ impl ArchiveWriter for MountedFolderArchiveWriter {
         path: &PathBuf,
         _ctime: u128,
         _mtime: u128,
-        _size: u64,
+        size: u64,
     ) -> Result<(), ArchiveError> {
         self.initialize()?
             .append_file(Path::join(Path::new(".files"), path), file)?;
-        println!("Adding file {:?} to {:?} secondary device", path, self.path);
+        self.bytes_written += size;
+        debug!("Adding file {:?} to {:?} secondary device", path, self.path);
+        Ok(())
+    }
+
+    fn add_sparse_file(
+        &mut self,
+        file: &mut File,
+        path: &PathBuf,
+        ctime: u128,
+        mtime: u128,
+        size: u64,
+        data_segments: &[SparseSegment],
+    ) -> Result<(), ArchiveError> {
+        let archive_path = Path::join(Path::new(".files"), path);
+        if !append_sparse_file(self.initialize()?, file, &archive_path, size, data_segments)? {
+            return self.add_file(file, path, ctime, mtime, size);
+        }
+        self.bytes_written += size;
+        debug!(
+            "Adding sparse file {:?} to {:?} secondary device",
+            path, self.path
+        );
+        Ok(())
+    }
+
+    fn add_extended_attributes(
+        &mut self,
+        path: &PathBuf,
+        xattrs: Vec<(String, Vec<u8>)>,
+    ) -> Result<(), ArchiveError> {
+        let archive_path = Path::join(Path::new(".files"), path);
+        append_xattrs_header(self.initialize()?, &archive_path, &xattrs)?;
+        debug!(
+            "Adding {} extended attributes of {:?} to {:?} secondary device",
+            xattrs.len(),
+            path,
+            self.path
+        );
         Ok(())
     }
 
@@ -206,7 +810,7 @@ impl ArchiveWriter for MountedFolderArchiveWriter {
     ) -> Result<(), ArchiveError> {
         self.initialize()?
             .append_dir(Path::join(Path::new(".files"), path), src_path)?;
-        println!(
+        debug!(
             "Adding directory {:?} to {:?} secondary device",
             path, self.path
         );
@@ -220,19 +824,36 @@ impl ArchiveWriter for MountedFolderArchiveWriter {
         _mtime: u128,
         _target: &PathBuf,
     ) -> Result<(), ArchiveError> {
-        println!(
+        debug!(
             "Adding symlink {:?} to {:?} secondary device",
             path, self.path
         );
         Ok(())
     }
 
+    fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
     fn finalize(
         &mut self,
         deleted_files: &Vec<PathBuf>,
         new_index: &Vec<u8>,
+        tags: &[String],
+    ) -> Result<(), ArchiveError> {
+        let result = self.finalize_archive(deleted_files, new_index, tags);
+        self.with_partial_archive_path(result)
+    }
+}
+
+impl MountedFolderArchiveWriter {
+    fn finalize_archive(
+        &mut self,
+        deleted_files: &[PathBuf],
+        new_index: &[u8],
+        tags: &[String],
     ) -> Result<(), ArchiveError> {
-        println!("Finalizing archive to {:?}", self.archive_path);
+        info!("Finalizing archive to {:?}", self.archive_path);
 
         // Create a file with the list of deleted files
         let deleted_files_data = deleted_files
@@ -246,22 +867,57 @@ impl ArchiveWriter for MountedFolderArchiveWriter {
         // Add a copy of the new index in the archive
         self.add_file_from_bytes(&new_index, Path::new(".index"))?;
 
+        if !tags.is_empty() {
+            self.add_file_from_bytes(tags.join("\n").as_bytes(), Path::new(TAGS_FILE_NAME))?;
+        }
+
         // Save the index for quick access to the latest version
         let current_index_path = Path::join(&self.project_dir, "current.index");
         std::fs::write(&current_index_path, new_index)?;
 
         // End the archive
         self.try_get_tar_builder()?.finish()?;
+        let timestamp = self
+            .archive_path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or_else(|| ArchiveError::from("Invalid archive path"))?;
+
+        // Keep the archive as is, only adding its checksum to its name
+        if !self.compress {
+            let checksum = compute_checksum(&self.archive_path)?;
+            let archive_name =
+                get_archive_file_name(timestamp, checksum, UNCOMPRESSED_ARCHIVE_EXTENSION);
+            std::fs::rename(
+                &self.archive_path,
+                Path::join(&self.project_dir, archive_name),
+            )?;
+            self.finalized = true;
+            return Ok(());
+        }
 
-        // Open the archive and a gzip file to compress it (just add .gz to the file name)
+        // Open the archive and a file to compress it (e.g. add .gz to the file name)
         let tar_file = File::open(&self.archive_path)?;
-        let gz_file = File::create(&format!("{}.gz", self.archive_path.display()))?;
+        let extension = self.compression_format.archive_extension();
+        let compressed_path = Path::join(&self.project_dir, format!("{}{}", timestamp, extension));
+        let mut compressed_file = CrcWriter::new(File::create(&compressed_path)?);
 
-        // Compress the archive
+        // Compress the archive, computing the checksum of the compressed data
         let tar_file_size = tar_file.metadata()?.len();
-        let mut encoder = GzEncoder::new(gz_file, flate2::Compression::default());
-        io::copy(&mut tar_file.take(tar_file_size), &mut encoder)?;
-        encoder.finish()?;
+        compress(
+            tar_file.take(tar_file_size),
+            &mut compressed_file,
+            self.compression_format,
+            self.compression,
+        )?;
+        let checksum = compressed_file.crc().sum();
+
+        // Add the checksum to the archive name
+        let archive_name = get_archive_file_name(timestamp, checksum, extension);
+        std::fs::rename(
+            &compressed_path,
+            Path::join(&self.project_dir, archive_name),
+        )?;
 
         // Remove the uncompressed archive
         std::fs::remove_file(&self.archive_path)?;
@@ -270,6 +926,13 @@ impl ArchiveWriter for MountedFolderArchiveWriter {
     }
 }
 
+// Backups failing before finalization leave an incomplete archive behind
+impl Drop for MountedFolderArchiveWriter {
+    fn drop(&mut self) {
+        self.keep_partial_archive();
+    }
+}
+
 pub struct MountedFolderExtractor {
     archive_paths: Vec<PathBuf>,
     index_from_start: usize,
@@ -285,11 +948,13 @@ impl MountedFolderExtractor {
             .filter(|path| {
                 path.file_name()
                     .and_then(|s| s.to_str())
-                    .map_or(false, |s| {
-                        s.ends_with(".tar.gz") && s[..s.len() - 7].chars().all(char::is_numeric)
-                    })
+                    .map_or(false, is_archive_file_name)
+            })
+            .sorted_by_key(|path| {
+                path.file_name()
+                    .and_then(|s| s.to_str())
+                    .and_then(get_archive_timestamp)
             })
-            .sorted()
             .collect();
 
         let index_from_end = archive_paths.len();
@@ -299,6 +964,18 @@ impl MountedFolderExtractor {
             index_from_end,
         }
     }
+
+    // Oldest archive not yet iterated over, without advancing the iterator. Operations
+    // only get extractors as `dyn Extractor`, so backup verification cannot call it
+    #[allow(dead_code)]
+    pub fn peek_first(&self) -> Option<&Path> {
+        if self.index_from_start >= self.index_from_end {
+            return None;
+        }
+        self.archive_paths
+            .get(self.index_from_start)
+            .map(PathBuf::as_path)
+    }
 }
 
 impl Iterator for MountedFolderExtractor {
@@ -312,9 +989,7 @@ impl Iterator for MountedFolderExtractor {
         let archive_path = &self.archive_paths[self.index_from_start];
         self.index_from_start += 1;
 
-        Some(Box::new(MountedFolderDifferentialArchiveStep {
-            archive_path: archive_path.clone(),
-        }))
+        Some(Self::get_step(archive_path))
     }
 }
 
@@ -327,9 +1002,21 @@ impl DoubleEndedIterator for MountedFolderExtractor {
         self.index_from_end -= 1;
         let archive_path = &self.archive_paths[self.index_from_end];
 
-        Some(Box::new(MountedFolderDifferentialArchiveStep {
-            archive_path: archive_path.clone(),
-        }))
+        Some(Self::get_step(archive_path))
+    }
+}
+
+impl MountedFolderExtractor {
+    fn get_step(archive_path: &Path) -> Box<dyn DifferentialArchiveStep> {
+        if archive_path.to_string_lossy().ends_with(SNAPSHOT_EXTENSION) {
+            return Box::new(ReflinkSnapshotDifferentialArchiveStep::new(
+                archive_path.to_path_buf(),
+            ));
+        }
+
+        Box::new(MountedFolderDifferentialArchiveStep {
+            archive_path: archive_path.to_path_buf(),
+        })
     }
 }
 
@@ -339,48 +1026,93 @@ pub struct MountedFolderDifferentialArchiveStep {
     archive_path: PathBuf,
 }
 
-impl MountedFolderDifferentialArchiveStep {}
+impl MountedFolderDifferentialArchiveStep {
+    fn open_archive(&self) -> io::Result<Box<dyn Read>> {
+        open_tar_archive(&self.archive_path)
+    }
+}
 
 impl DifferentialArchiveStep for MountedFolderDifferentialArchiveStep {
     fn get_step_name(&self) -> &str {
         &self.archive_path.to_str().unwrap()
     }
 
+    fn get_tags(&self) -> Vec<String> {
+        self.open_archive()
+            .and_then(read_archive_tags)
+            .unwrap_or_else(|e| {
+                warn!("Cannot read the tags of {:?}: {}", self.archive_path, e);
+                vec![]
+            })
+    }
+
     fn extract_to(
         &self,
         to: &PathBuf,
         paths_to_extract: &HashSet<PathBuf>,
     ) -> Result<HashSet<PathBuf>, ExtractorError> {
-        println!("Walking through archive {:?}", self.archive_path);
-        let file = File::open(&self.archive_path)?;
-        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
-        let mut extracted_paths = HashSet::new();
-
-        for entry in archive.entries()? {
-            let mut entry = entry?;
-            let path = entry.path()?;
-
-            // If path starts with ".files", remove it from path
-            if path.starts_with(".files") {
-                let path = path.strip_prefix(".files")?;
-                let path = path.to_path_buf();
-                if paths_to_extract.contains(&path) {
-                    entry.unpack_file_in(to)?;
-                    extracted_paths.insert(path.clone());
-                    println!("Extracted {:?}", path);
-                } else {
-                    println!("Skipping {:?}", path);
-                }
-            }
+        info!("Walking through archive {:?}", self.archive_path);
+        extract_archive_files(self.open_archive()?, to, paths_to_extract)
+    }
+}
+
+// Read the tags file of a tar archive, the whole archive is walked through to find it
+pub fn read_archive_tags(reader: impl Read) -> io::Result<Vec<String>> {
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if entry.path()? == Path::new(TAGS_FILE_NAME) {
+            let mut tags = String::new();
+            entry.read_to_string(&mut tags)?;
+            return Ok(parse_tags(&tags));
         }
+    }
+    Ok(vec![])
+}
+
+pub fn parse_tags(tags: &str) -> Vec<String> {
+    tags.lines()
+        .filter(|tag| !tag.is_empty())
+        .map(|tag| tag.to_string())
+        .collect()
+}
 
-        Ok(extracted_paths)
+// Extract the requested files of a tar archive, stored under .files, and return
+// the paths that were found
+pub fn extract_archive_files(
+    reader: impl Read,
+    to: &Path,
+    paths_to_extract: &HashSet<PathBuf>,
+) -> Result<HashSet<PathBuf>, ExtractorError> {
+    let mut archive = tar::Archive::new(reader);
+    let mut extracted_paths = HashSet::new();
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?;
+
+        // If path starts with ".files", remove it from path
+        if path.starts_with(".files") {
+            let path = path.strip_prefix(".files")?;
+            let path = path.to_path_buf();
+            if paths_to_extract.contains(&path) {
+                entry.unpack_file_in(to)?;
+                extracted_paths.insert(path.clone());
+                debug!("Extracted {:?}", path);
+            } else {
+                debug!("Skipping {:?}", path);
+            }
+        }
     }
+
+    Ok(extracted_paths)
 }
 
 pub struct MountedFolderFactory {
     path_question: Question,
     name_question: Question,
+    compression_question: Question,
+    base_path_question: Question,
     step: u8,
 }
 
@@ -395,20 +1127,92 @@ impl MountedFolderFactory {
                 "How would you name this device?".to_string(),
                 QuestionType::String,
             ),
+            compression_question: Question::new(
+                "Which compression level should be used for the archives?".to_string(),
+                QuestionType::Select(COMPRESSION_LEVELS.iter().map(|l| l.to_string()).collect()),
+            ),
+            base_path_question: Question::new(
+                "Under which folder of the device should backups be stored? (empty for its root)"
+                    .to_string(),
+                QuestionType::String,
+            ),
             step: 0,
         }
     }
 
+    // Non-interactive creation, for CI pipelines: reads <PREFIX>_PATH and the
+    // optional <PREFIX>_NAME environment variables
+    pub fn build_from_env(prefix: &str) -> Result<Box<dyn Device>, String> {
+        let path_var = format!("{}_PATH", prefix);
+        let path = std::env::var(&path_var)
+            .map_err(|_| format!("Missing environment variable {}", path_var))?;
+        if !Path::new(&path).is_absolute() {
+            return Err(format!("{} shall be an absolute path: {}", path_var, path));
+        }
+        let name = std::env::var(format!("{}_NAME", prefix))
+            .ok()
+            .filter(|name| !name.is_empty());
+
+        Ok(Box::new(MountedFolder {
+            name,
+            path: PathBuf::from(path),
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
+        }))
+    }
+
     fn get_current_question(&self) -> &Question {
         match self.step {
             0 => &self.path_question,
             1 => &self.name_question,
+            2 => &self.compression_question,
+            3 => &self.base_path_question,
             _ => panic!("No more questions"),
         }
     }
 }
 
+// An empty base path stores the backups in the device folder itself
+fn parse_optional_subpath(subpath: &str) -> Result<Option<PathBuf>, String> {
+    if subpath.is_empty() {
+        return Ok(None);
+    }
+    parse_subpath(subpath).map(Some)
+}
+
+const MOUNTED_FOLDER_DESCRIPTION: &str = "\
+A folder of the local filesystem, typically the mount point of a USB key or an external drive.
+The drive must be mounted at the same path before each backup or restore, otherwise the device
+is reported as unavailable.
+Backups are stored as compressed tar archives, or as reflink snapshots on filesystems supporting them.
+The folder is considered as local storage: its security level is Local.";
+
+// Folder under which removable drives are usually mounted on the current platform
+fn default_mount_point_parent() -> Option<String> {
+    if cfg!(target_os = "macos") {
+        Some("/Volumes/".to_string())
+    } else if cfg!(target_os = "linux") {
+        match std::env::var("USER") {
+            Ok(user) if !user.is_empty() => Some(format!("/media/{}/", user)),
+            _ => Some("/mnt/".to_string()),
+        }
+    } else {
+        None
+    }
+}
+
 impl DeviceFactory for MountedFolderFactory {
+    fn get_description(&self) -> &str {
+        MOUNTED_FOLDER_DESCRIPTION
+    }
+
+    fn default_answers(&self) -> Vec<Option<String>> {
+        vec![default_mount_point_parent(), None, None, None]
+    }
+
     fn get_question_statement(&self) -> &str {
         self.get_current_question().get_statement()
     }
@@ -417,10 +1221,24 @@ impl DeviceFactory for MountedFolderFactory {
         self.get_current_question().get_question_type()
     }
 
+    fn validate_answer(&self, answer: &str) -> Result<(), String> {
+        if !self.has_next() {
+            return Err("No more questions".to_string());
+        }
+        self.get_current_question().validate_answer(answer)?;
+        if self.step == 3 {
+            parse_optional_subpath(answer)?;
+        }
+        Ok(())
+    }
+
     fn set_question_answer(&mut self, answer: String) -> Result<(), String> {
         let status = match self.step {
             0 => self.path_question.set_answer(answer),
             1 => self.name_question.set_answer(answer),
+            2 => self.compression_question.set_answer(answer),
+            3 => parse_optional_subpath(&answer)
+                .and_then(|_| self.base_path_question.set_answer(answer)),
             _ => panic!("No more questions"),
         };
 
@@ -429,21 +1247,36 @@ impl DeviceFactory for MountedFolderFactory {
         Ok(())
     }
 
+    fn reset(&mut self) {
+        self.path_question.clear_answer();
+        self.name_question.clear_answer();
+        self.compression_question.clear_answer();
+        self.base_path_question.clear_answer();
+        self.step = 0;
+    }
+
     fn has_next(&self) -> bool {
-        self.step < 2
+        self.step < 4
     }
 
     fn build(&self) -> Result<Box<dyn Device>, String> {
-        if self.step < 2 {
+        if self.step < 4 {
             return Err("Not all questions have been answered".to_string());
         }
 
         let path = self.path_question.get_answer()?;
         let name = self.name_question.get_answer()?;
         let name = if name.is_empty() { None } else { Some(name) };
+        let compression = parse_compression_level(&self.compression_question.get_answer()?)?;
+        let subpath = parse_optional_subpath(&self.base_path_question.get_answer()?)?;
         Ok(Box::new(MountedFolder {
             name,
             path: PathBuf::from(path),
+            use_reflink: false,
+            compression,
+            compress: true,
+            last_seen: None,
+            subpath,
         }))
     }
 
@@ -457,10 +1290,62 @@ impl DeviceFactory for MountedFolderFactory {
             .ok_or_else(|| "missing field `path`".to_string())?
             .as_str()
             .ok_or_else(|| "Invalid string for 'path'".to_string())?;
+        let use_reflink = table
+            .get("use_reflink")
+            .map(|value| {
+                value
+                    .as_bool()
+                    .ok_or_else(|| "Invalid boolean for 'use_reflink'".to_string())
+            })
+            .transpose()?
+            .unwrap_or(false);
+        let compression = table
+            .get("compression_level")
+            .map(|value| {
+                value
+                    .as_str()
+                    .ok_or_else(|| "Invalid string for 'compression_level'".to_string())
+                    .and_then(parse_compression_level)
+            })
+            .transpose()?
+            .unwrap_or_default();
+        let compress = table
+            .get("compress")
+            .map(|value| {
+                value
+                    .as_bool()
+                    .ok_or_else(|| "Invalid boolean for 'compress'".to_string())
+            })
+            .transpose()?
+            .unwrap_or(true);
+        let last_seen = table
+            .get("last_seen")
+            .map(|value| {
+                value
+                    .as_integer()
+                    .and_then(|ms| u64::try_from(ms).ok())
+                    .map(|ms| UNIX_EPOCH + Duration::from_millis(ms))
+                    .ok_or_else(|| "Invalid timestamp for 'last_seen'".to_string())
+            })
+            .transpose()?;
+        let subpath = table
+            .get("base_path")
+            .map(|value| {
+                value
+                    .as_str()
+                    .ok_or_else(|| "Invalid string for 'base_path'".to_string())
+                    .and_then(parse_subpath)
+            })
+            .transpose()?;
 
         Ok(Box::new(MountedFolder {
             name: Some(name.to_string()),
             path: PathBuf::from(path),
+            use_reflink,
+            compression,
+            compress,
+            last_seen,
+            subpath,
         }))
     }
 }
@@ -469,17 +1354,38 @@ impl DeviceFactory for MountedFolderFactory {
 mod test {
     use std::fs;
 
-    use crate::core::test_utils::fs::create_tmp_dir;
+    use crate::core::test_utils::fs::{
+        assert_archive_contains, create_tmp_device, create_tmp_dir, create_tmp_project,
+    };
 
     use super::*;
 
     #[test]
-    fn i_should_be_able_to_get_first_questions_with_its_type() {
-        let factory = MountedFolderFactory::new();
+    fn when_building_from_env_it_shall_read_path_and_name() {
+        std::env::set_var("HIBERNACLI_TEST_ENV_DEVICE_PATH", "/mnt/ci");
+        std::env::set_var("HIBERNACLI_TEST_ENV_DEVICE_NAME", "CiDevice");
 
-        assert!(factory.has_next());
-        assert_eq!(
-            factory.get_question_statement(),
+        let device = MountedFolderFactory::build_from_env("HIBERNACLI_TEST_ENV_DEVICE").unwrap();
+        assert_eq!(device.get_name(), "CiDevice");
+        assert_eq!(device.get_location(), "/mnt/ci");
+    }
+
+    #[test]
+    fn when_building_from_env_without_path_it_shall_return_error() {
+        let result = MountedFolderFactory::build_from_env("HIBERNACLI_TEST_MISSING_DEVICE");
+        assert_eq!(
+            result.err().unwrap(),
+            "Missing environment variable HIBERNACLI_TEST_MISSING_DEVICE_PATH"
+        );
+    }
+
+    #[test]
+    fn i_should_be_able_to_get_first_questions_with_its_type() {
+        let factory = MountedFolderFactory::new();
+
+        assert!(factory.has_next());
+        assert_eq!(
+            factory.get_question_statement(),
             "What is the path of the folder?"
         );
         assert_eq!(factory.get_question_type(), &QuestionType::UnixPath);
@@ -498,12 +1404,90 @@ mod test {
         assert_eq!(factory.get_question_type(), &QuestionType::String);
     }
 
+    #[test]
+    fn after_reset_the_first_question_shall_be_asked_again() {
+        let mut factory = MountedFolderFactory::new();
+        factory.set_question_answer("/".to_string()).unwrap();
+        factory.set_question_answer("MyKey".to_string()).unwrap();
+        factory.set_question_answer("best".to_string()).unwrap();
+        factory.set_question_answer("".to_string()).unwrap();
+        assert!(!factory.has_next());
+
+        factory.reset();
+        assert!(factory.has_next());
+        assert_eq!(
+            factory.get_question_statement(),
+            "What is the path of the folder?"
+        );
+        assert_eq!(
+            factory.build().err().unwrap(),
+            "Not all questions have been answered"
+        );
+        assert_eq!(
+            factory.path_question.get_answer(),
+            Err("No answer provided".to_string())
+        );
+    }
+
+    #[test]
+    fn validating_an_answer_shall_not_advance_to_the_next_question() {
+        let factory = MountedFolderFactory::new();
+
+        assert_eq!(
+            factory.validate_answer("relative/path"),
+            Err("Invalid answer. Should be a valid Unix path".to_string())
+        );
+        assert_eq!(factory.validate_answer("/media/user/0000-0000"), Ok(()));
+        assert_eq!(
+            factory.get_question_statement(),
+            "What is the path of the folder?"
+        );
+    }
+
+    #[test]
+    fn validating_an_answer_when_all_questions_are_answered_shall_fail() {
+        let mut factory = MountedFolderFactory::new();
+        factory
+            .set_question_answer("/media/user/0000-0000".to_string())
+            .unwrap();
+        factory.set_question_answer("MyUsbKey".to_string()).unwrap();
+        factory.set_question_answer("default".to_string()).unwrap();
+        factory.set_question_answer("".to_string()).unwrap();
+
+        assert_eq!(
+            factory.validate_answer("anything"),
+            Err("No more questions".to_string())
+        );
+    }
+
+    #[test]
+    fn after_answering_q2_i_should_be_able_to_select_the_compression_level() {
+        let mut factory = MountedFolderFactory::new();
+
+        factory.set_question_answer("/".to_string()).unwrap();
+        factory.set_question_answer("MyFolder".to_string()).unwrap();
+        assert!(factory.has_next());
+        assert_eq!(
+            factory.get_question_type(),
+            &QuestionType::Select(vec![
+                "default".to_string(),
+                "fast".to_string(),
+                "best".to_string()
+            ])
+        );
+        factory
+            .set_question_answer("medium".to_string())
+            .unwrap_err();
+    }
+
     #[test]
     #[should_panic]
     fn when_getting_too_many_questions_it_shall_panic() {
         let mut factory = MountedFolderFactory::new();
         factory.set_question_answer("/".to_string()).unwrap();
         factory.set_question_answer("MyFolder".to_string()).unwrap();
+        factory.set_question_answer("default".to_string()).unwrap();
+        factory.set_question_answer("".to_string()).unwrap();
         factory.get_question_statement();
     }
 
@@ -513,6 +1497,8 @@ mod test {
         let mut factory = MountedFolderFactory::new();
         factory.set_question_answer("/".to_string()).unwrap();
         factory.set_question_answer("MyFolder".to_string()).unwrap();
+        factory.set_question_answer("default".to_string()).unwrap();
+        factory.set_question_answer("".to_string()).unwrap();
         factory.get_question_type();
     }
 
@@ -522,6 +1508,8 @@ mod test {
         let mut factory = MountedFolderFactory::new();
         factory.set_question_answer("/".to_string()).unwrap();
         factory.set_question_answer("MyFolder".to_string()).unwrap();
+        factory.set_question_answer("default".to_string()).unwrap();
+        factory.set_question_answer("".to_string()).unwrap();
         factory.set_question_answer("Random".to_string()).unwrap();
     }
 
@@ -533,12 +1521,38 @@ mod test {
             .set_question_answer("/media/user/0000-0000".to_string())
             .unwrap();
         factory.set_question_answer("MyUsbKey".to_string()).unwrap();
+        factory.set_question_answer("default".to_string()).unwrap();
+        factory.set_question_answer("".to_string()).unwrap();
 
         let device = factory.build().unwrap();
         assert_eq!(device.get_name(), "MyUsbKey");
         assert_eq!(device.get_location(), "/media/user/0000-0000");
     }
 
+    #[test]
+    fn when_answering_a_base_path_backups_shall_be_stored_under_it() {
+        let mut factory = MountedFolderFactory::new();
+
+        factory
+            .set_question_answer("/media/user/0000-0000".to_string())
+            .unwrap();
+        factory.set_question_answer("MyUsbKey".to_string()).unwrap();
+        factory.set_question_answer("default".to_string()).unwrap();
+        assert_eq!(
+            factory.validate_answer("../other-drive"),
+            Err("Invalid 'base_path' ../other-drive, it shall be relative and stay inside the device folder".to_string())
+        );
+        factory
+            .set_question_answer("backups/hibernacli".to_string())
+            .unwrap();
+
+        let device = factory.build().unwrap();
+        assert_eq!(
+            device.to_toml_table().get("base_path"),
+            Some(&toml::Value::String("backups/hibernacli".to_string()))
+        );
+    }
+
     #[test]
     fn when_answering_questions_but_not_name_default_name_is_used() {
         let mut factory = MountedFolderFactory::new();
@@ -547,6 +1561,8 @@ mod test {
             .set_question_answer("/media/user/0000-0000".to_string())
             .unwrap();
         factory.set_question_answer("".to_string()).unwrap();
+        factory.set_question_answer("default".to_string()).unwrap();
+        factory.set_question_answer("".to_string()).unwrap();
 
         let device = factory.build().unwrap();
         assert_eq!(device.get_name(), "MountedFolder[/media/user/0000-0000]");
@@ -605,6 +1621,11 @@ mod test {
         let device = MountedFolder {
             name: Some("MyUsbKey".to_string()),
             path: PathBuf::from("/media/user/0000-0000"),
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
         };
 
         let table = device.to_toml_table();
@@ -625,19 +1646,26 @@ type = "MountedFolder"
         let device = MountedFolder {
             name: Some("MyUsbKey".to_string()),
             path: tmp_device,
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
         };
 
-        let mut archive_writer = device.get_archive_writer("MyProject");
-        archive_writer.finalize(&vec![], &vec![]).unwrap();
+        let mut archive_writer =
+            device.get_archive_writer("MyProject", &ArchiveWriterOptions::default());
+        archive_writer.finalize(&vec![], &vec![], &[]).unwrap();
 
         let project_path = Path::join(&tmp_device_path, "MyProject");
         let index_path = Path::join(&project_path, "current.index");
-        let tar_path = Path::join(&project_path, "0.tar.gz");
 
         // Only 2 files: the index and the tar
         let files = std::fs::read_dir(&project_path).unwrap();
         assert_eq!(2, files.count());
-        assert!(tar_path.exists());
+        let archives = device.list_archives("MyProject").unwrap();
+        assert_eq!(archives.len(), 1);
+        assert!(archives[0].starts_with("0-"));
         assert!(index_path.exists());
     }
 
@@ -647,11 +1675,17 @@ type = "MountedFolder"
         let device = MountedFolder {
             name: Some("MyUsbKey".to_string()),
             path: tmp_device,
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
         };
 
-        let mut archive_writer = device.get_archive_writer("MyProject");
-        archive_writer.finalize(&vec![], &vec![]).unwrap();
-        let result = archive_writer.finalize(&vec![], &vec![]).unwrap_err();
+        let mut archive_writer =
+            device.get_archive_writer("MyProject", &ArchiveWriterOptions::default());
+        archive_writer.finalize(&vec![], &vec![], &[]).unwrap();
+        let result = archive_writer.finalize(&vec![], &vec![], &[]).unwrap_err();
         assert_eq!("Archive has already been finalized", result.message);
     }
 
@@ -660,14 +1694,17 @@ type = "MountedFolder"
         let device = MountedFolder {
             name: Some("MyUsbKey".to_string()),
             path: PathBuf::from("/media/user/0000-0000/not-found-device"),
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
         };
 
-        let mut archive_writer = device.get_archive_writer("MyProject");
-        let result = archive_writer.finalize(&vec![], &vec![]).unwrap_err();
-        assert_eq!(
-            "Project directory is missing on secondary device and failed to be created",
-            result.message
-        );
+        let mut archive_writer =
+            device.get_archive_writer("MyProject", &ArchiveWriterOptions::default());
+        let result = archive_writer.finalize(&vec![], &vec![], &[]).unwrap_err();
+        assert!(result.message.contains("os error"));
     }
 
     #[test]
@@ -675,6 +1712,11 @@ type = "MountedFolder"
         let device = MountedFolder {
             name: Some("MyUsbKey".to_string()),
             path: PathBuf::from("/media/user/0000-0000/not-found-device"),
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
         };
 
         let result = device.test_availability().unwrap_err();
@@ -687,11 +1729,48 @@ type = "MountedFolder"
         let device = MountedFolder {
             name: Some("MyUsbKey".to_string()),
             path: device_path,
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
         };
 
         device.test_availability().unwrap();
     }
 
+    #[test]
+    fn test_write_permission_shall_leave_no_file_on_the_device() {
+        let device_path = create_tmp_dir();
+        let device = MountedFolder {
+            name: Some("MyUsbKey".to_string()),
+            path: device_path.clone(),
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
+        };
+
+        device.test_write_permission().unwrap();
+        assert_eq!(device_path.read_dir().unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_write_permission_shall_return_error_if_path_is_missing() {
+        let device = MountedFolder {
+            name: Some("MyUsbKey".to_string()),
+            path: create_tmp_dir().join("not-found-device"),
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
+        };
+
+        assert!(device.test_write_permission().is_err());
+    }
+
     #[test]
     fn when_adding_a_file_to_archive_it_shall_pass() {
         let tmp_device = create_tmp_dir();
@@ -699,16 +1778,114 @@ type = "MountedFolder"
         let device = MountedFolder {
             name: Some("MyUsbKey".to_string()),
             path: tmp_device,
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
+        };
+
+        fs::write(Path::join(&tmp_project, "file.txt"), "Hello, world!").unwrap();
+        let mut file = fs::File::open(Path::join(&tmp_project, "file.txt")).unwrap();
+
+        let mut archive_writer =
+            device.get_archive_writer("MyProject", &ArchiveWriterOptions::default());
+        archive_writer
+            .add_file(&mut file, &PathBuf::from("file.txt"), 0, 0, 13)
+            .unwrap();
+        assert_eq!(archive_writer.bytes_written(), 13);
+        archive_writer.finalize(&vec![], &vec![], &[]).unwrap();
+    }
+
+    #[test]
+    fn when_adding_a_file_to_a_snapshot_its_size_shall_be_counted() {
+        let tmp_device = create_tmp_dir();
+        let tmp_project = create_tmp_dir();
+        let device = MountedFolder {
+            name: Some("MyUsbKey".to_string()),
+            path: tmp_device,
+            use_reflink: true,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
+        };
+
+        fs::write(Path::join(&tmp_project, "file.txt"), "Hello, world!").unwrap();
+        let mut file = fs::File::open(Path::join(&tmp_project, "file.txt")).unwrap();
+
+        // Counted whether the filesystem supports reflinks or the tar fallback is used
+        let mut archive_writer =
+            device.get_archive_writer("MyProject", &ArchiveWriterOptions::default());
+        archive_writer
+            .add_file(&mut file, &PathBuf::from("file.txt"), 0, 0, 13)
+            .unwrap();
+        assert_eq!(archive_writer.bytes_written(), 13);
+        archive_writer.finalize(&vec![], &vec![], &[]).unwrap();
+    }
+
+    #[test]
+    fn when_dropping_an_unfinalized_archive_with_keep_partial_it_shall_be_kept_as_partial() {
+        let tmp_device = create_tmp_dir();
+        let tmp_project = create_tmp_dir();
+        let device = MountedFolder {
+            name: Some("MyUsbKey".to_string()),
+            path: tmp_device.clone(),
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
+        };
+
+        fs::write(Path::join(&tmp_project, "file.txt"), "Hello, world!").unwrap();
+        let mut file = fs::File::open(Path::join(&tmp_project, "file.txt")).unwrap();
+
+        let options = ArchiveWriterOptions {
+            keep_partial: true,
+            ..Default::default()
+        };
+        let mut archive_writer = device.get_archive_writer("MyProject", &options);
+        archive_writer
+            .add_file(&mut file, &PathBuf::from("file.txt"), 0, 0, 13)
+            .unwrap();
+        drop(archive_writer);
+
+        let files = fs::read_dir(Path::join(&tmp_device, "MyProject"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(files.len(), 1);
+        assert!(files[0].ends_with(".tar.partial"));
+        assert!(device.list_archives("MyProject").unwrap().is_empty());
+    }
+
+    #[test]
+    fn when_dropping_an_unfinalized_archive_it_shall_be_deleted() {
+        let tmp_device = create_tmp_dir();
+        let tmp_project = create_tmp_dir();
+        let device = MountedFolder {
+            name: Some("MyUsbKey".to_string()),
+            path: tmp_device.clone(),
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
         };
 
         fs::write(Path::join(&tmp_project, "file.txt"), "Hello, world!").unwrap();
         let mut file = fs::File::open(Path::join(&tmp_project, "file.txt")).unwrap();
 
-        let mut archive_writer = device.get_archive_writer("MyProject");
+        let mut archive_writer =
+            device.get_archive_writer("MyProject", &ArchiveWriterOptions::default());
         archive_writer
             .add_file(&mut file, &PathBuf::from("file.txt"), 0, 0, 13)
             .unwrap();
-        archive_writer.finalize(&vec![], &vec![]).unwrap();
+        drop(archive_writer);
+
+        let files = fs::read_dir(Path::join(&tmp_device, "MyProject")).unwrap();
+        assert_eq!(files.count(), 0);
     }
 
     #[test]
@@ -718,17 +1895,1031 @@ type = "MountedFolder"
         let device = MountedFolder {
             name: Some("MyUsbKey".to_string()),
             path: tmp_device,
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
         };
 
         fs::write(Path::join(&tmp_project, "file.txt"), "Hello, world!").unwrap();
         let mut file = fs::File::open(Path::join(&tmp_project, "file.txt")).unwrap();
 
-        let mut archive_writer = device.get_archive_writer("MyProject");
-        archive_writer.finalize(&vec![], &vec![]).unwrap();
+        let mut archive_writer =
+            device.get_archive_writer("MyProject", &ArchiveWriterOptions::default());
+        archive_writer.finalize(&vec![], &vec![], &[]).unwrap();
         let result = archive_writer
             .add_file(&mut file, &PathBuf::from("file.txt"), 0, 0, 13)
             .unwrap_err();
 
         assert_eq!("Archive has already been finalized", result.message);
     }
+
+    #[test]
+    fn when_listing_archives_of_unknown_project_it_shall_be_empty() {
+        let device = MountedFolder {
+            name: Some("MyUsbKey".to_string()),
+            path: create_tmp_dir(),
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
+        };
+
+        assert!(device.list_archives("MyProject").unwrap().is_empty());
+    }
+
+    #[test]
+    fn when_listing_archives_it_shall_only_return_archives_sorted() {
+        let tmp_device = create_tmp_dir();
+        let project_path = Path::join(&tmp_device, "MyProject");
+        fs::create_dir_all(&project_path).unwrap();
+        fs::write(Path::join(&project_path, "20.tar.gz"), "").unwrap();
+        fs::write(Path::join(&project_path, "100.tar.gz"), "").unwrap();
+        fs::write(Path::join(&project_path, "current.index"), "").unwrap();
+        fs::write(Path::join(&project_path, "notes.tar.gz"), "").unwrap();
+        let device = MountedFolder {
+            name: Some("MyUsbKey".to_string()),
+            path: tmp_device,
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
+        };
+
+        assert_eq!(
+            device.list_archives("MyProject").unwrap(),
+            vec!["20.tar.gz", "100.tar.gz"]
+        );
+    }
+
+    #[test]
+    fn when_pruning_archives_only_the_superseded_ones_shall_be_removed() {
+        let tmp_device = create_tmp_dir();
+        let project_path = Path::join(&tmp_device, "MyProject");
+        for (snapshot, file) in [("10", "a"), ("10", "b"), ("20", "a"), ("30", "b")] {
+            let files_path = project_path
+                .join(format!("{}.snapshot", snapshot))
+                .join(".files");
+            fs::create_dir_all(&files_path).unwrap();
+            fs::write(files_path.join(file), "").unwrap();
+        }
+        let device = MountedFolder {
+            name: Some("MyUsbKey".to_string()),
+            path: tmp_device,
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
+        };
+
+        assert_eq!(device.prune_archives("MyProject", 1).unwrap(), 1);
+        assert_eq!(
+            device.list_archives("MyProject").unwrap(),
+            vec!["20.snapshot", "30.snapshot"]
+        );
+    }
+
+    #[test]
+    fn archive_files_shall_be_read_from_tar_archives_and_snapshots() {
+        let tmp_device = create_tmp_dir();
+        let project_path = Path::join(&tmp_device, "MyProject");
+        fs::create_dir_all(project_path.join("10.snapshot")).unwrap();
+        fs::write(project_path.join("10.snapshot/.index"), "snapshot index").unwrap();
+        for (archive_name, compressed) in [("20.tar", false), ("30.tar.gz", true)] {
+            let archive = File::create(project_path.join(archive_name)).unwrap();
+            let writer: Box<dyn io::Write> = match compressed {
+                true => Box::new(flate2::write::GzEncoder::new(
+                    archive,
+                    Compression::default(),
+                )),
+                false => Box::new(archive),
+            };
+            let mut builder = tar::Builder::new(writer);
+            for (path, content) in [(".files/notes.txt", "notes"), (".index", archive_name)] {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(content.len() as u64);
+                header.set_cksum();
+                builder
+                    .append_data(&mut header, path, content.as_bytes())
+                    .unwrap();
+            }
+            builder.into_inner().unwrap().flush().unwrap();
+        }
+        let device = MountedFolder {
+            name: Some("MyUsbKey".to_string()),
+            path: tmp_device,
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
+        };
+
+        let read = |archive_name, file_name| {
+            device
+                .read_archive_file("MyProject", archive_name, file_name)
+                .unwrap()
+                .map(|content| String::from_utf8(content).unwrap())
+        };
+        assert_eq!(read("10.snapshot", ".index").unwrap(), "snapshot index");
+        assert_eq!(read("20.tar", ".index").unwrap(), "20.tar");
+        assert_eq!(read("30.tar.gz", ".index").unwrap(), "30.tar.gz");
+        assert_eq!(read("10.snapshot", ".deleted-files"), None);
+        assert_eq!(read("20.tar", ".deleted-files"), None);
+    }
+
+    #[test]
+    fn when_pruning_tar_archives_only_the_superseded_ones_shall_be_removed() {
+        let tmp_device = create_tmp_dir();
+        let project_path = Path::join(&tmp_device, "MyProject");
+        fs::create_dir_all(&project_path).unwrap();
+        for (timestamp, files, deleted_files) in [
+            ("10", vec!["a", "b"], ""),
+            ("20", vec!["a"], ""),
+            ("30", vec![], "b"),
+        ] {
+            let archive = File::create(project_path.join(format!("{}.tar.gz", timestamp))).unwrap();
+            let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+                archive,
+                Compression::default(),
+            ));
+            for (path, content) in files
+                .iter()
+                .map(|file| (format!(".files/{}", file), ""))
+                .chain([(".deleted-files".to_string(), deleted_files)])
+            {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(content.len() as u64);
+                header.set_cksum();
+                builder
+                    .append_data(&mut header, path, content.as_bytes())
+                    .unwrap();
+            }
+            builder.into_inner().unwrap().finish().unwrap();
+        }
+        let device = MountedFolder {
+            name: Some("MyUsbKey".to_string()),
+            path: tmp_device,
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
+        };
+
+        assert_eq!(device.prune_archives("MyProject", 2).unwrap(), 1);
+        assert_eq!(
+            device.list_archives("MyProject").unwrap(),
+            vec!["20.tar.gz", "30.tar.gz"]
+        );
+    }
+
+    #[test]
+    fn when_listing_backed_up_projects_it_shall_ignore_directories_without_backups() {
+        let tmp_device = create_tmp_dir();
+        let indexed_path = Path::join(&tmp_device, "Indexed");
+        fs::create_dir_all(&indexed_path).unwrap();
+        fs::write(Path::join(&indexed_path, "current.index"), "").unwrap();
+        let archived_path = Path::join(&tmp_device, "Archived");
+        fs::create_dir_all(&archived_path).unwrap();
+        fs::write(Path::join(&archived_path, "20.tar.gz"), "").unwrap();
+        fs::create_dir_all(Path::join(&tmp_device, "Photos")).unwrap();
+        fs::write(Path::join(&tmp_device, "notes.txt"), "").unwrap();
+        let device = MountedFolder {
+            name: Some("MyUsbKey".to_string()),
+            path: tmp_device,
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
+        };
+
+        assert_eq!(
+            device.list_backed_up_projects().unwrap(),
+            vec!["Archived", "Indexed"]
+        );
+    }
+
+    #[test]
+    fn last_backup_time_shall_be_parsed_from_newest_archive_name() {
+        let tmp_device = create_tmp_dir();
+        let project_path = Path::join(&tmp_device, "MyProject");
+        fs::create_dir_all(&project_path).unwrap();
+        fs::write(Path::join(&project_path, "20.tar.gz"), "").unwrap();
+        fs::write(Path::join(&project_path, "1500.tar.gz"), "").unwrap();
+        let device = MountedFolder {
+            name: Some("MyUsbKey".to_string()),
+            path: tmp_device,
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
+        };
+
+        assert_eq!(
+            device.get_last_backup_time("MyProject").unwrap(),
+            Some(UNIX_EPOCH + Duration::from_millis(1500))
+        );
+    }
+
+    #[test]
+    fn when_project_was_never_backed_up_last_backup_time_shall_be_none() {
+        let device = MountedFolder {
+            name: Some("MyUsbKey".to_string()),
+            path: create_tmp_dir(),
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
+        };
+
+        assert_eq!(device.get_last_backup_time("MyProject").unwrap(), None);
+    }
+
+    #[test]
+    fn when_creating_device_from_toml_with_compression_level_it_shall_serialize_it_back() {
+        let factory = MountedFolderFactory::new();
+        let mut table = toml::value::Table::new();
+        table.insert(
+            "path".to_string(),
+            toml::Value::String("/media/user/0000-0000".to_string()),
+        );
+        table.insert(
+            "compression_level".to_string(),
+            toml::Value::String("best".to_string()),
+        );
+
+        let device = factory.build_from_toml_table("MyUsbKey", &table).unwrap();
+        let string_table = toml::to_string(&device.to_toml_table()).unwrap();
+        assert_eq!(
+            string_table,
+            "compression_level = \"best\"\nname = \"MyUsbKey\"\npath = \"/media/user/0000-0000\"\ntype = \"MountedFolder\"\n"
+        );
+    }
+
+    #[test]
+    fn when_creating_device_from_toml_with_unknown_compression_level_it_shall_return_error() {
+        let factory = MountedFolderFactory::new();
+        let mut table = toml::value::Table::new();
+        table.insert(
+            "path".to_string(),
+            toml::Value::String("/media/user/0000-0000".to_string()),
+        );
+        table.insert(
+            "compression_level".to_string(),
+            toml::Value::String("medium".to_string()),
+        );
+
+        let device = factory.build_from_toml_table("MyUsbKey", &table);
+        assert_eq!("Invalid compression level: medium", device.err().unwrap());
+    }
+
+    #[test]
+    fn reflink_capability_shall_depend_on_the_use_reflink_option() {
+        let factory = MountedFolderFactory::new();
+        let mut table = toml::value::Table::new();
+        table.insert(
+            "path".to_string(),
+            toml::Value::String("/media/user/0000-0000".to_string()),
+        );
+
+        let device = factory.build_from_toml_table("MyUsbKey", &table).unwrap();
+        assert!(!device.supports_capability(DeviceCapability::Reflink));
+        assert!(device.supports_capability(DeviceCapability::SparseFiles));
+        assert!(device.supports_capability(DeviceCapability::PartialRestore));
+        assert!(!device.supports_capability(DeviceCapability::Encryption));
+
+        table.insert("use_reflink".to_string(), toml::Value::Boolean(true));
+        let device = factory.build_from_toml_table("MyUsbKey", &table).unwrap();
+        assert!(device.supports_capability(DeviceCapability::Reflink));
+    }
+
+    #[test]
+    fn when_setting_last_seen_it_shall_be_serialized_and_read_back() {
+        let factory = MountedFolderFactory::new();
+        let mut table = toml::value::Table::new();
+        table.insert(
+            "path".to_string(),
+            toml::Value::String("/media/user/0000-0000".to_string()),
+        );
+
+        let mut device = factory.build_from_toml_table("MyUsbKey", &table).unwrap();
+        assert_eq!(device.get_last_seen(), None);
+        device.set_last_seen(UNIX_EPOCH + Duration::from_millis(1709301909000));
+        let table = device.to_toml_table();
+        assert_eq!(
+            table.get("last_seen"),
+            Some(&toml::Value::Integer(1709301909000))
+        );
+
+        let device = factory.build_from_toml_table("MyUsbKey", &table).unwrap();
+        assert_eq!(
+            device.get_last_seen(),
+            Some(UNIX_EPOCH + Duration::from_millis(1709301909000))
+        );
+    }
+
+    #[test]
+    fn when_creating_device_from_toml_with_invalid_last_seen_it_shall_return_error() {
+        let factory = MountedFolderFactory::new();
+        let mut table = toml::value::Table::new();
+        table.insert(
+            "path".to_string(),
+            toml::Value::String("/media/user/0000-0000".to_string()),
+        );
+        table.insert(
+            "last_seen".to_string(),
+            toml::Value::String("yesterday".to_string()),
+        );
+
+        let device = factory.build_from_toml_table("MyUsbKey", &table);
+        assert_eq!("Invalid timestamp for 'last_seen'", device.err().unwrap());
+    }
+
+    #[test]
+    fn free_space_of_existing_folder_shall_be_known() {
+        let device = MountedFolder {
+            name: Some("MyUsbKey".to_string()),
+            path: create_tmp_dir(),
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
+        };
+
+        assert!(device.get_free_space().is_some());
+    }
+
+    #[test]
+    fn when_creating_device_from_toml_with_reflink_it_shall_serialize_it_back() {
+        let factory = MountedFolderFactory::new();
+        let mut table = toml::value::Table::new();
+        table.insert(
+            "path".to_string(),
+            toml::Value::String("/media/user/0000-0000".to_string()),
+        );
+        table.insert("use_reflink".to_string(), toml::Value::Boolean(true));
+
+        let device = factory.build_from_toml_table("MyUsbKey", &table).unwrap();
+        let string_table = toml::to_string(&device.to_toml_table()).unwrap();
+        assert_eq!(
+            string_table,
+            r#"name = "MyUsbKey"
+path = "/media/user/0000-0000"
+type = "MountedFolder"
+use_reflink = true
+"#
+        );
+    }
+
+    #[test]
+    fn when_creating_device_from_toml_with_invalid_reflink_it_shall_return_error() {
+        let factory = MountedFolderFactory::new();
+        let mut table = toml::value::Table::new();
+        table.insert(
+            "path".to_string(),
+            toml::Value::String("/media/user/0000-0000".to_string()),
+        );
+        table.insert("use_reflink".to_string(), toml::Value::Integer(1));
+
+        let device = factory.build_from_toml_table("MyUsbKey", &table);
+        assert_eq!("Invalid boolean for 'use_reflink'", device.err().unwrap());
+    }
+
+    #[test]
+    fn peeking_the_first_archive_shall_not_advance_the_extractor() {
+        let tmp_device = create_tmp_dir();
+        let project_dir = Path::join(&tmp_device, "MyProject");
+        fs::create_dir_all(&project_dir).unwrap();
+        fs::write(Path::join(&project_dir, "2000-0000abcd.tar"), "").unwrap();
+        fs::write(Path::join(&project_dir, "1000-0000abcd.tar"), "").unwrap();
+
+        let mut extractor = MountedFolderExtractor::new(tmp_device, project_dir.clone());
+        let oldest = Path::join(&project_dir, "1000-0000abcd.tar");
+        assert_eq!(extractor.peek_first(), Some(oldest.as_path()));
+        assert_eq!(extractor.peek_first(), Some(oldest.as_path()));
+
+        extractor.next().unwrap();
+        let newest = Path::join(&project_dir, "2000-0000abcd.tar");
+        assert_eq!(extractor.peek_first(), Some(newest.as_path()));
+
+        extractor.next_back().unwrap();
+        assert_eq!(extractor.peek_first(), None);
+    }
+
+    #[test]
+    fn tags_given_on_finalization_shall_be_read_from_the_archive() {
+        for use_reflink in [false, true] {
+            let device = MountedFolder {
+                name: Some("MyUsbKey".to_string()),
+                path: create_tmp_dir(),
+                use_reflink,
+                compression: Compression::default(),
+                compress: true,
+                last_seen: None,
+                subpath: None,
+            };
+
+            let tags = ["before-upgrade".to_string(), "v1.0".to_string()];
+            device
+                .get_archive_writer("MyProject", &ArchiveWriterOptions::default())
+                .finalize(&vec![], &vec![], &tags)
+                .unwrap();
+            device
+                .get_archive_writer("UntaggedProject", &ArchiveWriterOptions::default())
+                .finalize(&vec![], &vec![], &[])
+                .unwrap();
+
+            let step = device.get_extractor("MyProject").next().unwrap();
+            assert_eq!(step.get_tags(), tags);
+            let step = device.get_extractor("UntaggedProject").next().unwrap();
+            assert!(step.get_tags().is_empty());
+        }
+    }
+
+    #[test]
+    fn when_backing_up_with_reflink_it_shall_be_restorable_whatever_the_filesystem() {
+        let tmp_device = create_tmp_dir();
+        let tmp_project = create_tmp_dir();
+        let device = MountedFolder {
+            name: Some("MyUsbKey".to_string()),
+            path: tmp_device,
+            use_reflink: true,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
+        };
+
+        fs::write(Path::join(&tmp_project, "file.txt"), "Hello, world!").unwrap();
+        let mut file = fs::File::open(Path::join(&tmp_project, "file.txt")).unwrap();
+
+        let mut archive_writer =
+            device.get_archive_writer("MyProject", &ArchiveWriterOptions::default());
+        archive_writer
+            .add_file(&mut file, &PathBuf::from("file.txt"), 0, 0, 13)
+            .unwrap();
+        archive_writer.finalize(&vec![], &vec![], &[]).unwrap();
+        assert_eq!(device.list_archives("MyProject").unwrap().len(), 1);
+
+        let restore_path = Path::join(&create_tmp_dir(), "restored");
+        fs::create_dir_all(&restore_path).unwrap();
+        let paths_to_extract = HashSet::from([PathBuf::from("file.txt")]);
+        for step in device.get_extractor("MyProject").rev() {
+            step.extract_to(&restore_path, &paths_to_extract).unwrap();
+        }
+
+        assert_eq!(
+            fs::read_to_string(Path::join(&restore_path, "file.txt")).unwrap(),
+            "Hello, world!"
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn when_backing_up_a_sparse_file_it_shall_be_restored_with_its_content() {
+        use crate::core::util::sparse_files::find_data_segments;
+        use std::io::{Seek, SeekFrom, Write};
+
+        let tmp_device = create_tmp_dir();
+        let tmp_project = create_tmp_dir();
+        let device = MountedFolder {
+            name: Some("MyUsbKey".to_string()),
+            path: tmp_device,
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
+        };
+
+        // A 4 MiB disk image with only 4 KiB of data in the middle
+        let image_path = Path::join(&tmp_project, "disk.img");
+        let mut image = fs::File::create(&image_path).unwrap();
+        image.set_len(4 * 1024 * 1024).unwrap();
+        image.seek(SeekFrom::Start(1024 * 1024)).unwrap();
+        image.write_all(&[42u8; 4096]).unwrap();
+        image.sync_all().unwrap();
+
+        let mut image = fs::File::open(&image_path).unwrap();
+        let data_segments = find_data_segments(&image, 4 * 1024 * 1024)
+            .unwrap()
+            .unwrap_or(vec![SparseSegment {
+                offset: 0,
+                length: 4 * 1024 * 1024,
+            }]);
+        let mut archive_writer =
+            device.get_archive_writer("MyProject", &ArchiveWriterOptions::default());
+        archive_writer
+            .add_sparse_file(
+                &mut image,
+                &PathBuf::from("disk.img"),
+                0,
+                0,
+                4 * 1024 * 1024,
+                &data_segments,
+            )
+            .unwrap();
+        archive_writer.finalize(&vec![], &vec![], &[]).unwrap();
+
+        let restore_path = Path::join(&create_tmp_dir(), "restored");
+        fs::create_dir_all(&restore_path).unwrap();
+        let paths_to_extract = HashSet::from([PathBuf::from("disk.img")]);
+        for step in device.get_extractor("MyProject").rev() {
+            step.extract_to(&restore_path, &paths_to_extract).unwrap();
+        }
+
+        assert_eq!(
+            fs::read(Path::join(&restore_path, "disk.img")).unwrap(),
+            fs::read(&image_path).unwrap()
+        );
+    }
+
+    #[test]
+    fn archive_timestamps_shall_be_read_from_archives_and_snapshots_names() {
+        assert_eq!(get_archive_timestamp("100.tar.gz"), Some(100));
+        assert_eq!(get_archive_timestamp("100-0a1b2c3d.tar.gz"), Some(100));
+        assert_eq!(get_archive_timestamp("100-xyz.tar.gz"), None);
+        assert_eq!(get_archive_timestamp("100.snapshot"), Some(100));
+        assert_eq!(get_archive_timestamp("100.tar"), None);
+        assert_eq!(get_archive_timestamp("+100.tar.gz"), None);
+        assert_eq!(get_archive_timestamp("current.index"), None);
+    }
+
+    #[test]
+    fn when_migrating_archives_legacy_names_shall_get_a_checksum() {
+        let tmp_device = create_tmp_dir();
+        let project_path = Path::join(&tmp_device, "MyProject");
+        fs::create_dir_all(&project_path).unwrap();
+        fs::write(Path::join(&project_path, "20.tar.gz"), "Hello").unwrap();
+        fs::write(Path::join(&project_path, "30-0a1b2c3d.tar.gz"), "").unwrap();
+        let device = MountedFolder {
+            name: Some("MyUsbKey".to_string()),
+            path: tmp_device,
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
+        };
+
+        assert_eq!(device.migrate_archives("MyProject").unwrap(), 1);
+        assert_eq!(
+            device.list_archives("MyProject").unwrap(),
+            vec!["20-f7d18982.tar.gz", "30-0a1b2c3d.tar.gz"]
+        );
+        assert_eq!(device.migrate_archives("MyProject").unwrap(), 0);
+    }
+
+    #[test]
+    fn when_writing_backup_index_it_shall_be_read_back() {
+        let tmp_device = create_tmp_dir();
+        fs::create_dir_all(Path::join(&tmp_device, "MyProject")).unwrap();
+        let device = MountedFolder {
+            name: Some("MyUsbKey".to_string()),
+            path: tmp_device,
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
+        };
+
+        device.write_backup_index("MyProject", b"index").unwrap();
+
+        let mut index = String::new();
+        device
+            .read_backup_index("MyProject")
+            .unwrap()
+            .unwrap()
+            .read_to_string(&mut index)
+            .unwrap();
+        assert_eq!(index, "index");
+    }
+
+    #[test]
+    fn when_reading_an_archive_it_shall_return_its_content() {
+        let tmp_device = create_tmp_dir();
+        let device = MountedFolder {
+            name: Some("MyUsbKey".to_string()),
+            path: tmp_device,
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
+        };
+
+        let mut archive_writer =
+            device.get_archive_writer("MyProject", &ArchiveWriterOptions::default());
+        archive_writer.finalize(&vec![], &vec![], &[]).unwrap();
+
+        let mut archive = tar::Archive::new(
+            device
+                .get_archive_reader("MyProject", &device.list_archives("MyProject").unwrap()[0])
+                .unwrap(),
+        );
+        let entries = archive
+            .entries()
+            .unwrap()
+            .map(|entry| entry.unwrap().path().unwrap().to_path_buf())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            entries,
+            vec![PathBuf::from(".deleted-files"), PathBuf::from(".index")]
+        );
+    }
+
+    #[test]
+    fn when_reading_an_archive_its_files_shall_be_decompressed() {
+        let tmp_project = create_tmp_dir();
+        fs::write(Path::join(&tmp_project, "file.txt"), "Hello, world!").unwrap();
+
+        for compress in [true, false] {
+            let device = MountedFolder {
+                name: Some("MyUsbKey".to_string()),
+                path: create_tmp_dir(),
+                use_reflink: false,
+                compression: Compression::default(),
+                compress: true,
+                last_seen: None,
+                subpath: None,
+            };
+            let mut file = fs::File::open(Path::join(&tmp_project, "file.txt")).unwrap();
+            let mut archive_writer = device.get_archive_writer(
+                "MyProject",
+                &ArchiveWriterOptions {
+                    compress,
+                    ..Default::default()
+                },
+            );
+            archive_writer
+                .add_file(&mut file, &PathBuf::from("file.txt"), 0, 0, 13)
+                .unwrap();
+            archive_writer.finalize(&vec![], &vec![], &[]).unwrap();
+
+            let archive_name = &device.list_archives("MyProject").unwrap()[0];
+            let mut archive = tar::Archive::new(
+                device
+                    .get_archive_reader("MyProject", archive_name)
+                    .unwrap(),
+            );
+            let mut entry = archive
+                .entries()
+                .unwrap()
+                .map(|entry| entry.unwrap())
+                .find(|entry| entry.path().unwrap() == Path::new(".files/file.txt"))
+                .unwrap();
+            let mut content = String::new();
+            entry.read_to_string(&mut content).unwrap();
+            assert_eq!(content, "Hello, world!");
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn default_path_of_a_new_mounted_folder_shall_be_a_mount_point_parent() {
+        let default_answers = MountedFolderFactory::new().default_answers();
+
+        assert_eq!(default_answers.len(), 4);
+        let default_path = default_answers[0].clone().unwrap();
+        assert!(default_path.starts_with("/media/") || default_path == "/mnt/");
+        assert_eq!(default_answers[1], None);
+    }
+
+    #[test]
+    fn when_archiving_a_project_tree_all_its_files_shall_be_in_the_archive() {
+        let tmp_project = create_tmp_project(&[
+            ("README.md", b"# My project"),
+            ("src/main.rs", b"fn main() {}"),
+        ]);
+        let tmp_device = create_tmp_device();
+        let device = MountedFolder {
+            name: Some("MyUsbKey".to_string()),
+            path: tmp_device.clone(),
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
+        };
+
+        let mut archive_writer =
+            device.get_archive_writer("MyProject", &ArchiveWriterOptions::default());
+        for name in ["README.md", "src/main.rs"] {
+            let mut file = fs::File::open(tmp_project.join(name)).unwrap();
+            let size = file.metadata().unwrap().len();
+            archive_writer
+                .add_file(&mut file, &PathBuf::from(name), 0, 0, size)
+                .unwrap();
+        }
+        archive_writer.finalize(&vec![], &vec![], &[]).unwrap();
+
+        let archive_name = &device.list_archives("MyProject").unwrap()[0];
+        assert_archive_contains(
+            &tmp_device.join("MyProject").join(archive_name),
+            &[".files/README.md", ".files/src/main.rs"],
+        );
+    }
+
+    #[test]
+    fn when_archiving_with_a_compression_format_its_extension_shall_be_used() {
+        let tmp_project = create_tmp_project(&[("file.txt", b"Hello, world!")]);
+
+        for format in CompressionFormat::ALL {
+            let device = MountedFolder {
+                name: Some("MyUsbKey".to_string()),
+                path: create_tmp_device(),
+                use_reflink: false,
+                compression: Compression::default(),
+                compress: true,
+                last_seen: None,
+                subpath: None,
+            };
+            let mut file = fs::File::open(Path::join(&tmp_project, "file.txt")).unwrap();
+            let mut archive_writer = device.get_archive_writer(
+                "MyProject",
+                &ArchiveWriterOptions {
+                    compression_format: format,
+                    ..Default::default()
+                },
+            );
+            archive_writer
+                .add_file(&mut file, &PathBuf::from("file.txt"), 0, 0, 13)
+                .unwrap();
+            archive_writer.finalize(&vec![], &vec![], &[]).unwrap();
+
+            let archives = device.list_archives("MyProject").unwrap();
+            assert_eq!(archives.len(), 1);
+            assert!(archives[0].ends_with(format.archive_extension()));
+
+            let restore_path = create_tmp_dir();
+            let paths_to_extract = HashSet::from([PathBuf::from("file.txt")]);
+            for step in device.get_extractor("MyProject").rev() {
+                step.extract_to(&restore_path, &paths_to_extract).unwrap();
+            }
+            assert_eq!(
+                fs::read_to_string(Path::join(&restore_path, "file.txt")).unwrap(),
+                "Hello, world!"
+            );
+        }
+    }
+
+    #[test]
+    fn when_reading_something_else_than_an_archive_it_shall_fail() {
+        let device = MountedFolder {
+            name: Some("MyUsbKey".to_string()),
+            path: create_tmp_dir(),
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
+        };
+
+        let result = device.get_archive_reader("MyProject", "../../etc/passwd");
+        assert_eq!(
+            result.err().unwrap(),
+            "Not a tar or tar.gz archive: ../../etc/passwd"
+        );
+    }
+
+    #[test]
+    fn when_creating_device_from_toml_with_base_path_it_shall_serialize_it_back() {
+        let factory = MountedFolderFactory::new();
+        let mut table = toml::value::Table::new();
+        table.insert(
+            "path".to_string(),
+            toml::Value::String("/media/user/0000-0000".to_string()),
+        );
+        table.insert(
+            "base_path".to_string(),
+            toml::Value::String("backups/hibernacli".to_string()),
+        );
+
+        let device = factory.build_from_toml_table("MyUsbKey", &table).unwrap();
+        let string_table = toml::to_string(&device.to_toml_table()).unwrap();
+        assert_eq!(
+            string_table,
+            r#"base_path = "backups/hibernacli"
+name = "MyUsbKey"
+path = "/media/user/0000-0000"
+type = "MountedFolder"
+"#
+        );
+    }
+
+    #[test]
+    fn when_creating_device_from_toml_with_base_path_outside_its_folder_it_shall_fail() {
+        let factory = MountedFolderFactory::new();
+        for base_path in ["/etc", "../other-drive", "backups/../../.."] {
+            let mut table = toml::value::Table::new();
+            table.insert(
+                "path".to_string(),
+                toml::Value::String("/media/user/0000-0000".to_string()),
+            );
+            table.insert(
+                "base_path".to_string(),
+                toml::Value::String(base_path.to_string()),
+            );
+
+            let result = factory.build_from_toml_table("MyUsbKey", &table);
+            assert_eq!(
+                result.err().unwrap(),
+                format!(
+                    "Invalid 'base_path' {}, it shall be relative and stay inside the device folder",
+                    base_path
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn when_backing_up_to_a_device_with_subpath_archives_shall_be_stored_under_it() {
+        let root_path = create_tmp_dir();
+        let device = MountedFolder::with_subpath(
+            "MyUsbKey".to_string(),
+            root_path.clone(),
+            PathBuf::from("backups/hibernacli"),
+        );
+        assert_eq!(
+            device.list_backed_up_projects().unwrap(),
+            Vec::<String>::new()
+        );
+
+        let mut archive_writer =
+            device.get_archive_writer("MyProject", &ArchiveWriterOptions::default());
+        archive_writer.finalize(&vec![], &vec![], &[]).unwrap();
+
+        assert!(root_path.join("backups/hibernacli/MyProject").is_dir());
+        assert!(!root_path.join("MyProject").exists());
+        assert_eq!(device.list_archives("MyProject").unwrap().len(), 1);
+        assert_eq!(device.list_backed_up_projects().unwrap(), vec!["MyProject"]);
+    }
+
+    #[test]
+    fn when_creating_device_from_toml_without_compression_it_shall_serialize_it_back() {
+        let factory = MountedFolderFactory::new();
+        let mut table = toml::value::Table::new();
+        table.insert(
+            "path".to_string(),
+            toml::Value::String("/media/user/0000-0000".to_string()),
+        );
+        table.insert("compress".to_string(), toml::Value::Boolean(false));
+
+        let device = factory.build_from_toml_table("MyUsbKey", &table).unwrap();
+        let string_table = toml::to_string(&device.to_toml_table()).unwrap();
+        assert_eq!(
+            string_table,
+            r#"compress = false
+name = "MyUsbKey"
+path = "/media/user/0000-0000"
+type = "MountedFolder"
+"#
+        );
+    }
+
+    #[test]
+    fn when_backing_up_without_compression_it_shall_be_stored_as_tar_and_restorable() {
+        let tmp_device = create_tmp_dir();
+        let tmp_project = create_tmp_dir();
+        let device = MountedFolder {
+            name: Some("MyUsbKey".to_string()),
+            path: tmp_device,
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
+        };
+
+        fs::write(Path::join(&tmp_project, "file.txt"), "Hello, world!").unwrap();
+        let mut file = fs::File::open(Path::join(&tmp_project, "file.txt")).unwrap();
+
+        let mut archive_writer = device.get_archive_writer(
+            "MyProject",
+            &ArchiveWriterOptions {
+                compress: false,
+                ..Default::default()
+            },
+        );
+        archive_writer
+            .add_file(&mut file, &PathBuf::from("file.txt"), 0, 0, 13)
+            .unwrap();
+        archive_writer.finalize(&vec![], &vec![], &[]).unwrap();
+        let archives = device.list_archives("MyProject").unwrap();
+        assert_eq!(archives.len(), 1);
+        assert!(archives[0].ends_with(UNCOMPRESSED_ARCHIVE_EXTENSION));
+        assert!(device.get_archive_reader("MyProject", &archives[0]).is_ok());
+
+        let restore_path = Path::join(&create_tmp_dir(), "restored");
+        fs::create_dir_all(&restore_path).unwrap();
+        let paths_to_extract = HashSet::from([PathBuf::from("file.txt")]);
+        for step in device.get_extractor("MyProject").rev() {
+            step.extract_to(&restore_path, &paths_to_extract).unwrap();
+        }
+
+        assert_eq!(
+            fs::read_to_string(Path::join(&restore_path, "file.txt")).unwrap(),
+            "Hello, world!"
+        );
+    }
+
+    #[test]
+    fn a_tar_archive_without_checksum_shall_not_be_listed() {
+        assert!(is_archive_file_name("20-0a1b2c3d.tar"));
+        assert!(!is_archive_file_name("20.tar"));
+    }
+
+    #[test]
+    fn when_creating_project_directory_it_shall_exist_on_the_device() {
+        let tmp_device = create_tmp_dir();
+        let device = MountedFolder {
+            name: Some("MyUsbKey".to_string()),
+            path: tmp_device.clone(),
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
+        };
+
+        device.create_project_directory("MyProject").unwrap();
+        assert!(Path::join(&tmp_device, "MyProject").is_dir());
+    }
+
+    #[test]
+    fn when_creating_project_directory_on_missing_device_it_shall_fail() {
+        let tmp_device = Path::join(&create_tmp_dir(), "unmounted");
+        let device = MountedFolder {
+            name: Some("MyUsbKey".to_string()),
+            path: tmp_device.clone(),
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
+        };
+
+        assert!(device.create_project_directory("MyProject").is_err());
+        assert!(!tmp_device.exists());
+    }
+
+    #[test]
+    fn when_renaming_project_directory_its_content_shall_be_moved() {
+        let tmp_device = create_tmp_dir();
+        let device = MountedFolder {
+            name: Some("MyUsbKey".to_string()),
+            path: tmp_device.clone(),
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
+        };
+        fs::create_dir_all(tmp_device.join("MyProject")).unwrap();
+        fs::write(tmp_device.join("MyProject/.index"), "").unwrap();
+        fs::create_dir_all(tmp_device.join("TakenName")).unwrap();
+
+        assert!(device
+            .rename_project_directory("MyProject", "TakenName")
+            .is_err());
+        device
+            .rename_project_directory("MyProject", "NewName")
+            .unwrap();
+        assert!(!tmp_device.join("MyProject").exists());
+        assert!(tmp_device.join("NewName/.index").is_file());
+    }
+
+    #[test]
+    fn when_deleting_project_data_it_shall_only_remove_this_project() {
+        let tmp_device = create_tmp_dir();
+        let device = MountedFolder {
+            name: Some("MyUsbKey".to_string()),
+            path: tmp_device.clone(),
+            use_reflink: false,
+            compression: Compression::default(),
+            compress: true,
+            last_seen: None,
+            subpath: None,
+        };
+        fs::create_dir_all(tmp_device.join("MyProject")).unwrap();
+        fs::write(tmp_device.join("MyProject/.index"), "").unwrap();
+        fs::create_dir_all(tmp_device.join("OtherProject")).unwrap();
+
+        device.delete_project_data("MyProject").unwrap();
+        assert!(!tmp_device.join("MyProject").exists());
+        assert!(tmp_device.join("OtherProject").is_dir());
+
+        // Nothing left to delete is not an error
+        device.delete_project_data("MyProject").unwrap();
+    }
 }
@@ -0,0 +1,968 @@
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{self, BufRead, Cursor, Read, Write},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, ChildStdout, Command, ExitStatus, Stdio},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use itertools::Itertools;
+use tracing::{debug, info, warn};
+
+use crate::{
+    core::{
+        util::timestamps::Timestamp, ArchiveError, ArchiveWriter, ArchiveWriterOptions,
+        CompressionFormat, Device, DeviceCapability, DeviceFactory, DifferentialArchiveStep,
+        Extractor, ExtractorError, Question, QuestionType, SecurityLevel,
+    },
+    devices::mounted_folder::{extract_archive_files, read_archive_tags, TAGS_FILE_NAME},
+    now,
+};
+
+const ARCHIVE_EXTENSION: &str = ".tar.gz";
+const UNCOMPRESSED_ARCHIVE_EXTENSION: &str = ".tar";
+const INDEX_FILE_NAME: &str = "current.index";
+const WRITE_TEST_FILE_NAME: &str = ".hibernacli-write-test";
+
+// Exit codes of rclone when the requested remote path does not exist
+const RCLONE_DIRECTORY_NOT_FOUND: i32 = 3;
+const RCLONE_FILE_NOT_FOUND: i32 = 4;
+
+// Device storing the backups on any remote supported by rclone (Google Drive, Dropbox,
+// Backblaze B2, S3...), through the rclone command line tool. The remote is configured
+// beforehand with `rclone config`, and archives are streamed to it without local copy
+struct RcloneDevice {
+    name: String,
+
+    // Name of the remote in the rclone configuration, and bucket (or top folder) on it
+    remote: String,
+    bucket: String,
+
+    // Folder of the bucket under which the projects are stored, may be empty
+    prefix: String,
+
+    // Last time the remote was found available, persisted in the configuration
+    last_seen: Option<SystemTime>,
+}
+
+impl RcloneDevice {
+    // Path given to rclone for a file or folder of the device: <remote>:<bucket>/<prefix>/<path>
+    fn get_remote_path(&self, path: &str) -> String {
+        let segments = [self.bucket.as_str(), self.prefix.as_str(), path]
+            .iter()
+            .map(|segment| segment.trim_matches('/'))
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>();
+        format!("{}:{}", self.remote, segments.join("/"))
+    }
+
+    fn get_project_file_path(&self, project_name: &str, file_name: &str) -> String {
+        self.get_remote_path(&format!("{}/{}", project_name, file_name))
+    }
+
+    fn list_project_files(&self, project_name: &str) -> Result<Vec<String>, String> {
+        let output = run_rclone(&["lsf", "--files-only", &self.get_remote_path(project_name)])?;
+        Ok(output.map(|output| lines(&output)).unwrap_or_default())
+    }
+}
+
+impl Device for RcloneDevice {
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_location(&self) -> String {
+        self.get_remote_path("")
+    }
+
+    fn get_security_level(&self) -> SecurityLevel {
+        SecurityLevel::NetworkUntrustedRestricted
+    }
+
+    fn get_device_type_name(&self) -> String {
+        "Rclone".to_string()
+    }
+
+    fn get_last_connection(&self) -> Option<Instant> {
+        None
+    }
+
+    fn get_last_disconnection(&self) -> Option<Instant> {
+        None
+    }
+
+    fn get_last_seen(&self) -> Option<SystemTime> {
+        self.last_seen
+    }
+
+    fn set_last_seen(&mut self, last_seen: SystemTime) {
+        self.last_seen = Some(last_seen);
+    }
+
+    fn supports_capability(&self, capability: DeviceCapability) -> bool {
+        match capability {
+            DeviceCapability::Streaming | DeviceCapability::PartialRestore => true,
+            DeviceCapability::Encryption
+            | DeviceCapability::Reflink
+            | DeviceCapability::SparseFiles => false,
+        }
+    }
+
+    fn to_toml_table(&self) -> toml::value::Table {
+        let mut table = toml::value::Table::new();
+        table.insert("type".to_string(), self.get_device_type_name().into());
+        table.insert("name".to_string(), self.get_name().into());
+        table.insert("remote".to_string(), self.remote.clone().into());
+        table.insert("bucket".to_string(), self.bucket.clone().into());
+        if !self.prefix.is_empty() {
+            table.insert("prefix".to_string(), self.prefix.clone().into());
+        }
+        if let Some(last_seen) = self.last_seen.and_then(|t| t.ms_since_epoch().ok()) {
+            table.insert("last_seen".to_string(), (last_seen as i64).into());
+        }
+        table
+    }
+
+    fn read_backup_index(&self, project_name: &str) -> Result<Option<Box<dyn BufRead>>, String> {
+        // On bucket based remotes, reading a missing file is not always an error
+        if !self
+            .list_project_files(project_name)?
+            .iter()
+            .any(|name| name == INDEX_FILE_NAME)
+        {
+            return Ok(None);
+        }
+
+        let index_path = self.get_project_file_path(project_name, INDEX_FILE_NAME);
+        let index = run_rclone(&["cat", &index_path])?;
+        Ok(index.map(|data| Box::new(Cursor::new(data)) as Box<dyn BufRead>))
+    }
+
+    fn write_backup_index(&self, project_name: &str, index: &[u8]) -> Result<(), String> {
+        let index_path = self.get_project_file_path(project_name, INDEX_FILE_NAME);
+        upload_with_rclone(&index_path, index)
+    }
+
+    // The bucket may not exist before the first backup, reaching the remote is enough
+    fn test_availability(&self) -> Result<(), String> {
+        run_rclone(&["lsf", "--max-depth", "1", &self.get_remote_path("")]).map(|_| ())
+    }
+
+    fn test_write_permission(&self) -> Result<(), String> {
+        let test_file_path = self.get_remote_path(WRITE_TEST_FILE_NAME);
+        run_rclone(&["touch", &test_file_path])?;
+        run_rclone(&["deletefile", &test_file_path]).map(|_| ())
+    }
+
+    // Not all remotes report their free space
+    fn get_free_space(&self) -> Option<u64> {
+        let output = run_rclone(&["about", "--json", &format!("{}:", self.remote)]).ok()??;
+        let about = serde_json::from_slice::<serde_json::Value>(&output).ok()?;
+        about.get("free")?.as_u64()
+    }
+
+    fn list_archives(&self, project_name: &str) -> Result<Vec<String>, String> {
+        let archive_names = self
+            .list_project_files(project_name)?
+            .into_iter()
+            .filter(|name| get_archive_timestamp(name).is_some())
+            .sorted_by_key(|name| get_archive_timestamp(name))
+            .collect();
+        Ok(archive_names)
+    }
+
+    // Listed in a single call, as each rclone call may be a network round trip
+    fn list_backed_up_projects(&self) -> Result<Vec<String>, String> {
+        let base_path = self.get_remote_path("");
+        let args = ["lsf", "-R", "--max-depth", "2", "--files-only", &base_path];
+        let Some(output) = run_rclone(&args)? else {
+            return Ok(vec![]);
+        };
+
+        let project_names = lines(&output)
+            .iter()
+            .filter_map(|path| path.split_once('/'))
+            .filter(|(_, name)| *name == INDEX_FILE_NAME || get_archive_timestamp(name).is_some())
+            .map(|(project_name, _)| project_name.to_string())
+            .sorted()
+            .dedup()
+            .collect();
+        Ok(project_names)
+    }
+
+    // Archives have always been named after their timestamp only on rclone remotes
+    fn migrate_archives(&self, _project_name: &str) -> Result<usize, String> {
+        Ok(0)
+    }
+
+    fn get_archive_time(&self, archive_name: &str) -> Option<SystemTime> {
+        get_archive_timestamp(archive_name).map(|ms| UNIX_EPOCH + Duration::from_millis(ms as u64))
+    }
+
+    fn get_last_backup_time(&self, project_name: &str) -> Result<Option<SystemTime>, String> {
+        let last_backup_time = self
+            .list_archives(project_name)?
+            .pop()
+            .and_then(|name| self.get_archive_time(&name));
+
+        Ok(last_backup_time)
+    }
+
+    fn get_archive_reader(
+        &self,
+        project_name: &str,
+        archive_name: &str,
+    ) -> Result<Box<dyn Read>, String> {
+        if get_archive_timestamp(archive_name).is_none() {
+            return Err(format!("Not a tar or tar.gz archive: {}", archive_name));
+        }
+
+        let archive_path = self.get_project_file_path(project_name, archive_name);
+        let download = RcloneDownload::start(&archive_path).map_err(|e| e.to_string())?;
+        Ok(if archive_name.ends_with(ARCHIVE_EXTENSION) {
+            Box::new(GzDecoder::new(download))
+        } else {
+            Box::new(download)
+        })
+    }
+
+    fn create_project_directory(&self, project_name: &str) -> Result<(), String> {
+        run_rclone(&["mkdir", &self.get_remote_path(project_name)]).map(|_| ())
+    }
+
+    fn rename_project_directory(&self, project_name: &str, new_name: &str) -> Result<(), String> {
+        run_rclone(&[
+            "moveto",
+            &self.get_remote_path(project_name),
+            &self.get_remote_path(new_name),
+        ])
+        .map(|_| ())
+    }
+
+    fn delete_project_data(&self, project_name: &str) -> Result<(), String> {
+        let project_path = self.get_remote_path(project_name);
+        info!("Deleting backups of {} in {}", project_name, project_path);
+        run_rclone(&["purge", &project_path]).map(|_| ())
+    }
+
+    // Finding the archives superseded by newer ones would require downloading them all
+    fn prune_archives(&self, project_name: &str, _max_archives: usize) -> Result<usize, String> {
+        warn!(
+            "Archives of {} on {} are not pruned, rclone remotes keep all their archives",
+            project_name, self.name
+        );
+        Ok(0)
+    }
+
+    // Killing rclone aborts the upload, no partial archive is left on the remote to keep
+    fn get_archive_writer(
+        &self,
+        project_name: &str,
+        options: &ArchiveWriterOptions,
+    ) -> Box<dyn ArchiveWriter> {
+        let extension = if options.compress {
+            ARCHIVE_EXTENSION
+        } else {
+            UNCOMPRESSED_ARCHIVE_EXTENSION
+        };
+        let archive_name = format!("{}{}", now!().ms_since_epoch().unwrap(), extension);
+        Box::new(RcloneArchiveWriter {
+            archive_path: self.get_project_file_path(project_name, &archive_name),
+            index_path: self.get_project_file_path(project_name, INDEX_FILE_NAME),
+            compress: options.compress,
+            compression_format: options.compression_format,
+            upload: None,
+            finalized: false,
+            bytes_written: 0,
+        })
+    }
+
+    fn get_extractor(&self, project_name: &str) -> Box<dyn Extractor> {
+        let archive_names = self.list_archives(project_name).unwrap_or_else(|e| {
+            warn!("Cannot list the archives of {}: {}", project_name, e);
+            vec![]
+        });
+        let archive_paths = archive_names
+            .iter()
+            .map(|name| self.get_project_file_path(project_name, name))
+            .collect::<Vec<_>>();
+        Box::new(RcloneExtractor {
+            index_from_end: archive_paths.len(),
+            archive_paths,
+            index_from_start: 0,
+        })
+    }
+}
+
+// Archives are named after the time of the backup in ms since epoch, as a tar.gz
+// file (<timestamp>.tar.gz) or an uncompressed tar file (<timestamp>.tar)
+fn get_archive_timestamp(file_name: &str) -> Option<u128> {
+    let timestamp = file_name
+        .strip_suffix(ARCHIVE_EXTENSION)
+        .or_else(|| file_name.strip_suffix(UNCOMPRESSED_ARCHIVE_EXTENSION))?;
+    if !timestamp.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    timestamp.parse().ok()
+}
+
+fn lines(output: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(output)
+        .lines()
+        .map(|line| line.to_string())
+        .collect()
+}
+
+// Run rclone and return its output, None when the remote path does not exist
+fn run_rclone(args: &[&str]) -> Result<Option<Vec<u8>>, String> {
+    debug!("Running rclone {}", args.join(" "));
+    let output = Command::new("rclone")
+        .args(args)
+        .output()
+        .map_err(|e| format!("Cannot run rclone: {}", e))?;
+    match output.status.code() {
+        Some(0) => Ok(Some(output.stdout)),
+        Some(RCLONE_DIRECTORY_NOT_FOUND | RCLONE_FILE_NOT_FOUND) => Ok(None),
+        _ => Err(format!(
+            "rclone {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )),
+    }
+}
+
+// Streamed commands let rclone write its errors to the terminal, as a full stderr
+// pipe would block it in the middle of a transfer
+fn spawn_rclone(args: &[&str], stdin: Stdio, stdout: Stdio) -> io::Result<Child> {
+    debug!("Running rclone {}", args.join(" "));
+    Command::new("rclone")
+        .args(args)
+        .stdin(stdin)
+        .stdout(stdout)
+        .spawn()
+}
+
+fn check_rclone_status(command: &str, status: ExitStatus) -> io::Result<()> {
+    if status.success() {
+        return Ok(());
+    }
+    Err(io::Error::other(format!(
+        "rclone {} failed with {}",
+        command, status
+    )))
+}
+
+// Write data to a remote file with rclone rcat
+fn upload_with_rclone(remote_path: &str, data: &[u8]) -> Result<(), String> {
+    let upload = || {
+        let mut child = spawn_rclone(&["rcat", remote_path], Stdio::piped(), Stdio::null())?;
+        // Dropping the standard input closes it, so that rclone completes the upload
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(data)?;
+        }
+        check_rclone_status("rcat", child.wait()?)
+    };
+    upload().map_err(|e| format!("Cannot upload {}: {}", remote_path, e))
+}
+
+// Content of a remote file, streamed from rclone cat
+struct RcloneDownload {
+    child: Child,
+    stdout: ChildStdout,
+}
+
+impl RcloneDownload {
+    fn start(remote_path: &str) -> io::Result<RcloneDownload> {
+        let mut child = spawn_rclone(&["cat", remote_path], Stdio::null(), Stdio::piped())?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| io::Error::other("Cannot read the output of rclone"))?;
+        Ok(RcloneDownload { child, stdout })
+    }
+}
+
+impl Read for RcloneDownload {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.stdout.read(buf)?;
+
+        // The end of the output is a complete file only if rclone succeeded
+        if read == 0 && !buf.is_empty() {
+            check_rclone_status("cat", self.child.wait()?)?;
+        }
+        Ok(read)
+    }
+}
+
+impl Drop for RcloneDownload {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+// Data sent to the standard input of rclone rcat
+enum UploadStream {
+    Compressed(GzEncoder<ChildStdin>),
+    Plain(ChildStdin),
+}
+
+impl UploadStream {
+    // Dropping the standard input once finished closes it, so that rclone completes the upload
+    fn finish(self) -> io::Result<()> {
+        match self {
+            UploadStream::Compressed(encoder) => encoder.finish().map(|_| ()),
+            UploadStream::Plain(_) => Ok(()),
+        }
+    }
+}
+
+impl Write for UploadStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            UploadStream::Compressed(encoder) => encoder.write(buf),
+            UploadStream::Plain(stdin) => stdin.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            UploadStream::Compressed(encoder) => encoder.flush(),
+            UploadStream::Plain(stdin) => stdin.flush(),
+        }
+    }
+}
+
+pub struct RcloneArchiveWriter {
+    archive_path: String,
+    index_path: String,
+    compress: bool,
+    // Only gzip is supported, archives are rejected in other formats
+    compression_format: CompressionFormat,
+
+    // The rclone rcat process receiving the archive, started with the first entry
+    upload: Option<(Child, tar::Builder<UploadStream>)>,
+    finalized: bool,
+    bytes_written: u64,
+}
+
+impl RcloneArchiveWriter {
+    fn initialize(&mut self) -> Result<&mut tar::Builder<UploadStream>, ArchiveError> {
+        if self.finalized {
+            return Err(ArchiveError::from("Archive has already been finalized"));
+        }
+        if self.compress && self.compression_format != CompressionFormat::Gz {
+            return Err(ArchiveError::from(
+                format!(
+                    "Rclone devices only store gzip archives, not {}",
+                    self.compression_format.archive_extension()
+                )
+                .as_str(),
+            ));
+        }
+
+        if self.upload.is_none() {
+            info!("Uploading archive to {}", self.archive_path);
+            let mut child =
+                spawn_rclone(&["rcat", &self.archive_path], Stdio::piped(), Stdio::null())?;
+            let stdin = child
+                .stdin
+                .take()
+                .ok_or_else(|| ArchiveError::from("Cannot write to rclone"))?;
+            let stream = if self.compress {
+                UploadStream::Compressed(GzEncoder::new(stdin, Compression::default()))
+            } else {
+                UploadStream::Plain(stdin)
+            };
+            self.upload = Some((child, tar::Builder::new(stream)));
+        }
+
+        self.upload
+            .as_mut()
+            .map(|(_, builder)| builder)
+            .ok_or_else(|| ArchiveError::from("Tar builder is missing"))
+    }
+
+    fn add_file_from_bytes(&mut self, data: &[u8], path: &Path) -> Result<(), ArchiveError> {
+        let mut header = tar::Header::new_gnu();
+        header.set_path(path)?;
+        header.set_size(data.len() as u64);
+        header.set_mtime(now!().s_since_epoch()?);
+        header.set_mode(0o644);
+        header.set_cksum();
+
+        self.initialize()?.append(&header, data)?;
+        Ok(())
+    }
+
+    fn finalize_upload(
+        &mut self,
+        deleted_files: &[PathBuf],
+        new_index: &[u8],
+        tags: &[String],
+    ) -> Result<(), ArchiveError> {
+        info!("Finalizing archive to {}", self.archive_path);
+
+        let deleted_files_data = deleted_files
+            .iter()
+            .map(|path| path.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("\n");
+        self.add_file_from_bytes(deleted_files_data.as_bytes(), Path::new(".deleted-files"))?;
+        self.add_file_from_bytes(new_index, Path::new(".index"))?;
+        if !tags.is_empty() {
+            self.add_file_from_bytes(tags.join("\n").as_bytes(), Path::new(TAGS_FILE_NAME))?;
+        }
+
+        let (mut child, builder) = self
+            .upload
+            .take()
+            .ok_or_else(|| ArchiveError::from("Tar builder is missing"))?;
+        let result = builder
+            .into_inner()
+            .and_then(UploadStream::finish)
+            .and_then(|_| check_rclone_status("rcat", child.wait()?));
+        if let Err(e) = result {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(e.into());
+        }
+
+        // Save the index for quick access to the latest version
+        upload_with_rclone(&self.index_path, new_index)
+            .map_err(|e| ArchiveError::from(e.as_str()))?;
+        self.finalized = true;
+        Ok(())
+    }
+
+    // Stop rclone before the tar builder is dropped, as it would end the archive
+    // and let rclone upload the incomplete archive as a valid one
+    fn abort_upload(&mut self) {
+        let Some((mut child, builder)) = self.upload.take() else {
+            return;
+        };
+        warn!("Aborting the upload of {}", self.archive_path);
+        let _ = child.kill();
+        let _ = child.wait();
+        drop(builder);
+
+        if let Err(e) = run_rclone(&["deletefile", &self.archive_path]) {
+            warn!("Cannot delete partial archive {}: {}", self.archive_path, e);
+        }
+    }
+}
+
+impl ArchiveWriter for RcloneArchiveWriter {
+    fn add_file(
+        &mut self,
+        file: &mut File,
+        path: &PathBuf,
+        _ctime: u128,
+        _mtime: u128,
+        size: u64,
+    ) -> Result<(), ArchiveError> {
+        self.initialize()?
+            .append_file(Path::join(Path::new(".files"), path), file)?;
+        self.bytes_written += size;
+        debug!("Adding file {:?} to {}", path, self.archive_path);
+        Ok(())
+    }
+
+    fn add_directory(
+        &mut self,
+        src_path: &Path,
+        path: &PathBuf,
+        _ctime: u128,
+        _mtime: u128,
+    ) -> Result<(), ArchiveError> {
+        self.initialize()?
+            .append_dir(Path::join(Path::new(".files"), path), src_path)?;
+        debug!("Adding directory {:?} to {}", path, self.archive_path);
+        Ok(())
+    }
+
+    fn add_symlink(
+        &mut self,
+        path: &PathBuf,
+        _ctime: u128,
+        _mtime: u128,
+        _target: &PathBuf,
+    ) -> Result<(), ArchiveError> {
+        debug!("Adding symlink {:?} to {}", path, self.archive_path);
+        Ok(())
+    }
+
+    fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    fn finalize(
+        &mut self,
+        deleted_files: &Vec<PathBuf>,
+        new_index: &Vec<u8>,
+        tags: &[String],
+    ) -> Result<(), ArchiveError> {
+        let result = self.finalize_upload(deleted_files, new_index, tags);
+        if result.is_err() {
+            self.abort_upload();
+        }
+        result
+    }
+}
+
+// Backups failing before finalization leave an upload in progress behind
+impl Drop for RcloneArchiveWriter {
+    fn drop(&mut self) {
+        if !self.finalized {
+            self.abort_upload();
+        }
+    }
+}
+
+pub struct RcloneExtractor {
+    archive_paths: Vec<String>,
+    index_from_start: usize,
+    index_from_end: usize,
+}
+
+impl Iterator for RcloneExtractor {
+    type Item = Box<dyn DifferentialArchiveStep>;
+
+    fn next(&mut self) -> Option<Box<dyn DifferentialArchiveStep>> {
+        if self.index_from_start >= self.index_from_end {
+            return None;
+        }
+
+        let archive_path = self.archive_paths[self.index_from_start].clone();
+        self.index_from_start += 1;
+
+        Some(Box::new(RcloneDifferentialArchiveStep { archive_path }))
+    }
+}
+
+impl DoubleEndedIterator for RcloneExtractor {
+    fn next_back(&mut self) -> Option<Box<dyn DifferentialArchiveStep>> {
+        if self.index_from_end <= self.index_from_start {
+            return None;
+        }
+
+        self.index_from_end -= 1;
+        let archive_path = self.archive_paths[self.index_from_end].clone();
+
+        Some(Box::new(RcloneDifferentialArchiveStep { archive_path }))
+    }
+}
+
+impl Extractor for RcloneExtractor {}
+
+pub struct RcloneDifferentialArchiveStep {
+    archive_path: String,
+}
+
+impl RcloneDifferentialArchiveStep {
+    fn open_archive(&self) -> io::Result<Box<dyn Read>> {
+        let download = RcloneDownload::start(&self.archive_path)?;
+        Ok(if self.archive_path.ends_with(ARCHIVE_EXTENSION) {
+            Box::new(GzDecoder::new(download))
+        } else {
+            Box::new(download)
+        })
+    }
+}
+
+impl DifferentialArchiveStep for RcloneDifferentialArchiveStep {
+    fn get_step_name(&self) -> &str {
+        &self.archive_path
+    }
+
+    // The whole archive is downloaded to find its tags
+    fn get_tags(&self) -> Vec<String> {
+        self.open_archive()
+            .and_then(read_archive_tags)
+            .unwrap_or_else(|e| {
+                warn!("Cannot read the tags of {}: {}", self.archive_path, e);
+                vec![]
+            })
+    }
+
+    fn extract_to(
+        &self,
+        to: &PathBuf,
+        paths_to_extract: &HashSet<PathBuf>,
+    ) -> Result<HashSet<PathBuf>, ExtractorError> {
+        info!("Downloading archive {}", self.archive_path);
+        extract_archive_files(self.open_archive()?, to, paths_to_extract)
+    }
+}
+
+pub struct RcloneDeviceFactory {
+    remote_question: Question,
+    bucket_question: Question,
+    prefix_question: Question,
+    name_question: Question,
+    step: u8,
+}
+
+impl RcloneDeviceFactory {
+    pub fn new() -> RcloneDeviceFactory {
+        RcloneDeviceFactory {
+            remote_question: Question::new(
+                "What is the name of the rclone remote?".to_string(),
+                QuestionType::String,
+            ),
+            bucket_question: Question::new(
+                "In which bucket (or top folder) of the remote shall the backups be stored?"
+                    .to_string(),
+                QuestionType::String,
+            ),
+            prefix_question: Question::new(
+                "Under which path of the bucket? (leave empty for its root)".to_string(),
+                QuestionType::String,
+            ),
+            name_question: Question::new(
+                "How would you name this device?".to_string(),
+                QuestionType::String,
+            ),
+            step: 0,
+        }
+    }
+
+    fn get_current_question(&self) -> &Question {
+        match self.step {
+            0 => &self.remote_question,
+            1 => &self.bucket_question,
+            2 => &self.prefix_question,
+            3 => &self.name_question,
+            _ => panic!("No more questions"),
+        }
+    }
+}
+
+const RCLONE_DESCRIPTION: &str = "\
+A remote of rclone, giving access to any cloud storage it supports (Google Drive, Dropbox,
+Backblaze B2, S3...). rclone must be installed, and the remote configured with `rclone config`.
+Backups are streamed to the remote as compressed tar archives, without local copy.
+The remote is considered as an untrusted cloud provider: its security level is NetworkUntrustedRestricted.";
+
+impl DeviceFactory for RcloneDeviceFactory {
+    fn get_description(&self) -> &str {
+        RCLONE_DESCRIPTION
+    }
+
+    fn get_question_statement(&self) -> &str {
+        self.get_current_question().get_statement()
+    }
+
+    fn get_question_type(&self) -> &QuestionType {
+        self.get_current_question().get_question_type()
+    }
+
+    fn validate_answer(&self, answer: &str) -> Result<(), String> {
+        if !self.has_next() {
+            return Err("No more questions".to_string());
+        }
+        match self.step {
+            0 if answer.trim_end_matches(':').is_empty() => {
+                Err("The remote name shall not be empty".to_string())
+            }
+            1 if answer.is_empty() => Err("The bucket shall not be empty".to_string()),
+            _ => self.get_current_question().validate_answer(answer),
+        }
+    }
+
+    fn set_question_answer(&mut self, answer: String) -> Result<(), String> {
+        self.validate_answer(&answer)?;
+        let status = match self.step {
+            // Remotes are often written as in rclone paths, with their colon
+            0 => self
+                .remote_question
+                .set_answer(answer.trim_end_matches(':').to_string()),
+            1 => self.bucket_question.set_answer(answer),
+            2 => self.prefix_question.set_answer(answer),
+            3 => self.name_question.set_answer(answer),
+            _ => panic!("No more questions"),
+        };
+
+        status?;
+        self.step += 1;
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.remote_question.clear_answer();
+        self.bucket_question.clear_answer();
+        self.prefix_question.clear_answer();
+        self.name_question.clear_answer();
+        self.step = 0;
+    }
+
+    fn has_next(&self) -> bool {
+        self.step < 4
+    }
+
+    fn build(&self) -> Result<Box<dyn Device>, String> {
+        if self.step < 4 {
+            return Err("Not all questions have been answered".to_string());
+        }
+
+        let remote = self.remote_question.get_answer()?;
+        let name = self.name_question.get_answer()?;
+        let name = if name.is_empty() {
+            remote.clone()
+        } else {
+            name
+        };
+        Ok(Box::new(RcloneDevice {
+            name,
+            remote,
+            bucket: self.bucket_question.get_answer()?,
+            prefix: self.prefix_question.get_answer()?,
+            last_seen: None,
+        }))
+    }
+
+    fn build_from_toml_table(
+        &self,
+        name: &str,
+        table: &toml::value::Table,
+    ) -> Result<Box<dyn Device>, String> {
+        let read_string = |key: &str| {
+            table
+                .get(key)
+                .map(|value| {
+                    value
+                        .as_str()
+                        .map(|s| s.to_string())
+                        .ok_or_else(|| format!("Invalid string for '{}'", key))
+                })
+                .transpose()
+        };
+        let remote = read_string("remote")?.ok_or_else(|| "missing field `remote`".to_string())?;
+        let bucket = read_string("bucket")?.ok_or_else(|| "missing field `bucket`".to_string())?;
+        let prefix = read_string("prefix")?.unwrap_or_default();
+        let last_seen = table
+            .get("last_seen")
+            .map(|value| {
+                value
+                    .as_integer()
+                    .and_then(|ms| u64::try_from(ms).ok())
+                    .map(|ms| UNIX_EPOCH + Duration::from_millis(ms))
+                    .ok_or_else(|| "Invalid timestamp for 'last_seen'".to_string())
+            })
+            .transpose()?;
+
+        Ok(Box::new(RcloneDevice {
+            name: name.to_string(),
+            remote,
+            bucket,
+            prefix,
+            last_seen,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn device(prefix: &str) -> RcloneDevice {
+        RcloneDevice {
+            name: "MyDrive".to_string(),
+            remote: "gdrive".to_string(),
+            bucket: "backups".to_string(),
+            prefix: prefix.to_string(),
+            last_seen: None,
+        }
+    }
+
+    #[test]
+    fn remote_paths_shall_join_bucket_prefix_and_path() {
+        assert_eq!(
+            device("").get_project_file_path("MyProject", "current.index"),
+            "gdrive:backups/MyProject/current.index"
+        );
+        assert_eq!(
+            device("/hibernacli/").get_remote_path("MyProject"),
+            "gdrive:backups/hibernacli/MyProject"
+        );
+        assert_eq!(device("").get_location(), "gdrive:backups");
+    }
+
+    #[test]
+    fn archives_compressed_with_another_format_than_gzip_shall_be_rejected() {
+        let options = ArchiveWriterOptions {
+            compression_format: CompressionFormat::Zstd,
+            ..Default::default()
+        };
+        let mut archive_writer = device("").get_archive_writer("MyProject", &options);
+
+        let error = archive_writer.finalize(&vec![], &vec![], &[]).unwrap_err();
+        assert_eq!(
+            error.message,
+            "Rclone devices only store gzip archives, not .tar.zst"
+        );
+    }
+
+    #[test]
+    fn archive_timestamps_shall_be_read_from_archive_names() {
+        assert_eq!(get_archive_timestamp("100.tar.gz"), Some(100));
+        assert_eq!(get_archive_timestamp("100.tar"), Some(100));
+        assert_eq!(get_archive_timestamp("100-0a1b2c3d.tar.gz"), None);
+        assert_eq!(get_archive_timestamp("current.index"), None);
+    }
+
+    #[test]
+    fn when_creating_device_from_toml_it_shall_serialize_it_back() {
+        let factory = RcloneDeviceFactory::new();
+        let mut table = toml::value::Table::new();
+        table.insert("remote".to_string(), "gdrive".into());
+        table.insert("bucket".to_string(), "backups".into());
+        table.insert("prefix".to_string(), "hibernacli".into());
+        table.insert("last_seen".to_string(), 1000.into());
+
+        let device = factory.build_from_toml_table("MyDrive", &table).unwrap();
+        assert_eq!(
+            device.get_security_level(),
+            SecurityLevel::NetworkUntrustedRestricted
+        );
+        assert_eq!(
+            toml::to_string(&device.to_toml_table()).unwrap(),
+            r#"bucket = "backups"
+last_seen = 1000
+name = "MyDrive"
+prefix = "hibernacli"
+remote = "gdrive"
+type = "Rclone"
+"#
+        );
+    }
+
+    #[test]
+    fn when_creating_device_from_toml_without_bucket_it_shall_fail() {
+        let factory = RcloneDeviceFactory::new();
+        let mut table = toml::value::Table::new();
+        table.insert("remote".to_string(), "gdrive".into());
+
+        let result = factory.build_from_toml_table("MyDrive", &table);
+        assert_eq!(result.err().unwrap(), "missing field `bucket`");
+    }
+
+    #[test]
+    fn when_answering_all_questions_it_shall_build_the_device() {
+        let mut factory = RcloneDeviceFactory::new();
+        assert_eq!(
+            factory.validate_answer(":"),
+            Err("The remote name shall not be empty".to_string())
+        );
+        for answer in ["gdrive:", "backups", "", ""] {
+            factory.set_question_answer(answer.to_string()).unwrap();
+        }
+
+        assert!(!factory.has_next());
+        let device = factory.build().unwrap();
+        assert_eq!(device.get_name(), "gdrive");
+        assert_eq!(device.get_location(), "gdrive:backups");
+    }
+}
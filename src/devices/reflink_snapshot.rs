@@ -0,0 +1,398 @@
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io,
+    num::NonZeroU64,
+    path::{Path, PathBuf},
+};
+
+use flate2::Compression;
+use reflink_copy::ReflinkBlockBuilder;
+use tracing::{debug, info, warn};
+use walkdir::WalkDir;
+
+use crate::core::{
+    util::sparse_files::SparseSegment, ArchiveError, ArchiveWriter, CompressionFormat,
+    DifferentialArchiveStep, ExtractorError,
+};
+
+use super::mounted_folder::{parse_tags, MountedFolderArchiveWriter, TAGS_FILE_NAME};
+
+/// Archive writer storing a backup step as a directory tree of reflink copies
+///
+/// On filesystems supporting reflinks (btrfs, XFS), copies share their blocks with
+/// the original files, so they are instantaneous and do not use additional space.
+/// Reflink support is detected on the first added file: if the filesystem does not
+/// support it, the already added directories are replayed into a regular tar
+/// archive, which is then used for the rest of the backup.
+pub struct ReflinkSnapshotArchiveWriter {
+    path: PathBuf,
+    project_dir: PathBuf,
+    snapshot_path: PathBuf,
+    tar_fallback: MountedFolderArchiveWriter,
+    reflink_supported: Option<bool>,
+    added_directories: Vec<(PathBuf, PathBuf, u128, u128)>,
+    finalized: bool,
+
+    // Size of the reflink copies, the tar fallback counting its own files
+    bytes_written: u64,
+}
+
+impl ReflinkSnapshotArchiveWriter {
+    pub fn new(
+        path: PathBuf,
+        project_dir: PathBuf,
+        snapshot_path: PathBuf,
+        tar_archive_path: PathBuf,
+        compression: Compression,
+        compress: bool,
+    ) -> ReflinkSnapshotArchiveWriter {
+        ReflinkSnapshotArchiveWriter {
+            tar_fallback: MountedFolderArchiveWriter::new(
+                path.clone(),
+                project_dir.clone(),
+                tar_archive_path,
+                compression,
+                compress,
+            ),
+            path,
+            project_dir,
+            snapshot_path,
+            reflink_supported: None,
+            added_directories: Vec::new(),
+            finalized: false,
+            bytes_written: 0,
+        }
+    }
+
+    // Format of the tar archive written when reflinks are not supported
+    pub fn with_compression_format(mut self, compression_format: CompressionFormat) -> Self {
+        self.tar_fallback = self
+            .tar_fallback
+            .with_compression_format(compression_format);
+        self
+    }
+
+    fn get_files_path(&self) -> PathBuf {
+        Path::join(&self.snapshot_path, ".files")
+    }
+
+    fn initialize(&mut self) -> Result<(), ArchiveError> {
+        if self.finalized {
+            return Err(ArchiveError::from("Archive has already been finalized"));
+        }
+
+        if !self.snapshot_path.exists() {
+            fs::create_dir_all(self.get_files_path())?;
+        }
+        Ok(())
+    }
+
+    fn reflink_file(&self, file: &File, path: &Path, size: u64) -> io::Result<()> {
+        let destination_path = Path::join(&self.get_files_path(), path);
+        if let Some(parent) = destination_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let destination = File::create_new(&destination_path)?;
+        let result = match NonZeroU64::new(size) {
+            Some(size) => ReflinkBlockBuilder::new(file, &destination, size).reflink_block(),
+            None => Ok(()),
+        };
+
+        if result.is_err() {
+            fs::remove_file(&destination_path)?;
+        }
+        result
+    }
+
+    // Replay everything added so far into the tar archive, and drop the snapshot
+    fn fall_back_to_tar(&mut self) -> Result<(), ArchiveError> {
+        warn!(
+            "Reflinks are not supported on {:?}, falling back to tar archive",
+            self.path
+        );
+        for (src_path, path, ctime, mtime) in self.added_directories.drain(..) {
+            self.tar_fallback
+                .add_directory(&src_path, &path, ctime, mtime)?;
+        }
+        fs::remove_dir_all(&self.snapshot_path)?;
+        self.reflink_supported = Some(false);
+        Ok(())
+    }
+}
+
+fn is_reflink_unsupported_error(error: &io::Error) -> bool {
+    // EOPNOTSUPP: filesystem without reflink support
+    // EXDEV: source and destination on different filesystems
+    // EINVAL: filesystem refusing the clone request
+    matches!(
+        error.kind(),
+        io::ErrorKind::Unsupported | io::ErrorKind::CrossesDevices | io::ErrorKind::InvalidInput
+    )
+}
+
+impl ArchiveWriter for ReflinkSnapshotArchiveWriter {
+    fn add_file(
+        &mut self,
+        file: &mut File,
+        path: &PathBuf,
+        ctime: u128,
+        mtime: u128,
+        size: u64,
+    ) -> Result<(), ArchiveError> {
+        if self.reflink_supported == Some(false) {
+            return self.tar_fallback.add_file(file, path, ctime, mtime, size);
+        }
+
+        self.initialize()?;
+        match self.reflink_file(file, path, size) {
+            Ok(()) => {
+                self.reflink_supported = Some(true);
+                self.bytes_written += size;
+                debug!(
+                    "Adding file {:?} to {:?} secondary device snapshot",
+                    path, self.path
+                );
+                Ok(())
+            }
+            Err(e) if self.reflink_supported.is_none() && is_reflink_unsupported_error(&e) => {
+                self.fall_back_to_tar()?;
+                self.tar_fallback.add_file(file, path, ctime, mtime, size)
+            }
+            Err(e) => Err(ArchiveError::from(e)),
+        }
+    }
+
+    fn add_sparse_file(
+        &mut self,
+        file: &mut File,
+        path: &PathBuf,
+        ctime: u128,
+        mtime: u128,
+        size: u64,
+        data_segments: &[SparseSegment],
+    ) -> Result<(), ArchiveError> {
+        if self.reflink_supported == Some(false) {
+            return self.tar_fallback.add_sparse_file(
+                file,
+                path,
+                ctime,
+                mtime,
+                size,
+                data_segments,
+            );
+        }
+
+        // Reflink copies keep the holes of the original file
+        self.add_file(file, path, ctime, mtime, size)
+    }
+
+    fn add_extended_attributes(
+        &mut self,
+        path: &PathBuf,
+        xattrs: Vec<(String, Vec<u8>)>,
+    ) -> Result<(), ArchiveError> {
+        // Reflink copies are made without their extended attributes
+        if self.reflink_supported == Some(false) {
+            return self.tar_fallback.add_extended_attributes(path, xattrs);
+        }
+        Ok(())
+    }
+
+    fn add_directory(
+        &mut self,
+        src_path: &Path,
+        path: &PathBuf,
+        ctime: u128,
+        mtime: u128,
+    ) -> Result<(), ArchiveError> {
+        if self.reflink_supported == Some(false) {
+            return self
+                .tar_fallback
+                .add_directory(src_path, path, ctime, mtime);
+        }
+
+        self.initialize()?;
+        fs::create_dir_all(Path::join(&self.get_files_path(), path))?;
+        if self.reflink_supported.is_none() {
+            self.added_directories
+                .push((src_path.to_path_buf(), path.clone(), ctime, mtime));
+        }
+        debug!(
+            "Adding directory {:?} to {:?} secondary device snapshot",
+            path, self.path
+        );
+        Ok(())
+    }
+
+    fn add_symlink(
+        &mut self,
+        path: &PathBuf,
+        ctime: u128,
+        mtime: u128,
+        target: &PathBuf,
+    ) -> Result<(), ArchiveError> {
+        if self.reflink_supported == Some(false) {
+            return self.tar_fallback.add_symlink(path, ctime, mtime, target);
+        }
+
+        debug!(
+            "Adding symlink {:?} to {:?} secondary device",
+            path, self.path
+        );
+        Ok(())
+    }
+
+    fn bytes_written(&self) -> u64 {
+        self.bytes_written + self.tar_fallback.bytes_written()
+    }
+
+    fn finalize(
+        &mut self,
+        deleted_files: &Vec<PathBuf>,
+        new_index: &Vec<u8>,
+        tags: &[String],
+    ) -> Result<(), ArchiveError> {
+        if self.reflink_supported == Some(false) {
+            return self.tar_fallback.finalize(deleted_files, new_index, tags);
+        }
+
+        self.initialize()?;
+        info!("Finalizing snapshot to {:?}", self.snapshot_path);
+
+        // Save the list of deleted files and the new index within the snapshot
+        let deleted_files_data = deleted_files
+            .iter()
+            .map(|path| path.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("\n");
+        fs::write(
+            Path::join(&self.snapshot_path, ".deleted-files"),
+            deleted_files_data,
+        )?;
+        fs::write(Path::join(&self.snapshot_path, ".index"), new_index)?;
+        if !tags.is_empty() {
+            fs::write(
+                Path::join(&self.snapshot_path, TAGS_FILE_NAME),
+                tags.join("\n"),
+            )?;
+        }
+
+        // Save the index for quick access to the latest version
+        let current_index_path = Path::join(&self.project_dir, "current.index");
+        fs::write(current_index_path, new_index)?;
+
+        self.finalized = true;
+        Ok(())
+    }
+}
+
+pub struct ReflinkSnapshotDifferentialArchiveStep {
+    snapshot_path: PathBuf,
+}
+
+impl ReflinkSnapshotDifferentialArchiveStep {
+    pub fn new(snapshot_path: PathBuf) -> ReflinkSnapshotDifferentialArchiveStep {
+        ReflinkSnapshotDifferentialArchiveStep { snapshot_path }
+    }
+}
+
+impl DifferentialArchiveStep for ReflinkSnapshotDifferentialArchiveStep {
+    fn get_step_name(&self) -> &str {
+        self.snapshot_path.to_str().unwrap()
+    }
+
+    fn get_tags(&self) -> Vec<String> {
+        match fs::read_to_string(Path::join(&self.snapshot_path, TAGS_FILE_NAME)) {
+            Ok(tags) => parse_tags(&tags),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => vec![],
+            Err(e) => {
+                warn!("Cannot read the tags of {:?}: {}", self.snapshot_path, e);
+                vec![]
+            }
+        }
+    }
+
+    fn extract_to(
+        &self,
+        to: &PathBuf,
+        paths_to_extract: &HashSet<PathBuf>,
+    ) -> Result<HashSet<PathBuf>, ExtractorError> {
+        info!("Walking through snapshot {:?}", self.snapshot_path);
+        let files_path = Path::join(&self.snapshot_path, ".files");
+        let mut extracted_paths = HashSet::new();
+
+        for entry in WalkDir::new(&files_path).min_depth(1) {
+            let entry = entry.map_err(io::Error::from)?;
+            let path = entry.path().strip_prefix(&files_path)?.to_path_buf();
+            if !paths_to_extract.contains(&path) {
+                debug!("Skipping {:?}", path);
+                continue;
+            }
+
+            let destination_path = Path::join(to, &path);
+            if entry.file_type().is_dir() {
+                fs::create_dir_all(&destination_path)?;
+            } else if entry.file_type().is_file() {
+                if let Some(parent) = destination_path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                reflink_copy::reflink_or_copy(entry.path(), &destination_path)?;
+            } else {
+                continue;
+            }
+
+            extracted_paths.insert(path.clone());
+            debug!("Extracted {:?}", path);
+        }
+
+        Ok(extracted_paths)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::core::test_utils::fs::create_tmp_dir;
+
+    use super::*;
+
+    #[test]
+    fn when_extracting_a_snapshot_only_requested_paths_shall_be_extracted() {
+        let snapshot_path = Path::join(&create_tmp_dir(), "0.snapshot");
+        let files_path = Path::join(&snapshot_path, ".files");
+        fs::create_dir_all(Path::join(&files_path, "dir")).unwrap();
+        fs::write(Path::join(&files_path, "dir/file.txt"), "Hello").unwrap();
+        fs::write(Path::join(&files_path, "skipped.txt"), "World").unwrap();
+
+        let to = Path::join(&create_tmp_dir(), "restored");
+        let paths_to_extract = HashSet::from([PathBuf::from("dir"), PathBuf::from("dir/file.txt")]);
+        let extracted_paths = ReflinkSnapshotDifferentialArchiveStep::new(snapshot_path)
+            .extract_to(&to, &paths_to_extract)
+            .unwrap();
+
+        assert_eq!(extracted_paths, paths_to_extract);
+        assert_eq!(
+            fs::read_to_string(Path::join(&to, "dir/file.txt")).unwrap(),
+            "Hello"
+        );
+        assert!(!Path::join(&to, "skipped.txt").exists());
+    }
+
+    #[test]
+    fn finalizing_two_times_the_same_snapshot_shall_fail() {
+        let project_dir = Path::join(&create_tmp_dir(), "MyProject");
+        let mut archive_writer = ReflinkSnapshotArchiveWriter::new(
+            PathBuf::from("/"),
+            project_dir.clone(),
+            Path::join(&project_dir, "0.snapshot"),
+            Path::join(&project_dir, "0.tar"),
+            Compression::default(),
+            true,
+        );
+
+        archive_writer.finalize(&vec![], &vec![], &[]).unwrap();
+        let result = archive_writer.finalize(&vec![], &vec![], &[]).unwrap_err();
+        assert_eq!("Archive has already been finalized", result.message);
+    }
+}
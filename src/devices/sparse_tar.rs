@@ -0,0 +1,248 @@
+use std::{
+    fs::File,
+    io::{self, Cursor, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use tar::{Builder, EntryType, GnuExtSparseHeader, GnuSparseHeader, Header};
+
+use crate::core::util::sparse_files::SparseSegment;
+
+// Sparse maps are stored as octal numbers on 11 digits
+const MAX_SPARSE_VALUE: u64 = 0o77777777777;
+const SEGMENTS_IN_HEADER: usize = 4;
+const SEGMENTS_IN_EXTENSION: usize = 21;
+
+/// Append a sparse file to a tar archive using the GNU sparse format,
+/// only storing its data segments
+///
+/// Returns false if the file is too large to be described in this format,
+/// in which case nothing is written.
+pub fn append_sparse_file<W: io::Write>(
+    builder: &mut Builder<W>,
+    file: &mut File,
+    path: &Path,
+    size: u64,
+    data_segments: &[SparseSegment],
+) -> io::Result<bool> {
+    if size > MAX_SPARSE_VALUE {
+        return Ok(false);
+    }
+    let segments = align_segments(data_segments, size);
+
+    let mut header = Header::new_gnu();
+    header.set_metadata(&file.metadata()?);
+    header.set_entry_type(EntryType::GNUSparse);
+    header.set_size(segments.iter().map(|segment| segment.length).sum());
+
+    // The first segments are stored in the header, the others in extension
+    // headers written right after it
+    let (header_segments, extension_segments) =
+        segments.split_at(segments.len().min(SEGMENTS_IN_HEADER));
+    let mut extensions = extension_segments
+        .chunks(SEGMENTS_IN_EXTENSION)
+        .map(|chunk| {
+            let mut extension = GnuExtSparseHeader::new();
+            set_sparse_map(&mut extension.sparse, chunk);
+            extension.isextended[0] = 1;
+            extension
+        })
+        .collect::<Vec<_>>();
+    if let Some(last_extension) = extensions.last_mut() {
+        last_extension.isextended[0] = 0;
+    }
+
+    let gnu_header = header
+        .as_gnu_mut()
+        .ok_or_else(|| io::Error::other("Not a GNU header"))?;
+    set_octal(&mut gnu_header.realsize, size);
+    set_sparse_map(&mut gnu_header.sparse, header_segments);
+    gnu_header.isextended[0] = if extensions.is_empty() { 0 } else { 1 };
+
+    let extensions_data = extensions
+        .iter()
+        .flat_map(|extension| extension.as_bytes().to_vec())
+        .collect::<Vec<_>>();
+    let data = Cursor::new(extensions_data).chain(SparseDataReader::new(file, segments));
+    builder.append_data(&mut header, path, data)?;
+    Ok(true)
+}
+
+// Tar requires all data segments but the last one to be aligned on 512 bytes,
+// and the map to end at the size of the file
+fn align_segments(data_segments: &[SparseSegment], size: u64) -> Vec<SparseSegment> {
+    let mut segments: Vec<SparseSegment> = Vec::new();
+    for segment in data_segments {
+        let start = segment.offset / 512 * 512;
+        let end = (segment.offset + segment.length).div_ceil(512) * 512;
+        let end = end.min(size);
+
+        match segments.last_mut() {
+            Some(last) if last.offset + last.length >= start => {
+                last.length = end.max(last.offset + last.length) - last.offset;
+            }
+            _ => segments.push(SparseSegment {
+                offset: start,
+                length: end - start,
+            }),
+        }
+    }
+
+    let data_end = segments
+        .last()
+        .map_or(0, |segment| segment.offset + segment.length);
+    if data_end < size {
+        segments.push(SparseSegment {
+            offset: size,
+            length: 0,
+        });
+    }
+    segments
+}
+
+fn set_sparse_map(map: &mut [GnuSparseHeader], segments: &[SparseSegment]) {
+    for (entry, segment) in map.iter_mut().zip(segments) {
+        set_octal(&mut entry.offset, segment.offset);
+        set_octal(&mut entry.numbytes, segment.length);
+    }
+}
+
+fn set_octal(field: &mut [u8; 12], value: u64) {
+    field[..11].copy_from_slice(format!("{:011o}", value).as_bytes());
+    field[11] = 0;
+}
+
+// Read the data segments of a file one after the other
+struct SparseDataReader<'a> {
+    file: &'a mut File,
+    segments: std::vec::IntoIter<SparseSegment>,
+    remaining_in_segment: u64,
+}
+
+impl<'a> SparseDataReader<'a> {
+    fn new(file: &'a mut File, segments: Vec<SparseSegment>) -> Self {
+        SparseDataReader {
+            file,
+            segments: segments.into_iter(),
+            remaining_in_segment: 0,
+        }
+    }
+}
+
+impl Read for SparseDataReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.remaining_in_segment == 0 {
+            match self.segments.next() {
+                Some(segment) => {
+                    self.file.seek(SeekFrom::Start(segment.offset))?;
+                    self.remaining_in_segment = segment.length;
+                }
+                None => return Ok(0),
+            }
+        }
+
+        let max_length = buf.len().min(self.remaining_in_segment as usize);
+        let read = self.file.read(&mut buf[..max_length])?;
+        if read == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Sparse file was truncated while being archived",
+            ));
+        }
+        self.remaining_in_segment -= read as u64;
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, io::Write, path::PathBuf};
+
+    use crate::core::test_utils::fs::create_tmp_dir;
+
+    use super::*;
+
+    fn create_file_with_data_at(offsets: &[u64], size: u64) -> (PathBuf, File) {
+        let path = Path::join(&create_tmp_dir(), "sparse.bin");
+        let mut file = File::create(&path).unwrap();
+        file.set_len(size).unwrap();
+        for offset in offsets {
+            file.seek(SeekFrom::Start(*offset)).unwrap();
+            file.write_all(&[1u8; 512]).unwrap();
+        }
+        let file = File::open(&path).unwrap();
+        (path, file)
+    }
+
+    fn archive_and_read_back(file: &mut File, size: u64, segments: &[SparseSegment]) -> Vec<u8> {
+        let mut builder = Builder::new(Vec::new());
+        assert!(
+            append_sparse_file(&mut builder, file, Path::new("sparse.bin"), size, segments)
+                .unwrap()
+        );
+        let archive_data = builder.into_inner().unwrap();
+
+        let mut archive = tar::Archive::new(Cursor::new(archive_data));
+        let mut entry = archive.entries().unwrap().next().unwrap().unwrap();
+        assert_eq!(entry.header().entry_type(), EntryType::GNUSparse);
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content).unwrap();
+        content
+    }
+
+    #[test]
+    fn a_sparse_file_shall_be_read_back_with_its_holes() {
+        let (_, mut file) = create_file_with_data_at(&[4096], 16384);
+        let content = archive_and_read_back(
+            &mut file,
+            16384,
+            &[SparseSegment {
+                offset: 4096,
+                length: 512,
+            }],
+        );
+
+        let mut expected = vec![0u8; 16384];
+        expected[4096..4608].fill(1);
+        assert_eq!(content, expected);
+    }
+
+    #[test]
+    fn a_sparse_file_with_many_segments_shall_use_extension_headers() {
+        let offsets = (0..30).map(|i| i * 1024).collect::<Vec<_>>();
+        let segments = offsets
+            .iter()
+            .map(|offset| SparseSegment {
+                offset: *offset,
+                length: 512,
+            })
+            .collect::<Vec<_>>();
+        let (path, mut file) = create_file_with_data_at(&offsets, 40960);
+
+        let content = archive_and_read_back(&mut file, 40960, &segments);
+        assert_eq!(content, fs::read(path).unwrap());
+    }
+
+    #[test]
+    fn unaligned_segments_shall_be_extended_to_512_bytes_blocks() {
+        assert_eq!(
+            align_segments(
+                &[
+                    SparseSegment {
+                        offset: 100,
+                        length: 10
+                    },
+                    SparseSegment {
+                        offset: 600,
+                        length: 1000
+                    },
+                ],
+                1500
+            ),
+            vec![SparseSegment {
+                offset: 0,
+                length: 1500
+            }]
+        );
+    }
+}
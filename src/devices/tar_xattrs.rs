@@ -0,0 +1,104 @@
+use std::{io, path::Path};
+
+use tar::{Builder, EntryType, Header};
+
+// Prefix of the pax records holding extended attributes, as written by GNU tar and bsdtar
+const XATTR_RECORD_PREFIX: &str = "SCHILY.xattr.";
+
+/// Append a pax extended header holding extended attributes, applying to the
+/// entry appended right after it
+pub fn append_xattrs_header<W: io::Write>(
+    builder: &mut Builder<W>,
+    path: &Path,
+    xattrs: &[(String, Vec<u8>)],
+) -> io::Result<()> {
+    if xattrs.is_empty() {
+        return Ok(());
+    }
+
+    let data = xattrs
+        .iter()
+        .flat_map(|(name, value)| pax_record(&format!("{}{}", XATTR_RECORD_PREFIX, name), value))
+        .collect::<Vec<_>>();
+
+    let header_path = match path.file_name() {
+        Some(file_name) => Path::new("PaxHeaders").join(file_name),
+        None => Path::new("PaxHeaders").to_path_buf(),
+    };
+    let mut header = Header::new_ustar();
+    header.set_entry_type(EntryType::XHeader);
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    builder.append_data(&mut header, header_path, data.as_slice())
+}
+
+// A pax record is "<length> <key>=<value>\n", the length counting its own digits
+fn pax_record(key: &str, value: &[u8]) -> Vec<u8> {
+    let content_length = key.len() + value.len() + 3;
+    let mut length = content_length + content_length.to_string().len();
+    if length.to_string().len() + content_length > length {
+        length += 1;
+    }
+
+    let mut record = format!("{} {}=", length, key).into_bytes();
+    record.extend_from_slice(value);
+    record.push(b'\n');
+    record
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use tar::Archive;
+
+    use super::*;
+
+    #[test]
+    fn pax_record_length_shall_include_its_own_digits() {
+        assert_eq!(pax_record("a", b"b"), b"6 a=b\n");
+        let record = pax_record("SCHILY.xattr.user.comment", &[b'x'; 70]);
+        assert_eq!(record.len(), 101);
+        assert!(record.starts_with(b"101 SCHILY.xattr.user.comment="));
+    }
+
+    #[test]
+    fn extended_attributes_shall_be_read_back_as_pax_extensions_of_the_next_entry() {
+        let mut builder = Builder::new(Vec::new());
+        append_xattrs_header(
+            &mut builder,
+            Path::new("dir/file.txt"),
+            &[("user.comment".to_string(), b"Hello".to_vec())],
+        )
+        .unwrap();
+        let mut header = Header::new_gnu();
+        header.set_size(5);
+        header.set_mode(0o644);
+        builder
+            .append_data(&mut header, "dir/file.txt", &b"World"[..])
+            .unwrap();
+        let data = builder.into_inner().unwrap();
+
+        let mut archive = Archive::new(Cursor::new(data));
+        let mut entries = archive.entries().unwrap();
+        let mut entry = entries.next().unwrap().unwrap();
+        assert_eq!(entry.path().unwrap(), Path::new("dir/file.txt"));
+        let extensions = entry
+            .pax_extensions()
+            .unwrap()
+            .unwrap()
+            .map(|extension| extension.unwrap())
+            .map(|extension| {
+                (
+                    extension.key().unwrap().to_string(),
+                    extension.value_bytes().to_vec(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            extensions,
+            vec![("SCHILY.xattr.user.comment".to_string(), b"Hello".to_vec())]
+        );
+        assert!(entries.next().is_none());
+    }
+}
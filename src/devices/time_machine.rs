@@ -0,0 +1,428 @@
+use std::{
+    fs::File,
+    io::{BufRead, Read},
+    path::{Path, PathBuf},
+    process::Command,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use tracing::{debug, info};
+
+use crate::core::{
+    util::timestamps::Timestamp, ArchiveError, ArchiveWriter, ArchiveWriterOptions, Device,
+    DeviceFactory, DifferentialArchiveStep, Extractor, Question, QuestionType, SecurityLevel,
+};
+
+// Device delegating backups to Time Machine, through the tmutil command line tool
+// Time Machine backs up the whole system and manages its own index, so the files
+// walked by hibernacli are not copied, the backup is only triggered when finalizing
+struct MacOSTimeMachineDevice {
+    name: String,
+
+    // Time Machine destination, as given to tmutil
+    destination: PathBuf,
+
+    // Last time the destination was found available, persisted in the configuration
+    last_seen: Option<SystemTime>,
+}
+
+impl Device for MacOSTimeMachineDevice {
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_location(&self) -> String {
+        self.destination.display().to_string()
+    }
+
+    fn get_security_level(&self) -> SecurityLevel {
+        SecurityLevel::Local
+    }
+
+    fn get_device_type_name(&self) -> String {
+        "TimeMachine".to_string()
+    }
+
+    fn get_last_connection(&self) -> Option<Instant> {
+        None
+    }
+
+    fn get_last_disconnection(&self) -> Option<Instant> {
+        None
+    }
+
+    fn get_last_seen(&self) -> Option<SystemTime> {
+        self.last_seen
+    }
+
+    fn set_last_seen(&mut self, last_seen: SystemTime) {
+        self.last_seen = Some(last_seen);
+    }
+
+    fn to_toml_table(&self) -> toml::value::Table {
+        let mut table = toml::value::Table::new();
+        table.insert("type".to_string(), self.get_device_type_name().into());
+        table.insert(
+            "path".to_string(),
+            self.destination.display().to_string().into(),
+        );
+        table.insert("name".to_string(), self.get_name().into());
+        if let Some(last_seen) = self.last_seen.and_then(|t| t.ms_since_epoch().ok()) {
+            table.insert("last_seen".to_string(), (last_seen as i64).into());
+        }
+        table
+    }
+
+    // Time Machine manages its own index
+    fn read_backup_index(&self, _project_name: &str) -> Result<Option<Box<dyn BufRead>>, String> {
+        Ok(None)
+    }
+
+    fn write_backup_index(&self, _project_name: &str, _index: &[u8]) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn test_availability(&self) -> Result<(), String> {
+        let output = run_tmutil(&["destinationinfo"])?;
+        if !output.contains(&self.destination.display().to_string()) {
+            return Err(format!(
+                "{} is not a Time Machine destination",
+                self.destination.display()
+            ));
+        }
+        Ok(())
+    }
+
+    // Backups are written by Time Machine itself, with its own permissions
+    fn test_write_permission(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn get_free_space(&self) -> Option<u64> {
+        fs2::available_space(&self.destination).ok()
+    }
+
+    // Time Machine snapshots cover the whole system, not single projects
+    fn list_archives(&self, _project_name: &str) -> Result<Vec<String>, String> {
+        Ok(vec![])
+    }
+
+    fn list_backed_up_projects(&self) -> Result<Vec<String>, String> {
+        Ok(vec![])
+    }
+
+    fn migrate_archives(&self, _project_name: &str) -> Result<usize, String> {
+        Ok(0)
+    }
+
+    fn get_archive_time(&self, _archive_name: &str) -> Option<SystemTime> {
+        None
+    }
+
+    fn get_last_backup_time(&self, _project_name: &str) -> Result<Option<SystemTime>, String> {
+        Ok(None)
+    }
+
+    fn get_archive_reader(
+        &self,
+        _project_name: &str,
+        archive_name: &str,
+    ) -> Result<Box<dyn Read>, String> {
+        Err(format!(
+            "Time Machine backups cannot be read as archives: {}",
+            archive_name
+        ))
+    }
+
+    fn create_project_directory(&self, _project_name: &str) -> Result<(), String> {
+        Ok(())
+    }
+
+    // Time Machine backs up whole volumes, there is no folder per project
+    fn rename_project_directory(&self, _project_name: &str, _new_name: &str) -> Result<(), String> {
+        Ok(())
+    }
+
+    // Backups made by Time Machine are deleted with tmutil, not per project
+    fn delete_project_data(&self, _project_name: &str) -> Result<(), String> {
+        Ok(())
+    }
+
+    // Time Machine applies its own retention to its snapshots
+    fn prune_archives(&self, _project_name: &str, _max_archives: usize) -> Result<usize, String> {
+        Ok(0)
+    }
+
+    fn get_archive_writer(
+        &self,
+        _project_name: &str,
+        _options: &ArchiveWriterOptions,
+    ) -> Box<dyn ArchiveWriter> {
+        Box::new(MacOSTimeMachineArchiveWriter {
+            destination: self.destination.clone(),
+            finalized: false,
+        })
+    }
+
+    fn get_extractor(&self, _project_name: &str) -> Box<dyn Extractor> {
+        Box::new(MacOSTimeMachineExtractor)
+    }
+}
+
+fn run_tmutil(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("tmutil")
+        .args(args)
+        .output()
+        .map_err(|e| format!("Cannot run tmutil: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "tmutil {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+pub struct MacOSTimeMachineArchiveWriter {
+    destination: PathBuf,
+    finalized: bool,
+}
+
+impl ArchiveWriter for MacOSTimeMachineArchiveWriter {
+    fn add_file(
+        &mut self,
+        _file: &mut File,
+        path: &PathBuf,
+        _ctime: u128,
+        _mtime: u128,
+        _size: u64,
+    ) -> Result<(), ArchiveError> {
+        debug!("File {:?} is left to Time Machine", path);
+        Ok(())
+    }
+
+    fn add_directory(
+        &mut self,
+        _src_path: &Path,
+        path: &PathBuf,
+        _ctime: u128,
+        _mtime: u128,
+    ) -> Result<(), ArchiveError> {
+        debug!("Directory {:?} is left to Time Machine", path);
+        Ok(())
+    }
+
+    fn add_symlink(
+        &mut self,
+        path: &PathBuf,
+        _ctime: u128,
+        _mtime: u128,
+        _target: &PathBuf,
+    ) -> Result<(), ArchiveError> {
+        debug!("Symlink {:?} is left to Time Machine", path);
+        Ok(())
+    }
+
+    fn finalize(
+        &mut self,
+        _deleted_files: &Vec<PathBuf>,
+        _new_index: &Vec<u8>,
+        _tags: &[String],
+    ) -> Result<(), ArchiveError> {
+        if self.finalized {
+            return Err(ArchiveError::from("Archive has already been finalized"));
+        }
+
+        info!("Starting Time Machine backup to {:?}", self.destination);
+        let destination = self.destination.display().to_string();
+        run_tmutil(&["startbackup", "--block", "--destination", &destination])
+            .map_err(|e| ArchiveError::from(e.as_str()))?;
+        self.finalized = true;
+        Ok(())
+    }
+}
+
+// Restoring is done with the Time Machine tools, there is no step to extract
+pub struct MacOSTimeMachineExtractor;
+
+impl Iterator for MacOSTimeMachineExtractor {
+    type Item = Box<dyn DifferentialArchiveStep>;
+
+    fn next(&mut self) -> Option<Box<dyn DifferentialArchiveStep>> {
+        None
+    }
+}
+
+impl DoubleEndedIterator for MacOSTimeMachineExtractor {
+    fn next_back(&mut self) -> Option<Box<dyn DifferentialArchiveStep>> {
+        None
+    }
+}
+
+impl Extractor for MacOSTimeMachineExtractor {}
+
+pub struct MacOSTimeMachineDeviceFactory {
+    destination_question: Question,
+    name_question: Question,
+    step: u8,
+}
+
+impl MacOSTimeMachineDeviceFactory {
+    pub fn new() -> MacOSTimeMachineDeviceFactory {
+        MacOSTimeMachineDeviceFactory {
+            destination_question: Question::new(
+                "What is the mount point of the Time Machine destination?".to_string(),
+                QuestionType::UnixPath,
+            ),
+            name_question: Question::new(
+                "How would you name this device?".to_string(),
+                QuestionType::String,
+            ),
+            step: 0,
+        }
+    }
+
+    fn get_current_question(&self) -> &Question {
+        match self.step {
+            0 => &self.destination_question,
+            1 => &self.name_question,
+            _ => panic!("No more questions"),
+        }
+    }
+}
+
+const TIME_MACHINE_DESCRIPTION: &str = "\
+A Time Machine destination, on macOS only. Backups are delegated to Time Machine through tmutil,
+which backs up the whole system to the destination and manages its own index.
+Restoring is done with the Time Machine tools.
+The destination is considered as local storage: its security level is Local.";
+
+impl DeviceFactory for MacOSTimeMachineDeviceFactory {
+    fn get_description(&self) -> &str {
+        TIME_MACHINE_DESCRIPTION
+    }
+
+    fn get_question_statement(&self) -> &str {
+        self.get_current_question().get_statement()
+    }
+
+    fn get_question_type(&self) -> &QuestionType {
+        self.get_current_question().get_question_type()
+    }
+
+    fn validate_answer(&self, answer: &str) -> Result<(), String> {
+        if !self.has_next() {
+            return Err("No more questions".to_string());
+        }
+        self.get_current_question().validate_answer(answer)
+    }
+
+    fn set_question_answer(&mut self, answer: String) -> Result<(), String> {
+        let status = match self.step {
+            0 => self.destination_question.set_answer(answer),
+            1 => self.name_question.set_answer(answer),
+            _ => panic!("No more questions"),
+        };
+
+        status?;
+        self.step += 1;
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.destination_question.clear_answer();
+        self.name_question.clear_answer();
+        self.step = 0;
+    }
+
+    fn has_next(&self) -> bool {
+        self.step < 2
+    }
+
+    fn build(&self) -> Result<Box<dyn Device>, String> {
+        if self.step < 2 {
+            return Err("Not all questions have been answered".to_string());
+        }
+
+        let destination = self.destination_question.get_answer()?;
+        let name = self.name_question.get_answer()?;
+        let name = if name.is_empty() {
+            "TimeMachine".to_string()
+        } else {
+            name
+        };
+        Ok(Box::new(MacOSTimeMachineDevice {
+            name,
+            destination: PathBuf::from(destination),
+            last_seen: None,
+        }))
+    }
+
+    fn build_from_toml_table(
+        &self,
+        name: &str,
+        table: &toml::value::Table,
+    ) -> Result<Box<dyn Device>, String> {
+        let destination = table
+            .get("path")
+            .ok_or_else(|| "missing field `path`".to_string())?
+            .as_str()
+            .ok_or_else(|| "Invalid string for 'path'".to_string())?;
+        let last_seen = table
+            .get("last_seen")
+            .map(|value| {
+                value
+                    .as_integer()
+                    .and_then(|ms| u64::try_from(ms).ok())
+                    .map(|ms| UNIX_EPOCH + Duration::from_millis(ms))
+                    .ok_or_else(|| "Invalid timestamp for 'last_seen'".to_string())
+            })
+            .transpose()?;
+
+        Ok(Box::new(MacOSTimeMachineDevice {
+            name: name.to_string(),
+            destination: PathBuf::from(destination),
+            last_seen,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn when_creating_device_from_toml_it_shall_serialize_it_back() {
+        let factory = MacOSTimeMachineDeviceFactory::new();
+        let mut table = toml::value::Table::new();
+        table.insert(
+            "path".to_string(),
+            toml::Value::String("/Volumes/Backups".to_string()),
+        );
+
+        let device = factory
+            .build_from_toml_table("MyTimeMachine", &table)
+            .unwrap();
+        assert_eq!(device.get_security_level(), SecurityLevel::Local);
+        assert_eq!(
+            toml::to_string(&device.to_toml_table()).unwrap(),
+            r#"name = "MyTimeMachine"
+path = "/Volumes/Backups"
+type = "TimeMachine"
+"#
+        );
+    }
+
+    #[test]
+    fn time_machine_device_shall_not_provide_any_index() {
+        let device = MacOSTimeMachineDevice {
+            name: "MyTimeMachine".to_string(),
+            destination: PathBuf::from("/Volumes/Backups"),
+            last_seen: None,
+        };
+
+        assert!(device.read_backup_index("MyProject").unwrap().is_none());
+        assert_eq!(device.get_extractor("MyProject").count(), 0);
+    }
+}
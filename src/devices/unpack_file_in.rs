@@ -1,10 +1,9 @@
 use std::{
-    fs::{self, File},
-    io::{self, Error, ErrorKind},
+    fs,
+    io::{self, Error, ErrorKind, Read},
     path::{Component, Path, PathBuf},
 };
 
-use flate2::read::GzDecoder;
 use tar::Entry;
 
 /// Extension trait for `Entry` to unpack safely one of the files from .files directory
@@ -19,7 +18,7 @@ pub trait UnpackFileIn {
     fn validate_inside_dst(&self, dst: &Path, file_dst: &Path) -> io::Result<PathBuf>;
 }
 
-impl UnpackFileIn for Entry<'_, GzDecoder<File>> {
+impl<R: Read> UnpackFileIn for Entry<'_, R> {
     fn unpack_file_in(&mut self, dst: &Path) -> io::Result<bool> {
         // Notes regarding bsdtar 2.8.3 / libarchive 2.8.3:
         // * Leading '/'s are trimmed. For example, `///test` is treated as
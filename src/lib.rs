@@ -8,35 +8,55 @@ mod core {
 
     pub mod util {
         pub mod buffer_ext;
+        pub mod sparse_files;
         pub mod timestamps;
     }
 
     mod backup;
     mod config;
     mod device;
+    mod notification;
     mod project;
 
     pub mod operations;
 
+    pub use backup::read_archive_entry;
     pub use config::GlobalConfigProvider;
     pub use device::SecurityLevel;
     pub use device::{
-        ArchiveError, ArchiveWriter, Device, DeviceFactory, DeviceFactoryKey,
-        DifferentialArchiveStep, Extractor, ExtractorError, Question, QuestionType,
+        ArchiveError, ArchiveWriter, ArchiveWriterOptions, CompressionFormat, Device,
+        DeviceCapability, DeviceFactory, DeviceFactoryKey, DifferentialArchiveStep, Extractor,
+        ExtractorError, Question, QuestionType,
     };
+    pub use notification::{BackupEvent, NotificationSender};
+    pub use project::{Project, ProjectTrackingStatus};
 
     #[cfg(test)]
     pub use device::{MockDevice, MockDeviceFactory};
 }
 
 mod devices {
+    mod compression;
     pub mod local_file_storage;
     pub mod mounted_folder;
+    pub mod rclone;
+    mod reflink_snapshot;
+    mod sparse_tar;
+    mod tar_xattrs;
+    #[cfg(target_os = "macos")]
+    pub mod time_machine;
     mod unpack_file_in;
 }
 
+mod notifiers {
+    pub mod email;
+    pub mod webhook;
+}
+
 pub mod cli;
 
+mod completions;
+
 pub mod macros;
 
 pub mod run;
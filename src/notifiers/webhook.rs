@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+use reqwest::{blocking::Client, header::CONTENT_TYPE};
+use tracing::debug;
+
+use crate::core::{BackupEvent, NotificationSender};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Posts each event as a JSON document to an HTTP endpoint
+pub struct WebhookNotifier {
+    url: String,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: &str) -> Self {
+        WebhookNotifier {
+            url: url.to_string(),
+        }
+    }
+}
+
+impl NotificationSender for WebhookNotifier {
+    fn send(&self, event: BackupEvent) -> Result<(), String> {
+        let body = serde_json::to_string(&event).map_err(|e| e.to_string())?;
+        debug!("Posting notification to {}", self.url);
+        Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| format!("Cannot create the HTTP client: {}", e))?
+            .post(&self.url)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body)
+            .send()
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| format!("Cannot post the notification to {}: {}", self.url, e))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    use super::*;
+
+    // Accept a single request, answer it with the given status line and
+    // return the request body
+    fn serve_once(status: &'static str) -> (String, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                    content_length = value.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            write!(&stream, "HTTP/1.1 {}\r\nContent-Length: 0\r\n\r\n", status).unwrap();
+            String::from_utf8(body).unwrap()
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn event_shall_be_posted_as_json() {
+        let (url, server) = serve_once("200 OK");
+
+        let result = WebhookNotifier::new(&url).send(BackupEvent::Success {
+            project: "my-project".to_string(),
+            device: "usb".to_string(),
+            files_changed: 2,
+        });
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            server.join().unwrap(),
+            r#"{"status":"success","project":"my-project","device":"usb","files_changed":2}"#
+        );
+    }
+
+    #[test]
+    fn error_status_shall_be_reported() {
+        let (url, server) = serve_once("500 Internal Server Error");
+
+        let result = WebhookNotifier::new(&url).send(BackupEvent::Failure {
+            project: "my-project".to_string(),
+            device: "usb".to_string(),
+            error: "Device not available".to_string(),
+        });
+
+        server.join().unwrap();
+        assert!(result.unwrap_err().contains("500"));
+    }
+}
@@ -1,27 +1,137 @@
+use std::{env, str::FromStr, sync::Arc, time::SystemTime};
+
+use tracing::{info, level_filters::LevelFilter, warn};
+
+#[cfg(not(feature = "xdg-strict"))]
+use crate::devices::local_file_storage::StandardPathProvider as ConfigPathProvider;
+#[cfg(feature = "xdg-strict")]
+use crate::devices::local_file_storage::XdgPathProvider as ConfigPathProvider;
 use crate::{
-    cli::{CommandRunner, Console},
-    core::operations::Operations,
+    cli::{CommandRunner, Console, RunContext},
+    core::{
+        operations::{AddDeviceResult, DeviceOperations, OperationsBuilder},
+        GlobalConfigProvider,
+    },
     devices::{
-        local_file_storage::{LocalFileStorage, StandardFileSystem, StandardPathProvider},
+        local_file_storage::{LocalFileStorage, StandardFileSystem},
         mounted_folder::MountedFolderFactory,
+        rclone::RcloneDeviceFactory,
     },
 };
+
 const DEFAULT_CONFIG: &str = "";
+const LOG_LEVEL_ENV_VAR: &str = "HIBERNACLI_LOG";
+const LOG_LEVEL_FLAG: &str = "--log-level";
+const ENV_DEVICE_PREFIX: &str = "HIBERNACLI_DEVICE";
 
-pub fn run(args: Vec<String>) {
-    let standard_path_provider = StandardPathProvider {};
-    let local_file_storage = LocalFileStorage::new(
-        &standard_path_provider,
-        &StandardFileSystem {},
-        DEFAULT_CONFIG,
-    );
-    let mut operations = Operations::new(Box::new(local_file_storage));
-    operations.register_device_factory(
-        "MountedFolder".to_string(),
-        "Mounted device".to_string(),
-        || Box::new(MountedFolderFactory::new()),
+pub fn run(mut args: Vec<String>) {
+    init_logging(&mut args);
+
+    let path_provider = ConfigPathProvider {};
+    let local_file_storage =
+        LocalFileStorage::new(&path_provider, &StandardFileSystem {}, DEFAULT_CONFIG);
+    let context = RunContext {
+        app_version: env!("CARGO_PKG_VERSION"),
+        config_path: Some(local_file_storage.get_config_path()),
+        timestamp: SystemTime::now(),
+    };
+    let operations_builder = OperationsBuilder::new(Arc::new(local_file_storage))
+        .register_device_factory(
+            "MountedFolder".to_string(),
+            "Mounted device".to_string(),
+            || Box::new(MountedFolderFactory::new()),
+        )
+        .register_device_factory(
+            "Rclone".to_string(),
+            "Rclone remote (cloud storage)".to_string(),
+            || Box::new(RcloneDeviceFactory::new()),
+        );
+    #[cfg(target_os = "macos")]
+    let operations_builder = operations_builder.register_device_factory(
+        "TimeMachine".to_string(),
+        "Time Machine destination".to_string(),
+        || Box::new(crate::devices::time_machine::MacOSTimeMachineDeviceFactory::new()),
     );
+    let operations = operations_builder.build();
+    register_env_device(&operations);
+
+    let mut command_runner =
+        CommandRunner::new(Console, &operations, &operations, &operations, &operations);
+    command_runner.run_with_context(args, context);
+}
+
+// In CI jobs, the backup device is given by HIBERNACLI_DEVICE_PATH (and optionally
+// HIBERNACLI_DEVICE_NAME), and registered unless a device with the same name exists
+fn register_env_device(device_operations: &dyn DeviceOperations) {
+    if env::var_os(format!("{}_PATH", ENV_DEVICE_PREFIX)).is_none() {
+        return;
+    }
+
+    let result = MountedFolderFactory::build_from_env(ENV_DEVICE_PREFIX).and_then(|device| {
+        let name = device.get_name();
+        if device_operations.add_device_idempotent(device)? == AddDeviceResult::Added {
+            info!("Registered device {} from the environment", name);
+        }
+        Ok(())
+    });
+    if let Err(e) = result {
+        warn!("Cannot register the device from the environment: {}", e);
+    }
+}
+
+// Logs go to stderr, at the level given by --log-level, or by the HIBERNACLI_LOG
+// environment variable, warnings only by default
+fn init_logging(args: &mut Vec<String>) {
+    let level = take_log_level_flag(args).or_else(|| env::var(LOG_LEVEL_ENV_VAR).ok());
+    let level_filter = match level {
+        Some(level) => LevelFilter::from_str(&level).unwrap_or_else(|_| {
+            eprintln!("Invalid log level: {}", level);
+            LevelFilter::WARN
+        }),
+        None => LevelFilter::WARN,
+    };
+
+    tracing_subscriber::fmt()
+        .with_max_level(level_filter)
+        .with_writer(std::io::stderr)
+        .init();
+}
+
+// Remove the log level flag and its value from the arguments, so that the
+// commands do not see it
+fn take_log_level_flag(args: &mut Vec<String>) -> Option<String> {
+    let position = args.iter().position(|arg| arg == LOG_LEVEL_FLAG)?;
+    args.remove(position);
+    if position < args.len() {
+        Some(args.remove(position))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_level_flag_shall_be_removed_from_arguments() {
+        let mut args: Vec<String> = ["hibernacli", "--log-level", "debug", "device", "list"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+
+        assert_eq!(take_log_level_flag(&mut args), Some("debug".to_string()));
+        assert_eq!(args, vec!["hibernacli", "device", "list"]);
+    }
+
+    #[test]
+    fn arguments_without_log_level_flag_shall_be_kept() {
+        let mut args: Vec<String> = ["hibernacli", "device", "list"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
 
-    let command_runner = CommandRunner::new(Console, &operations, &operations, &operations);
-    command_runner.run(args);
+        assert_eq!(take_log_level_flag(&mut args), None);
+        assert_eq!(args, vec!["hibernacli", "device", "list"]);
+    }
 }